@@ -1,3 +1,4 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
@@ -36,10 +37,18 @@ pub struct Args {
     #[arg(long, global = true)]
     pub quiet: bool,
 
+    /// Stable tab-separated output for scripts (list, search, tags, backlinks)
+    #[arg(long, global = true, conflicts_with = "json")]
+    pub porcelain: bool,
+
     /// Disable colored output
     #[arg(long, global = true)]
     pub no_color: bool,
 
+    /// Color output even when stdout is not a terminal (e.g. piped into `less -R`)
+    #[arg(long, global = true, conflicts_with = "no_color")]
+    pub force_color: bool,
+
     /// Enable verbose output
     #[arg(long, short, global = true)]
     pub verbose: bool,
@@ -47,15 +56,61 @@ pub struct Args {
     /// Enable debug output with backtraces
     #[arg(long, global = true)]
     pub debug: bool,
+
+    /// Use a specific index database file instead of the default
+    #[arg(long, global = true, env = "KDEX_DB_PATH", value_name = "PATH")]
+    pub db_path: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
+    /// Set up kdex: config, semantic search, MCP integration and a first index
+    #[command(
+        after_help = "Asks for each choice on a terminal. Choices not given as flags
+default to no when stdin isn't a terminal or with --json.
+
+Examples:
+  kdex init                                 Interactive setup
+  kdex init --semantic --mcp claude --index Set up everything without prompts
+  kdex --json init --no-semantic --no-mcp --no-index
+"
+    )]
+    Init {
+        /// Enable semantic search and download the embedding model
+        #[arg(long, conflicts_with = "no_semantic")]
+        semantic: bool,
+
+        /// Keep semantic search off
+        #[arg(long)]
+        no_semantic: bool,
+
+        /// Configure kdex as an MCP server for this AI tool
+        #[arg(long, value_enum, value_name = "TOOL", conflicts_with = "no_mcp")]
+        mcp: Option<McpTool>,
+
+        /// Skip MCP setup
+        #[arg(long)]
+        no_mcp: bool,
+
+        /// Index the current directory
+        #[arg(long, conflicts_with = "no_index")]
+        index: bool,
+
+        /// Skip indexing the current directory
+        #[arg(long)]
+        no_index: bool,
+    },
+
     /// Index a directory (code repository or knowledge base)
     #[command(after_help = "Examples:
   kdex index                    Index current directory
   kdex index ~/projects/myapp   Index specific project
   kdex index ~/Documents/notes  Index Obsidian vault
+  kdex index . --watch          Index, then keep watching for changes
+  kdex index ~/dotfiles --follow-symlinks
+  kdex index ~/archive --max-files 500000 --max-total-mb 0
+  pg_dump_docs | kdex index --stdin --name db-docs --path-label schema.md
 ")]
     Index {
         /// Directory to index (defaults to current directory)
@@ -65,6 +120,50 @@ pub enum Commands {
         /// Custom name for the repository
         #[arg(long)]
         name: Option<String>,
+
+        /// Keep watching for changes after the initial index
+        #[arg(long, short)]
+        watch: bool,
+
+        /// Descend into symlinked directories (overrides `follow_symlinks` config)
+        #[arg(long)]
+        follow_symlinks: bool,
+
+        /// Index hidden files and directories such as `.env` (overrides `index_hidden` config)
+        #[arg(long, conflicts_with = "exclude_hidden")]
+        include_hidden: bool,
+
+        /// Skip hidden files and directories even if `index_hidden` is on
+        #[arg(long)]
+        exclude_hidden: bool,
+
+        /// Abort if the directory has more indexable files than this (0 = no limit)
+        #[arg(long, value_name = "N")]
+        max_files: Option<usize>,
+
+        /// Abort if the indexable content exceeds this many MB (0 = no limit)
+        #[arg(long, value_name = "MB")]
+        max_total_mb: Option<u64>,
+
+        /// Progress output: interactive bar, or NDJSON lines on stderr
+        #[arg(long, value_enum, default_value_t = ProgressFormat::Bar, value_name = "FORMAT")]
+        progress: ProgressFormat,
+
+        /// Embed files during this run even if `enable_semantic_search` is off
+        #[arg(long, conflicts_with = "no_embed")]
+        embed: bool,
+
+        /// Skip embedding during this run even if `enable_semantic_search` is on
+        #[arg(long)]
+        no_embed: bool,
+
+        /// Read one file's content from stdin into the repository given by --name
+        #[arg(long, requires_all = ["name", "path_label"], conflicts_with_all = ["watch", "follow_symlinks", "include_hidden", "exclude_hidden", "max_files", "max_total_mb", "embed", "no_embed"])]
+        stdin: bool,
+
+        /// Relative path the stdin content is indexed under (its extension sets the file type)
+        #[arg(long, value_name = "PATH", requires = "stdin")]
+        path_label: Option<PathBuf>,
     },
 
     /// Add a repository (local or remote GitHub)
@@ -74,6 +173,7 @@ pub enum Commands {
   kdex add --remote https://github.com/owner/repo
   kdex add --remote owner/repo --branch develop
   kdex add --remote owner/repo --shallow
+  kdex add . --watch              Add, then keep watching for changes
 ")]
     Add {
         /// Local directory path (when not using --remote)
@@ -94,6 +194,22 @@ pub enum Commands {
         /// Custom name for the repository
         #[arg(long)]
         name: Option<String>,
+
+        /// Keep watching for changes after the initial index (local only)
+        #[arg(long, short, conflicts_with = "remote")]
+        watch: bool,
+
+        /// Progress output: interactive bar, or NDJSON lines on stderr
+        #[arg(long, value_enum, default_value_t = ProgressFormat::Bar, value_name = "FORMAT")]
+        progress: ProgressFormat,
+
+        /// Embed files during this run even if `enable_semantic_search` is off
+        #[arg(long, conflicts_with = "no_embed")]
+        embed: bool,
+
+        /// Skip embedding during this run even if `enable_semantic_search` is on
+        #[arg(long)]
+        no_embed: bool,
     },
 
     /// Search indexed content
@@ -103,6 +219,25 @@ pub enum Commands {
   kdex search \"TODO\" --file-type markdown
   kdex search \"error handling\" --semantic
   kdex search \"authentication\" --hybrid
+  kdex search \"retry logic\" --rerank
+  kdex search TODO --absolute     Print absolute paths (for piping to other tools)
+  kdex search \"auth flow\" --hybrid --explain
+  kdex search timeout --show-score
+  kdex search meeting --show-title   Show note titles: notes: Weekly Sync (meetings/w12.md)
+  kdex search timeout --group-by file-type
+  kdex search TODO --repo api --repo-path ~/work/
+  kdex search handler --within src/api   Only files under src/api in each repo
+  kdex search TODO --include-all  Also search repos that are still indexing
+  kdex search \"rate limit\" --json -o results.json
+  kdex search \"retry backoff\" --near 5   Words within 5 tokens, any order
+  kdex search TODO --highlight always | less -R
+  kdex search \"tokio::spawn\" --in-code --code-lang rust
+  kdex search \"deploy\" --json-lines --with-content > hits.ndjson
+  kdex search uth --substring     Find \"uth\" inside words (authentication, OAuth)
+  kdex search TODO --all --json   Every match, e.g. for an audit export
+  kdex search \"cache invalidation\" --min-score 0.6   Trim weak matches
+  kdex search retry --open        Pick a result by number, open it in $EDITOR
+  kdex search retry --preview 'bat {}' --open   Preview picks, then open one
 
 Or use the shorthand (search is the default command):
   kdex \"database connection\"
@@ -116,6 +251,25 @@ Or use the shorthand (search is the default command):
         #[arg(long, short)]
         repo: Option<String>,
 
+        /// Filter by repository path (substring match, combinable with --repo)
+        #[arg(long, value_name = "SUBSTR")]
+        repo_path: Option<String>,
+
+        /// Only search files under this path: relative to each repository's
+        /// root (`src/api`), or absolute / starting with `.` (`./docs`)
+        #[arg(long, value_name = "PATH")]
+        within: Option<String>,
+
+        /// Also search repositories that are still indexing or syncing, or whose
+        /// last run failed (by default only ready repositories are searched)
+        #[arg(long)]
+        include_all: bool,
+
+        /// Drop results whose path (relative to the repository root) matches
+        /// this glob, e.g. `*/test/*` or `*.generated.*` (repeatable)
+        #[arg(long = "exclude-pattern", value_name = "GLOB")]
+        exclude_patterns: Vec<String>,
+
         /// Filter by file type (code, markdown, config)
         #[arg(long, short = 't')]
         file_type: Option<String>,
@@ -124,47 +278,166 @@ Or use the shorthand (search is the default command):
         #[arg(long)]
         tag: Option<String>,
 
-        /// Maximum number of results
-        #[arg(long, short, default_value = "20")]
-        limit: usize,
+        /// Maximum number of results, 0 for no limit [default: `default_search_limit` config, 20]
+        #[arg(long, short)]
+        limit: Option<usize>,
+
+        /// Return every match (same as --limit 0)
+        #[arg(long, conflicts_with = "limit")]
+        all: bool,
+
+        /// At most N results from any one repository, so a dominant repository
+        /// can't fill the whole --limit (more candidates are fetched to fill it)
+        #[arg(long, value_name = "N", conflicts_with_all = ["fuzzy", "regex", "substring"])]
+        per_repo_limit: Option<usize>,
+
+        /// Stop regex and semantic searches after this many seconds and show
+        /// the results found so far
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+        timeout: Option<u64>,
 
-        /// Group results by repository
-        #[arg(long, short = 'g')]
+        /// Group results by repository (same as --group-by repo)
+        #[arg(long, short = 'g', conflicts_with = "group_by")]
         group_by_repo: bool,
 
+        /// Group results by repository or file type
+        #[arg(long, value_enum, value_name = "KEY")]
+        group_by: Option<GroupBy>,
+
+        /// Order of groups when grouping results
+        #[arg(long, value_enum, default_value_t = GroupSort::Name, value_name = "ORDER")]
+        group_sort: GroupSort,
+
         /// Use semantic (vector) search
-        #[arg(long, short = 's', conflicts_with_all = ["hybrid", "lexical", "fuzzy", "regex"])]
+        #[arg(long, short = 's', conflicts_with_all = ["hybrid", "lexical", "fuzzy", "regex", "substring"])]
         semantic: bool,
 
         /// Use hybrid search (combines lexical + semantic)
-        #[arg(long, short = 'H', conflicts_with_all = ["semantic", "lexical", "fuzzy", "regex"])]
+        #[arg(long, short = 'H', conflicts_with_all = ["semantic", "lexical", "fuzzy", "regex", "substring"])]
         hybrid: bool,
 
         /// Use lexical (full-text) search (default)
-        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "fuzzy", "regex"])]
+        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "fuzzy", "regex", "substring"])]
         lexical: bool,
 
+        /// Search mode; `auto` picks lexical, semantic or hybrid from the query
+        /// [default: `default_search_mode` config, lexical]
+        #[arg(long, value_name = "MODE", value_parser = ["lexical", "semantic", "hybrid", "auto"], conflicts_with_all = ["semantic", "hybrid", "lexical", "fuzzy", "regex", "substring", "rerank", "in_code"])]
+        mode: Option<String>,
+
         /// Use fuzzy matching (tolerates typos)
-        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "lexical", "regex"])]
+        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "lexical", "regex", "substring"])]
         fuzzy: bool,
 
         /// Use regex pattern matching
-        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "lexical", "fuzzy"])]
+        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "lexical", "fuzzy", "substring"])]
         regex: bool,
+
+        /// Match the query as a literal substring inside words (case-insensitive)
+        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "lexical", "fuzzy", "regex"])]
+        substring: bool,
+
+        /// Re-rank the top lexical matches by semantic similarity (needs embeddings)
+        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "fuzzy", "regex", "substring"])]
+        rerank: bool,
+
+        /// Print absolute file paths instead of repo:relative paths
+        #[arg(long)]
+        absolute: bool,
+
+        /// Show each result's score breakdown (BM25, cosine, RRF and per-list ranks)
+        #[arg(long, conflicts_with_all = ["fuzzy", "regex", "substring"])]
+        explain: bool,
+
+        /// Prefix each result with its raw score (BM25, cosine similarity, or RRF)
+        #[arg(long, conflicts_with_all = ["fuzzy", "regex", "substring"])]
+        show_score: bool,
+
+        /// Drop lexical results with a normalized BM25 relevance below this,
+        /// between 0 and 1 [default: `min_lexical_score` config, 0]
+        #[arg(long, value_name = "SCORE", value_parser = parse_relevance, conflicts_with_all = ["semantic", "fuzzy", "regex", "substring"])]
+        min_score: Option<f64>,
+
+        /// Show the title of markdown and org results next to the path
+        #[arg(long)]
+        show_title: bool,
+
+        /// Don't prefix results with a file type label such as [md] or [rs]
+        #[arg(long)]
+        no_type_labels: bool,
+
+        /// Match results where all query words appear within N tokens of each other
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["semantic", "fuzzy", "regex", "substring"])]
+        near: Option<u32>,
+
+        /// Match only within fenced code blocks of markdown files
+        #[arg(long, conflicts_with_all = ["semantic", "hybrid", "fuzzy", "regex", "substring"])]
+        in_code: bool,
+
+        /// Only match code blocks with this language tag (e.g. rust)
+        #[arg(long, value_name = "LANG", requires = "in_code")]
+        code_lang: Option<String>,
+
+        /// Highlight matches in snippets, independent of --no-color
+        #[arg(long, value_enum, default_value_t = HighlightMode::Auto, value_name = "WHEN")]
+        highlight: HighlightMode,
+
+        /// Maximum snippet lines shown per result
+        #[arg(long, value_name = "N", default_value_t = 3, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        snippet_lines: usize,
+
+        /// Stream results as NDJSON: one JSON object per result, without the envelope
+        #[arg(long, conflicts_with_all = ["fuzzy", "regex", "substring", "group_by", "group_by_repo"])]
+        json_lines: bool,
+
+        /// Include each result's file content, read from disk (needs --json or --json-lines)
+        #[arg(long, conflicts_with_all = ["fuzzy", "regex", "substring"])]
+        with_content: bool,
+
+        /// Maximum characters of content per result with --with-content [default: 4000]
+        #[arg(long, value_name = "N", requires = "with_content")]
+        max_chars: Option<usize>,
+
+        /// Write results to a file instead of stdout (parent directories are created)
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// After listing results, pick one by number and open it in $VISUAL / $EDITOR
+        #[arg(long, conflicts_with_all = ["json_lines", "output"])]
+        open: bool,
+
+        /// After listing results, run CMD on picked ones; `{}` is replaced by the
+        /// file's absolute path, e.g. 'bat {}'
+        #[arg(long, value_name = "CMD", conflicts_with_all = ["json_lines", "output"])]
+        preview: Option<String>,
     },
 
     /// Update an existing index
     #[command(after_help = "Examples:
   kdex update .            Update current directory
   kdex update --all        Update all repositories
+  kdex update notes        Update by repository name (or unique prefix)
+  kdex update . --since 2h Only re-index files changed in the last 2 hours
+  kdex update --all --since 2024-06-01
+  kdex update . --force    Drop the repository's index and rebuild it from scratch
+
+With --since, deleted files are not detected; run a full update to prune them.
 ")]
     Update {
-        /// Repository path to update
+        /// Repository path or name to update
         path: Option<PathBuf>,
 
         /// Update all indexed repositories
         #[arg(long)]
         all: bool,
+
+        /// Only consider files modified after this time (e.g. 30m, 2h, 3d, 1w, 2024-06-01)
+        #[arg(long, value_parser = parse_since, value_name = "DURATION|DATE")]
+        since: Option<DateTime<Utc>>,
+
+        /// Rebuild from scratch instead of diffing (clears files, tags, links, embeddings)
+        #[arg(long, visible_alias = "full", conflicts_with = "since")]
+        force: bool,
     },
 
     /// Sync remote repositories with their origins
@@ -182,22 +455,100 @@ Or use the shorthand (search is the default command):
     },
 
     /// List all indexed repositories
-    List {},
+    #[command(after_help = "Examples:
+  kdex list                             All repositories by name
+  kdex list --sort size --limit 5       Five largest repositories
+  kdex list --json --sort indexed --limit 20 --offset 20
+  kdex list --filter notes              Repositories whose name or path matches \"notes\"
+  kdex list --filter dcos               Fuzzy: also finds \"docs\"
+")]
+    List {
+        /// Sort by: name, size, files, indexed (size/files/indexed are descending).
+        /// Default: name, or best match first with --filter
+        #[arg(long, value_enum)]
+        sort: Option<RepoSort>,
+
+        /// Only list repositories whose name or path matches QUERY (substring or fuzzy)
+        #[arg(long, value_name = "QUERY")]
+        filter: Option<String>,
+
+        /// Maximum number of repositories to show
+        #[arg(long, short)]
+        limit: Option<usize>,
+
+        /// Number of repositories to skip (for pagination)
+        #[arg(long, default_value = "0")]
+        offset: usize,
+    },
 
     /// Remove a repository from the index
     #[command(after_help = "Examples:
   kdex remove ~/projects/old-project
-  kdex remove . --force    Skip confirmation
+  kdex remove my-notes              Remove by repository name
+  kdex remove . --force             Skip confirmation
+  kdex remove owner/repo --delete-clone   Also delete the cloned files
+  kdex remove owner/repo --keep-clone     Keep the clone (the default), without the reminder
+  kdex remove --all                 Clear the entire index
 ")]
     Remove {
-        /// Repository path to remove
-        path: PathBuf,
+        /// Repository path or name to remove
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        target: Option<String>,
+
+        /// Remove all indexed repositories
+        #[arg(long)]
+        all: bool,
+
+        /// Skip confirmation prompt
+        #[arg(long, short)]
+        force: bool,
 
+        /// Also delete the on-disk clone of remote repositories
+        #[arg(long, alias = "delete-files", conflicts_with = "keep_clone")]
+        delete_clone: bool,
+
+        /// Keep the on-disk clone of remote repositories (the default)
+        #[arg(long)]
+        keep_clone: bool,
+    },
+
+    /// Remove repositories whose directory no longer exists
+    #[command(after_help = "Examples:
+  kdex prune                    List stale repositories and confirm removal
+  kdex prune --force            Remove them without asking
+  kdex prune --json             Remove and report as JSON (no prompt)
+")]
+    Prune {
         /// Skip confirmation prompt
         #[arg(long, short)]
         force: bool,
     },
 
+    /// Manage indexed repositories
+    Repos {
+        #[command(subcommand)]
+        action: ReposAction,
+    },
+
+    /// Check the index for corruption and optimize the full-text index
+    #[command(after_help = "Examples:
+  kdex maintenance               Check, then optimize the full-text index
+  kdex maintenance --check-only  Only check for corruption, change nothing
+  kdex maintenance --force       Rebuild a damaged full-text index without asking
+
+A damaged full-text index is rebuilt from the content stored in it; if that
+fails, re-index from disk with: kdex update --all --force
+")]
+    Maintenance {
+        /// Only check for corruption, don't rebuild or optimize anything
+        #[arg(long, conflicts_with = "force")]
+        check_only: bool,
+
+        /// Rebuild a damaged full-text index without asking
+        #[arg(long, short)]
+        force: bool,
+    },
+
     /// Show or edit configuration
     Config {
         #[command(subcommand)]
@@ -215,9 +566,41 @@ Or use the shorthand (search is the default command):
     },
 
     /// Start MCP server for AI tool integration
-    Mcp {},
+    Mcp {
+        /// Load the embedding model on the first semantic query instead of at
+        /// startup (faster startup, slower first query)
+        #[arg(long)]
+        no_warmup: bool,
+    },
+
+    /// Serve a local HTTP JSON API for scripts and editor plugins
+    #[command(after_help = "Examples:
+  kdex serve                    Serve on http://127.0.0.1:8080
+  kdex serve --port 9000
+
+Endpoints:
+  GET /search?q=QUERY&mode=MODE&limit=N&repo=NAME&type=TYPE
+  GET /repos
+  GET /stats
+  GET /file?path=ABSOLUTE_PATH
+")]
+    Serve {
+        /// Port to listen on
+        #[arg(long, short, default_value = "8080")]
+        port: u16,
+
+        /// Address to bind (localhost only by default)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
 
     /// Watch for file changes and re-index automatically
+    #[command(after_help = "Examples:
+  kdex watch                      Watch the repository in the current directory
+  kdex watch --all                Watch every indexed repository
+  kdex watch /mnt/notes --poll    Scan for changes instead of using OS file events
+  kdex watch --all --poll 10      Scan every 10 seconds
+")]
     Watch {
         /// Watch all indexed repositories
         #[arg(long)]
@@ -225,17 +608,63 @@ Or use the shorthand (search is the default command):
 
         /// Specific repository path to watch
         path: Option<PathBuf>,
+
+        /// Scan for changes every SECS seconds instead of using OS file events, for
+        /// network filesystems (NFS, SMB) and WSL-mounted drives (default:
+        /// `watcher_poll_interval_secs`, 2)
+        #[arg(long, value_name = "SECS", num_args = 0..=1, value_parser = clap::value_parser!(u64).range(1..))]
+        #[allow(clippy::option_option)]
+        poll: Option<Option<u64>>,
     },
 
     /// Rebuild embeddings for semantic search
     #[command(after_help = "Examples:
   kdex rebuild-embeddings         Rebuild all embeddings
   kdex rebuild-embeddings --repo myproject
+  kdex rebuild-embeddings --batch 64   Lower memory use
+  kdex rebuild-embeddings --model bge-small-en-v1.5   Try another model
+  kdex rebuild-embeddings --missing-only   Embed only files without embeddings
 ")]
     RebuildEmbeddings {
         /// Filter by repository name
         #[arg(long)]
         repo: Option<String>,
+
+        /// Number of chunks sent to the model per embed call
+        #[arg(long, default_value = "256")]
+        batch: usize,
+
+        /// Embedding model for this run, overriding `embedding_model` from config
+        #[arg(long, value_name = "NAME")]
+        model: Option<String>,
+
+        /// Only embed files that have no embeddings yet, keeping existing ones
+        #[arg(long, conflicts_with = "model")]
+        missing_only: bool,
+    },
+
+    /// Export stored embeddings for use in other vector stores
+    #[command(after_help = "Examples:
+  kdex export-embeddings > embeddings.jsonl
+  kdex export-embeddings --repo notes -o notes.jsonl
+  kdex export-embeddings --format npy -o vectors.npy   Also writes vectors.meta.jsonl
+
+Rows are streamed from the index, so memory use does not grow with its size.
+In Python: numpy.load(\"vectors.npy\") gives a rows x dimensions float32 matrix;
+line i of vectors.meta.jsonl describes row i.
+")]
+    ExportEmbeddings {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = EmbeddingFormat::Jsonl, value_name = "FORMAT")]
+        format: EmbeddingFormat,
+
+        /// Filter by repository name
+        #[arg(long, short)]
+        repo: Option<String>,
+
+        /// Write to a file instead of stdout (required for npy)
+        #[arg(long, short, value_name = "FILE", required_if_eq("format", "npy"))]
+        output: Option<PathBuf>,
     },
 
     /// Generate shell completions
@@ -260,19 +689,46 @@ Or use the shorthand (search is the default command):
     },
 
     /// List all tags from indexed files
-    #[command(after_help = "Extracts tags from YAML frontmatter in markdown files.")]
-    Tags,
+    #[command(after_help = "Extracts tags from YAML frontmatter in markdown files.
+
+Examples:
+  kdex tags                      All tags, most used first
+  kdex tags --repo notes         Tags of one repository
+  kdex tags --co-occur rust      Tags that appear on the same files as #rust
+")]
+    Tags {
+        /// Only tags of this repository (path, name, or unique name prefix)
+        #[arg(long, short)]
+        repo: Option<String>,
+
+        /// List tags sharing files with this tag instead, by number of shared files
+        #[arg(long, value_name = "TAG")]
+        co_occur: Option<String>,
+    },
 
     /// Build AI context from search results
     #[command(after_help = "Examples:
   kdex context \"authentication\"         Build context for AI prompt
   kdex context \"error handling\" -l 5    Limit to 5 files
   kdex context \"api design\" --tokens 2000  Limit by tokens
+  kdex context \"routing\" --repo api-service -t code
+  kdex context \"onboarding\" -o bundle.md   Write to a file
+
+Filters (--repo, --file-type) are applied before the token budget,
+so --tokens is spent only on matching files.
 ")]
     Context {
         /// Search query to find relevant files
         query: String,
 
+        /// Filter by repository name
+        #[arg(long, short)]
+        repo: Option<String>,
+
+        /// Filter by file type (code, markdown, config)
+        #[arg(long, short = 't')]
+        file_type: Option<String>,
+
         /// Maximum number of files to include
         #[arg(long, short, default_value = "10")]
         limit: usize,
@@ -284,10 +740,47 @@ Or use the shorthand (search is the default command):
         /// Output format (markdown, text, json)
         #[arg(long, default_value = "markdown")]
         format: String,
+
+        /// How files are picked from the search results
+        #[arg(long, value_enum, default_value_t)]
+        strategy: ContextStrategy,
+
+        /// Files taken from any one repository with --strategy per-repo
+        #[arg(long, value_name = "N", default_value = "3")]
+        max_per_repo: usize,
+
+        /// Write the context to a file instead of stdout (parent directories are created)
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
     },
 
     /// Show knowledge index statistics
-    Stats {},
+    #[command(after_help = "Examples:
+  kdex stats                        Aggregate statistics
+  kdex stats --largest-files 10     Also list the 10 largest indexed files
+  kdex stats --largest-files 5 -r notes
+  kdex stats --queries              Top searches and searches that found nothing
+  kdex stats --watch                Refresh every 2 seconds while an index grows
+  kdex --json stats --watch 10      One JSON line every 10 seconds
+")]
+    Stats {
+        /// List the N largest indexed files
+        #[arg(long, value_name = "N")]
+        largest_files: Option<usize>,
+
+        /// Restrict --largest-files to one repository
+        #[arg(long, short, requires = "largest_files")]
+        repo: Option<String>,
+
+        /// List the N (default 10) most frequent logged searches and
+        /// zero-result searches (needs `log_queries`)
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "10")]
+        queries: Option<usize>,
+
+        /// Re-print statistics every SECS seconds (default 2) until interrupted
+        #[arg(long, value_name = "SECS", num_args = 0..=1, default_missing_value = "2", value_parser = clap::value_parser!(u64).range(1..))]
+        watch: Option<u64>,
+    },
 
     /// Export knowledge graph visualization
     #[command(after_help = "Examples:
@@ -295,6 +788,8 @@ Or use the shorthand (search is the default command):
   kdex graph --json             Output JSON for web visualization
   kdex graph --repo myproject   Graph only one repository
   kdex graph > graph.dot && dot -Tpng graph.dot -o graph.png
+  kdex graph --focus notes/rust.md          Notes linked to or from rust.md
+  kdex graph --focus rust --depth 2         Two link-hops around it
 ")]
     Graph {
         /// Output format (dot, json)
@@ -304,6 +799,25 @@ Or use the shorthand (search is the default command):
         /// Filter by repository name
         #[arg(long, short)]
         repo: Option<String>,
+
+        /// Only show the neighborhood of this file (path or note name)
+        #[arg(long, value_name = "FILE")]
+        focus: Option<String>,
+
+        /// Link-hops from the focus file to include, following links both ways
+        #[arg(long, value_name = "N", default_value_t = 1, requires = "focus")]
+        depth: usize,
+    },
+
+    /// Export note headings as symbols for editor navigation
+    #[command(after_help = "Examples:
+  kdex symbols --json           Flat JSON array of headings across all notes
+  kdex symbols --repo notes     Headings from one repository
+")]
+    Symbols {
+        /// Filter by repository name
+        #[arg(long, short)]
+        repo: Option<String>,
     },
 
     /// Check knowledge index health
@@ -311,13 +825,34 @@ Or use the shorthand (search is the default command):
   kdex health                   Run all health checks
   kdex health --repo myproject  Check specific repository
   kdex health --json            Output as JSON
+  kdex health --check-external  Also verify http(s) links (network)
 ")]
     Health {
         /// Filter by repository name
         #[arg(long, short)]
         repo: Option<String>,
+
+        /// Also check external http(s) links in markdown files
+        #[arg(long)]
+        check_external: bool,
+
+        /// Timeout per external link request in seconds
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..), requires = "check_external")]
+        timeout: u64,
     },
 
+    /// Diagnose configuration, database and environment problems
+    #[command(
+        after_help = "Checks config validity, database schema, embedding model,
+file watch limits and git credentials. Exits non-zero if any check fails.
+
+Examples:
+  kdex doctor
+  kdex doctor --json
+"
+    )]
+    Doctor,
+
     /// Configure MCP integration for AI tools
     #[command(after_help = "Examples:
   kdex add-mcp copilot    Configure GitHub Copilot CLI
@@ -342,6 +877,107 @@ For other installation methods:
     SelfUpdate,
 }
 
+/// Progress reporting style for indexing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProgressFormat {
+    /// Interactive progress bar
+    #[default]
+    Bar,
+    /// One JSON object per update on stderr (NDJSON)
+    Json,
+}
+
+/// File format for `export-embeddings`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum EmbeddingFormat {
+    /// One JSON object per chunk, including its vector
+    #[default]
+    Jsonl,
+    /// `NumPy` float32 matrix plus a `.meta.jsonl` sidecar with one line per row
+    Npy,
+}
+
+/// Result grouping for `search --group-by`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// Flat list
+    #[default]
+    None,
+    /// One group per repository
+    Repo,
+    /// One group per file type (markdown, rust, config, ...)
+    FileType,
+}
+
+impl GroupBy {
+    #[must_use]
+    pub fn is_grouped(self) -> bool {
+        self != Self::None
+    }
+}
+
+/// Order of result groups for `search --group-sort`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupSort {
+    /// Alphabetically by repository or file type
+    #[default]
+    Name,
+    /// Most results first
+    Count,
+    /// Group with the best-ranked result first
+    Score,
+}
+
+/// Snippet match highlighting for `search --highlight`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum HighlightMode {
+    /// ANSI highlighting when colors are on, [brackets] otherwise
+    #[default]
+    Auto,
+    /// Always emit ANSI highlighting (e.g. for `less -R`)
+    Always,
+    /// Never mark matches
+    Never,
+}
+
+/// File selection for `context --strategy`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ContextStrategy {
+    /// Best-scoring files first
+    #[default]
+    #[value(name = "topk")]
+    TopK,
+    /// Trade score against similarity to files already picked (MMR)
+    Diverse,
+    /// Best-scoring files, at most --max-per-repo from each repository
+    PerRepo,
+}
+
+impl ContextStrategy {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TopK => "topk",
+            Self::Diverse => "diverse",
+            Self::PerRepo => "per-repo",
+        }
+    }
+}
+
+/// Sort order for `list`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RepoSort {
+    /// Repository name (A-Z)
+    #[default]
+    Name,
+    /// Total indexed size, largest first
+    Size,
+    /// Number of files, most first
+    Files,
+    /// Last indexed time, most recent first
+    Indexed,
+}
+
 /// AI tool for MCP configuration
 #[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
 pub enum McpTool {
@@ -364,6 +1000,38 @@ pub enum Shell {
     Elvish,
 }
 
+#[derive(Subcommand, Clone)]
+pub enum ReposAction {
+    /// Rename a repository, keeping its index
+    #[command(after_help = "Examples:
+  kdex repos rename owner/knowledge-base kb
+  kdex repos rename ~/projects/notes-2024 notes   Resolve the old name by path
+")]
+    Rename {
+        /// Current repository name or path
+        old: String,
+
+        /// New repository name
+        new: String,
+    },
+
+    /// Reveal a repository's directory in the file manager
+    #[command(
+        after_help = "Opens the directory with `open` (macOS), `explorer` (Windows) or
+`xdg-open`. Remote repositories open their clone directory. Without a
+graphical session or file manager the path is printed instead.
+
+Examples:
+  kdex repos open notes
+  cd \"$(kdex repos open notes)\"      On a headless machine
+"
+    )]
+    Open {
+        /// Repository name or path
+        name: String,
+    },
+}
+
 #[derive(Subcommand, Clone)]
 pub enum ConfigAction {
     /// Show current configuration
@@ -398,6 +1066,7 @@ pub enum ConfigAction {
   kdex config import kdex-config.yaml
   kdex config import kdex-config.yaml --merge
   cat config.yaml | kdex config import -
+  kdex config import kdex-config.yaml --remap /home/alice=/home/bob
 ")]
     Import {
         /// Input file (use '-' for stdin)
@@ -410,5 +1079,130 @@ pub enum ConfigAction {
         /// Skip cloning remote repositories
         #[arg(long)]
         skip_clone: bool,
+
+        /// Rewrite local repository paths starting with OLD to start with NEW (repeatable)
+        #[arg(long, value_parser = parse_remap, value_name = "OLD=NEW")]
+        remap: Vec<(PathBuf, PathBuf)>,
     },
 }
+
+/// Parse a `--since` value: a relative duration (`30m`, `2h`, `3d`, `1w`)
+/// or an absolute date (`2024-06-01`) / RFC 3339 timestamp
+fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in '{value}' (use s, m, h, d or w)"))?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration or date: '{value}'"))?;
+
+    let duration = match unit {
+        "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        "w" => chrono::Duration::try_weeks(amount),
+        _ => return Err(format!("unknown unit '{unit}' (use s, m, h, d or w)")),
+    }
+    .ok_or_else(|| format!("duration out of range: '{value}'"))?;
+
+    Ok(Utc::now() - duration)
+}
+
+/// Parse a `--min-score` relevance between 0 and 1
+fn parse_relevance(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|n| (0.0..=1.0).contains(n))
+        .ok_or_else(|| format!("expected a number between 0 and 1, got '{value}'"))
+}
+
+/// Parse a `--remap OLD=NEW` path prefix rewrite
+fn parse_remap(value: &str) -> Result<(PathBuf, PathBuf), String> {
+    match value.split_once('=') {
+        Some((old, new)) if !old.is_empty() && !new.is_empty() => {
+            Ok((PathBuf::from(old), PathBuf::from(new)))
+        }
+        _ => Err(format!("expected OLD=NEW, got '{value}'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remap() {
+        assert_eq!(
+            parse_remap("/home/alice=/home/bob").unwrap(),
+            (PathBuf::from("/home/alice"), PathBuf::from("/home/bob"))
+        );
+        assert!(parse_remap("/home/alice").is_err());
+        assert!(parse_remap("=/home/bob").is_err());
+        assert!(parse_remap("/home/alice=").is_err());
+    }
+
+    #[test]
+    fn test_parse_relevance() {
+        assert!((parse_relevance("0.5").unwrap() - 0.5).abs() < f64::EPSILON);
+        assert!(parse_relevance("0").is_ok());
+        assert!(parse_relevance("1").is_ok());
+        assert!(parse_relevance("1.5").is_err());
+        assert!(parse_relevance("-0.1").is_err());
+        assert!(parse_relevance("high").is_err());
+    }
+
+    #[test]
+    fn test_parse_since_duration() {
+        let cutoff = parse_since("2h").unwrap();
+        let expected = Utc::now() - chrono::Duration::hours(2);
+        assert!((cutoff - expected).num_seconds().abs() < 5);
+
+        assert!(parse_since("3d").unwrap() < parse_since("1d").unwrap());
+    }
+
+    #[test]
+    fn test_parse_since_date() {
+        let cutoff = parse_since("2024-06-01").unwrap();
+        assert_eq!(cutoff.to_rfc3339(), "2024-06-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_parse_since_invalid() {
+        assert!(parse_since("yesterday").is_err());
+        assert!(parse_since("5").is_err());
+        assert!(parse_since("5y").is_err());
+    }
+
+    #[test]
+    fn test_force_color_conflicts_with_no_color() {
+        assert!(Args::try_parse_from(["kdex", "--force-color", "list"]).is_ok());
+        assert!(Args::try_parse_from(["kdex", "list", "--force-color", "--no-color"]).is_err());
+    }
+
+    #[test]
+    fn test_export_embeddings_npy_needs_output() {
+        assert!(Args::try_parse_from(["kdex", "export-embeddings", "--format", "npy"]).is_err());
+        assert!(Args::try_parse_from([
+            "kdex",
+            "export-embeddings",
+            "--format",
+            "npy",
+            "-o",
+            "v.npy"
+        ])
+        .is_ok());
+        assert!(Args::try_parse_from(["kdex", "export-embeddings"]).is_ok());
+    }
+}
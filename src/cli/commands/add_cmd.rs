@@ -4,51 +4,82 @@ use indicatif::{ProgressBar, ProgressStyle};
 use owo_colors::OwoColorize;
 use std::path::Path;
 
-use crate::cli::args::Args;
+use crate::cli::args::{Args, ProgressFormat};
 use crate::config::Config;
-use crate::core::remote::{clone_repository, get_clone_path, parse_github_url};
-use crate::core::Indexer;
+use crate::core::remote::{clone_repository, get_clone_path, head_commit, parse_github_url};
+use crate::core::{resolve_root, truncate_chars_start};
 use crate::db::Database;
 use crate::error::Result;
 
-use super::{print_success, print_warning, use_colors};
+use super::watch::watch_repositories;
+use super::{
+    add_index_result_json, build_indexer, emit_progress_json, print_success, print_warning,
+    use_colors,
+};
 
 /// Run the add command
 #[allow(clippy::too_many_lines)]
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     path: Option<&Path>,
     remote: Option<&str>,
     branch: Option<&str>,
     shallow: bool,
     name: Option<String>,
+    watch: bool,
+    progress_format: ProgressFormat,
+    embed: Option<bool>,
     args: &Args,
 ) -> Result<()> {
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
     let config = Config::load()?;
     let db = Database::open()?;
 
     // Determine if this is a local or remote add
     if let Some(remote_url) = remote {
         add_remote(
-            &db, &config, remote_url, branch, shallow, name, args, colors,
+            &db,
+            &config,
+            remote_url,
+            branch,
+            shallow,
+            name,
+            progress_format,
+            embed,
+            args,
+            colors,
         )
     } else {
         // Default to current directory if no path specified
         let path = path.unwrap_or_else(|| Path::new("."));
-        add_local(&db, &config, path, name, args, colors)
+        add_local(
+            &db,
+            &config,
+            path,
+            name,
+            watch,
+            progress_format,
+            embed,
+            args,
+            colors,
+        )
     }
 }
 
 /// Add a local repository
+#[allow(clippy::too_many_arguments)]
 fn add_local(
     db: &Database,
     config: &Config,
     path: &Path,
     name: Option<String>,
+    watch: bool,
+    progress_format: ProgressFormat,
+    embed: Option<bool>,
     args: &Args,
     colors: bool,
 ) -> Result<()> {
-    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let canonical = resolve_root(path)?;
 
     if !args.quiet && !args.json {
         if colors {
@@ -75,9 +106,9 @@ fn add_local(
     }
 
     // Index the repository
-    let indexer = Indexer::new(db.clone(), config.clone());
+    let indexer = build_indexer(db.clone(), config.clone(), embed, args);
 
-    let progress_bar = if !args.quiet && !args.json {
+    let progress_bar = if !args.quiet && !args.json && progress_format == ProgressFormat::Bar {
         let pb = ProgressBar::new(0);
         pb.set_style(
             ProgressStyle::default_bar()
@@ -91,18 +122,13 @@ fn add_local(
     };
 
     let result = indexer.index(&canonical, name, |progress| {
+        if progress_format == ProgressFormat::Json {
+            emit_progress_json(progress);
+        }
         if let Some(pb) = &progress_bar {
             pb.set_length(progress.total_files as u64);
             pb.set_position(progress.processed_files as u64);
-            let display_file = if progress.current_file.len() > 40 {
-                format!(
-                    "...{}",
-                    &progress.current_file[progress.current_file.len() - 37..]
-                )
-            } else {
-                progress.current_file.clone()
-            };
-            pb.set_message(display_file);
+            pb.set_message(truncate_chars_start(&progress.current_file, 40, "..."));
         }
     })?;
 
@@ -111,16 +137,13 @@ fn add_local(
     }
 
     if args.json {
-        println!(
-            "{}",
-            serde_json::json!({
-                "success": true,
-                "type": "local",
-                "path": canonical.to_string_lossy(),
-                "files_added": result.files_added,
-                "files_updated": result.files_updated,
-            })
-        );
+        let mut output = serde_json::json!({
+            "success": true,
+            "type": "local",
+            "path": canonical.to_string_lossy(),
+        });
+        add_index_result_json(&mut output, &result);
+        println!("{output}");
     } else if !args.quiet {
         let total_files = result.files_added + result.files_updated + result.files_unchanged;
         print_success(
@@ -129,6 +152,15 @@ fn add_local(
         );
     }
 
+    if watch {
+        if let Some(repo) = db.get_repository_by_path(&canonical)? {
+            if !args.quiet && !args.json {
+                println!();
+            }
+            return watch_repositories(db, config, &[repo], None, args);
+        }
+    }
+
     Ok(())
 }
 
@@ -141,6 +173,8 @@ fn add_remote(
     branch: Option<&str>,
     shallow: bool,
     name: Option<String>,
+    progress_format: ProgressFormat,
+    embed: Option<bool>,
     args: &Args,
     colors: bool,
 ) -> Result<()> {
@@ -224,9 +258,9 @@ fn add_remote(
     }
 
     // Index the cloned repository
-    let indexer = Indexer::new(db.clone(), config.clone());
+    let indexer = build_indexer(db.clone(), config.clone(), embed, args);
 
-    let progress_bar = if !args.quiet && !args.json {
+    let progress_bar = if !args.quiet && !args.json && progress_format == ProgressFormat::Bar {
         let pb = ProgressBar::new(0);
         pb.set_style(
             ProgressStyle::default_bar()
@@ -240,18 +274,13 @@ fn add_remote(
     };
 
     let result = indexer.index(&clone_path, Some(repo_name.clone()), |progress| {
+        if progress_format == ProgressFormat::Json {
+            emit_progress_json(progress);
+        }
         if let Some(pb) = &progress_bar {
             pb.set_length(progress.total_files as u64);
             pb.set_position(progress.processed_files as u64);
-            let display_file = if progress.current_file.len() > 40 {
-                format!(
-                    "...{}",
-                    &progress.current_file[progress.current_file.len() - 37..]
-                )
-            } else {
-                progress.current_file.clone()
-            };
-            pb.set_message(display_file);
+            pb.set_message(truncate_chars_start(&progress.current_file, 40, "..."));
         }
     })?;
 
@@ -259,24 +288,24 @@ fn add_remote(
         pb.finish_and_clear();
     }
 
-    // Update sync time
+    // Update sync time and the commit the index reflects
+    let commit = head_commit(&clone_path);
     if let Some(repo) = db.get_repository_by_path(&clone_path)? {
         db.update_repository_synced(repo.id)?;
+        db.update_repository_commit(repo.id, commit.as_deref())?;
     }
 
     if args.json {
-        println!(
-            "{}",
-            serde_json::json!({
-                "success": true,
-                "type": "remote",
-                "url": url,
-                "path": clone_path.to_string_lossy(),
-                "owner": owner,
-                "repo": repo,
-                "files_added": result.files_added,
-            })
-        );
+        let mut output = serde_json::json!({
+            "success": true,
+            "type": "remote",
+            "url": url,
+            "path": clone_path.to_string_lossy(),
+            "owner": owner,
+            "repo": repo,
+        });
+        add_index_result_json(&mut output, &result);
+        println!("{output}");
     } else if !args.quiet {
         let total_files = result.files_added + result.files_updated + result.files_unchanged;
         print_success(
@@ -74,13 +74,19 @@ fn generate_mcp_config(tool: McpTool) -> serde_json::Value {
     }
 }
 
-pub fn run(tool: McpTool, json_output: bool) -> Result<()> {
-    let config_path = get_config_path(tool)?;
-    let tool_name = match tool {
+/// Display name of each tool
+pub fn tool_name(tool: McpTool) -> &'static str {
+    match tool {
         McpTool::Copilot => "GitHub Copilot CLI",
         McpTool::Gemini => "Gemini CLI",
         McpTool::Claude => "Claude Desktop",
-    };
+    }
+}
+
+/// Write the kdex server entry into the tool's MCP config.
+/// Returns the config path and whether kdex was already configured there.
+pub fn configure(tool: McpTool) -> Result<(PathBuf, bool)> {
+    let config_path = get_config_path(tool)?;
 
     // Ensure parent directory exists
     if let Some(parent) = config_path.parent() {
@@ -131,6 +137,13 @@ pub fn run(tool: McpTool, json_output: bool) -> Result<()> {
         ))
     })?;
 
+    Ok((config_path, already_configured))
+}
+
+pub fn run(tool: McpTool, json_output: bool) -> Result<()> {
+    let (config_path, already_configured) = configure(tool)?;
+    let tool_name = tool_name(tool);
+
     if json_output {
         let result = serde_json::json!({
             "success": true,
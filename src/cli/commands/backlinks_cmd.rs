@@ -8,11 +8,11 @@ use owo_colors::OwoColorize;
 use serde::Serialize;
 use std::path::Path;
 
-use super::use_colors;
+use super::{print_porcelain, use_colors};
 
 #[derive(Serialize)]
 struct BacklinkInfo {
-    file: String,
+    source: String,
     repo: String,
     link_text: String,
     line: Option<usize>,
@@ -29,7 +29,7 @@ struct BacklinksOutput {
 pub fn run(file: &Path, args: &Args) -> Result<()> {
     let db = Database::open()?;
     let _config = Config::load()?;
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
 
     // Normalize the file path - we'll search for files that contain links to this
     let target_name = file
@@ -54,7 +54,7 @@ pub fn run(file: &Path, args: &Args) -> Result<()> {
             backlinks: backlinks
                 .into_iter()
                 .map(|(file_path, repo_name, link_text, line)| BacklinkInfo {
-                    file: file_path,
+                    source: file_path,
                     repo: repo_name,
                     link_text,
                     line,
@@ -65,6 +65,14 @@ pub fn run(file: &Path, args: &Args) -> Result<()> {
         return Ok(());
     }
 
+    if args.porcelain {
+        for (file_path, repo_name, link_text, line) in &backlinks {
+            let line = line.map(|l| l.to_string()).unwrap_or_default();
+            print_porcelain(&[file_path, repo_name, &line, link_text]);
+        }
+        return Ok(());
+    }
+
     if backlinks.is_empty() {
         if !args.quiet {
             println!("No backlinks found for: {target_name}");
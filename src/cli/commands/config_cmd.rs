@@ -2,11 +2,12 @@ use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{self, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::cli::args::{Args, ConfigAction};
 use crate::config::Config;
 use crate::core::remote::{clone_repository, get_clone_path, parse_github_url};
+use crate::core::resolve_root;
 use crate::db::{Database, SourceType};
 use crate::error::{AppError, Result};
 
@@ -76,12 +77,13 @@ pub fn run(
                 file,
                 merge,
                 skip_clone,
-            } => run_import(&file, merge, skip_clone, args),
+                remap,
+            } => run_import(&file, merge, skip_clone, &remap, args),
         };
     }
 
     // Legacy behavior for backwards compatibility
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
     let config_path = Config::config_file_path()?;
 
     if reset {
@@ -113,6 +115,12 @@ pub fn run(
                         .parse()
                         .map_err(|_| AppError::Other("Invalid number".into()))?;
                 }
+                "watcher_poll_interval_secs" => {
+                    config.watcher_poll_interval_secs =
+                        value.parse().ok().filter(|secs| *secs > 0).ok_or_else(|| {
+                            AppError::Other("Invalid number (must be at least 1)".into())
+                        })?;
+                }
                 "batch_size" => {
                     config.batch_size = value
                         .parse()
@@ -133,13 +141,125 @@ pub fn run(
                         .parse()
                         .map_err(|_| AppError::Other("Invalid boolean".into()))?;
                 }
+                "binary_null_scan_bytes" => {
+                    config.binary_null_scan_bytes = value
+                        .parse()
+                        .map_err(|_| AppError::Other("Invalid number".into()))?;
+                }
+                "binary_detection" => {
+                    if !["null", "ratio"].contains(&value.as_str()) {
+                        return Err(AppError::Other(
+                            "Invalid binary detection. Must be: null or ratio".into(),
+                        ));
+                    }
+                    config.binary_detection.clone_from(&value);
+                }
+                "fts_tokenizer" => {
+                    let tokenizer = value.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if !Config::is_valid_fts_tokenizer(&tokenizer) {
+                        return Err(AppError::Other(
+                            "Invalid tokenizer. Must start with: porter, unicode61, ascii, or trigram, followed by plain options (e.g. \"unicode61 remove_diacritics 2\")".into(),
+                        ));
+                    }
+                    config.fts_tokenizer = tokenizer;
+                }
+                "min_content_chars" => {
+                    config.min_content_chars = value
+                        .parse()
+                        .map_err(|_| AppError::Other("Invalid number".into()))?;
+                }
+                "max_files" => {
+                    config.max_files = value
+                        .parse()
+                        .map_err(|_| AppError::Other("Invalid number".into()))?;
+                }
+                "max_total_mb" => {
+                    config.max_total_mb = value
+                        .parse()
+                        .map_err(|_| AppError::Other("Invalid number".into()))?;
+                }
+                "follow_symlinks" => {
+                    config.follow_symlinks = value
+                        .parse()
+                        .map_err(|_| AppError::Other("Invalid boolean".into()))?;
+                }
+                "index_hidden" => {
+                    config.index_hidden = value
+                        .parse()
+                        .map_err(|_| AppError::Other("Invalid boolean".into()))?;
+                }
+                "generated_file_patterns" => {
+                    config.generated_file_patterns = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|p| !p.is_empty())
+                        .map(String::from)
+                        .collect();
+                }
+                "max_avg_line_length" => {
+                    config.max_avg_line_length = value
+                        .parse()
+                        .map_err(|_| AppError::Other("Invalid number".into()))?;
+                }
+                "embedding_exclude_types" => {
+                    config.embedding_exclude_types = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty())
+                        .map(String::from)
+                        .collect();
+                }
                 "embedding_model" => {
                     config.embedding_model.clone_from(&value);
                 }
+                "default_search_limit" => {
+                    config.default_search_limit =
+                        value.parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+                            AppError::Other("Invalid number (must be > 0)".into())
+                        })?;
+                }
+                "preview_max_kb" => {
+                    config.preview_max_kb =
+                        value.parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+                            AppError::Other("Invalid number (must be > 0)".into())
+                        })?;
+                }
+                "min_lexical_score" => {
+                    config.min_lexical_score = value
+                        .parse()
+                        .ok()
+                        .filter(|n| (0.0..=1.0).contains(n))
+                        .ok_or_else(|| {
+                            AppError::Other("Invalid number (must be between 0 and 1)".into())
+                        })?;
+                }
+                "log_queries" => {
+                    config.log_queries = value
+                        .parse()
+                        .map_err(|_| AppError::Other("Invalid boolean".into()))?;
+                }
+                "model_cache_dir" => {
+                    // An empty value falls back to fastembed's default cache
+                    config.model_cache_dir = Some(value.trim())
+                        .filter(|v| !v.is_empty())
+                        .map(PathBuf::from);
+                }
+                "clone_dir" => {
+                    // An empty value falls back to the config directory
+                    config.clone_dir = Some(value.trim())
+                        .filter(|v| !v.is_empty())
+                        .map(PathBuf::from);
+                }
+                "post_index_command" => {
+                    // An empty value clears the hook
+                    config.post_index_command =
+                        Some(value.clone()).filter(|v| !v.trim().is_empty());
+                }
                 "default_search_mode" => {
-                    if !["lexical", "semantic", "hybrid"].contains(&value.as_str()) {
+                    if !["lexical", "semantic", "hybrid", "auto"].contains(&value.as_str()) {
                         return Err(AppError::Other(
-                            "Invalid search mode. Must be: lexical, semantic, or hybrid".into(),
+                            "Invalid search mode. Must be: lexical, semantic, hybrid, or auto"
+                                .into(),
                         ));
                     }
                     config.default_search_mode.clone_from(&value);
@@ -152,6 +272,9 @@ pub fn run(
             if !args.quiet {
                 println!("Set {key} = {value}");
             }
+            if key == "fts_tokenizer" {
+                retokenize_index(&config.fts_tokenizer, args)?;
+            }
         } else {
             // Show single value
             let config = Config::load()?;
@@ -159,12 +282,37 @@ pub fn run(
                 "max_file_size_mb" => config.max_file_size_mb.to_string(),
                 "color_enabled" => config.color_enabled.to_string(),
                 "watcher_debounce_ms" => config.watcher_debounce_ms.to_string(),
+                "watcher_poll_interval_secs" => config.watcher_poll_interval_secs.to_string(),
                 "batch_size" => config.batch_size.to_string(),
                 "enable_semantic_search" => config.enable_semantic_search.to_string(),
                 "strip_markdown_syntax" => config.strip_markdown_syntax.to_string(),
                 "index_code_blocks" => config.index_code_blocks.to_string(),
+                "binary_null_scan_bytes" => config.binary_null_scan_bytes.to_string(),
+                "binary_detection" => config.binary_detection,
+                "fts_tokenizer" => config.fts_tokenizer,
+                "min_content_chars" => config.min_content_chars.to_string(),
+                "max_files" => config.max_files.to_string(),
+                "max_total_mb" => config.max_total_mb.to_string(),
+                "follow_symlinks" => config.follow_symlinks.to_string(),
+                "index_hidden" => config.index_hidden.to_string(),
+                "generated_file_patterns" => config.generated_file_patterns.join(","),
+                "max_avg_line_length" => config.max_avg_line_length.to_string(),
+                "embedding_exclude_types" => config.embedding_exclude_types.join(","),
                 "embedding_model" => config.embedding_model,
+                "model_cache_dir" => config
+                    .model_cache_dir
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default(),
+                "clone_dir" => config
+                    .clone_dir
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default(),
                 "default_search_mode" => config.default_search_mode,
+                "default_search_limit" => config.default_search_limit.to_string(),
+                "preview_max_kb" => config.preview_max_kb.to_string(),
+                "log_queries" => config.log_queries.to_string(),
+                "min_lexical_score" => config.min_lexical_score.to_string(),
+                "post_index_command" => config.post_index_command.unwrap_or_default(),
                 _ => {
                     return Err(AppError::Other(format!("Unknown config key: {key}")));
                 }
@@ -178,12 +326,30 @@ pub fn run(
     run_show_internal(&config_path, args, colors)
 }
 
+/// Re-tokenize the full-text index after `fts_tokenizer` changed; the stored
+/// content is carried over, so no re-index from disk is needed
+fn retokenize_index(tokenizer: &str, args: &Args) -> Result<()> {
+    let db = Database::open()?;
+    if db.fts_tokenizer()?.as_deref() == Some(tokenizer) {
+        return Ok(());
+    }
+    if !args.quiet {
+        println!("Rebuilding the full-text index with the {tokenizer} tokenizer...");
+    }
+    let files = db.rebuild_fts(tokenizer)?;
+    if !args.quiet {
+        print_success(&format!("Re-tokenized {files} files"), use_colors(args));
+    }
+    Ok(())
+}
+
 fn run_show(args: &Args) -> Result<()> {
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
     let config_path = Config::config_file_path()?;
     run_show_internal(&config_path, args, colors)
 }
 
+#[allow(clippy::too_many_lines)]
 fn run_show_internal(config_path: &Path, args: &Args, colors: bool) -> Result<()> {
     let config = Config::load()?;
 
@@ -195,14 +361,33 @@ fn run_show_internal(config_path: &Path, args: &Args, colors: bool) -> Result<()
                 "config": {
                     "max_file_size_mb": config.max_file_size_mb,
                     "ignore_patterns": config.ignore_patterns,
+                    "generated_file_patterns": config.generated_file_patterns,
+                    "max_avg_line_length": config.max_avg_line_length,
                     "color_enabled": config.color_enabled,
                     "watcher_debounce_ms": config.watcher_debounce_ms,
+                    "watcher_poll_interval_secs": config.watcher_poll_interval_secs,
                     "batch_size": config.batch_size,
                     "enable_semantic_search": config.enable_semantic_search,
                     "embedding_model": config.embedding_model,
                     "default_search_mode": config.default_search_mode,
+                    "default_search_limit": config.default_search_limit,
+                    "preview_max_kb": config.preview_max_kb,
+                    "min_lexical_score": config.min_lexical_score,
+                    "log_queries": config.log_queries,
                     "strip_markdown_syntax": config.strip_markdown_syntax,
                     "index_code_blocks": config.index_code_blocks,
+                    "binary_null_scan_bytes": config.binary_null_scan_bytes,
+                    "binary_detection": config.binary_detection,
+                    "fts_tokenizer": config.fts_tokenizer,
+                    "min_content_chars": config.min_content_chars,
+                    "max_files": config.max_files,
+                    "max_total_mb": config.max_total_mb,
+                    "follow_symlinks": config.follow_symlinks,
+                    "index_hidden": config.index_hidden,
+                    "embedding_exclude_types": config.embedding_exclude_types,
+                    "post_index_command": config.post_index_command,
+                    "model_cache_dir": config.model_cache_dir,
+                    "clone_dir": config.clone_dir,
                 }
             })
         );
@@ -218,14 +403,57 @@ fn run_show_internal(config_path: &Path, args: &Args, colors: bool) -> Result<()
         println!("Config file: {}", config_path.display());
         println!();
         println!("max_file_size_mb: {}", config.max_file_size_mb);
+        println!(
+            "generated_file_patterns: {}",
+            config.generated_file_patterns.join(", ")
+        );
+        println!("max_avg_line_length: {}", config.max_avg_line_length);
         println!("color_enabled: {}", config.color_enabled);
         println!("watcher_debounce_ms: {}", config.watcher_debounce_ms);
+        println!(
+            "watcher_poll_interval_secs: {}",
+            config.watcher_poll_interval_secs
+        );
         println!("batch_size: {}", config.batch_size);
         println!("enable_semantic_search: {}", config.enable_semantic_search);
         println!("embedding_model: {}", config.embedding_model);
+        println!(
+            "model_cache_dir: {}",
+            config.model_cache_dir.as_ref().map_or_else(
+                || "(fastembed default)".to_string(),
+                |dir| dir.display().to_string()
+            )
+        );
+        println!(
+            "clone_dir: {}",
+            config.clone_dir.as_ref().map_or_else(
+                || "(config directory)".to_string(),
+                |dir| dir.display().to_string()
+            )
+        );
         println!("default_search_mode: {}", config.default_search_mode);
+        println!("default_search_limit: {}", config.default_search_limit);
+        println!("preview_max_kb: {}", config.preview_max_kb);
+        println!("min_lexical_score: {}", config.min_lexical_score);
+        println!("log_queries: {}", config.log_queries);
         println!("strip_markdown_syntax: {}", config.strip_markdown_syntax);
         println!("index_code_blocks: {}", config.index_code_blocks);
+        println!("binary_null_scan_bytes: {}", config.binary_null_scan_bytes);
+        println!("binary_detection: {}", config.binary_detection);
+        println!("fts_tokenizer: {}", config.fts_tokenizer);
+        println!("min_content_chars: {}", config.min_content_chars);
+        println!("max_files: {}", config.max_files);
+        println!("max_total_mb: {}", config.max_total_mb);
+        println!("follow_symlinks: {}", config.follow_symlinks);
+        println!("index_hidden: {}", config.index_hidden);
+        println!(
+            "embedding_exclude_types: {}",
+            config.embedding_exclude_types.join(", ")
+        );
+        println!(
+            "post_index_command: {}",
+            config.post_index_command.as_deref().unwrap_or("(none)")
+        );
         println!();
         println!("ignore_patterns:");
         for pattern in &config.ignore_patterns {
@@ -254,7 +482,7 @@ fn run_export(
     format: &str,
     args: &Args,
 ) -> Result<()> {
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
     let config = Config::load()?;
     let db = Database::open()?;
     let repos = db.list_repositories()?;
@@ -293,6 +521,8 @@ fn run_export(
                     });
                 }
             }
+            // Stdin content has no source to re-import from
+            SourceType::Stdin => {}
         }
     }
 
@@ -317,9 +547,35 @@ fn run_export(
     Ok(())
 }
 
+/// Apply the longest matching `--remap` prefix to an exported local path.
+/// Prefixes match whole path components, so `/home/al` does not touch `/home/alice`.
+fn remap_path(path: &Path, remaps: &[(PathBuf, PathBuf)]) -> PathBuf {
+    remaps
+        .iter()
+        .filter_map(|(old, new)| {
+            path.strip_prefix(old)
+                .ok()
+                .map(|rest| (old.components().count(), new.join(rest)))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map_or_else(|| path.to_path_buf(), |(_, remapped)| remapped)
+}
+
+/// Canonical directory an exported local path refers to on this machine, so a
+/// trailing slash, `..` or a symlink finds the repository it was indexed as
+fn import_local_path(path: &str, remaps: &[(PathBuf, PathBuf)]) -> Result<PathBuf> {
+    resolve_root(&remap_path(Path::new(path), remaps))
+}
+
 #[allow(clippy::too_many_lines)]
-fn run_import(file: &Path, merge: bool, skip_clone: bool, args: &Args) -> Result<()> {
-    let colors = use_colors(args.no_color);
+fn run_import(
+    file: &Path,
+    merge: bool,
+    skip_clone: bool,
+    remaps: &[(PathBuf, PathBuf)],
+    args: &Args,
+) -> Result<()> {
+    let colors = use_colors(args);
 
     // Read input
     let content = if file.to_string_lossy() == "-" {
@@ -448,24 +704,23 @@ fn run_import(file: &Path, merge: bool, skip_clone: bool, args: &Args) -> Result
             }
             "local" => {
                 if let Some(path) = &repo.path {
-                    let path = Path::new(path);
-                    if !path.exists() {
-                        if !args.quiet && !args.json {
-                            print_warning(
-                                &format!("Local path not found: {}", path.display()),
-                                colors,
-                            );
+                    let path = match import_local_path(path, remaps) {
+                        Ok(path) => path,
+                        Err(e) => {
+                            if !args.quiet && !args.json {
+                                print_warning(&format!("Skipping local repository: {e}"), colors);
+                            }
+                            skipped += 1;
+                            continue;
                         }
-                        skipped += 1;
-                        continue;
-                    }
+                    };
 
-                    if db.get_repository_by_path(path)?.is_some() {
+                    if db.get_repository_by_path(&path)?.is_some() {
                         skipped += 1;
                         continue;
                     }
 
-                    db.add_repository(path, repo.name.clone())?;
+                    db.add_repository(&path, repo.name.clone())?;
                     added += 1;
                 }
             }
@@ -497,3 +752,75 @@ fn run_import(file: &Path, merge: bool, skip_clone: bool, args: &Args) -> Result
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remaps(pairs: &[(&str, &str)]) -> Vec<(PathBuf, PathBuf)> {
+        pairs
+            .iter()
+            .map(|(old, new)| (PathBuf::from(old), PathBuf::from(new)))
+            .collect()
+    }
+
+    #[test]
+    fn test_remap_path_longest_component_prefix() {
+        let remaps = remaps(&[
+            ("/home/al", "/home/x"),
+            ("/home/alice", "/home/bob"),
+            ("/home/alice/work", "/srv/work"),
+        ]);
+        assert_eq!(
+            remap_path(Path::new("/home/alice/notes"), &remaps),
+            PathBuf::from("/home/bob/notes")
+        );
+        assert_eq!(
+            remap_path(Path::new("/home/alice/work/api"), &remaps),
+            PathBuf::from("/srv/work/api")
+        );
+        assert_eq!(
+            remap_path(Path::new("/home/alice"), &remaps),
+            PathBuf::from("/home/bob")
+        );
+        // Not a whole-component match, and no match at all
+        assert_eq!(
+            remap_path(Path::new("/home/alfred/notes"), &remaps),
+            PathBuf::from("/home/alfred/notes")
+        );
+        assert_eq!(
+            remap_path(Path::new("/opt/notes"), &remaps),
+            PathBuf::from("/opt/notes")
+        );
+    }
+
+    #[test]
+    fn test_import_local_path_finds_existing_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = dir.path().join("notes");
+        fs::create_dir(&notes).unwrap();
+        let db = Database::open_in_memory().unwrap();
+        let repo = db.add_repository(&notes, None).unwrap();
+        fs::create_dir(dir.path().join("other")).unwrap();
+
+        let remaps = vec![(PathBuf::from("/home/alice"), dir.path().to_path_buf())];
+        for exported in ["/home/alice/notes/", "/home/alice/other/../notes"] {
+            let path = import_local_path(exported, &remaps).unwrap();
+            assert_eq!(path, repo.path, "{exported}");
+            let found = db.get_repository_by_path(&path).unwrap().unwrap();
+            assert_eq!(found.id, repo.id);
+        }
+
+        assert!(matches!(
+            import_local_path("/home/alice/missing", &remaps),
+            Err(AppError::PathNotFound(_))
+        ));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&notes, dir.path().join("link")).unwrap();
+            let path = import_local_path("/home/alice/link", &remaps).unwrap();
+            assert_eq!(path, repo.path);
+        }
+    }
+}
@@ -1,31 +1,41 @@
 //! Context builder command for AI prompts.
 
-use crate::cli::args::Args;
+use crate::cli::args::{Args, ContextStrategy};
 use crate::config::Config;
-use crate::core::{Embedder, SearchMode, Searcher};
+use crate::core::{
+    mmr_order, DiversityItem, Embedder, IndexedFileReader, SearchMode, Searcher,
+    UnifiedSearchResult, MMR_LAMBDA,
+};
 use crate::db::Database;
 use crate::error::Result;
 use owo_colors::OwoColorize;
 use serde::Serialize;
-use std::fs;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
 
-use super::use_colors;
+use super::{finish_output, open_output, use_colors, warn_missing_files};
 
+/// A single file included in a context bundle
 #[derive(Serialize)]
-struct ContextFile {
-    path: String,
+struct ContextEntry {
     repo: String,
+    path: String,
+    score: f64,
     content: String,
-    tokens_approx: usize,
+    token_estimate: usize,
 }
 
+/// Context bundle emitted by `--format json` / `--json`
 #[derive(Serialize)]
-struct ContextOutput {
+struct ContextBundle {
     query: String,
+    strategy: &'static str,
+    entries: Vec<ContextEntry>,
     files_included: usize,
-    total_tokens_approx: usize,
-    context: String,
-    files: Vec<ContextFile>,
+    total_tokens: usize,
+    /// True when content was cut short or files were dropped to fit the token budget
+    truncated: bool,
 }
 
 /// Approximate token count (roughly 4 chars per token)
@@ -33,16 +43,52 @@ fn estimate_tokens(text: &str) -> usize {
     text.len() / 4
 }
 
+/// Reorder ranked results by maximal marginal relevance, comparing files by
+/// their stored embeddings where available and by location otherwise
+fn diversify(db: &Database, results: Vec<UnifiedSearchResult>) -> Vec<UnifiedSearchResult> {
+    let items: Vec<DiversityItem> = results
+        .iter()
+        .map(|r| DiversityItem {
+            repo: &r.repo_name,
+            path: &r.file_path,
+            embedding: db.file_embedding(&r.repo_path, &r.file_path).ok().flatten(),
+        })
+        .collect();
+    let order = mmr_order(&items, MMR_LAMBDA);
+
+    let mut results: Vec<Option<UnifiedSearchResult>> = results.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|i| results[i].take())
+        .collect()
+}
+
 /// Build context from search results for AI prompts
+#[allow(clippy::too_many_arguments)]
 #[allow(clippy::too_many_lines)]
-pub fn run(query: &str, limit: usize, max_tokens: usize, format: &str, args: &Args) -> Result<()> {
+pub fn run(
+    query: &str,
+    repo: Option<&str>,
+    file_type: Option<&str>,
+    limit: usize,
+    max_tokens: usize,
+    format: &str,
+    strategy: ContextStrategy,
+    max_per_repo: usize,
+    output_path: Option<&Path>,
+    args: &Args,
+) -> Result<()> {
     let db = Database::open()?;
+    let embeddings_db = db.clone();
+    let reader = IndexedFileReader::new(db.clone());
     let config = Config::load()?;
-    let colors = use_colors(args.no_color);
+    // File output is never colored
+    let colors = use_colors(args) && output_path.is_none();
+    let mut out = open_output(output_path)?;
 
     // Create searcher with embedder if available
     let searcher = if config.enable_semantic_search {
-        match Embedder::new(&config.embedding_model) {
+        match Embedder::new(&config.embedding_model, config.model_cache_dir().as_deref()) {
             Ok(embedder) => Searcher::with_embedder(db, embedder),
             Err(_) => Searcher::new(db),
         }
@@ -50,39 +96,57 @@ pub fn run(query: &str, limit: usize, max_tokens: usize, format: &str, args: &Ar
         Searcher::new(db)
     };
 
-    // Search for relevant files
-    let results =
-        searcher.search_with_mode(query, SearchMode::Lexical, None, None, limit * 2, 0)?;
+    // Search for relevant files; filters apply before the token budget below.
+    // Diverse and per-repo selection skip files, so they draw from a larger pool.
+    let pool = match strategy {
+        ContextStrategy::TopK => limit.saturating_mul(2),
+        ContextStrategy::Diverse | ContextStrategy::PerRepo => limit.saturating_mul(4),
+    };
+    let mut results =
+        searcher.search_with_mode(query, SearchMode::Lexical, repo, file_type, pool, 0)?;
+    if strategy == ContextStrategy::Diverse {
+        results = diversify(&embeddings_db, results);
+    }
+    let max_per_repo = match strategy {
+        ContextStrategy::PerRepo => max_per_repo.max(1),
+        ContextStrategy::TopK | ContextStrategy::Diverse => usize::MAX,
+    };
 
     if results.is_empty() {
-        if args.json {
-            let output = ContextOutput {
+        if args.json || format == "json" {
+            let bundle = ContextBundle {
                 query: query.to_string(),
+                strategy: strategy.as_str(),
+                entries: vec![],
                 files_included: 0,
-                total_tokens_approx: 0,
-                context: String::new(),
-                files: vec![],
+                total_tokens: 0,
+                truncated: false,
             };
-            println!("{}", serde_json::to_string_pretty(&output)?);
+            writeln!(out, "{}", serde_json::to_string_pretty(&bundle)?)?;
         } else if !args.quiet {
-            println!("No results found for: {query}");
+            writeln!(out, "No results found for: {query}")?;
         }
-        return Ok(());
+        return finish_output(out, output_path, args);
     }
 
     // Build context respecting token limits
     let mut context_parts: Vec<String> = Vec::new();
-    let mut files: Vec<ContextFile> = Vec::new();
+    let mut entries: Vec<ContextEntry> = Vec::new();
     let mut total_tokens = 0;
     let mut files_included = 0;
+    let mut truncated = false;
+    let mut files_per_repo: HashMap<String, usize> = HashMap::new();
 
     for result in results {
         if files_included >= limit {
             break;
         }
+        if files_per_repo.get(&result.repo_name).copied().unwrap_or(0) >= max_per_repo {
+            continue;
+        }
 
-        // Try to read the full file content
-        let Ok(content) = fs::read_to_string(&result.absolute_path) else {
+        // Files gone from disk are left out rather than bundled with stale content
+        let Some(content) = reader.read_from_disk(&result.absolute_path) else {
             continue;
         };
 
@@ -90,6 +154,8 @@ pub fn run(query: &str, limit: usize, max_tokens: usize, format: &str, args: &Ar
 
         // Check if adding this file would exceed the limit
         if total_tokens + file_tokens > max_tokens && files_included > 0 {
+            truncated = true;
+
             // Try to include a truncated version
             let remaining_tokens = max_tokens.saturating_sub(total_tokens);
             if remaining_tokens > 100 {
@@ -100,11 +166,12 @@ pub fn run(query: &str, limit: usize, max_tokens: usize, format: &str, args: &Ar
                 let header = format!("## {}/{}\n\n", result.repo_name, result.file_path.display());
                 context_parts.push(format!("{header}{truncated_content}"));
 
-                files.push(ContextFile {
-                    path: result.file_path.display().to_string(),
+                entries.push(ContextEntry {
                     repo: result.repo_name.clone(),
+                    path: result.file_path.display().to_string(),
+                    score: result.score,
                     content: truncated_content,
-                    tokens_approx: remaining_tokens,
+                    token_estimate: remaining_tokens,
                 });
 
                 total_tokens += remaining_tokens;
@@ -112,80 +179,75 @@ pub fn run(query: &str, limit: usize, max_tokens: usize, format: &str, args: &Ar
             }
             break;
         }
+        *files_per_repo.entry(result.repo_name.clone()).or_default() += 1;
 
         // Add full file content
         let header = format!("## {}/{}\n\n", result.repo_name, result.file_path.display());
         context_parts.push(format!("{header}{content}"));
 
-        files.push(ContextFile {
-            path: result.file_path.display().to_string(),
+        entries.push(ContextEntry {
             repo: result.repo_name,
+            path: result.file_path.display().to_string(),
+            score: result.score,
             content,
-            tokens_approx: file_tokens,
+            token_estimate: file_tokens,
         });
 
         total_tokens += file_tokens;
         files_included += 1;
     }
 
+    warn_missing_files(&reader, args);
+
     let context = context_parts.join("\n---\n\n");
 
     // Output based on format
-    match format {
-        "json" => {
-            let output = ContextOutput {
-                query: query.to_string(),
-                files_included,
-                total_tokens_approx: total_tokens,
-                context,
-                files,
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
-        }
-        "text" => {
-            println!("{context}");
+    if args.json || format == "json" {
+        let bundle = ContextBundle {
+            query: query.to_string(),
+            strategy: strategy.as_str(),
+            entries,
+            files_included,
+            total_tokens,
+            truncated,
+        };
+        writeln!(out, "{}", serde_json::to_string_pretty(&bundle)?)?;
+        return finish_output(out, output_path, args);
+    }
+
+    if format == "text" {
+        writeln!(out, "{context}")?;
+        return finish_output(out, output_path, args);
+    }
+
+    // Print header with stats
+    if !args.quiet {
+        if colors {
+            writeln!(out, "{} {}", "Context for:".bold(), query.cyan())?;
+            writeln!(
+                out,
+                "{} files, ~{} tokens",
+                files_included.to_string().green(),
+                total_tokens.to_string().green()
+            )?;
+            writeln!(out, "{}", "─".repeat(50).dimmed())?;
+            writeln!(out)?;
+        } else {
+            writeln!(out, "Context for: {query}")?;
+            writeln!(out, "{files_included} files, ~{total_tokens} tokens")?;
+            writeln!(out, "{}", "─".repeat(50))?;
+            writeln!(out)?;
         }
-        _ => {
-            if args.json {
-                let output = ContextOutput {
-                    query: query.to_string(),
-                    files_included,
-                    total_tokens_approx: total_tokens,
-                    context: context.clone(),
-                    files,
-                };
-                println!("{}", serde_json::to_string_pretty(&output)?);
-            } else {
-                // Print header with stats
-                if !args.quiet {
-                    if colors {
-                        println!("{} {}", "Context for:".bold(), query.cyan());
-                        println!(
-                            "{} files, ~{} tokens",
-                            files_included.to_string().green(),
-                            total_tokens.to_string().green()
-                        );
-                        println!("{}", "─".repeat(50).dimmed());
-                        println!();
-                    } else {
-                        println!("Context for: {query}");
-                        println!("{files_included} files, ~{total_tokens} tokens");
-                        println!("{}", "─".repeat(50));
-                        println!();
-                    }
-                }
-
-                println!("{context}");
-
-                if !args.quiet {
-                    println!();
-                    if colors {
-                        println!("{}", "Tip: Pipe to clipboard or AI tool".dimmed());
-                    }
-                }
-            }
+    }
+
+    writeln!(out, "{context}")?;
+
+    if !args.quiet {
+        writeln!(out)?;
+        if colors {
+            writeln!(out, "{}", "Tip: Pipe to clipboard or AI tool".dimmed())?;
         }
     }
 
-    Ok(())
+    finish_output(out, output_path, args)
 }
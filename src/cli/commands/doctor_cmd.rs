@@ -0,0 +1,320 @@
+//! Doctor command - diagnose the local kdex environment.
+
+use crate::cli::args::Args;
+use crate::config::Config;
+use crate::core::{check_inotify_limit, estimate_directory_count, Embedder};
+use crate::db::{Database, Repository, SourceType, SCHEMA_VERSION};
+use crate::error::{AppError, Result};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use super::use_colors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize)]
+struct Check {
+    name: &'static str,
+    status: CheckStatus,
+    message: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DoctorReport {
+    checks: Vec<Check>,
+    passed: usize,
+    warnings: usize,
+    failed: usize,
+}
+
+/// Run environment diagnostics
+pub fn run(args: &Args) -> Result<()> {
+    let colors = use_colors(args);
+    let mut checks = Vec::new();
+
+    let config = check_config(&mut checks);
+    let db = check_database(&mut checks);
+    let repos = db
+        .as_ref()
+        .and_then(|db| db.list_repositories().ok())
+        .unwrap_or_default();
+
+    checks.push(check_fts_tokenizer(config.as_ref(), db.as_ref()));
+    checks.push(check_embedding_model(config.as_ref()));
+    checks.push(check_watch_limits(&repos));
+    checks.push(check_git_credentials(&repos));
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let report = DoctorReport {
+        passed: count(CheckStatus::Pass),
+        warnings: count(CheckStatus::Warn),
+        failed: count(CheckStatus::Fail),
+        checks,
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if !args.quiet {
+        for check in &report.checks {
+            print_check(check, colors);
+        }
+        println!();
+        println!(
+            "{} passed, {} warning{}, {} failed",
+            report.passed,
+            report.warnings,
+            if report.warnings == 1 { "" } else { "s" },
+            report.failed
+        );
+    }
+
+    if report.failed > 0 {
+        return Err(AppError::Reported(format!(
+            "{} check{} failed",
+            report.failed,
+            if report.failed == 1 { "" } else { "s" }
+        )));
+    }
+
+    Ok(())
+}
+
+fn print_check(check: &Check, colors: bool) {
+    let label = match check.status {
+        CheckStatus::Pass => "pass",
+        CheckStatus::Warn => "warn",
+        CheckStatus::Fail => "fail",
+    };
+
+    if colors {
+        let label = match check.status {
+            CheckStatus::Pass => label.green().to_string(),
+            CheckStatus::Warn => label.yellow().to_string(),
+            CheckStatus::Fail => label.red().to_string(),
+        };
+        println!("[{label}] {}: {}", check.name.bold(), check.message);
+    } else {
+        println!("[{label}] {}: {}", check.name, check.message);
+    }
+}
+
+/// Config file exists and parses
+fn check_config(checks: &mut Vec<Check>) -> Option<Config> {
+    let path = match Config::config_file_path() {
+        Ok(path) => path,
+        Err(e) => {
+            checks.push(Check::new("config", CheckStatus::Fail, e.to_string()));
+            return None;
+        }
+    };
+
+    if !path.exists() {
+        checks.push(Check::new(
+            "config",
+            CheckStatus::Warn,
+            format!("{} not found, using defaults", path.display()),
+        ));
+        return Some(Config::default());
+    }
+
+    let parsed = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| toml::from_str::<Config>(&content).map_err(|e| e.to_string()));
+
+    match parsed {
+        Ok(config) => {
+            checks.push(Check::new(
+                "config",
+                CheckStatus::Pass,
+                format!("{} is valid", path.display()),
+            ));
+            Some(config)
+        }
+        Err(e) => {
+            checks.push(Check::new(
+                "config",
+                CheckStatus::Fail,
+                format!("{}: {e}", path.display()),
+            ));
+            None
+        }
+    }
+}
+
+/// Database exists and is on the current schema. The version is read before
+/// opening, since opening creates a missing database and migrates an old one.
+fn check_database(checks: &mut Vec<Check>) -> Option<Database> {
+    let problem = match Database::stored_schema_version() {
+        Ok(Some(version)) if version == SCHEMA_VERSION => None,
+        Ok(None) => Some((
+            CheckStatus::Warn,
+            "no index yet; it is created by the first `kdex index`".to_string(),
+        )),
+        Ok(Some(version)) if version < SCHEMA_VERSION => Some((
+            CheckStatus::Warn,
+            format!(
+                "schema v{version} needs migration to v{SCHEMA_VERSION}; \
+                 it runs when another kdex command opens the index"
+            ),
+        )),
+        Ok(Some(version)) => Some((
+            CheckStatus::Fail,
+            format!("schema v{version} is newer than this kdex supports (v{SCHEMA_VERSION}); upgrade kdex"),
+        )),
+        Err(e) => Some((CheckStatus::Fail, e.to_string())),
+    };
+    if let Some((status, message)) = problem {
+        checks.push(Check::new("database", status, message));
+        return None;
+    }
+
+    match Database::open() {
+        Ok(db) => {
+            let repo_count = db.list_repositories().map_or(0, |r| r.len());
+            checks.push(Check::new(
+                "database",
+                CheckStatus::Pass,
+                format!("schema v{SCHEMA_VERSION}, {repo_count} repositories"),
+            ));
+            Some(db)
+        }
+        Err(e) => {
+            checks.push(Check::new("database", CheckStatus::Fail, e.to_string()));
+            None
+        }
+    }
+}
+
+/// The full-text index uses the configured `fts_tokenizer`
+fn check_fts_tokenizer(config: Option<&Config>, db: Option<&Database>) -> Check {
+    let (Some(config), Some(db)) = (config, db) else {
+        return Check::new(
+            "fts tokenizer",
+            CheckStatus::Warn,
+            "skipped (config or database could not be loaded)",
+        );
+    };
+
+    match db.fts_tokenizer() {
+        Ok(Some(tokenizer)) if tokenizer == config.fts_tokenizer => {
+            Check::new("fts tokenizer", CheckStatus::Pass, tokenizer)
+        }
+        Ok(current) => Check::new(
+            "fts tokenizer",
+            CheckStatus::Warn,
+            format!(
+                "index uses {}, config says {}; rebuild with: kdex config fts_tokenizer \"{}\"",
+                current.as_deref().unwrap_or("unknown"),
+                config.fts_tokenizer,
+                config.fts_tokenizer
+            ),
+        ),
+        Err(e) => Check::new("fts tokenizer", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+/// Embedding model loads when semantic search is enabled
+fn check_embedding_model(config: Option<&Config>) -> Check {
+    let Some(config) = config else {
+        return Check::new(
+            "embedding model",
+            CheckStatus::Warn,
+            "skipped (config could not be loaded)",
+        );
+    };
+
+    if !config.enable_semantic_search {
+        return Check::new(
+            "embedding model",
+            CheckStatus::Pass,
+            "semantic search disabled",
+        );
+    }
+
+    match Embedder::new(&config.embedding_model, config.model_cache_dir().as_deref()) {
+        Ok(_) => Check::new(
+            "embedding model",
+            CheckStatus::Pass,
+            format!("{} loaded", config.embedding_model),
+        ),
+        Err(e) => Check::new("embedding model", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+/// Platform file watch limits are enough for the indexed repositories
+fn check_watch_limits(repos: &[Repository]) -> Check {
+    let total_dirs: usize = repos
+        .iter()
+        .filter_map(|r| estimate_directory_count(&r.path).ok())
+        .sum();
+
+    let limits = check_inotify_limit(total_dirs);
+    if let Some(warning) = limits.warning {
+        let summary = warning.lines().next().unwrap_or_default().to_string();
+        return Check::new("watch limits", CheckStatus::Warn, summary);
+    }
+
+    match limits.current_limit {
+        Some(limit) => Check::new(
+            "watch limits",
+            CheckStatus::Pass,
+            format!("~{total_dirs} directories, limit {limit}"),
+        ),
+        None => Check::new("watch limits", CheckStatus::Pass, "no limit detected"),
+    }
+}
+
+/// Credentials are available for cloning/syncing remote repositories
+fn check_git_credentials(repos: &[Repository]) -> Check {
+    let remote_count = repos
+        .iter()
+        .filter(|r| r.source_type == SourceType::Remote)
+        .count();
+
+    let has_token =
+        std::env::var("KDEX_GITHUB_TOKEN").is_ok() || std::env::var("GITHUB_TOKEN").is_ok();
+    let has_ssh_agent = std::env::var("SSH_AUTH_SOCK").is_ok();
+
+    if has_token || has_ssh_agent {
+        let source = if has_token {
+            "GitHub token"
+        } else {
+            "SSH agent"
+        };
+        return Check::new(
+            "git credentials",
+            CheckStatus::Pass,
+            format!("{source} available"),
+        );
+    }
+
+    if remote_count == 0 {
+        Check::new(
+            "git credentials",
+            CheckStatus::Pass,
+            "none configured (no remote repositories)",
+        )
+    } else {
+        Check::new(
+            "git credentials",
+            CheckStatus::Warn,
+            "no GITHUB_TOKEN or SSH agent; only public remotes can be synced",
+        )
+    }
+}
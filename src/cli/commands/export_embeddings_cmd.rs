@@ -0,0 +1,131 @@
+//! Export embeddings command - stream stored chunk vectors to JSONL or `.npy`.
+
+use std::fs;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cli::args::{Args, EmbeddingFormat};
+use crate::db::{Database, EmbeddingRow};
+use crate::error::{AppError, Result};
+
+use super::{open_output, print_success, use_colors};
+
+/// Size of the `.npy` preamble (magic, version, header length and header
+/// dict). Fixed so the header can be rewritten with the final row count.
+const NPY_HEADER_LEN: usize = 128;
+
+pub fn run(
+    format: EmbeddingFormat,
+    repo: Option<&str>,
+    output: Option<PathBuf>,
+    args: &Args,
+) -> Result<()> {
+    let db = Database::open()?;
+
+    let (rows, written) = match format {
+        EmbeddingFormat::Jsonl => {
+            let mut out = open_output(output.as_deref())?;
+            let rows = db.iter_embeddings(repo, |row| {
+                writeln!(out, "{}", row_json(&row, true))?;
+                Ok(())
+            })?;
+            out.flush()?;
+            (rows, output.map(|p| vec![p]).unwrap_or_default())
+        }
+        EmbeddingFormat::Npy => {
+            // Required by clap for this format
+            let path =
+                output.ok_or_else(|| AppError::Other("--format npy needs --output".into()))?;
+            let meta_path = path.with_extension("meta.jsonl");
+            let rows = export_npy(&db, repo, &path, &meta_path)?;
+            (rows, vec![path, meta_path])
+        }
+    };
+
+    if written.is_empty() {
+        return Ok(());
+    }
+    if args.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "success": true,
+                "rows": rows,
+                "files": written.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>(),
+            })
+        );
+    } else if !args.quiet {
+        let files: Vec<_> = written.iter().map(|p| p.display().to_string()).collect();
+        print_success(
+            &format!("Exported {rows} embeddings to {}", files.join(" and ")),
+            use_colors(args),
+        );
+    }
+    Ok(())
+}
+
+/// JSON object for one chunk; the vector is left out of `.npy` metadata rows
+fn row_json(row: &EmbeddingRow, with_vector: bool) -> serde_json::Value {
+    let mut value = serde_json::json!({
+        "repo": row.repo_name,
+        "file": row.file_path,
+        "chunk_index": row.chunk_index,
+        "start_offset": row.start_offset,
+        "end_offset": row.end_offset,
+        "text": row.chunk_text,
+    });
+    if with_vector {
+        value["vector"] = serde_json::json!(row.embedding);
+    }
+    value
+}
+
+/// Write vectors as a `rows x dim` little-endian `f32` matrix to `path`,
+/// and row `i`'s file, offsets and text as line `i` of `meta_path`
+fn export_npy(db: &Database, repo: Option<&str>, path: &Path, meta_path: &Path) -> Result<usize> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut npy = BufWriter::new(fs::File::create(path)?);
+    let mut meta = BufWriter::new(fs::File::create(meta_path)?);
+
+    // The row count is only known at the end: reserve the header, fill it in after
+    npy.write_all(&[0; NPY_HEADER_LEN])?;
+    let mut dim = None;
+    let rows = db.iter_embeddings(repo, |row| {
+        let expected = *dim.get_or_insert(row.embedding.len());
+        if row.embedding.len() != expected {
+            return Err(AppError::Other(format!(
+                "{}: embedding has {} dimensions, expected {expected}. \
+                 Run `kdex rebuild-embeddings` so all vectors come from one model",
+                row.file_path,
+                row.embedding.len()
+            )));
+        }
+        for x in &row.embedding {
+            npy.write_all(&x.to_le_bytes())?;
+        }
+        writeln!(meta, "{}", row_json(&row, false))?;
+        Ok(())
+    })?;
+
+    meta.flush()?;
+    let mut file = npy.into_inner().map_err(io::IntoInnerError::into_error)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&npy_header(rows, dim.unwrap_or(0)))?;
+    Ok(rows)
+}
+
+/// `.npy` version 1.0 preamble for a C-order `<f4` matrix, padded with
+/// spaces to `NPY_HEADER_LEN` bytes
+fn npy_header(rows: usize, dim: usize) -> Vec<u8> {
+    let dict = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({rows}, {dim}), }}");
+    let header_len = NPY_HEADER_LEN - 10;
+    let mut header = Vec::with_capacity(NPY_HEADER_LEN);
+    header.extend_from_slice(b"\x93NUMPY\x01\x00");
+    header.extend_from_slice(&u16::try_from(header_len).unwrap_or(u16::MAX).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header.resize(NPY_HEADER_LEN - 1, b' ');
+    header.push(b'\n');
+    header
+}
@@ -1,16 +1,26 @@
 //! Graph visualization command - export knowledge graph in DOT or JSON format.
 
 use crate::cli::args::Args;
-use crate::db::Database;
-use crate::error::Result;
+use crate::db::{Database, GraphLink};
+use crate::error::{AppError, Result};
 use owo_colors::OwoColorize;
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
 
 use super::use_colors;
 
+/// A file in the graph: (relative path, repository name)
+type Node = (String, String);
+/// A link between node ids (`repo:path`), or to an unresolved link target
+type Edge = (String, String);
+
 #[derive(Serialize)]
 struct GraphOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    focus: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    depth: Option<usize>,
     nodes: Vec<GraphNode>,
     edges: Vec<GraphEdge>,
     stats: GraphStats,
@@ -37,10 +47,17 @@ struct GraphStats {
     orphan_nodes: usize,
 }
 
-/// Generate knowledge graph visualization
-pub fn run(format: &str, repo: Option<&str>, args: &Args) -> Result<()> {
+/// Generate knowledge graph visualization, optionally limited to the files
+/// within `depth` link-hops of `focus`
+pub fn run(
+    format: &str,
+    repo: Option<&str>,
+    focus: Option<&str>,
+    depth: usize,
+    args: &Args,
+) -> Result<()> {
     let db = Database::open()?;
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
 
     // Get all links
     let links = db.get_all_links(repo)?;
@@ -70,6 +87,16 @@ pub fn run(format: &str, repo: Option<&str>, args: &Args) -> Result<()> {
         }
     }
 
+    // Narrow down to the neighborhood of the focus file
+    let focus_id = match focus {
+        Some(focus) => {
+            let id = resolve_focus(focus, &nodes)?;
+            (nodes, edges) = ego_graph(&id, depth, &nodes, &links);
+            Some(id)
+        }
+        None => None,
+    };
+
     // Count connected vs orphan nodes
     let mut connected: HashSet<String> = HashSet::new();
     for (source, target) in &edges {
@@ -86,6 +113,7 @@ pub fn run(format: &str, repo: Option<&str>, args: &Args) -> Result<()> {
 
     match format {
         "json" => output_json(
+            focus_id.as_deref().map(|id| (id, depth)),
             &nodes,
             &edges,
             &node_to_repo,
@@ -94,6 +122,7 @@ pub fn run(format: &str, repo: Option<&str>, args: &Args) -> Result<()> {
             orphan_count,
         )?,
         _ => output_dot(
+            focus_id.as_deref(),
             &nodes,
             &edges,
             colors,
@@ -106,7 +135,134 @@ pub fn run(format: &str, repo: Option<&str>, args: &Args) -> Result<()> {
     Ok(())
 }
 
+/// Node id (`repo:path`) of the file `--focus` names: an indexed relative
+/// path, a path ending in one (e.g. an absolute path), or a note name
+fn resolve_focus(focus: &str, nodes: &HashSet<(String, String)>) -> Result<String> {
+    let focus = focus.trim_start_matches("./");
+    let by_path: Vec<_> = nodes
+        .iter()
+        .filter(|(path, _)| path == focus || focus.ends_with(&format!("/{path}")))
+        .collect();
+    let mut matches: Vec<String> = if by_path.is_empty() {
+        let focus_key = link_key(focus);
+        nodes
+            .iter()
+            .filter(|(path, _)| {
+                let key = link_key(path);
+                key == focus_key || key.ends_with(&format!("/{focus_key}"))
+            })
+            .map(|(path, repo)| format!("{repo}:{path}"))
+            .collect()
+    } else {
+        by_path
+            .into_iter()
+            .map(|(path, repo)| format!("{repo}:{path}"))
+            .collect()
+    };
+    matches.sort();
+
+    match matches.len() {
+        0 => Err(AppError::Other(format!(
+            "No indexed file matches \"{focus}\""
+        ))),
+        1 => Ok(matches.remove(0)),
+        n => Err(AppError::Other(format!(
+            "\"{focus}\" matches {n} files ({}{}); use a longer path or --repo",
+            matches[..n.min(3)].join(", "),
+            if n > 3 { ", ..." } else { "" }
+        ))),
+    }
+}
+
+/// Lowercase path without extension or `#heading` anchor, so that `[[Note]]`,
+/// `[[folder/note#Intro]]` and `folder/Note.md` compare equal
+fn link_key(path: &str) -> String {
+    let path = path.split('#').next().unwrap_or(path).trim();
+    Path::new(path)
+        .with_extension("")
+        .to_string_lossy()
+        .to_lowercase()
+}
+
+/// Files within `depth` link-hops of `focus_id`, following links in both
+/// directions, and the links between them. Link targets are resolved to files
+/// by note name or path; a target found in the linking file's own repository
+/// wins over same-named files elsewhere.
+fn ego_graph(
+    focus_id: &str,
+    depth: usize,
+    nodes: &HashSet<Node>,
+    links: &[GraphLink],
+) -> (HashSet<Node>, Vec<Edge>) {
+    // Files by note name (file stem), for resolving link targets
+    let mut by_name: HashMap<String, Vec<(&str, &str)>> = HashMap::new();
+    for (path, repo) in nodes {
+        let key = link_key(path);
+        let name = key.rsplit('/').next().unwrap_or(&key).to_string();
+        by_name.entry(name).or_default().push((path, repo));
+    }
+
+    let mut edges: Vec<Edge> = Vec::new();
+    for link in links {
+        let target = link_key(&link.target_name);
+        let name = target.rsplit('/').next().unwrap_or(&target);
+        let candidates: Vec<_> = by_name
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|(path, _)| {
+                let key = link_key(path);
+                key == target || key.ends_with(&format!("/{target}"))
+            })
+            .collect();
+        let same_repo = candidates.iter().any(|(_, repo)| *repo == link.source_repo);
+        let source_id = format!("{}:{}", link.source_repo, link.source_path);
+        for (path, repo) in candidates {
+            if !same_repo || *repo == link.source_repo {
+                edges.push((source_id.clone(), format!("{repo}:{path}")));
+            }
+        }
+    }
+    edges.sort();
+    edges.dedup();
+
+    let mut neighbors: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (source, target) in &edges {
+        neighbors.entry(source).or_default().push(target);
+        neighbors.entry(target).or_default().push(source);
+    }
+
+    // Breadth-first search up to `depth` hops
+    let mut visited: HashSet<&str> = HashSet::from([focus_id]);
+    let mut queue = VecDeque::from([(focus_id, 0)]);
+    while let Some((id, hops)) = queue.pop_front() {
+        if hops == depth {
+            continue;
+        }
+        for &next in neighbors.get(id).into_iter().flatten() {
+            if visited.insert(next) {
+                queue.push_back((next, hops + 1));
+            }
+        }
+    }
+
+    let ego_nodes = nodes
+        .iter()
+        .filter(|(path, repo)| visited.contains(format!("{repo}:{path}").as_str()))
+        .cloned()
+        .collect();
+    let ego_edges = edges
+        .iter()
+        .filter(|(source, target)| {
+            visited.contains(source.as_str()) && visited.contains(target.as_str())
+        })
+        .cloned()
+        .collect();
+    (ego_nodes, ego_edges)
+}
+
 fn output_json(
+    focus: Option<(&str, usize)>,
     nodes: &HashSet<(String, String)>,
     edges: &[(String, String)],
     _node_to_repo: &HashMap<String, String>,
@@ -135,6 +291,8 @@ fn output_json(
         .collect();
 
     let output = GraphOutput {
+        focus: focus.map(|(id, _)| id.to_string()),
+        depth: focus.map(|(_, depth)| depth),
         nodes: graph_nodes,
         edges: graph_edges,
         stats: GraphStats {
@@ -150,6 +308,7 @@ fn output_json(
 }
 
 fn output_dot(
+    focus: Option<&str>,
     nodes: &HashSet<(String, String)>,
     edges: &[(String, String)],
     colors: bool,
@@ -179,7 +338,11 @@ fn output_dot(
             let node_id = format!("{repo}:{path}");
             let escaped_id = escape_dot_id(&node_id);
             let label = path.rsplit('/').next().unwrap_or(path);
-            println!("    \"{escaped_id}\" [label=\"{label}\"];");
+            if focus == Some(node_id.as_str()) {
+                println!("    \"{escaped_id}\" [label=\"{label}\", penwidth=2];");
+            } else {
+                println!("    \"{escaped_id}\" [label=\"{label}\"];");
+            }
         }
         println!("  }}");
         println!();
@@ -222,3 +385,131 @@ fn escape_dot_id(s: &str) -> String {
         .replace('"', "\\\"")
         .replace('\n', "\\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(repo: &str, path: &str) -> Node {
+        (path.to_string(), repo.to_string())
+    }
+
+    fn link(repo: &str, path: &str, target: &str) -> GraphLink {
+        GraphLink {
+            source_path: path.to_string(),
+            source_repo: repo.to_string(),
+            target_name: target.to_string(),
+        }
+    }
+
+    /// notes: d -> a -> b -> folder/c, plus an orphan; work has its own b.md
+    fn sample() -> (HashSet<Node>, Vec<GraphLink>) {
+        let nodes = HashSet::from([
+            node("notes", "a.md"),
+            node("notes", "b.md"),
+            node("notes", "folder/c.md"),
+            node("notes", "d.md"),
+            node("notes", "orphan.md"),
+            node("work", "b.md"),
+        ]);
+        let links = vec![
+            link("notes", "a.md", "b"),
+            link("notes", "b.md", "folder/c#Intro"),
+            link("notes", "d.md", "A"),
+        ];
+        (nodes, links)
+    }
+
+    fn ids(nodes: &HashSet<Node>) -> Vec<String> {
+        let mut ids: Vec<_> = nodes
+            .iter()
+            .map(|(path, repo)| format!("{repo}:{path}"))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_resolve_focus_by_path_or_name() {
+        let (nodes, _) = sample();
+        assert_eq!(resolve_focus("a.md", &nodes).unwrap(), "notes:a.md");
+        assert_eq!(resolve_focus("./a.md", &nodes).unwrap(), "notes:a.md");
+        assert_eq!(
+            resolve_focus("/home/me/notes/folder/c.md", &nodes).unwrap(),
+            "notes:folder/c.md"
+        );
+        assert_eq!(resolve_focus("C", &nodes).unwrap(), "notes:folder/c.md");
+    }
+
+    #[test]
+    fn test_resolve_focus_ambiguous_or_unknown() {
+        let (nodes, _) = sample();
+        let err = resolve_focus("b.md", &nodes).unwrap_err().to_string();
+        assert!(err.contains("matches 2 files"), "{err}");
+        assert!(
+            err.contains("notes:b.md") && err.contains("work:b.md"),
+            "{err}"
+        );
+        assert!(resolve_focus("b", &nodes).is_err());
+        let err = resolve_focus("missing", &nodes).unwrap_err().to_string();
+        assert!(err.contains("No indexed file matches"), "{err}");
+    }
+
+    #[test]
+    fn test_ego_graph_follows_incoming_and_outgoing_links() {
+        let (nodes, links) = sample();
+        let (ego_nodes, mut ego_edges) = ego_graph("notes:a.md", 1, &nodes, &links);
+        assert_eq!(ids(&ego_nodes), ["notes:a.md", "notes:b.md", "notes:d.md"]);
+        ego_edges.sort();
+        assert_eq!(
+            ego_edges,
+            [
+                ("notes:a.md".to_string(), "notes:b.md".to_string()),
+                ("notes:d.md".to_string(), "notes:a.md".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ego_graph_depth_cutoff() {
+        let (nodes, links) = sample();
+        let (ego_nodes, ego_edges) = ego_graph("notes:a.md", 0, &nodes, &links);
+        assert_eq!(ids(&ego_nodes), ["notes:a.md"]);
+        assert!(ego_edges.is_empty());
+
+        let (ego_nodes, ego_edges) = ego_graph("notes:a.md", 2, &nodes, &links);
+        assert_eq!(
+            ids(&ego_nodes),
+            [
+                "notes:a.md",
+                "notes:b.md",
+                "notes:d.md",
+                "notes:folder/c.md"
+            ]
+        );
+        assert!(ego_edges.contains(&("notes:b.md".to_string(), "notes:folder/c.md".to_string())));
+    }
+
+    #[test]
+    fn test_ego_graph_resolves_links_by_title_or_filename() {
+        let (nodes, links) = sample();
+        for target in ["orphan.md", "Orphan", "ORPHAN#Heading"] {
+            let mut links = links.clone();
+            links.push(link("work", "b.md", target));
+            let (ego_nodes, ego_edges) = ego_graph("work:b.md", 1, &nodes, &links);
+            assert_eq!(
+                ids(&ego_nodes),
+                ["notes:orphan.md", "work:b.md"],
+                "{target}"
+            );
+            assert_eq!(
+                ego_edges,
+                [("work:b.md".to_string(), "notes:orphan.md".to_string())]
+            );
+        }
+
+        // A target in the linking file's own repository wins
+        let (_, ego_edges) = ego_graph("notes:a.md", 1, &nodes, &links);
+        assert!(!ego_edges.iter().any(|(_, target)| target == "work:b.md"));
+    }
+}
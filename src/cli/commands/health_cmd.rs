@@ -1,18 +1,39 @@
-//! Health check command - find orphans, broken links, and stale repos.
+//! Health check command - find orphans, broken links, stale repos and missing embeddings.
 
 use crate::cli::args::Args;
-use crate::db::Database;
+use crate::config::Config;
+use crate::core::extract_external_links;
+use crate::db::{Database, EmbeddingCoverageRow};
 use crate::error::Result;
 use owo_colors::OwoColorize;
-use serde::Serialize;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
 
 use super::use_colors;
 
+/// File in the config directory holding recent external link results
+const LINK_CACHE_FILE: &str = "link_cache.json";
+
+/// How long a cached external link result is trusted (seconds)
+const LINK_CACHE_TTL_SECS: i64 = 3600;
+
+/// Number of concurrent requests when checking external links
+const LINK_CHECK_WORKERS: usize = 4;
+
+/// Embeddings coverage (percent) below which `rebuild-embeddings` is suggested
+const LOW_EMBEDDING_COVERAGE: u8 = 90;
+
 #[derive(Serialize)]
 struct HealthReport {
     orphan_files: Vec<OrphanFile>,
     broken_links: Vec<BrokenLink>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dead_external_links: Option<Vec<DeadExternalLink>>,
+    embeddings_coverage: EmbeddingCoverage,
     summary: HealthSummary,
 }
 
@@ -29,18 +50,92 @@ struct BrokenLink {
     target: String,
 }
 
+#[derive(Serialize)]
+struct DeadExternalLink {
+    source_path: String,
+    source_repo: String,
+    url: String,
+    /// HTTP status code or failure kind (e.g. "timeout")
+    status: String,
+}
+
+/// Share of files with embeddings; files of types in `embedding_exclude_types` are not counted
+#[derive(Serialize)]
+struct EmbeddingCoverage {
+    semantic_search_enabled: bool,
+    files: usize,
+    files_with_embeddings: usize,
+    percent: u8,
+    repos: Vec<RepoCoverage>,
+}
+
+#[derive(Serialize)]
+struct RepoCoverage {
+    repo: String,
+    files: usize,
+    files_with_embeddings: usize,
+    percent: u8,
+}
+
 #[derive(Serialize)]
 struct HealthSummary {
     total_orphans: usize,
     total_broken_links: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_dead_external_links: Option<usize>,
     health_score: u8,
 }
 
-/// Run health diagnostics on the knowledge index
+/// Cached outcome of an external link check
+#[derive(Serialize, Deserialize, Clone)]
+struct CachedLinkCheck {
+    /// Failure status, `None` if the link was reachable
+    status: Option<String>,
+    checked_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct LinkCache {
+    entries: HashMap<String, CachedLinkCheck>,
+}
+
+impl LinkCache {
+    fn path() -> Result<std::path::PathBuf> {
+        Ok(Config::config_dir()?.join(LINK_CACHE_FILE))
+    }
+
+    /// Load the cache, dropping expired entries. A missing or corrupt file yields an empty cache.
+    fn load(now: i64) -> Self {
+        let mut cache: Self = Self::path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        cache
+            .entries
+            .retain(|_, entry| now - entry.checked_at < LINK_CACHE_TTL_SECS);
+        cache
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Run health diagnostics on the knowledge index.
+///
+/// `external_timeout` enables checking external `http(s)` links, using the
+/// given per-request timeout in seconds.
 #[allow(clippy::too_many_lines)]
-pub fn run(repo: Option<&str>, args: &Args) -> Result<()> {
+pub fn run(repo: Option<&str>, external_timeout: Option<u64>, args: &Args) -> Result<()> {
     let db = Database::open()?;
-    let colors = use_colors(args.no_color);
+    let config = Config::load()?;
+    let colors = use_colors(args);
 
     // Get all links and files
     let links = db.get_all_links(repo)?;
@@ -108,14 +203,33 @@ pub fn run(repo: Option<&str>, args: &Args) -> Result<()> {
         })
         .count();
 
+    let coverage = embedding_coverage(&db, &config, repo)?;
+
     #[allow(clippy::cast_possible_truncation)]
-    let health_score = if total_md_files == 0 {
+    let link_score = if total_md_files == 0 {
         100
     } else {
         let orphan_penalty = (orphans.len() * 100 / total_md_files.max(1)).min(50);
         let broken_penalty = (broken_links.len() * 5).min(50);
         100_u8.saturating_sub((orphan_penalty + broken_penalty) as u8)
     };
+    // Missing embeddings only degrade search when semantic search is on, and weigh less than links
+    let coverage_penalty = if coverage.semantic_search_enabled {
+        (100 - coverage.percent) / 5
+    } else {
+        0
+    };
+    let health_score = link_score.saturating_sub(coverage_penalty);
+
+    let dead_external = match external_timeout {
+        Some(timeout) => Some(check_external_links(
+            &db,
+            repo,
+            timeout,
+            !args.json && !args.quiet,
+        )?),
+        None => None,
+    };
 
     let orphan_count = orphans.len();
     let broken_count = broken_links.len();
@@ -127,8 +241,11 @@ pub fn run(repo: Option<&str>, args: &Args) -> Result<()> {
             summary: HealthSummary {
                 total_orphans: orphan_count,
                 total_broken_links: broken_count,
+                total_dead_external_links: dead_external.as_ref().map(Vec::len),
                 health_score,
             },
+            dead_external_links: dead_external,
+            embeddings_coverage: coverage,
         };
         println!("{}", serde_json::to_string_pretty(&report)?);
         return Ok(());
@@ -174,6 +291,11 @@ pub fn run(repo: Option<&str>, args: &Args) -> Result<()> {
         }
         println!();
 
+        if let Some(dead) = &dead_external {
+            print_dead_external_links(dead, true);
+            println!();
+        }
+
         // Orphan files
         if orphans.is_empty() {
             println!("{} No orphan files found", "✓".green());
@@ -190,6 +312,9 @@ pub fn run(repo: Option<&str>, args: &Args) -> Result<()> {
                 println!("  ... and {} more", orphans.len() - 10);
             }
         }
+        println!();
+
+        print_embedding_coverage(&coverage, repo, true);
     } else {
         println!("Knowledge Index Health Report");
         println!("{}", "═".repeat(40));
@@ -213,6 +338,11 @@ pub fn run(repo: Option<&str>, args: &Args) -> Result<()> {
         }
         println!();
 
+        if let Some(dead) = &dead_external {
+            print_dead_external_links(dead, false);
+            println!();
+        }
+
         if orphans.is_empty() {
             println!("✓ No orphan files found");
         } else {
@@ -224,7 +354,352 @@ pub fn run(repo: Option<&str>, args: &Args) -> Result<()> {
                 println!("  ... and {} more", orphans.len() - 10);
             }
         }
+        println!();
+
+        print_embedding_coverage(&coverage, repo, false);
     }
 
     Ok(())
 }
+
+/// Percentage of `part` in `total`, rounded down; an empty total counts as fully covered
+#[allow(clippy::cast_possible_truncation)]
+fn percent(part: usize, total: usize) -> u8 {
+    (part.min(total) * 100)
+        .checked_div(total)
+        .map_or(100, |p| p as u8)
+}
+
+/// Count files with embeddings, overall and per repository
+fn embedding_coverage(
+    db: &Database,
+    config: &Config,
+    repo: Option<&str>,
+) -> Result<EmbeddingCoverage> {
+    Ok(aggregate_coverage(db.embedding_coverage(repo)?, config))
+}
+
+/// Sum per-type rows per repository, leaving out file types that are never
+/// embedded
+fn aggregate_coverage(rows: Vec<EmbeddingCoverageRow>, config: &Config) -> EmbeddingCoverage {
+    let mut repos: Vec<(i64, RepoCoverage)> = Vec::new();
+    for row in rows {
+        if !config.embeds_file_type(&row.file_type) {
+            continue;
+        }
+        // Rows of a repository arrive together; names alone may repeat
+        match repos.last_mut() {
+            Some((id, last)) if *id == row.repo_id => {
+                last.files += row.files;
+                last.files_with_embeddings += row.files_with_embeddings;
+            }
+            _ => repos.push((
+                row.repo_id,
+                RepoCoverage {
+                    repo: row.repo,
+                    files: row.files,
+                    files_with_embeddings: row.files_with_embeddings,
+                    percent: 0,
+                },
+            )),
+        }
+    }
+    let repos: Vec<RepoCoverage> = repos
+        .into_iter()
+        .map(|(_, mut entry)| {
+            entry.percent = percent(entry.files_with_embeddings, entry.files);
+            entry
+        })
+        .collect();
+
+    let files = repos.iter().map(|r| r.files).sum();
+    let files_with_embeddings = repos.iter().map(|r| r.files_with_embeddings).sum();
+    EmbeddingCoverage {
+        semantic_search_enabled: config.enable_semantic_search,
+        files,
+        files_with_embeddings,
+        percent: percent(files_with_embeddings, files),
+        repos,
+    }
+}
+
+/// Check external links in indexed markdown files and return the dead ones
+fn check_external_links(
+    db: &Database,
+    repo: Option<&str>,
+    timeout: u64,
+    show_progress: bool,
+) -> Result<Vec<DeadExternalLink>> {
+    // Stdin repositories have no files on disk to read links from
+    let repo_roots: HashMap<String, std::path::PathBuf> = db
+        .list_repositories()?
+        .into_iter()
+        .filter(|r| !r.is_stdin() && repo.is_none_or(|name| name == r.name))
+        .map(|r| (r.name, r.path))
+        .collect();
+
+    let mut occurrences: Vec<(String, String, String)> = Vec::new();
+    for (path, repo_name) in db.get_all_file_paths()? {
+        let Some(root) = repo_roots.get(&repo_name) else {
+            continue;
+        };
+        let is_markdown = Path::new(&path).extension().is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown")
+        });
+        if !is_markdown {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(root.join(&path)) else {
+            continue;
+        };
+        for url in extract_external_links(&content) {
+            occurrences.push((repo_name.clone(), path.clone(), url));
+        }
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let mut cache = LinkCache::load(now);
+    let mut pending: Vec<&str> = occurrences
+        .iter()
+        .map(|(_, _, url)| url.as_str())
+        .filter(|url| !cache.entries.contains_key(*url))
+        .collect();
+    pending.sort_unstable();
+    pending.dedup();
+
+    if show_progress && !pending.is_empty() {
+        eprintln!("Checking {} external links...", pending.len());
+    }
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(timeout))
+        .user_agent(concat!("kdex/", env!("CARGO_PKG_VERSION")))
+        .build();
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(String, Option<String>)>> = Mutex::new(Vec::new());
+
+    std::thread::scope(|scope| {
+        for _ in 0..LINK_CHECK_WORKERS.min(pending.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(url) = pending.get(i) else {
+                    break;
+                };
+                let status = check_url(&agent, url);
+                if let Ok(mut results) = results.lock() {
+                    results.push(((*url).to_string(), status));
+                }
+            });
+        }
+    });
+
+    for (url, status) in results.into_inner().unwrap_or_default() {
+        cache.entries.insert(
+            url,
+            CachedLinkCheck {
+                status,
+                checked_at: now,
+            },
+        );
+    }
+    // The cache is only an optimization; failing to persist it is not fatal
+    let _ = cache.save();
+
+    Ok(occurrences
+        .into_iter()
+        .filter_map(|(source_repo, source_path, url)| {
+            let status = cache.entries.get(&url)?.status.clone()?;
+            Some(DeadExternalLink {
+                source_path,
+                source_repo,
+                url,
+                status,
+            })
+        })
+        .collect())
+}
+
+/// Request a URL, returning the failure status if it is dead
+fn check_url(agent: &ureq::Agent, url: &str) -> Option<String> {
+    // Some servers reject HEAD; retry those with GET before calling the link dead
+    let response = match agent.head(url).call() {
+        Err(ureq::Error::Status(405 | 501, _)) => agent.get(url).call(),
+        other => other,
+    };
+
+    match response {
+        Ok(_) => None,
+        Err(ureq::Error::Status(code, _)) => Some(code.to_string()),
+        Err(ureq::Error::Transport(transport)) => {
+            if transport.to_string().contains("timed out") {
+                Some("timeout".to_string())
+            } else {
+                Some(transport.kind().to_string().to_lowercase())
+            }
+        }
+    }
+}
+
+/// Print the dead external links section of the report
+fn print_dead_external_links(dead: &[DeadExternalLink], colors: bool) {
+    if dead.is_empty() {
+        if colors {
+            println!("{} No dead external links found", "✓".green());
+        } else {
+            println!("✓ No dead external links found");
+        }
+        return;
+    }
+
+    if colors {
+        println!(
+            "{} {} dead external links:",
+            "✗".red(),
+            dead.len().to_string().red()
+        );
+    } else {
+        println!("✗ {} dead external links:", dead.len());
+    }
+    for link in dead.iter().take(10) {
+        if colors {
+            println!(
+                "  {} → {} ({} {})",
+                link.source_repo.dimmed(),
+                link.source_path,
+                link.url.yellow(),
+                link.status.red()
+            );
+        } else {
+            println!(
+                "  {} → {} ({} {})",
+                link.source_repo, link.source_path, link.url, link.status
+            );
+        }
+    }
+    if dead.len() > 10 {
+        println!("  ... and {} more", dead.len() - 10);
+    }
+}
+
+/// Print the embeddings coverage section of the report
+fn print_embedding_coverage(coverage: &EmbeddingCoverage, repo: Option<&str>, colors: bool) {
+    let disabled = if coverage.semantic_search_enabled {
+        ""
+    } else {
+        " (semantic search disabled)"
+    };
+
+    if coverage.files_with_embeddings >= coverage.files {
+        if colors {
+            println!(
+                "{} All indexed files have embeddings{disabled}",
+                "✓".green()
+            );
+        } else {
+            println!("✓ All indexed files have embeddings{disabled}");
+        }
+        return;
+    }
+
+    let low = coverage.semantic_search_enabled && coverage.percent < LOW_EMBEDDING_COVERAGE;
+    if colors {
+        let marker = if low {
+            "!".yellow().to_string()
+        } else {
+            "-".dimmed().to_string()
+        };
+        let percent = if low {
+            format!("{}%", coverage.percent).yellow().to_string()
+        } else {
+            format!("{}%", coverage.percent)
+        };
+        println!(
+            "{marker} Embeddings coverage: {percent} ({}/{} files){disabled}",
+            coverage.files_with_embeddings, coverage.files
+        );
+    } else {
+        let marker = if low { "!" } else { "-" };
+        println!(
+            "{marker} Embeddings coverage: {}% ({}/{} files){disabled}",
+            coverage.percent, coverage.files_with_embeddings, coverage.files
+        );
+    }
+
+    let incomplete: Vec<&RepoCoverage> = coverage
+        .repos
+        .iter()
+        .filter(|r| r.files_with_embeddings < r.files)
+        .collect();
+    for entry in incomplete.iter().take(10) {
+        let counts = format!(
+            "{}% ({}/{})",
+            entry.percent, entry.files_with_embeddings, entry.files
+        );
+        if colors {
+            println!("  {} {counts}", entry.repo.dimmed());
+        } else {
+            println!("  {} {counts}", entry.repo);
+        }
+    }
+    if incomplete.len() > 10 {
+        println!("  ... and {} more", incomplete.len() - 10);
+    }
+
+    if low {
+        let command = match repo {
+            Some(name) => format!("kdex rebuild-embeddings --missing-only --repo {name}"),
+            None => "kdex rebuild-embeddings --missing-only".to_string(),
+        };
+        if colors {
+            println!("  Run {} to embed the rest", command.cyan());
+        } else {
+            println!("  Run {command} to embed the rest");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(0, 0), 100);
+        assert_eq!(percent(3, 4), 75);
+        assert_eq!(percent(2, 3), 66);
+        assert_eq!(percent(5, 4), 100);
+    }
+
+    #[test]
+    fn test_aggregate_coverage_per_repository() {
+        let config = Config {
+            embedding_exclude_types: vec!["json".to_string()],
+            ..Config::default()
+        };
+        let row = |repo_id, file_type: &str, files, files_with_embeddings| EmbeddingCoverageRow {
+            repo_id,
+            repo: "notes".to_string(),
+            file_type: file_type.to_string(),
+            files,
+            files_with_embeddings,
+        };
+        let rows = vec![
+            row(1, "markdown", 3, 3),
+            row(1, "json", 10, 0),
+            row(1, "text", 1, 0),
+            // A second repository with the same name stays separate
+            row(2, "markdown", 2, 0),
+        ];
+        let coverage = aggregate_coverage(rows, &config);
+
+        assert_eq!(coverage.files, 6);
+        assert_eq!(coverage.files_with_embeddings, 3);
+        assert_eq!(coverage.percent, 50);
+        let repos: Vec<(usize, usize, u8)> = coverage
+            .repos
+            .iter()
+            .map(|r| (r.files, r.files_with_embeddings, r.percent))
+            .collect();
+        assert_eq!(repos, [(4, 3, 75), (2, 0, 0)]);
+    }
+}
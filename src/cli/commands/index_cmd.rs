@@ -1,22 +1,95 @@
 use indicatif::{ProgressBar, ProgressStyle};
 use owo_colors::OwoColorize;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Component, Path};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-use crate::cli::args::Args;
+use crate::cli::args::{Args, ProgressFormat};
 use crate::config::Config;
-use crate::core::Indexer;
+use crate::core::{resolve_root, run_post_index_command, truncate_chars_start};
 use crate::db::Database;
-use crate::error::Result;
+use crate::error::{AppError, Result};
 
-use super::{print_success, print_warning, use_colors};
+use super::watch::watch_repositories;
+use super::{
+    add_index_result_json, build_indexer, emit_progress_json, print_skipped_details, print_success,
+    print_warning, use_colors,
+};
 
-#[allow(clippy::too_many_lines)]
-pub fn run(path: &Path, name: Option<String>, args: &Args) -> Result<()> {
-    let colors = use_colors(args.no_color);
-    let config = Config::load()?;
+/// Install a Ctrl+C handler that asks the indexer to stop after the current file.
+/// A second Ctrl+C exits immediately.
+fn cancel_on_ctrl_c() -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    let handler_flag = Arc::clone(&flag);
+    // Installing fails only if a handler already exists; indexing then just isn't cancelable
+    let _ = ctrlc::set_handler(move || {
+        if handler_flag.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+        eprintln!("\nStopping after the current file (press Ctrl+C again to abort)...");
+    });
+    flag
+}
+
+/// Report an interrupted index; the error exits with the conventional SIGINT status
+fn report_cancelled(
+    db: &Database,
+    canonical: &Path,
+    result: &crate::core::IndexResult,
+    args: &Args,
+) -> Result<()> {
+    let name = db
+        .get_repository_by_path(canonical)?
+        .map_or_else(|| canonical.display().to_string(), |repo| repo.name);
+    let indexed = result.files_added + result.files_updated;
+
+    let message =
+        format!("Indexing interrupted after {indexed} files. Resume with `kdex update {name}`");
+
+    if args.json {
+        let mut output = serde_json::json!({
+            "success": false,
+            "cancelled": true,
+            "path": canonical.to_string_lossy(),
+        });
+        add_index_result_json(&mut output, result);
+        println!("{output}");
+    } else if !args.quiet {
+        print_warning(&message, use_colors(args));
+    }
+
+    Err(AppError::Cancelled(message))
+}
+
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
+pub fn run(
+    path: &Path,
+    name: Option<String>,
+    watch: bool,
+    follow_symlinks: bool,
+    hidden: Option<bool>,
+    max_files: Option<usize>,
+    max_total_mb: Option<u64>,
+    progress_format: ProgressFormat,
+    embed: Option<bool>,
+    args: &Args,
+) -> Result<()> {
+    let colors = use_colors(args);
+    let mut config = Config::load()?;
+    config.follow_symlinks |= follow_symlinks;
+    if let Some(hidden) = hidden {
+        config.index_hidden = hidden;
+    }
+    if let Some(n) = max_files {
+        config.max_files = n;
+    }
+    if let Some(mb) = max_total_mb {
+        config.max_total_mb = mb;
+    }
     let db = Database::open()?;
 
-    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let canonical = resolve_root(path)?;
 
     if !args.quiet && !args.json {
         if colors {
@@ -39,10 +112,14 @@ pub fn run(path: &Path, name: Option<String>, args: &Args) -> Result<()> {
         }
     }
 
-    let indexer = Indexer::new(db, config);
+    let mut indexer = build_indexer(db.clone(), config.clone(), embed, args);
+    // Watch mode keeps the default Ctrl+C behavior so it can still be stopped
+    if !watch {
+        indexer = indexer.with_cancel_flag(cancel_on_ctrl_c());
+    }
 
     // Create progress bar
-    let progress_bar = if !args.quiet && !args.json {
+    let progress_bar = if !args.quiet && !args.json && progress_format == ProgressFormat::Bar {
         let pb = ProgressBar::new(0);
         pb.set_style(
             ProgressStyle::default_bar()
@@ -56,20 +133,15 @@ pub fn run(path: &Path, name: Option<String>, args: &Args) -> Result<()> {
     };
 
     let result = indexer.index(&canonical, name, |progress| {
+        if progress_format == ProgressFormat::Json {
+            emit_progress_json(progress);
+        }
         if let Some(pb) = &progress_bar {
             pb.set_length(progress.total_files as u64);
             pb.set_position(progress.processed_files as u64);
 
             // Truncate filename for display
-            let display_file = if progress.current_file.len() > 40 {
-                format!(
-                    "...{}",
-                    &progress.current_file[progress.current_file.len() - 37..]
-                )
-            } else {
-                progress.current_file.clone()
-            };
-            pb.set_message(display_file);
+            pb.set_message(truncate_chars_start(&progress.current_file, 40, "..."));
         }
     })?;
 
@@ -77,22 +149,20 @@ pub fn run(path: &Path, name: Option<String>, args: &Args) -> Result<()> {
         pb.finish_and_clear();
     }
 
+    if result.cancelled {
+        return report_cancelled(&db, &canonical, &result, args);
+    }
+
+    run_post_index_command(&config, &canonical, &result);
+
     // Output results
     if args.json {
-        println!(
-            "{}",
-            serde_json::json!({
-                "success": true,
-                "path": canonical.to_string_lossy(),
-                "files_added": result.files_added,
-                "files_updated": result.files_updated,
-                "files_deleted": result.files_deleted,
-                "files_unchanged": result.files_unchanged,
-                "files_skipped": result.files_skipped,
-                "total_bytes": result.total_bytes,
-                "elapsed_secs": result.elapsed_secs,
-            })
-        );
+        let mut output = serde_json::json!({
+            "success": true,
+            "path": canonical.to_string_lossy(),
+        });
+        add_index_result_json(&mut output, &result);
+        println!("{output}");
     } else if !args.quiet {
         let total_files = result.files_added + result.files_updated + result.files_unchanged;
 
@@ -122,10 +192,55 @@ pub fn run(path: &Path, name: Option<String>, args: &Args) -> Result<()> {
         if result.files_deleted > 0 {
             println!("  Deleted: {}", result.files_deleted);
         }
-        if result.files_skipped > 0 {
-            println!("  Skipped: {} (binary/too large)", result.files_skipped);
+        let other_skipped =
+            result.skipped_details.len() - result.files_empty - result.files_minified;
+        if other_skipped > 0 {
+            println!("  Skipped: {other_skipped} (binary/too large)");
+        }
+        if result.files_empty > 0 {
+            println!("  Empty: {} (min_content_chars)", result.files_empty);
+        }
+        if result.files_minified > 0 {
+            println!(
+                "  Minified: {} (max_avg_line_length)",
+                result.files_minified
+            );
+        }
+        if result.embeddings_skipped > 0 {
+            println!(
+                "  Not embedded: {} (embedding_exclude_types)",
+                result.embeddings_skipped
+            );
+        }
+        if !result.failed_details.is_empty() {
+            print_warning(
+                &format!(
+                    "Failed to index {} file{} (run with --verbose for details)",
+                    result.failed_details.len(),
+                    if result.failed_details.len() == 1 {
+                        ""
+                    } else {
+                        "s"
+                    }
+                ),
+                colors,
+            );
+        }
+        if args.verbose {
+            print_skipped_details(&result, colors);
+        }
+    }
+
+    if watch {
+        if let Some(repo) = db.get_repository_by_path(&canonical)? {
+            if !args.quiet && !args.json {
+                println!();
+            }
+            return watch_repositories(&db, &config, &[repo], None, args);
         }
+    }
 
+    if !args.quiet && !args.json {
         // Next steps hint for first-time users
         println!();
         println!("What's next:");
@@ -144,3 +259,88 @@ pub fn run(path: &Path, name: Option<String>, args: &Args) -> Result<()> {
 
     Ok(())
 }
+
+/// Index content piped on stdin as a single file of a named stdin repository
+pub fn run_stdin(name: &str, path_label: &Path, args: &Args) -> Result<()> {
+    let colors = use_colors(args);
+
+    // The label becomes a relative path inside the repository
+    if path_label.as_os_str().is_empty()
+        || !path_label
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(AppError::Other(format!(
+            "Invalid --path-label \"{}\": use a relative path like notes/today.md",
+            path_label.display()
+        )));
+    }
+
+    let mut content = Vec::new();
+    std::io::stdin().read_to_end(&mut content)?;
+
+    let config = Config::load()?;
+    let db = Database::open()?;
+    // Embed like any indexed file when semantic search is on
+    let indexer = build_indexer(db, config, None, args);
+    let result = indexer.index_content(name, path_label, &content)?;
+    let location = format!("{name}:{}", path_label.display());
+
+    if let Some((_, reason)) = result.skipped_details.first() {
+        if args.json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "success": false,
+                    "name": name,
+                    "path": path_label.to_string_lossy(),
+                    "skipped": reason,
+                })
+            );
+        }
+        return Err(AppError::Reported(format!(
+            "{location} was not indexed: {reason}"
+        )));
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "success": true,
+                "name": name,
+                "path": path_label.to_string_lossy(),
+                "files_added": result.files_added,
+                "files_updated": result.files_updated,
+                "total_bytes": result.total_bytes,
+                "elapsed_secs": result.elapsed_secs,
+            })
+        );
+    } else if !args.quiet {
+        let verb = if result.files_updated > 0 {
+            "Updated"
+        } else {
+            "Indexed"
+        };
+        if colors {
+            print_success(
+                &format!(
+                    "{verb} {} ({} bytes from stdin)",
+                    location.cyan(),
+                    result.total_bytes
+                ),
+                true,
+            );
+        } else {
+            print_success(
+                &format!(
+                    "{verb} {location} ({} bytes from stdin)",
+                    result.total_bytes
+                ),
+                false,
+            );
+        }
+    }
+
+    Ok(())
+}
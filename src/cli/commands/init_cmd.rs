@@ -0,0 +1,234 @@
+//! Init command - set up config, semantic search, MCP integration and a first index in one step.
+
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use crate::cli::args::{Args, McpTool, ProgressFormat};
+use crate::config::Config;
+use crate::core::{resolve_root, Embedder};
+use crate::db::Database;
+use crate::error::Result;
+
+use super::{
+    add_index_result_json, add_mcp, build_indexer, confirm, index, print_success, print_warning,
+    use_colors,
+};
+
+/// Ask which tool to configure for MCP; an empty answer skips the step
+fn prompt_mcp_tool() -> Option<McpTool> {
+    loop {
+        print!("Configure kdex as an MCP server for an AI tool? [copilot/gemini/claude/N] ");
+        io::stdout().flush().ok();
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() {
+            return None;
+        }
+        let input = input.trim();
+        if input.is_empty() || matches!(input.to_lowercase().as_str(), "n" | "no" | "none") {
+            return None;
+        }
+        if let Ok(tool) = McpTool::from_str(input, true) {
+            return Some(tool);
+        }
+        println!("Unknown tool \"{input}\"");
+    }
+}
+
+/// Create the config file if missing, then optionally enable semantic search
+/// (downloading the model), configure MCP for one tool and index the current
+/// directory. Choices not given as flags are asked for on a terminal and
+/// default to no otherwise (and with `--json`).
+#[allow(clippy::too_many_lines)]
+pub fn run(
+    semantic: Option<bool>,
+    mcp: Option<McpTool>,
+    no_mcp: bool,
+    index_cwd: Option<bool>,
+    args: &Args,
+) -> Result<()> {
+    let colors = use_colors(args);
+    let interactive = !args.json && io::stdin().is_terminal();
+    let show = !args.quiet && !args.json;
+
+    // Config file
+    let config_path = Config::config_file_path()?;
+    let config_created = !config_path.exists();
+    let mut config = Config::load()?;
+    if show {
+        if config_created {
+            print_success(
+                &format!("Created config at {}", config_path.display()),
+                colors,
+            );
+        } else {
+            print_success(
+                &format!("Using existing config at {}", config_path.display()),
+                colors,
+            );
+        }
+    }
+
+    // Semantic search
+    let enable_semantic = semantic.unwrap_or_else(|| {
+        config.enable_semantic_search
+            || (interactive
+                && confirm("Enable semantic search? (downloads the embedding model, ~25MB)"))
+    });
+    if enable_semantic != config.enable_semantic_search {
+        config.enable_semantic_search = enable_semantic;
+        config.save()?;
+    }
+    let mut model_ready = false;
+    if enable_semantic {
+        if show {
+            if colors {
+                print!(
+                    "{} Loading embedding model {}... ",
+                    "→".blue(),
+                    config.embedding_model.cyan()
+                );
+            } else {
+                print!("Loading embedding model {}... ", config.embedding_model);
+            }
+            io::stdout().flush().ok();
+        }
+        match Embedder::new(&config.embedding_model, config.model_cache_dir().as_deref()) {
+            Ok(_) => {
+                model_ready = true;
+                if show {
+                    if colors {
+                        println!("{}", "done".green());
+                    } else {
+                        println!("done");
+                    }
+                }
+            }
+            Err(e) => {
+                if show {
+                    if colors {
+                        println!("{}", "failed".red());
+                    } else {
+                        println!("failed");
+                    }
+                }
+                if !args.quiet {
+                    print_warning(
+                        &format!(
+                            "Could not load the embedding model ({e}). Semantic search stays \
+                             enabled; the download is retried on first use."
+                        ),
+                        colors,
+                    );
+                }
+            }
+        }
+        if show {
+            print_success("Semantic search enabled", colors);
+        }
+    }
+
+    // MCP integration
+    let mcp_tool = if no_mcp || mcp.is_some() || !interactive {
+        mcp
+    } else {
+        prompt_mcp_tool()
+    };
+    let mcp_json = match mcp_tool {
+        Some(tool) => {
+            let (path, already_configured) = add_mcp::configure(tool)?;
+            if show {
+                let action = if already_configured {
+                    "Updated"
+                } else {
+                    "Added"
+                };
+                print_success(
+                    &format!(
+                        "{action} kdex MCP configuration for {} ({})",
+                        add_mcp::tool_name(tool),
+                        path.display()
+                    ),
+                    colors,
+                );
+            }
+            serde_json::json!({
+                "tool": format!("{tool:?}").to_lowercase(),
+                "config_path": path.to_string_lossy(),
+                "action": if already_configured { "updated" } else { "added" },
+            })
+        }
+        None => serde_json::Value::Null,
+    };
+
+    // First index
+    let cwd = std::env::current_dir()?;
+    let index_cwd = index_cwd.unwrap_or_else(|| {
+        interactive && confirm(&format!("Index the current directory ({})?", cwd.display()))
+    });
+    let mut index_json = serde_json::Value::Null;
+    if index_cwd {
+        if args.json {
+            // `index` prints its own JSON, so index here and nest the counts
+            let canonical = resolve_root(&cwd)?;
+            let db = Database::open()?;
+            let indexer = build_indexer(db, config, None, args);
+            let result = indexer.index(&canonical, None, |_| {})?;
+            index_json = serde_json::json!({ "path": canonical.to_string_lossy() });
+            add_index_result_json(&mut index_json, &result);
+        } else {
+            if show {
+                println!();
+            }
+            index::run(
+                Path::new("."),
+                None,
+                false,
+                false,
+                None,
+                None,
+                None,
+                ProgressFormat::Bar,
+                None,
+                args,
+            )?;
+        }
+    } else if show {
+        println!();
+        println!("What's next:");
+        if colors {
+            println!(
+                "  {} Index a directory: {}",
+                "•".dimmed(),
+                "kdex index <path>".cyan()
+            );
+            println!(
+                "  {} Search: {}",
+                "•".dimmed(),
+                "kdex search \"your query\"".cyan()
+            );
+        } else {
+            println!("  • Index a directory: kdex index <path>");
+            println!("  • Search: kdex search \"your query\"");
+        }
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "success": true,
+                "config_path": config_path.to_string_lossy(),
+                "config_created": config_created,
+                "semantic_search": enable_semantic,
+                "model_ready": model_ready,
+                "mcp": mcp_json,
+                "index": index_json,
+            })
+        );
+    }
+
+    Ok(())
+}
@@ -1,24 +1,141 @@
 use chrono::Utc;
 use owo_colors::OwoColorize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use strsim::jaro_winkler;
 
-use crate::cli::args::Args;
+use crate::cli::args::{Args, RepoSort};
 use crate::core::VaultType;
-use crate::db::{Database, RepoStatus, SourceType};
+use crate::db::{Database, RepoStatus, Repository, SourceType};
 use crate::error::Result;
 
-use super::use_colors;
+use super::{print_porcelain, use_colors};
+
+/// JSON shape shared by `list --json` and the HTTP API
+pub(super) fn repositories_json(repos: &[Repository]) -> serde_json::Value {
+    let json_repos: Vec<_> = repos
+        .iter()
+        .map(|r| {
+            serde_json::json!({
+                "name": r.name,
+                "path": r.path.to_string_lossy(),
+                "file_count": r.file_count,
+                "total_size_bytes": r.total_size_bytes,
+                "status": r.status.as_str(),
+                "source_type": r.source_type.as_str(),
+                "vault_type": r.vault_type.as_str(),
+                "remote_url": r.remote_url,
+                "remote_branch": r.remote_branch,
+                "last_indexed_at": r.last_indexed_at.map(|dt| dt.to_rfc3339()),
+                "last_synced_at": r.last_synced_at.map(|dt| dt.to_rfc3339()),
+                "last_indexed_commit": r.last_indexed_commit,
+                "created_at": r.created_at.to_rfc3339(),
+            })
+        })
+        .collect();
+
+    serde_json::json!({"repositories": json_repos})
+}
+
+/// Minimum Jaro-Winkler similarity for a fuzzy `--filter` match
+const FILTER_MIN_SIMILARITY: f64 = 0.85;
+
+/// How well `query` (lowercase) matches a repository: 1 when its name or path
+/// contains the query, otherwise the best similarity to the name or one of the
+/// words of its name and path, if above `FILTER_MIN_SIMILARITY`
+fn filter_score(repo: &Repository, query: &str) -> Option<f64> {
+    let name = repo.name.to_lowercase();
+    let path = repo.path.to_string_lossy().to_lowercase();
+    if name.contains(query) || path.contains(query) {
+        return Some(1.0);
+    }
+
+    let score = name
+        .split(|c: char| !c.is_alphanumeric())
+        .chain(path.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(|word| jaro_winkler(query, word))
+        .fold(jaro_winkler(query, &name), f64::max);
+    (score >= FILTER_MIN_SIMILARITY).then_some(score)
+}
+
+/// Sort repositories in place; ties fall back to name order
+fn sort_repositories(repos: &mut [Repository], sort: RepoSort) {
+    match sort {
+        RepoSort::Name => repos.sort_by(|a, b| a.name.cmp(&b.name)),
+        RepoSort::Size => repos.sort_by_key(|r| Reverse(r.total_size_bytes)),
+        RepoSort::Files => repos.sort_by_key(|r| Reverse(r.file_count)),
+        RepoSort::Indexed => repos.sort_by_key(|r| Reverse(r.last_indexed_at)),
+    }
+}
 
 #[allow(clippy::too_many_lines)]
-pub fn run(args: &Args) -> Result<()> {
-    let colors = use_colors(args.no_color);
+pub fn run(
+    sort: Option<RepoSort>,
+    filter: Option<&str>,
+    limit: Option<usize>,
+    offset: usize,
+    args: &Args,
+) -> Result<()> {
+    let colors = use_colors(args);
     let db = Database::open()?;
 
-    let repos = db.list_repositories()?;
+    let mut repos = db.list_repositories()?;
+    let indexed = repos.len();
+
+    // Keep matching repositories with their score, best first unless --sort is given
+    let mut scores: HashMap<i64, f64> = HashMap::new();
+    if let Some(filter) = filter {
+        let query = filter.trim().to_lowercase();
+        let mut matches: Vec<(Repository, f64)> = repos
+            .into_iter()
+            .filter_map(|r| filter_score(&r, &query).map(|score| (r, score)))
+            .collect();
+        if sort.is_none() {
+            matches.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        }
+        scores = matches.iter().map(|(r, score)| (r.id, *score)).collect();
+        repos = matches.into_iter().map(|(r, _)| r).collect();
+    }
+    let total = repos.len();
 
-    if repos.is_empty() {
+    if filter.is_none() || sort.is_some() {
+        sort_repositories(&mut repos, sort.unwrap_or_default());
+    }
+    let repos: Vec<Repository> = repos
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+
+    if args.porcelain {
+        for r in &repos {
+            print_porcelain(&[
+                &r.name,
+                &r.path.to_string_lossy(),
+                r.source_type.as_str(),
+                r.status.as_str(),
+                &r.file_count.to_string(),
+                &r.total_size_bytes.to_string(),
+                &r.last_indexed_at
+                    .map(|dt| dt.to_rfc3339())
+                    .unwrap_or_default(),
+            ]);
+        }
+        return Ok(());
+    }
+
+    if total == 0 {
         if args.json {
-            println!("{}", serde_json::json!({"repositories": []}));
-        } else if !args.quiet {
+            println!(
+                "{}",
+                serde_json::json!({"repositories": [], "total": 0, "offset": offset, "limit": limit})
+            );
+        } else if args.quiet {
+            // Nothing to print
+        } else if let Some(filter) = filter.filter(|_| indexed > 0) {
+            println!("No repositories match \"{filter}\".");
+        } else {
             println!("No repositories indexed yet.");
             println!();
             println!("Get started by indexing a project:");
@@ -34,27 +151,19 @@ pub fn run(args: &Args) -> Result<()> {
     }
 
     if args.json {
-        let json_repos: Vec<_> = repos
-            .iter()
-            .map(|r| {
-                serde_json::json!({
-                    "name": r.name,
-                    "path": r.path.to_string_lossy(),
-                    "file_count": r.file_count,
-                    "total_size_bytes": r.total_size_bytes,
-                    "status": r.status.as_str(),
-                    "source_type": r.source_type.as_str(),
-                    "vault_type": r.vault_type.as_str(),
-                    "remote_url": r.remote_url,
-                    "remote_branch": r.remote_branch,
-                    "last_indexed_at": r.last_indexed_at.map(|dt| dt.to_rfc3339()),
-                    "last_synced_at": r.last_synced_at.map(|dt| dt.to_rfc3339()),
-                    "created_at": r.created_at.to_rfc3339(),
-                })
-            })
-            .collect();
-
-        println!("{}", serde_json::json!({"repositories": json_repos}));
+        let mut output = repositories_json(&repos);
+        if let Some(filter) = filter {
+            if let Some(json_repos) = output["repositories"].as_array_mut() {
+                for (json, repo) in json_repos.iter_mut().zip(&repos) {
+                    json["score"] = scores.get(&repo.id).copied().unwrap_or_default().into();
+                }
+            }
+            output["filter"] = filter.into();
+        }
+        output["total"] = total.into();
+        output["offset"] = offset.into();
+        output["limit"] = limit.into();
+        println!("{output}");
     } else if !args.quiet {
         let now = Utc::now();
 
@@ -108,6 +217,7 @@ pub fn run(args: &Args) -> Result<()> {
                     match repo.source_type {
                         SourceType::Remote => "☁ ",
                         SourceType::Local => "📁",
+                        SourceType::Stdin => "📥",
                     }
                 }
             };
@@ -145,11 +255,18 @@ pub fn run(args: &Args) -> Result<()> {
             .iter()
             .filter(|r| r.source_type == SourceType::Remote)
             .count();
-        let local_count = repos.len() - remote_count;
+        let stdin_count = repos.iter().filter(|r| r.is_stdin()).count();
+        let local_count = repos.len() - remote_count - stdin_count;
+        let stdin_part = if stdin_count > 0 {
+            format!(", {stdin_count} stdin")
+        } else {
+            String::new()
+        };
         println!(
-            "{} local, {} remote │ Status: {} ready  {} pending  {} syncing  {} error",
+            "{} local, {} remote{} │ Status: {} ready  {} pending  {} syncing  {} error",
             local_count,
             remote_count,
+            stdin_part,
             if colors {
                 "●".green().to_string()
             } else {
@@ -171,6 +288,11 @@ pub fn run(args: &Args) -> Result<()> {
                 "!".to_string()
             },
         );
+        if repos.is_empty() {
+            println!("No repositories at offset {offset} ({total} total)");
+        } else if repos.len() < total {
+            println!("Showing {}-{} of {total}", offset + 1, offset + repos.len());
+        }
     }
 
     Ok(())
@@ -0,0 +1,175 @@
+//! Maintenance command - optimize the full-text index and check the database for corruption.
+
+use owo_colors::OwoColorize;
+use std::io::{self, IsTerminal, Write};
+use std::time::Instant;
+
+use crate::cli::args::Args;
+use crate::config::Config;
+use crate::db::Database;
+use crate::error::{AppError, Result};
+
+use super::{confirm, print_success, print_warning, use_colors};
+
+/// Recovery hint when a damaged index can't be repaired from its stored content
+const REINDEX_HINT: &str = "re-index from disk with: kdex update --all --force";
+
+/// Recovery hint when the database file itself is damaged
+const RECREATE_HINT: &str =
+    "back up the repository list with `kdex config export --include-local -o repos.yaml`, \
+     delete the database and restore it with `kdex config import repos.yaml`";
+
+/// Print a step label and flush, so it shows while the step runs
+fn step(label: &str, colors: bool, args: &Args) {
+    if args.quiet || args.json {
+        return;
+    }
+    if colors {
+        print!("{} {label}... ", "→".blue());
+    } else {
+        print!("{label}... ");
+    }
+    io::stdout().flush().ok();
+}
+
+/// Finish a step line with its outcome
+fn step_done(outcome: &str, ok: bool, colors: bool, args: &Args) {
+    if args.quiet || args.json {
+        return;
+    }
+    match (ok, colors) {
+        (true, true) => println!("{}", outcome.green()),
+        (false, true) => println!("{}", outcome.red()),
+        _ => println!("{outcome}"),
+    }
+}
+
+/// Check the database and full-text indexes, optimize the indexes, and rebuild
+/// damaged full-text tables from their stored content (after confirmation,
+/// or right away with `force`). With `check_only` nothing is changed.
+#[allow(clippy::too_many_lines)]
+pub fn run(check_only: bool, force: bool, args: &Args) -> Result<()> {
+    let colors = use_colors(args);
+    let db = Database::open()?;
+
+    step("Checking database integrity", colors, args);
+    let mut problems = db.integrity_check()?;
+    step_done(
+        if problems.is_empty() {
+            "ok"
+        } else {
+            "problems found"
+        },
+        problems.is_empty(),
+        colors,
+        args,
+    );
+
+    step("Checking full-text indexes", colors, args);
+    let mut damaged = db.fts_integrity_check()?;
+    step_done(
+        if damaged.is_empty() { "ok" } else { "damaged" },
+        damaged.is_empty(),
+        colors,
+        args,
+    );
+
+    if !args.quiet && !args.json {
+        for problem in &problems {
+            print_warning(problem, colors);
+        }
+        for (table, error) in &damaged {
+            print_warning(&format!("{table}: {error}"), colors);
+        }
+    }
+
+    // Rebuild damaged full-text tables from the content they store
+    let mut rebuilt = false;
+    if !damaged.is_empty() && !check_only {
+        let confirmed = force
+            || (!args.json
+                && io::stdin().is_terminal()
+                && confirm("Rebuild the full-text index from its stored content?"));
+        if confirmed {
+            step("Rebuilding full-text indexes", colors, args);
+            let tokenizer = match db.fts_tokenizer()? {
+                Some(tokenizer) => tokenizer,
+                None => Config::load()?.fts_tokenizer,
+            };
+            let result = db
+                .rebuild_fts(&tokenizer)
+                .and_then(|files| Ok((files, db.rebuild_code_blocks()?)));
+            match result {
+                Ok((files, blocks)) => {
+                    step_done(
+                        &format!("{files} files, {blocks} code blocks"),
+                        true,
+                        colors,
+                        args,
+                    );
+                    rebuilt = true;
+                    damaged = db.fts_integrity_check()?;
+                    problems = db.integrity_check()?;
+                }
+                Err(e) => {
+                    step_done("failed", false, colors, args);
+                    return Err(AppError::Other(format!(
+                        "Could not rebuild the full-text index ({e}); {REINDEX_HINT}"
+                    )));
+                }
+            }
+        }
+    }
+
+    // Optimizing a damaged index would only spread the damage
+    let mut optimized = false;
+    if !check_only && damaged.is_empty() {
+        step("Optimizing full-text indexes", colors, args);
+        let start = Instant::now();
+        db.optimize_fts()?;
+        optimized = true;
+        step_done(
+            &format!("done ({:.1}s)", start.elapsed().as_secs_f64()),
+            true,
+            colors,
+            args,
+        );
+    }
+
+    let healthy = problems.is_empty() && damaged.is_empty();
+    if args.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "healthy": healthy,
+                "integrity_problems": problems,
+                "damaged_tables": damaged
+                    .iter()
+                    .map(|(table, error)| serde_json::json!({"table": table, "error": error}))
+                    .collect::<Vec<_>>(),
+                "rebuilt": rebuilt,
+                "optimized": optimized,
+            })
+        );
+    } else if !args.quiet && healthy {
+        print_success("Index is healthy", colors);
+    }
+
+    // FTS damage also shows up in `integrity_check`, so it's reported first:
+    // unlike other damage, a rebuild can fix it. The JSON report above already
+    // covers both.
+    if !damaged.is_empty() {
+        return Err(AppError::Reported(if rebuilt {
+            format!("The full-text index is still damaged after a rebuild; {REINDEX_HINT}")
+        } else {
+            "The full-text index is damaged; rebuild it with: kdex maintenance --force".into()
+        }));
+    }
+    if !problems.is_empty() {
+        return Err(AppError::Reported(format!(
+            "The database is damaged; {RECREATE_HINT}"
+        )));
+    }
+
+    Ok(())
+}
@@ -4,24 +4,33 @@ mod backlinks_cmd;
 mod completions_cmd;
 mod config_cmd;
 mod context_cmd;
+mod doctor_cmd;
+mod export_embeddings_cmd;
 mod graph_cmd;
 mod health_cmd;
 mod index_cmd;
+mod init_cmd;
 mod list_cmd;
+mod maintenance_cmd;
+mod prune_cmd;
 mod rebuild_embeddings_cmd;
 mod remove_cmd;
+mod repos_cmd;
 mod search_cmd;
 mod self_update_cmd;
+mod serve_cmd;
 mod stats_cmd;
+mod symbols_cmd;
 mod sync_cmd;
 mod tags_cmd;
 mod update_cmd;
+mod watch_cmd;
 
 pub mod add {
     pub use super::add_cmd::run;
 }
 pub mod add_mcp {
-    pub use super::add_mcp_cmd::run;
+    pub use super::add_mcp_cmd::{configure, run, tool_name};
 }
 pub mod backlinks {
     pub use super::backlinks_cmd::run;
@@ -29,6 +38,12 @@ pub mod backlinks {
 pub mod completions {
     pub use super::completions_cmd::run;
 }
+pub mod doctor {
+    pub use super::doctor_cmd::run;
+}
+pub mod export_embeddings {
+    pub use super::export_embeddings_cmd::run;
+}
 pub mod graph {
     pub use super::graph_cmd::run;
 }
@@ -36,20 +51,32 @@ pub mod health {
     pub use super::health_cmd::run;
 }
 pub mod index {
-    pub use super::index_cmd::run;
+    pub use super::index_cmd::{run, run_stdin};
+}
+pub mod init {
+    pub use super::init_cmd::run;
 }
 pub mod search {
-    pub use super::search_cmd::run;
+    pub use super::search_cmd::{run, SearchOptions};
 }
 pub mod list {
     pub use super::list_cmd::run;
 }
+pub mod maintenance {
+    pub use super::maintenance_cmd::run;
+}
 pub mod update {
     pub use super::update_cmd::run;
 }
 pub mod remove {
     pub use super::remove_cmd::run;
 }
+pub mod repos {
+    pub use super::repos_cmd::{open, rename};
+}
+pub mod prune {
+    pub use super::prune_cmd::run;
+}
 pub mod config {
     pub use super::config_cmd::run;
 }
@@ -64,6 +91,9 @@ pub mod sync {
 pub mod stats {
     pub use super::stats_cmd::run;
 }
+pub mod symbols {
+    pub use super::symbols_cmd::run;
+}
 pub mod tags {
     pub use super::tags_cmd::run;
 }
@@ -73,19 +103,45 @@ pub mod rebuild_embeddings {
 pub mod self_update {
     pub use super::self_update_cmd::run;
 }
+pub mod serve {
+    pub use super::serve_cmd::run;
+}
+pub mod watch {
+    pub use super::watch_cmd::{run, watch_repositories};
+}
 
 use owo_colors::OwoColorize;
+use std::fs;
 use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use crate::cli::args::Args;
+use crate::config::Config;
+use crate::core::{
+    missing_files_warning, Embedder, IndexProgress, IndexResult, IndexedFileReader, Indexer,
+};
+use crate::db::Database;
+use crate::error::Result;
 
-/// Check if colors should be used
-pub fn use_colors(no_color: bool) -> bool {
-    if no_color {
+/// Check if colors should be used.
+///
+/// `--no-color` and `--force-color` win over the environment; then `NO_COLOR`
+/// disables and `CLICOLOR_FORCE` (set and not `0`) forces colors. Otherwise
+/// colors are used when stdout is a terminal.
+pub fn use_colors(args: &Args) -> bool {
+    if args.no_color {
         return false;
     }
+    if args.force_color {
+        return true;
+    }
     // Respect NO_COLOR environment variable
     if std::env::var("NO_COLOR").is_ok() {
         return false;
     }
+    if std::env::var("CLICOLOR_FORCE").is_ok_and(|v| !v.is_empty() && v != "0") {
+        return true;
+    }
     // Check if stdout is a terminal
     std::io::stdout().is_terminal()
 }
@@ -118,6 +174,145 @@ pub fn print_warning(msg: &str, use_colors: bool) {
     }
 }
 
+/// Create an indexer that embeds files while indexing when `enable_semantic_search`
+/// is on, unless `embed` (`--embed` / `--no-embed`) overrides it for this run.
+/// If the model can't be loaded, indexing continues without embeddings.
+pub fn build_indexer(db: Database, config: Config, embed: Option<bool>, args: &Args) -> Indexer {
+    if !embed.unwrap_or(config.enable_semantic_search) {
+        return Indexer::new(db, config);
+    }
+    match Embedder::new(&config.embedding_model, config.model_cache_dir().as_deref()) {
+        Ok(embedder) => Indexer::with_embedder(db, config, embedder),
+        Err(e) => {
+            if !args.quiet {
+                print_warning(
+                    &format!("Could not load embeddings: {e}. Indexing without embeddings."),
+                    use_colors(args),
+                );
+            }
+            Indexer::new(db, config)
+        }
+    }
+}
+
+/// Warn once (on stderr) when indexed files read back from disk no longer exist there
+pub fn warn_missing_files(reader: &IndexedFileReader, args: &Args) {
+    if reader.missing() > 0 && !args.quiet {
+        print_warning(&missing_files_warning(reader.missing()), use_colors(args));
+    }
+}
+
+/// Print per-file skip reasons and failures from an indexing run (for --verbose)
+pub fn print_skipped_details(result: &IndexResult, use_colors: bool) {
+    if !result.skipped_details.is_empty() {
+        println!("  Skipped files:");
+        for (path, reason) in &result.skipped_details {
+            if use_colors {
+                println!("    {} {}", path.display(), format!("({reason})").dimmed());
+            } else {
+                println!("    {} ({reason})", path.display());
+            }
+        }
+    }
+
+    if !result.failed_details.is_empty() {
+        println!("  Failed files:");
+        for (path, error) in &result.failed_details {
+            if use_colors {
+                println!(
+                    "    {} {}",
+                    path.display().to_string().red(),
+                    error.dimmed()
+                );
+            } else {
+                println!("    {} {error}", path.display());
+            }
+        }
+    }
+}
+
+/// Convert per-file skip details into JSON entries
+pub fn skipped_details_json(details: &[(std::path::PathBuf, String)]) -> Vec<serde_json::Value> {
+    details
+        .iter()
+        .map(|(path, reason)| {
+            serde_json::json!({
+                "path": path.to_string_lossy(),
+                "reason": reason,
+            })
+        })
+        .collect()
+}
+
+/// Add every count of an indexing run to a JSON object, so scripts see the
+/// full picture (e.g. that nothing changed) in `add`, `index` and `sync` output
+pub fn add_index_result_json(value: &mut serde_json::Value, result: &IndexResult) {
+    value["files_added"] = result.files_added.into();
+    value["files_updated"] = result.files_updated.into();
+    value["files_deleted"] = result.files_deleted.into();
+    value["files_unchanged"] = result.files_unchanged.into();
+    value["files_skipped"] = result.files_skipped.into();
+    value["files_empty"] = result.files_empty.into();
+    value["files_minified"] = result.files_minified.into();
+    value["skipped"] = skipped_details_json(&result.skipped_details).into();
+    value["failed"] = skipped_details_json(&result.failed_details).into();
+    value["total_bytes"] = result.total_bytes.into();
+    value["embeddings_skipped"] = result.embeddings_skipped.into();
+    value["elapsed_secs"] = result.elapsed_secs.into();
+}
+
+/// Write one NDJSON progress line to stderr (for `--progress json`)
+pub fn emit_progress_json(progress: &IndexProgress) {
+    eprintln!(
+        "{}",
+        serde_json::json!({
+            "processed": progress.processed_files,
+            "total": progress.total_files,
+            "current_file": progress.current_file,
+            "bytes": progress.bytes_processed,
+            "elapsed": progress.elapsed_secs,
+        })
+    );
+}
+
+/// Print one tab-separated `--porcelain` record.
+/// Tabs and newlines inside fields are replaced with spaces.
+pub fn print_porcelain(fields: &[&str]) {
+    let _ = write_porcelain(&mut io::stdout(), fields);
+}
+
+/// Write one `--porcelain` record to `out` (see [`print_porcelain`])
+pub fn write_porcelain(out: &mut dyn Write, fields: &[&str]) -> io::Result<()> {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|f| f.replace(['\t', '\n', '\r'], " "))
+        .collect();
+    writeln!(out, "{}", fields.join("\t"))
+}
+
+/// Open the destination for `--output`: the given file (creating parent
+/// directories as needed), or stdout when no path is set
+pub fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>> {
+    let Some(path) = path else {
+        return Ok(Box::new(io::stdout().lock()));
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(Box::new(io::BufWriter::new(fs::File::create(path)?)))
+}
+
+/// Flush `--output` and report the written file unless `--quiet`
+pub fn finish_output(mut out: Box<dyn Write>, path: Option<&Path>, args: &Args) -> Result<()> {
+    out.flush()?;
+    if let Some(path) = path {
+        if !args.quiet {
+            print_success(&format!("Wrote {}", path.display()), use_colors(args));
+        }
+    }
+    Ok(())
+}
+
 /// Prompt for confirmation
 pub fn confirm(prompt: &str) -> bool {
     print!("{prompt} [y/N] ");
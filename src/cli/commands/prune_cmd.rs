@@ -0,0 +1,194 @@
+//! Prune command - remove repositories whose directory no longer exists.
+
+use std::io;
+
+use owo_colors::OwoColorize;
+
+use crate::cli::args::Args;
+use crate::db::{Database, Repository};
+use crate::error::Result;
+
+use super::{confirm, print_success, print_warning, use_colors};
+
+/// Whether a repository's directory (or clone, for remote repositories) is gone.
+/// Stdin repositories have no directory and are never stale. An error means the
+/// path could not be checked (permissions, a stale mount), so it is unknown
+/// rather than missing.
+fn is_stale(repo: &Repository) -> io::Result<bool> {
+    if repo.is_stdin() {
+        return Ok(false);
+    }
+    repo.path.try_exists().map(|exists| !exists)
+}
+
+/// Warn about repositories whose path could not be checked and return their
+/// JSON entries.
+fn report_unknown(
+    unknown: &[(Repository, io::Error)],
+    args: &Args,
+    colors: bool,
+) -> Vec<serde_json::Value> {
+    if !args.json && !args.quiet {
+        for (repo, e) in unknown {
+            print_warning(
+                &format!(
+                    "Skipped \"{}\": cannot check {} ({e})",
+                    repo.name,
+                    repo.path.display()
+                ),
+                colors,
+            );
+        }
+    }
+    unknown
+        .iter()
+        .map(|(r, e)| {
+            serde_json::json!({
+                "name": r.name,
+                "path": r.path.to_string_lossy(),
+                "error": e.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn run(force: bool, args: &Args) -> Result<()> {
+    let colors = use_colors(args);
+    let db = Database::open()?;
+
+    let mut stale: Vec<Repository> = Vec::new();
+    let mut unknown: Vec<(Repository, io::Error)> = Vec::new();
+    for repo in db.list_repositories()? {
+        match is_stale(&repo) {
+            Ok(true) => stale.push(repo),
+            Ok(false) => {}
+            Err(e) => unknown.push((repo, e)),
+        }
+    }
+
+    let unknown_json = report_unknown(&unknown, args, colors);
+
+    if stale.is_empty() {
+        if args.json {
+            println!(
+                "{}",
+                serde_json::json!({"success": true, "pruned": [], "total": 0, "unknown": unknown_json})
+            );
+        } else if !args.quiet {
+            print_success("No stale repositories found", colors);
+        }
+        return Ok(());
+    }
+
+    if !force && !args.json {
+        println!("Repositories whose path no longer exists:");
+        for repo in &stale {
+            if colors {
+                println!(
+                    "  {} {} ({} files)",
+                    repo.name.cyan(),
+                    repo.path.display().to_string().dimmed(),
+                    repo.file_count
+                );
+            } else {
+                println!(
+                    "  {} {} ({} files)",
+                    repo.name,
+                    repo.path.display(),
+                    repo.file_count
+                );
+            }
+        }
+
+        let prompt = format!(
+            "Remove {} repositor{} from index?",
+            stale.len(),
+            if stale.len() == 1 { "y" } else { "ies" }
+        );
+        if !confirm(&prompt) {
+            if !args.quiet {
+                println!("Cancelled.");
+            }
+            return Ok(());
+        }
+    }
+
+    for repo in &stale {
+        db.delete_repository(repo.id)?;
+    }
+
+    if args.json {
+        let pruned: Vec<_> = stale
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "name": r.name,
+                    "path": r.path.to_string_lossy(),
+                    "files_removed": r.file_count,
+                    "source_type": r.source_type.as_str(),
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::json!({
+                "success": true,
+                "pruned": pruned,
+                "total": stale.len(),
+                "unknown": unknown_json,
+            })
+        );
+    } else if !args.quiet {
+        for repo in &stale {
+            if colors {
+                print_success(
+                    &format!(
+                        "Pruned \"{}\" ({} files)",
+                        repo.name.cyan(),
+                        repo.file_count
+                    ),
+                    true,
+                );
+            } else {
+                print_success(
+                    &format!("Pruned \"{}\" ({} files)", repo.name, repo.file_count),
+                    false,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_stale() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let present = dir.path().join("present");
+        std::fs::create_dir(&present).unwrap();
+        let repo = db.add_repository(&present, None).unwrap();
+        assert!(!is_stale(&repo).unwrap());
+
+        std::fs::remove_dir(&present).unwrap();
+        assert!(is_stale(&repo).unwrap());
+
+        // A path below a regular file cannot be checked: unknown, not stale
+        #[cfg(unix)]
+        {
+            let file = dir.path().join("file.txt");
+            std::fs::write(&file, "x").unwrap();
+            let mut unreadable = repo.clone();
+            unreadable.path = file.join("repo");
+            assert!(is_stale(&unreadable).is_err());
+        }
+
+        let stdin = db.add_stdin_repository("notes").unwrap();
+        assert!(!is_stale(&stdin).unwrap());
+    }
+}
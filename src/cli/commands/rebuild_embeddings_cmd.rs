@@ -1,21 +1,39 @@
 //! Rebuild embeddings command handler
 
 use owo_colors::OwoColorize;
-use std::io::{self, Write};
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use crate::cli::args::Args;
 use crate::config::Config;
-use crate::core::Embedder;
-use crate::db::Database;
+use crate::core::{ChunkEmbedding, Embedder, Indexer, TextChunk};
+use crate::db::{Database, FileRecord, FileType};
 use crate::error::Result;
 
-use super::use_colors;
+use super::{confirm, print_success, print_warning, use_colors};
 
-/// Rebuild embeddings for all or specific repositories
+/// Rebuild embeddings for all or specific repositories. With `missing_only`,
+/// only files without any embeddings are embedded.
 #[allow(clippy::too_many_lines)]
-pub fn run(repo_filter: Option<String>, args: &Args) -> Result<()> {
-    let colors = use_colors(args.no_color);
-    let config = Config::load()?;
+pub fn run(
+    repo_filter: Option<String>,
+    batch_size: usize,
+    model: Option<String>,
+    missing_only: bool,
+    args: &Args,
+) -> Result<()> {
+    let batch_size = batch_size.max(1);
+    let colors = use_colors(args);
+    let mut config = Config::load()?;
+
+    // Validate a --model override before doing any work
+    let configured_model = config.embedding_model.clone();
+    if let Some(model) = &model {
+        Embedder::parse_model_name(model)?;
+        config.embedding_model.clone_from(model);
+    }
 
     // Check if semantic search is enabled
     if !config.enable_semantic_search {
@@ -32,17 +50,40 @@ pub fn run(repo_filter: Option<String>, args: &Args) -> Result<()> {
         return Ok(());
     }
 
+    let db = Database::open()?;
+
+    // Find files lacking embeddings up front, so a complete index doesn't load the model
+    let mut missing: Option<HashMap<i64, Vec<FileRecord>>> = None;
+    if missing_only {
+        let mut by_repo: HashMap<i64, Vec<FileRecord>> = HashMap::new();
+        for file in db.files_without_embeddings(repo_filter.as_deref())? {
+            by_repo.entry(file.repo_id).or_default().push(file);
+        }
+        if by_repo.is_empty() {
+            if !args.quiet {
+                print_success("All indexed files already have embeddings", colors);
+            }
+            return Ok(());
+        }
+        missing = Some(by_repo);
+    }
+
     // Load embedder
     if !args.quiet {
         if colors {
-            print!("{} Loading embedding model... ", "→".blue());
+            print!(
+                "{} Loading embedding model {}... ",
+                "→".blue(),
+                config.embedding_model.cyan()
+            );
         } else {
-            print!("Loading embedding model... ");
+            print!("Loading embedding model {}... ", config.embedding_model);
         }
         io::stdout().flush().ok();
     }
 
-    let embedder = match Embedder::new(&config.embedding_model) {
+    let model_dir = config.model_cache_dir();
+    let embedder = match Embedder::new(&config.embedding_model, model_dir.as_deref()) {
         Ok(e) => {
             if !args.quiet {
                 if colors {
@@ -66,18 +107,17 @@ pub fn run(repo_filter: Option<String>, args: &Args) -> Result<()> {
         }
     };
 
-    let db = Database::open()?;
-
     // Get repositories to process
     let repos = db.list_repositories()?;
-    let repos_to_process: Vec<_> = if let Some(ref filter) = repo_filter {
-        repos
-            .into_iter()
-            .filter(|r| r.name.contains(filter))
-            .collect()
-    } else {
-        repos
-    };
+    let repos_to_process: Vec<_> = repos
+        .into_iter()
+        .filter(|r| {
+            repo_filter
+                .as_ref()
+                .is_none_or(|f| r.name.contains(f.as_str()))
+        })
+        .filter(|r| missing.as_ref().is_none_or(|m| m.contains_key(&r.id)))
+        .collect();
 
     if repos_to_process.is_empty() {
         if !args.quiet {
@@ -100,28 +140,45 @@ pub fn run(repo_filter: Option<String>, args: &Args) -> Result<()> {
         return Ok(());
     }
 
+    let indexer = Indexer::new(db.clone(), config.clone());
+    let embed = |chunks: &[TextChunk]| embedder.embed_chunks_batched(chunks, Some(batch_size));
+    let mut batch = PendingBatch::default();
+    let mut failed = Vec::new();
     let mut total_files = 0;
     let mut total_embeddings = 0;
+    let mut excluded_files = 0;
+    let mut embed_time = Duration::ZERO;
 
     for repo in &repos_to_process {
         // Get files for this repository
-        let files = db.get_repository_files(repo.id)?;
+        let files = match missing.as_mut() {
+            Some(missing) => missing.remove(&repo.id).unwrap_or_default(),
+            None => db.get_repository_files(repo.id)?,
+        };
         let file_count = files.len();
+        let files_label = if missing_only {
+            "files without embeddings"
+        } else {
+            "files"
+        };
 
         if !args.quiet {
             if colors {
                 println!(
-                    "{} Processing {} ({} files)...",
+                    "{} Processing {} ({} {})...",
                     "→".blue(),
                     repo.name.cyan(),
-                    file_count
+                    file_count,
+                    files_label
                 );
             } else {
-                println!("Processing {} ({} files)...", repo.name, file_count);
+                println!("Processing {} ({file_count} {files_label})...", repo.name);
             }
         }
 
         let mut processed = 0;
+        let mut excluded_ids = Vec::new();
+        let mut stale_ids = Vec::new();
 
         for file in &files {
             processed += 1;
@@ -136,39 +193,51 @@ pub fn run(repo_filter: Option<String>, args: &Args) -> Result<()> {
                 io::stdout().flush().ok();
             }
 
-            // Read file content
-            let full_path = repo.path.join(&file.relative_path);
-            let Ok(content) = std::fs::read_to_string(&full_path) else {
-                continue; // Skip files we can't read
+            // Excluded types stay lexically searchable; drop any stale vectors
+            if !config.embeds_file_type(&file.file_type) {
+                excluded_ids.push(file.id);
+                continue;
+            }
+
+            // Decode like indexing does; stdin repositories have no files on disk
+            let label = PathBuf::from(format!("{}:{}", repo.name, file.relative_path.display()));
+            let Some(content) = indexer.embedding_text(repo, file)? else {
+                // Vectors left from an earlier run (or another model) would be stale
+                stale_ids.push(file.id);
+                failed.push((label, "content not readable from disk or the index".into()));
+                continue;
             };
 
-            // Generate embeddings
-            if let Ok(chunk_embeddings) = embedder.embed_content(&content) {
-                if chunk_embeddings.is_empty() {
-                    continue;
-                }
+            let file_type = FileType::from_extension(
+                file.relative_path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or_default(),
+            );
+            let chunks = if matches!(file_type, FileType::Code(_)) {
+                Embedder::chunk_code(&content, 512, 50)
+            } else {
+                Embedder::chunk_text(&content, 512, 50)
+            };
+            if chunks.is_empty() {
+                continue;
+            }
+            batch.push(file.id, label, chunks);
 
-                let embeddings: Vec<(usize, usize, usize, &str, &[f32])> = chunk_embeddings
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, ce)| {
-                        (
-                            idx,
-                            ce.chunk.start_offset,
-                            ce.chunk.end_offset,
-                            ce.chunk.text.as_str(),
-                            ce.embedding.as_slice(),
-                        )
-                    })
-                    .collect();
-
-                if db.store_embeddings(file.id, &embeddings).is_ok() {
-                    total_files += 1;
-                    total_embeddings += embeddings.len();
-                }
+            // Embed once enough chunks from one or more files have accumulated
+            if batch.chunk_count() >= batch_size {
+                let stats = batch.flush(&db, embed);
+                total_files += stats.files;
+                total_embeddings += stats.chunks;
+                embed_time += stats.elapsed;
+                failed.extend(stats.failed);
             }
         }
 
+        excluded_files += excluded_ids.len();
+        db.delete_embeddings(&excluded_ids)?;
+        db.delete_embeddings(&stale_ids)?;
+
         // Clear the progress line
         if !args.quiet && file_count > 0 {
             print!("\r                                                  \r");
@@ -176,10 +245,18 @@ pub fn run(repo_filter: Option<String>, args: &Args) -> Result<()> {
         }
     }
 
+    // Embed whatever is left over
+    let stats = batch.flush(&db, embed);
+    total_files += stats.files;
+    total_embeddings += stats.chunks;
+    embed_time += stats.elapsed;
+    failed.extend(stats.failed);
+
+    let verb = if missing_only { "Added" } else { "Rebuilt" };
     if !args.quiet {
         if colors {
             println!(
-                "{} Rebuilt embeddings for {} file{} ({} chunks) in {} repositor{}",
+                "{} {verb} embeddings for {} file{} ({} chunks) in {} repositor{}",
                 "✓".green(),
                 total_files.to_string().green(),
                 if total_files == 1 { "" } else { "s" },
@@ -191,10 +268,23 @@ pub fn run(repo_filter: Option<String>, args: &Args) -> Result<()> {
                     "ies"
                 }
             );
+            println!(
+                "  Embedding time: {:.1}s (batch size {})",
+                embed_time.as_secs_f64(),
+                batch_size
+            );
+            if excluded_files > 0 {
+                println!(
+                    "  Skipped {} file{} excluded by {}",
+                    excluded_files.to_string().yellow(),
+                    if excluded_files == 1 { "" } else { "s" },
+                    "embedding_exclude_types".cyan()
+                );
+            }
         } else {
             println!();
             println!(
-                "Rebuilt embeddings for {} file(s) ({} chunks) in {} repositor{}",
+                "{verb} embeddings for {} file(s) ({} chunks) in {} repositor{}",
                 total_files,
                 total_embeddings,
                 repos_to_process.len(),
@@ -204,8 +294,226 @@ pub fn run(repo_filter: Option<String>, args: &Args) -> Result<()> {
                     "ies"
                 }
             );
+            println!(
+                "  Embedding time: {:.1}s (batch size {})",
+                embed_time.as_secs_f64(),
+                batch_size
+            );
+            if excluded_files > 0 {
+                println!("  Skipped {excluded_files} file(s) excluded by embedding_exclude_types");
+            }
+        }
+    }
+
+    if !failed.is_empty() && !args.quiet {
+        print_warning(
+            &format!(
+                "Failed to embed {} file{}:",
+                failed.len(),
+                if failed.len() == 1 { "" } else { "s" }
+            ),
+            colors,
+        );
+        for (path, error) in &failed {
+            eprintln!("    {} ({error})", path.display());
         }
     }
 
+    if let Some(model) = model.filter(|m| !m.eq_ignore_ascii_case(&configured_model)) {
+        offer_to_persist_model(&model, &configured_model, colors, args)?;
+    }
+
     Ok(())
 }
+
+/// After a rebuild with `--model`, offer to make it the configured `embedding_model`
+fn offer_to_persist_model(model: &str, configured: &str, colors: bool, args: &Args) -> Result<()> {
+    if args.quiet {
+        return Ok(());
+    }
+
+    println!();
+    if io::stdin().is_terminal() && confirm(&format!("Save {model} as embedding_model in config?"))
+    {
+        let mut config = Config::load()?;
+        config.embedding_model = model.to_string();
+        config.save()?;
+        print_success(&format!("Set embedding_model = {model}"), colors);
+    } else {
+        println!("Note: searches still embed queries with {configured}.");
+        println!("  Persist the new model with: kdex config embedding_model {model}");
+    }
+
+    Ok(())
+}
+
+/// Result of embedding and storing one batch
+#[derive(Default)]
+struct FlushStats {
+    files: usize,
+    chunks: usize,
+    elapsed: Duration,
+    /// Files whose chunks could not be embedded or stored, with the error
+    failed: Vec<(PathBuf, String)>,
+}
+
+/// Chunks from several files waiting to be embedded together
+#[derive(Default)]
+struct PendingBatch {
+    files: Vec<(i64, PathBuf, Vec<TextChunk>)>,
+}
+
+impl PendingBatch {
+    fn push(&mut self, file_id: i64, path: PathBuf, chunks: Vec<TextChunk>) {
+        self.files.push((file_id, path, chunks));
+    }
+
+    fn chunk_count(&self) -> usize {
+        self.files.iter().map(|(_, _, chunks)| chunks.len()).sum()
+    }
+
+    /// Embed all pending chunks in one `embed` call and store them per file.
+    /// When the call fails, each file is embedded on its own so that only
+    /// the files that fail again are left without embeddings.
+    fn flush(
+        &mut self,
+        db: &Database,
+        embed: impl Fn(&[TextChunk]) -> Result<Vec<ChunkEmbedding>>,
+    ) -> FlushStats {
+        let mut stats = FlushStats::default();
+        if self.files.is_empty() {
+            return stats;
+        }
+
+        let files = std::mem::take(&mut self.files);
+        let all_chunks: Vec<TextChunk> = files
+            .iter()
+            .flat_map(|(_, _, chunks)| chunks.iter().cloned())
+            .collect();
+
+        let start = Instant::now();
+        match embed(&all_chunks) {
+            Ok(chunk_embeddings) => {
+                let mut remaining = chunk_embeddings.as_slice();
+                for (file_id, path, chunks) in &files {
+                    let (file_embeddings, rest) =
+                        remaining.split_at(chunks.len().min(remaining.len()));
+                    remaining = rest;
+                    stats.store(db, *file_id, path, file_embeddings);
+                }
+            }
+            Err(e) if files.len() == 1 => stats.failed.push((files[0].1.clone(), e.to_string())),
+            Err(_) => {
+                for (file_id, path, chunks) in &files {
+                    match embed(chunks) {
+                        Ok(file_embeddings) => stats.store(db, *file_id, path, &file_embeddings),
+                        Err(e) => stats.failed.push((path.clone(), e.to_string())),
+                    }
+                }
+            }
+        }
+        stats.elapsed = start.elapsed();
+
+        stats
+    }
+}
+
+impl FlushStats {
+    /// Store the embeddings of one file and count them
+    fn store(
+        &mut self,
+        db: &Database,
+        file_id: i64,
+        path: &Path,
+        file_embeddings: &[ChunkEmbedding],
+    ) {
+        let embeddings: Vec<(usize, usize, usize, &str, &[f32])> = file_embeddings
+            .iter()
+            .enumerate()
+            .map(|(idx, ce)| {
+                (
+                    idx,
+                    ce.chunk.start_offset,
+                    ce.chunk.end_offset,
+                    ce.chunk.text.as_str(),
+                    ce.embedding.as_slice(),
+                )
+            })
+            .collect();
+        if embeddings.is_empty() {
+            return;
+        }
+
+        match db.store_embeddings(file_id, &embeddings) {
+            Ok(()) => {
+                self.files += 1;
+                self.chunks += embeddings.len();
+            }
+            Err(e) => self.failed.push((path.to_path_buf(), e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    fn chunk(text: &str) -> TextChunk {
+        TextChunk {
+            text: text.to_string(),
+            start_offset: 0,
+            end_offset: text.len(),
+        }
+    }
+
+    #[test]
+    fn test_flush_retries_failed_batch_file_by_file() {
+        let db = Database::open_in_memory().unwrap();
+        let repo = db.add_stdin_repository("notes").unwrap();
+        let mut batch = PendingBatch::default();
+        for name in ["a.md", "bad.md", "c.md"] {
+            let file_id = db
+                .insert_file(
+                    repo.id,
+                    Path::new(name),
+                    name,
+                    0,
+                    chrono::Utc::now(),
+                    "markdown",
+                    name,
+                )
+                .unwrap();
+            batch.push(file_id, PathBuf::from(name), vec![chunk(name), chunk(name)]);
+        }
+
+        let calls = std::cell::Cell::new(0);
+        let embed = |chunks: &[TextChunk]| {
+            calls.set(calls.get() + 1);
+            if chunks.iter().any(|c| c.text == "bad.md") {
+                return Err(AppError::Other("model failure".into()));
+            }
+            Ok(chunks
+                .iter()
+                .map(|c| ChunkEmbedding {
+                    chunk: c.clone(),
+                    embedding: vec![1.0, 0.0],
+                })
+                .collect())
+        };
+        let stats = batch.flush(&db, embed);
+
+        // One call for the whole batch, then one per file
+        assert_eq!(calls.get(), 4);
+        assert_eq!((stats.files, stats.chunks), (2, 4));
+        assert_eq!(stats.failed.len(), 1);
+        assert_eq!(stats.failed[0].0, PathBuf::from("bad.md"));
+        let missing: Vec<PathBuf> = db
+            .files_without_embeddings(None)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.relative_path)
+            .collect();
+        assert_eq!(missing, [PathBuf::from("bad.md")]);
+    }
+}
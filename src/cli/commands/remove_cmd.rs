@@ -1,41 +1,94 @@
 use owo_colors::OwoColorize;
-use std::path::Path;
+use std::io::{self, IsTerminal};
 
 use crate::cli::args::Args;
-use crate::core::remote::{delete_clone, is_remote_clone};
-use crate::db::{Database, SourceType};
+use crate::core::remote::{self, is_remote_clone};
+use crate::db::{Database, Repository, SourceType};
 use crate::error::{AppError, Result};
 
 use super::{confirm, print_success, print_warning, use_colors};
 
-pub fn run(path: &Path, force: bool, args: &Args) -> Result<()> {
-    let colors = use_colors(args.no_color);
+/// Outcome of removing a single repository
+struct Removed {
+    repo: Repository,
+    clone_deleted: bool,
+}
+
+#[allow(clippy::too_many_lines)]
+#[allow(clippy::fn_params_excessive_bools)]
+pub fn run(
+    target: Option<&str>,
+    all: bool,
+    force: bool,
+    delete_clone: bool,
+    keep_clone: bool,
+    args: &Args,
+) -> Result<()> {
+    let colors = use_colors(args);
     let db = Database::open()?;
 
-    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let repos = if all {
+        db.list_repositories()?
+    } else {
+        let target = target.ok_or_else(|| {
+            AppError::Other("Specify a repository path or name, or use --all".into())
+        })?;
+        vec![db.resolve_repository(target)?.into_exact(target)?]
+    };
 
-    // Check if repository exists
-    let repo = db
-        .get_repository_by_path(&canonical)?
-        .ok_or_else(|| AppError::RepoNotFound(canonical.clone()))?;
+    if repos.is_empty() {
+        if args.json {
+            println!("{}", serde_json::json!({"success": true, "removed": []}));
+        } else if !args.quiet {
+            println!("No repositories indexed.");
+        }
+        return Ok(());
+    }
 
-    let is_remote = repo.source_type == SourceType::Remote;
-    let will_delete_clone = is_remote && is_remote_clone(&repo.path).unwrap_or(false);
+    // Wiping the whole index needs an explicit --force when nobody can answer the prompt
+    if all && !force && (args.json || !io::stdin().is_terminal()) {
+        return Err(AppError::Other(
+            "Removing all repositories without a terminal to confirm requires --force".into(),
+        ));
+    }
 
     // Confirm deletion
     if !force && !args.json {
-        let prompt = if will_delete_clone {
+        let prompt = if all {
+            let total_files: i64 = repos.iter().map(|r| r.file_count).sum();
             format!(
-                "Remove \"{}\" from index AND delete cloned files at {}? ({} files)",
-                repo.name,
-                repo.path.display(),
-                repo.file_count
+                "Remove ALL {} repositor{} from index? ({} files){}",
+                repos.len(),
+                if repos.len() == 1 { "y" } else { "ies" },
+                total_files,
+                if delete_clone {
+                    " Cloned files of remote repositories will also be deleted."
+                } else {
+                    ""
+                }
             )
         } else {
-            format!(
-                "Remove \"{}\" from index? ({} files will be removed from the index)",
-                repo.name, repo.file_count
-            )
+            let repo = &repos[0];
+            if will_delete_clone(repo, delete_clone) {
+                format!(
+                    "Remove \"{}\" from index AND delete cloned files at {}? ({} files)",
+                    repo.name,
+                    repo.path.display(),
+                    repo.file_count
+                )
+            } else if repo.source_type == SourceType::Remote {
+                format!(
+                    "Remove \"{}\" from index? ({} files; the clone at {} is kept)",
+                    repo.name,
+                    repo.file_count,
+                    repo.path.display()
+                )
+            } else {
+                format!(
+                    "Remove \"{}\" from index? ({} files will be removed from the index)",
+                    repo.name, repo.file_count
+                )
+            }
         };
 
         if !confirm(&prompt) {
@@ -46,59 +99,123 @@ pub fn run(path: &Path, force: bool, args: &Args) -> Result<()> {
         }
     }
 
-    // Delete repository from database
-    db.delete_repository(repo.id)?;
-
-    // If remote, also delete the cloned directory
-    let clone_deleted = if will_delete_clone {
-        match delete_clone(&repo.path) {
-            Ok(()) => true,
-            Err(e) => {
-                if !args.quiet && !args.json {
-                    print_warning(&format!("Could not delete clone directory: {e}"), colors);
-                }
-                false
-            }
-        }
-    } else {
-        false
-    };
+    let mut removed = Vec::with_capacity(repos.len());
+    for repo in repos {
+        let clone_deleted = remove_repository(&db, &repo, delete_clone, args, colors)?;
+        removed.push(Removed {
+            repo,
+            clone_deleted,
+        });
+    }
 
     if args.json {
-        println!(
-            "{}",
-            serde_json::json!({
-                "success": true,
-                "name": repo.name,
-                "path": canonical.to_string_lossy(),
-                "files_removed": repo.file_count,
-                "clone_deleted": clone_deleted,
-                "source_type": if is_remote { "remote" } else { "local" },
+        let json_removed: Vec<_> = removed
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "name": r.repo.name,
+                    "path": r.repo.path.to_string_lossy(),
+                    "files_removed": r.repo.file_count,
+                    "clone_deleted": r.clone_deleted,
+                    "source_type": r.repo.source_type.as_str(),
+                })
             })
-        );
-    } else if !args.quiet {
-        if colors {
-            print_success(
-                &format!(
-                    "Removed \"{}\" ({} files)",
-                    repo.name.cyan(),
-                    repo.file_count
-                ),
-                true,
+            .collect();
+
+        if all {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "success": true,
+                    "removed": json_removed,
+                    "total": removed.len(),
+                })
             );
         } else {
-            print_success(
-                &format!("Removed \"{}\" ({} files)", repo.name, repo.file_count),
-                false,
-            );
+            let mut output = json_removed.into_iter().next().unwrap_or_default();
+            output["success"] = serde_json::json!(true);
+            println!("{output}");
+        }
+    } else if !args.quiet {
+        for r in &removed {
+            if colors {
+                print_success(
+                    &format!(
+                        "Removed \"{}\" ({} files)",
+                        r.repo.name.cyan(),
+                        r.repo.file_count
+                    ),
+                    true,
+                );
+            } else {
+                print_success(
+                    &format!("Removed \"{}\" ({} files)", r.repo.name, r.repo.file_count),
+                    false,
+                );
+            }
         }
 
-        if clone_deleted {
+        if removed.iter().any(|r| r.clone_deleted) {
             println!("Cloned directory deleted.");
-        } else if !is_remote {
+        }
+        if removed
+            .iter()
+            .any(|r| r.repo.source_type == SourceType::Local)
+        {
             println!("Note: The actual files were not affected.");
         }
+        if !delete_clone
+            && !keep_clone
+            && removed
+                .iter()
+                .any(|r| r.repo.source_type == SourceType::Remote)
+        {
+            println!("Note: Cloned files were kept. Use --delete-clone to delete them.");
+        }
     }
 
     Ok(())
 }
+
+/// Whether removing this repository will also delete files on disk
+fn will_delete_clone(repo: &Repository, delete_clone: bool) -> bool {
+    delete_clone
+        && repo.source_type == SourceType::Remote
+        && is_remote_clone(&repo.path).unwrap_or(false)
+}
+
+/// Remove a repository from the index, deleting its clone if requested.
+/// Returns whether the clone directory was deleted.
+fn remove_repository(
+    db: &Database,
+    repo: &Repository,
+    delete_clone: bool,
+    args: &Args,
+    colors: bool,
+) -> Result<bool> {
+    db.delete_repository(repo.id)?;
+
+    if !will_delete_clone(repo, delete_clone) {
+        // Never delete directories kdex did not clone itself
+        if delete_clone && repo.source_type == SourceType::Remote && !args.quiet && !args.json {
+            print_warning(
+                &format!(
+                    "{} is not a kdex-managed clone; its files were kept",
+                    repo.path.display()
+                ),
+                colors,
+            );
+        }
+        return Ok(false);
+    }
+
+    match remote::delete_clone(&repo.path) {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            if !args.quiet && !args.json {
+                print_warning(&format!("Could not delete clone directory: {e}"), colors);
+            }
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Repos command - manage indexed repositories in place
+
+use owo_colors::OwoColorize;
+
+use crate::cli::args::Args;
+use crate::core::open_in_file_manager;
+use crate::db::{Database, Repository};
+use crate::error::{AppError, Result};
+
+use super::{print_success, use_colors};
+
+/// Rename the repository `old` (by exact name or path) to `new`.
+/// Files, embeddings and links stay indexed; only the name changes.
+pub fn rename(old: &str, new: &str, args: &Args) -> Result<()> {
+    let colors = use_colors(args);
+    let db = Database::open()?;
+
+    let new = new.trim();
+    let (repo, renamed) = rename_in(&db, old, new)?;
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "success": true,
+                "old_name": repo.name,
+                "name": new,
+                "path": repo.path.to_string_lossy(),
+                "renamed": renamed,
+            })
+        );
+    } else if !args.quiet {
+        if !renamed {
+            println!("Repository is already named \"{new}\"");
+        } else if colors {
+            print_success(
+                &format!("Renamed {} → {}", repo.name.dimmed(), new.cyan()),
+                colors,
+            );
+        } else {
+            print_success(&format!("Renamed {} → {new}", repo.name), colors);
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate and apply a rename; returns the repository as it was before and
+/// whether its name changed
+fn rename_in(db: &Database, old: &str, new: &str) -> Result<(Repository, bool)> {
+    let new = new.trim();
+    if new.is_empty() {
+        return Err(AppError::Other("Repository name can't be empty".into()));
+    }
+
+    let repos = db.list_repositories()?;
+    let repo = db.resolve_repository(old)?.into_exact(old)?;
+    if let Some(existing) = repos.iter().find(|r| r.name == new && r.id != repo.id) {
+        return Err(AppError::Other(format!(
+            "A repository named \"{new}\" already exists ({}). Choose another name.",
+            existing.path.display()
+        )));
+    }
+
+    let renamed = repo.name != new;
+    if renamed {
+        db.rename_repository(repo.id, new)?;
+    }
+    Ok((repo, renamed))
+}
+
+/// Reveal the directory of the repository `query` (by name or path; the clone
+/// for remote repositories) in the platform file manager. Without a graphical
+/// session the path is printed instead, e.g. for `cd "$(kdex repos open notes)"`.
+pub fn open(query: &str, args: &Args) -> Result<()> {
+    let colors = use_colors(args);
+    let db = Database::open()?;
+
+    let repo = db.resolve_repository(query)?.into_result(query)?;
+    if repo.is_stdin() {
+        return Err(AppError::Other(format!(
+            "\"{}\" was indexed from stdin and has no directory to open",
+            repo.name
+        )));
+    }
+    if !repo.path.is_dir() {
+        return Err(AppError::PathNotFound(repo.path));
+    }
+
+    let opened = open_in_file_manager(&repo.path)?;
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "success": true,
+                "name": repo.name,
+                "path": repo.path.to_string_lossy(),
+                "opened": opened,
+            })
+        );
+    } else if !opened {
+        // Nothing to open it in: the bare path is the useful output
+        println!("{}", repo.path.display());
+    } else if !args.quiet {
+        if colors {
+            print_success(
+                &format!("Opened {}", repo.path.display().to_string().cyan()),
+                colors,
+            );
+        } else {
+            print_success(&format!("Opened {}", repo.path.display()), colors);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_rejects_empty_or_duplicate_name() {
+        let db = Database::open_in_memory().unwrap();
+        db.add_stdin_repository("notes").unwrap();
+        db.add_stdin_repository("work").unwrap();
+
+        let err = rename_in(&db, "notes", "  ").unwrap_err().to_string();
+        assert!(err.contains("can't be empty"), "{err}");
+
+        let err = rename_in(&db, "notes", "work").unwrap_err().to_string();
+        assert!(err.contains("already exists"), "{err}");
+
+        let names: Vec<_> = db
+            .list_repositories()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+        assert!(names.contains(&"notes".to_string()));
+        assert!(names.contains(&"work".to_string()));
+    }
+
+    #[test]
+    fn test_rename_updates_stdin_path() {
+        let db = Database::open_in_memory().unwrap();
+        let repo = db.add_stdin_repository("notes").unwrap();
+
+        let (before, renamed) = rename_in(&db, "notes", " journal ").unwrap();
+        assert!(renamed);
+        assert_eq!(before.id, repo.id);
+
+        let after = db
+            .resolve_repository("journal")
+            .unwrap()
+            .into_exact("journal")
+            .unwrap();
+        assert_eq!(after.id, repo.id);
+        assert_eq!(after.path, std::path::PathBuf::from("stdin:journal"));
+        assert!(db
+            .get_repository_by_path(std::path::Path::new("stdin:notes"))
+            .unwrap()
+            .is_none());
+
+        // Renaming to the current name is a no-op
+        let (_, renamed) = rename_in(&db, "journal", "journal").unwrap();
+        assert!(!renamed);
+    }
+}
@@ -1,78 +1,926 @@
-use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use owo_colors::OwoColorize;
 use regex::Regex;
 
-use crate::cli::args::Args;
+use crate::cli::args::{Args, GroupBy, GroupSort, HighlightMode};
 use crate::config::Config;
-use crate::core::{Embedder, SearchMode, Searcher};
-use crate::db::Database;
-use crate::error::Result;
+use crate::core::{
+    fetch_capped, fetch_kept, highlight, open_in_editor, replace_highlights, run_preview,
+    strip_highlights, Embedder, IndexedFileReader, PathExclude, SearchMode, Searcher,
+    UnifiedSearchResult,
+};
+use crate::db::{Database, RepoStatus, Repository, SearchFilters, SearchResult, Within};
+use crate::error::{AppError, Result};
 
-use super::use_colors;
+use super::{
+    confirm, finish_output, open_output, print_warning, use_colors, warn_missing_files,
+    write_porcelain,
+};
 
-#[allow(clippy::needless_pass_by_value)]
+/// Content cap for `--with-content` without `--max-chars`; keeps exports of
+/// large files (and of many results) to a manageable size
+const DEFAULT_CONTENT_MAX_CHARS: usize = 4000;
+
+/// Result limit of `--limit 0` / `--all`
+const UNLIMITED: usize = usize::MAX;
+
+/// Repositories a search leaves out because they aren't ready (still indexing
+/// or syncing, or their last run failed); none with `--include-all`
+fn not_ready_repositories(db: &Database, include_all: bool) -> Result<Vec<Repository>> {
+    if include_all {
+        return Ok(Vec::new());
+    }
+    Ok(db
+        .list_repositories()?
+        .into_iter()
+        .filter(|r| r.status != RepoStatus::Ready)
+        .collect())
+}
+
+/// `not_ready` entries of a no-results JSON response
+fn not_ready_json(repos: &[Repository]) -> Vec<serde_json::Value> {
+    repos
+        .iter()
+        .map(|r| serde_json::json!({"name": r.name, "status": r.status.as_str()}))
+        .collect()
+}
+
+/// Warn (on stderr) about repositories a search with results left out, so
+/// they don't go unnoticed when only part of the index was searched
+fn warn_not_ready(db: &Database, include_all: bool, args: &Args) -> Result<()> {
+    if args.quiet || args.json {
+        return Ok(());
+    }
+    if let Some(hint) = not_ready_hint(&not_ready_repositories(db, include_all)?) {
+        print_warning(&hint, use_colors(args));
+    }
+    Ok(())
+}
+
+/// Hint naming the repositories that weren't searched, if any
+fn not_ready_hint(repos: &[Repository]) -> Option<String> {
+    if repos.is_empty() {
+        return None;
+    }
+    let names: Vec<String> = repos
+        .iter()
+        .map(|r| format!("{} ({})", r.name, r.status.as_str()))
+        .collect();
+    Some(format!(
+        "Skipped {} repositor{} that {} not ready: {}. Search {} with --include-all",
+        repos.len(),
+        if repos.len() == 1 { "y" } else { "ies" },
+        if repos.len() == 1 { "is" } else { "are" },
+        names.join(", "),
+        if repos.len() == 1 { "it" } else { "them" },
+    ))
+}
+
+/// Warn (on stderr) that `--timeout` cut a search short
+fn warn_timed_out(args: &Args) {
+    if !args.quiet {
+        print_warning(
+            "Search timed out; showing the results found so far",
+            use_colors(args),
+        );
+    }
+}
+
+/// Record a search in the query log when `log_queries` is on. A failed
+/// write never fails the search itself.
+fn log_query(db: &Database, config: &Config, query: &str, mode: &str, result_count: usize) {
+    if config.log_queries {
+        let _ = db.log_search(query, mode, result_count);
+    }
+}
+
+/// `--show-score` prefix for a result line: the raw score, or nothing
+fn score_prefix(score: Option<f64>, colors: bool) -> String {
+    match score {
+        Some(score) if colors => format!("{} ", format!("{score:>9.4}").yellow()),
+        Some(score) => format!("{score:>9.4} "),
+        None => String::new(),
+    }
+}
+
+/// Short label for a stored file type, shown as `[md]` before each result
+fn type_label(file_type: &str) -> &str {
+    match file_type {
+        "markdown" => "md",
+        "plaintext" => "txt",
+        "orgmode" => "org",
+        "notebook" => "nb",
+        "config" => "cfg",
+        "unknown" => "?",
+        "rust" => "rs",
+        "python" => "py",
+        "javascript" => "js",
+        "typescript" => "ts",
+        "csharp" => "cs",
+        "ruby" => "rb",
+        "kotlin" => "kt",
+        "shell" => "sh",
+        "elixir" => "ex",
+        "erlang" => "erl",
+        "haskell" => "hs",
+        "clojure" => "clj",
+        "ocaml" => "ml",
+        "fsharp" => "fs",
+        // Short names (go, sql, rst, pdf, ...) are their own label
+        other => other,
+    }
+}
+
+/// File type prefix for a result line, colored by category (notes green,
+/// config yellow, code magenta), or nothing with `--no-type-labels`
+fn type_prefix(file_type: Option<&str>, colors: bool) -> String {
+    let Some(file_type) = file_type else {
+        return String::new();
+    };
+    let label = format!("[{}]", type_label(file_type));
+    if !colors {
+        return format!("{label} ");
+    }
+    let label = match file_type {
+        "markdown" | "plaintext" | "orgmode" | "rst" | "pdf" | "notebook" => {
+            label.green().to_string()
+        }
+        "config" => label.yellow().to_string(),
+        "unknown" => label.dimmed().to_string(),
+        _ => label.magenta().to_string(),
+    };
+    format!("{label} ")
+}
+
+/// Legend for the labels shown: `Types: [md] markdown, [rs] rust`, in order
+/// of first appearance
+fn print_type_legend<'a>(
+    out: &mut dyn Write,
+    file_types: impl IntoIterator<Item = &'a str>,
+    colors: bool,
+) -> Result<()> {
+    let mut seen: Vec<&str> = Vec::new();
+    for file_type in file_types {
+        if !seen.contains(&file_type) {
+            seen.push(file_type);
+        }
+    }
+    if seen.is_empty() {
+        return Ok(());
+    }
+    let entries: Vec<String> = seen
+        .iter()
+        .map(|t| format!("{}{t}", type_prefix(Some(t), colors)))
+        .collect();
+    if colors {
+        writeln!(out, "{} {}", "Types:".dimmed(), entries.join(", "))?;
+    } else {
+        writeln!(out, "Types: {}", entries.join(", "))?;
+    }
+    Ok(())
+}
+
+/// The title `--show-title` displays for a result, if it has a non-empty one
+fn shown_title(title: Option<&str>, show_title: bool) -> Option<&str> {
+    title.filter(|t| show_title && !t.trim().is_empty())
+}
+
+/// Print a result's location: `repo:relative/path`, or the absolute path,
+/// preceded by the score with `--show-score` and the file type label. With a title (`--show-title`)
+/// it reads `repo: Title (relative/path)`.
 #[allow(clippy::too_many_arguments)]
-#[allow(clippy::too_many_lines)]
-#[allow(clippy::fn_params_excessive_bools)]
-pub fn run(
-    query: String,
-    repo: Option<String>,
-    file_type: Option<String>,
-    _tag: Option<String>, // TODO: Implement tag filtering
-    limit: usize,
-    group_by_repo: bool,
-    semantic: bool,
-    hybrid: bool,
-    lexical: bool,
-    fuzzy: bool,
-    regex: bool,
-    args: &Args,
+fn print_result_location(
+    out: &mut dyn Write,
+    repo: &str,
+    path: &Path,
+    absolute_path: &Path,
+    absolute: bool,
+    title: Option<&str>,
+    score: Option<f64>,
+    file_type: Option<&str>,
+    colors: bool,
+) -> Result<()> {
+    write!(
+        out,
+        "{}{}",
+        score_prefix(score, colors),
+        type_prefix(file_type, colors)
+    )?;
+    if let Some(title) = title {
+        let path = if absolute { absolute_path } else { path }
+            .display()
+            .to_string();
+        match (absolute, colors) {
+            (true, true) => writeln!(out, "{} ({})", title.bold(), path.cyan())?,
+            (true, false) => writeln!(out, "{title} ({path})")?,
+            (false, true) => writeln!(
+                out,
+                "{}{} {} ({})",
+                repo.blue(),
+                ":".dimmed(),
+                title.bold(),
+                path.cyan()
+            )?,
+            (false, false) => writeln!(out, "{repo}: {title} ({path})")?,
+        }
+        return Ok(());
+    }
+    match (absolute, colors) {
+        (true, true) => writeln!(out, "{}", absolute_path.display().to_string().cyan())?,
+        (true, false) => writeln!(out, "{}", absolute_path.display())?,
+        (false, true) => writeln!(
+            out,
+            "{}{}{}",
+            repo.blue(),
+            ":".dimmed(),
+            path.display().to_string().cyan()
+        )?,
+        (false, false) => writeln!(out, "{repo}:{}", path.display())?,
+    }
+    Ok(())
+}
+
+/// Render highlight markers in a snippet per `--highlight`
+fn format_snippet(snippet: &str, highlight: HighlightMode, colors: bool) -> String {
+    match highlight {
+        HighlightMode::Always => replace_highlights(snippet, "\x1b[1;33m", "\x1b[0m"),
+        HighlightMode::Auto if colors => replace_highlights(snippet, "\x1b[1;33m", "\x1b[0m"),
+        HighlightMode::Auto => replace_highlights(snippet, "[", "]"),
+        HighlightMode::Never => strip_highlights(snippet),
+    }
+}
+
+/// Print up to `max_lines` lines of a result snippet, indented and dimmed when colored
+fn print_snippet(
+    out: &mut dyn Write,
+    snippet: &str,
+    indent: &str,
+    max_lines: usize,
+    highlight_mode: HighlightMode,
+    colors: bool,
 ) -> Result<()> {
-    let colors = use_colors(args.no_color);
+    let snippet = snippet.trim();
+    if snippet.is_empty() {
+        return Ok(());
+    }
+
+    let formatted = format_snippet(snippet, highlight_mode, colors);
+    for line in formatted.lines().take(max_lines) {
+        if colors {
+            writeln!(out, "{indent}{}", line.dimmed())?;
+        } else {
+            writeln!(out, "{indent}{line}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Grouping key of a result for `--group-by`
+fn group_key<'a>(group_by: GroupBy, repo: &'a str, file_type: &'a str) -> &'a str {
+    match group_by {
+        GroupBy::FileType => file_type,
+        GroupBy::Repo | GroupBy::None => repo,
+    }
+}
+
+/// `--json` output of grouped results: one object per group in the chosen
+/// order, built by `value` without the now-redundant grouping field. `key`
+/// gives a result's repository and file type. Callers add the fields
+/// describing the search.
+fn grouped_json<T>(
+    results: &[T],
+    group_by: GroupBy,
+    group_sort: GroupSort,
+    key: impl Fn(&T) -> (&str, &str),
+    value: impl Fn(&T) -> serde_json::Value,
+) -> serde_json::Value {
+    let key_field = match group_by {
+        GroupBy::FileType => "file_type",
+        GroupBy::Repo | GroupBy::None => "repo",
+    };
+    let groups: Vec<serde_json::Value> = group_results(
+        results,
+        |r| {
+            let (repo, file_type) = key(r);
+            group_key(group_by, repo, file_type)
+        },
+        group_sort,
+    )
+    .into_iter()
+    .map(|(key, group)| {
+        let values: Vec<serde_json::Value> = group
+            .into_iter()
+            .map(|r| {
+                let mut value = value(r);
+                if let Some(obj) = value.as_object_mut() {
+                    obj.remove(key_field);
+                }
+                value
+            })
+            .collect();
+        serde_json::json!({
+            "key": key,
+            "count": values.len(),
+            "results": values,
+        })
+    })
+    .collect();
+
+    let mut output = serde_json::json!({
+        "group_by": key_field,
+        "group_count": groups.len(),
+        "total": results.len(),
+    });
+    if group_by == GroupBy::Repo {
+        output["repo_count"] = groups.len().into();
+    }
+    output["groups"] = groups.into();
+    output
+}
+
+/// Bucket results by `key`, ordered per `--group-sort`. Results keep their
+/// ranked order within a group, so a group's best score is its first result.
+fn group_results<T>(
+    results: &[T],
+    key: impl Fn(&T) -> &str,
+    sort: GroupSort,
+) -> Vec<(String, Vec<&T>)> {
+    // Groups start in order of their best-ranked result
+    let mut grouped: Vec<(String, Vec<&T>)> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for result in results {
+        let key = key(result);
+        let i = *index.entry(key).or_insert_with(|| {
+            grouped.push((key.to_string(), Vec::new()));
+            grouped.len() - 1
+        });
+        grouped[i].1.push(result);
+    }
+    match sort {
+        GroupSort::Name => grouped.sort_by(|a, b| a.0.cmp(&b.0)),
+        GroupSort::Count => grouped.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0))),
+        GroupSort::Score => {}
+    }
+    grouped
+}
+
+/// Print a group header: `▶ key (N results)`
+fn print_group_header(out: &mut dyn Write, key: &str, count: usize, colors: bool) -> Result<()> {
+    let plural = if count == 1 { "" } else { "s" };
+    if colors {
+        writeln!(
+            out,
+            "{} {} ({count} result{plural})",
+            "▶".blue(),
+            key.blue().bold()
+        )?;
+    } else {
+        writeln!(out, "▶ {key} ({count} result{plural})")?;
+    }
+    Ok(())
+}
+
+/// Print a grouped result's location; the repo is implied when grouping by repo
+#[allow(clippy::too_many_arguments)]
+fn print_grouped_location(
+    out: &mut dyn Write,
+    group_by: GroupBy,
+    repo: &str,
+    path: &Path,
+    absolute_path: &Path,
+    absolute: bool,
+    title: Option<&str>,
+    score: Option<f64>,
+    file_type: Option<&str>,
+    colors: bool,
+) -> Result<()> {
+    let score = score_prefix(score, colors) + &type_prefix(file_type, colors);
+    let location = match (title, absolute) {
+        (Some(title), true) => format!("{title} ({})", absolute_path.display()),
+        (Some(title), false) if group_by == GroupBy::Repo => {
+            format!("{title} ({})", path.display())
+        }
+        (Some(title), false) => format!("{repo}: {title} ({})", path.display()),
+        (None, true) => absolute_path.display().to_string(),
+        (None, false) if group_by == GroupBy::Repo => path.display().to_string(),
+        (None, false) => format!("{repo}:{}", path.display()),
+    };
+    if colors {
+        writeln!(out, "  {score}{}", location.cyan())?;
+    } else {
+        writeln!(out, "  {score}{location}")?;
+    }
+    Ok(())
+}
+
+/// Footer for grouped output: `─ N results in M repositories`
+fn print_group_footer(
+    out: &mut dyn Write,
+    group_by: GroupBy,
+    results: usize,
+    groups: usize,
+    colors: bool,
+) -> Result<()> {
+    let unit = match (group_by, groups == 1) {
+        (GroupBy::FileType, true) => "file type",
+        (GroupBy::FileType, false) => "file types",
+        (_, true) => "repository",
+        (_, false) => "repositories",
+    };
+    let plural = if results == 1 { "" } else { "s" };
+    if colors {
+        writeln!(
+            out,
+            "{} {} result{plural} in {} {unit}",
+            "─".dimmed(),
+            results.to_string().green(),
+            groups.to_string().green(),
+        )?;
+    } else {
+        writeln!(out, "─ {results} result{plural} in {groups} {unit}")?;
+    }
+    Ok(())
+}
+
+/// `--porcelain` record: repo, path, absolute path, file type, score, snippet
+fn print_result_porcelain(
+    out: &mut dyn Write,
+    repo: &str,
+    path: &Path,
+    absolute_path: &Path,
+    file_type: &str,
+    score: f64,
+    snippet: &str,
+) -> Result<()> {
+    let snippet = strip_highlights(snippet);
+    let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    write_porcelain(
+        out,
+        &[
+            repo,
+            &path.to_string_lossy(),
+            &absolute_path.to_string_lossy(),
+            file_type,
+            &score.to_string(),
+            &snippet,
+        ],
+    )?;
+    Ok(())
+}
+
+/// `--explain` breakdown: per-list ranks and scores, plus the fused RRF value
+fn explain_json(r: &UnifiedSearchResult) -> serde_json::Value {
+    serde_json::json!({
+        "lexical_rank": r.lexical_rank,
+        "bm25": r.lexical_score,
+        "semantic_rank": r.semantic_rank,
+        "cosine": r.semantic_similarity,
+        "rrf": (r.search_mode == SearchMode::Hybrid).then_some(r.score),
+    })
+}
+
+/// One-line `--explain` breakdown, e.g. `bm25 -4.210 (lexical #3) · cosine 0.812 (semantic #1)`
+fn explain_line(r: &UnifiedSearchResult) -> String {
+    let rank =
+        |rank: Option<usize>, list: &str| rank.map_or(String::new(), |n| format!(" ({list} #{n})"));
+
+    let mut parts = Vec::new();
+    if let Some(bm25) = r.lexical_score {
+        parts.push(format!("bm25 {bm25:.3}{}", rank(r.lexical_rank, "lexical")));
+    }
+    if let Some(cosine) = r.semantic_similarity {
+        parts.push(format!(
+            "cosine {cosine:.3}{}",
+            rank(r.semantic_rank, "semantic")
+        ));
+    }
+    if r.search_mode == SearchMode::Hybrid {
+        parts.push(format!("rrf {:.4}", r.score));
+    }
+    format!("why: {}", parts.join(" · "))
+}
+
+/// Print the `--explain` line under a result
+fn print_explain(
+    out: &mut dyn Write,
+    r: &UnifiedSearchResult,
+    indent: &str,
+    colors: bool,
+) -> Result<()> {
+    if colors {
+        writeln!(out, "{indent}{}", explain_line(r).magenta())?;
+    } else {
+        writeln!(out, "{indent}{}", explain_line(r))?;
+    }
+    Ok(())
+}
+
+/// A single result as JSON, with the score breakdown when `explain` is set
+fn result_json(r: &UnifiedSearchResult, explain: bool) -> serde_json::Value {
+    let mut value = serde_json::json!({
+        "repo": r.repo_name,
+        "file": r.file_path.to_string_lossy(),
+        "absolute_path": r.absolute_path.to_string_lossy(),
+        "snippet": r.snippet,
+        "file_type": r.file_type,
+        "score": r.score,
+        "search_mode": r.search_mode.as_str(),
+        "title": r.title,
+    });
+    if explain {
+        value["explain"] = explain_json(r);
+    }
+    value
+}
+
+/// Add the result's file content, read from disk (or the index, for files gone
+/// from disk and PDFs) and capped at `max_chars`
+fn add_content(
+    value: &mut serde_json::Value,
+    reader: &IndexedFileReader,
+    r: &UnifiedSearchResult,
+    max_chars: usize,
+) {
+    match reader.read(&r.repo_name, &r.file_path, &r.absolute_path) {
+        Some(content) => {
+            let truncated = content.chars().count() > max_chars;
+            value["content"] = if truncated {
+                content.chars().take(max_chars).collect::<String>()
+            } else {
+                content
+            }
+            .into();
+            value["content_truncated"] = truncated.into();
+        }
+        None => value["content"] = serde_json::Value::Null,
+    }
+}
+
+/// JSON value of a result limit: `null` when unlimited
+fn limit_json(limit: usize) -> Option<usize> {
+    (limit != UNLIMITED).then_some(limit)
+}
+
+/// Parse a `--within` path. Absolute paths and paths starting with `.` are
+/// resolved against the current directory; anything else is a path relative
+/// to each repository's root.
+fn parse_within(input: &str) -> Result<Within> {
+    let path = Path::new(input);
+    if path.is_absolute()
+        || input == "."
+        || input == ".."
+        || input.starts_with("./")
+        || input.starts_with("../")
+    {
+        let resolved = std::env::current_dir()?.join(path);
+        return resolved
+            .canonicalize()
+            .map(Within::Absolute)
+            .map_err(|_| AppError::PathNotFound(resolved));
+    }
+    let prefix = input.trim_matches('/');
+    if prefix.is_empty() {
+        return Err(AppError::Other("--within needs a non-empty path".into()));
+    }
+    Ok(Within::Relative(prefix.to_string()))
+}
+
+/// Interactive follow-up to a search listing (`--open`, `--preview`)
+struct Picker {
+    open: bool,
+    preview: Option<String>,
+    colors: bool,
+}
+
+impl Picker {
+    /// `None` unless a flag asks for it and stdin and stdout are terminals;
+    /// JSON, porcelain and file output are never interactive
+    fn new(open: bool, preview: Option<String>, to_file: bool, args: &Args) -> Option<Self> {
+        let interactive = !args.json
+            && !args.porcelain
+            && !to_file
+            && io::stdin().is_terminal()
+            && io::stdout().is_terminal();
+        ((open || preview.is_some()) && interactive).then(|| Self {
+            open,
+            preview,
+            colors: use_colors(args),
+        })
+    }
+
+    /// List `choices` (label, absolute path) by number and act on the picked
+    /// ones: preview and continue, or open in the editor and stop
+    fn run(&self, choices: &[(String, &Path)]) -> Result<()> {
+        if choices.is_empty() {
+            return Ok(());
+        }
+        println!();
+        for (i, (label, _)) in choices.iter().enumerate() {
+            if self.colors {
+                println!("{:>4}  {}", (i + 1).to_string().dimmed(), label.cyan());
+            } else {
+                println!("{:>4}  {label}", i + 1);
+            }
+        }
+
+        let verb = if self.preview.is_some() {
+            "Preview"
+        } else {
+            "Open"
+        };
+        loop {
+            print!("{verb} [1-{}, Enter to quit]: ", choices.len());
+            io::stdout().flush()?;
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input)? == 0 {
+                return Ok(());
+            }
+            let input = input.trim();
+            if input.is_empty() || input.eq_ignore_ascii_case("q") {
+                return Ok(());
+            }
+            let Some((_, path)) = input
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| choices.get(i))
+            else {
+                eprintln!("Enter a number between 1 and {}", choices.len());
+                continue;
+            };
+
+            if let Some(template) = &self.preview {
+                if let Err(e) = run_preview(template, path) {
+                    eprintln!("{e}");
+                }
+                if !self.open || !confirm(&format!("Open {} in the editor?", path.display())) {
+                    continue;
+                }
+            }
+            return open_in_editor(path);
+        }
+    }
+}
+
+/// Picker entry for a result: `repo:path` label and the file to act on
+fn pick_choice<'a>(repo: &str, path: &Path, absolute_path: &'a Path) -> (String, &'a Path) {
+    (format!("{repo}:{}", path.display()), absolute_path)
+}
+
+/// Run the picker, if any, on plain search results
+fn pick(picker: Option<&Picker>, results: &[SearchResult]) -> Result<()> {
+    let Some(picker) = picker else {
+        return Ok(());
+    };
+    let choices: Vec<_> = results
+        .iter()
+        .map(|r| pick_choice(&r.repo_name, &r.file_path, &r.absolute_path))
+        .collect();
+    picker.run(&choices)
+}
+
+/// JSON shape shared by `search --json` and the HTTP API
+pub(super) fn results_json(
+    results: &[UnifiedSearchResult],
+    query: &str,
+    limit: usize,
+    mode: SearchMode,
+    reranked: bool,
+    explain: bool,
+) -> serde_json::Value {
+    let json_results: Vec<_> = results.iter().map(|r| result_json(r, explain)).collect();
+
+    serde_json::json!({
+        "results": json_results,
+        "total": results.len(),
+        "query": query,
+        "limit": limit_json(limit),
+        "mode": mode.as_str(),
+        "reranked": reranked,
+    })
+}
+
+/// What a `kdex search` run was asked for, as resolved from its flags
+#[allow(clippy::struct_excessive_bools)]
+pub struct SearchOptions {
+    pub query: String,
+    pub repo: Option<String>,
+    pub repo_path: Option<String>,
+    pub within: Option<String>,
+    pub include_all: bool,
+    pub exclude_patterns: Vec<String>,
+    pub file_type: Option<String>,
+    /// TODO: Implement tag filtering
+    pub tag: Option<String>,
+    /// `Some(0)` means no limit; `None` uses `default_search_limit`
+    pub limit: Option<usize>,
+    /// Most results per repository (`--per-repo-limit`, 0 already mapped to `None`)
+    pub per_repo_limit: Option<usize>,
+    pub timeout: Option<Duration>,
+    pub group_by: GroupBy,
+    pub group_sort: GroupSort,
+    pub semantic: bool,
+    pub hybrid: bool,
+    pub lexical: bool,
+    pub mode: Option<String>,
+    pub fuzzy: bool,
+    pub regex: bool,
+    pub substring: bool,
+    pub rerank: bool,
+    pub absolute: bool,
+    pub explain: bool,
+    pub show_score: bool,
+    pub min_score: Option<f64>,
+    pub show_title: bool,
+    /// Show file type labels (off with `--no-type-labels`)
+    pub type_labels: bool,
+    pub near: Option<u32>,
+    pub in_code: bool,
+    pub code_lang: Option<String>,
+    pub highlight_mode: HighlightMode,
+    pub snippet_lines: usize,
+    pub json_lines: bool,
+    pub with_content: bool,
+    pub max_chars: Option<usize>,
+    /// Write results to this file instead of stdout
+    pub output_path: Option<PathBuf>,
+    pub open: bool,
+    pub preview: Option<String>,
+}
+
+#[allow(clippy::too_many_lines)]
+pub fn run(options: SearchOptions, args: &Args) -> Result<()> {
+    let SearchOptions {
+        query,
+        repo,
+        repo_path,
+        within,
+        include_all,
+        exclude_patterns,
+        file_type,
+        tag: _tag,
+        limit,
+        per_repo_limit,
+        timeout,
+        group_by,
+        group_sort,
+        semantic,
+        hybrid,
+        lexical,
+        mode,
+        fuzzy,
+        regex,
+        substring,
+        rerank,
+        absolute,
+        explain,
+        show_score,
+        min_score,
+        show_title,
+        type_labels,
+        near,
+        in_code,
+        code_lang,
+        highlight_mode,
+        snippet_lines,
+        json_lines,
+        with_content,
+        max_chars,
+        output_path,
+        open,
+        preview,
+    } = options;
+
+    if with_content && !args.json && !json_lines {
+        return Err(AppError::Other(
+            "--with-content needs --json or --json-lines".into(),
+        ));
+    }
+    let content_cap = with_content.then(|| max_chars.unwrap_or(DEFAULT_CONTENT_MAX_CHARS));
+    let deadline = timeout.map(|t| Instant::now() + t);
+
+    // File output is never colored
+    let colors = use_colors(args) && output_path.is_none();
     let db = Database::open()?;
+    let reader = IndexedFileReader::new(db.clone());
+    let status_db = db.clone();
     let config = Config::load()?;
+    let limit = match limit.unwrap_or(config.default_search_limit) {
+        0 => UNLIMITED,
+        n => n,
+    };
+    let within = within.as_deref().map(parse_within).transpose()?;
+    let exclude = PathExclude::new(&exclude_patterns)?;
+    let picker = Picker::new(open, preview, output_path.is_some(), args);
+    let mut out = open_output(output_path.as_deref())?;
 
     // Handle regex search mode
     if regex {
-        return run_regex_search(
+        let results = run_regex_search(
+            &mut *out,
             &query,
             repo.as_deref(),
+            repo_path.as_deref(),
+            within.as_ref(),
+            exclude.as_ref(),
+            include_all,
             file_type.as_deref(),
             limit,
-            group_by_repo,
+            deadline,
+            group_by,
+            group_sort,
+            absolute,
+            show_title,
+            type_labels,
+            highlight_mode,
+            snippet_lines,
+            colors,
             args,
-        );
+        )?;
+        log_query(&status_db, &config, &query, "regex", results.len());
+        finish_output(out, output_path.as_deref(), args)?;
+        return pick(picker.as_ref(), &results);
+    }
+
+    // Handle substring search mode
+    if substring {
+        let results = run_substring_search(
+            &mut *out,
+            &query,
+            repo.as_deref(),
+            repo_path.as_deref(),
+            within.as_ref(),
+            exclude.as_ref(),
+            include_all,
+            file_type.as_deref(),
+            limit,
+            group_by,
+            group_sort,
+            absolute,
+            show_title,
+            type_labels,
+            highlight_mode,
+            snippet_lines,
+            colors,
+            args,
+        )?;
+        log_query(&status_db, &config, &query, "substring", results.len());
+        finish_output(out, output_path.as_deref(), args)?;
+        return pick(picker.as_ref(), &results);
     }
 
     // Handle fuzzy search mode
     if fuzzy {
-        return run_fuzzy_search(
+        let results = run_fuzzy_search(
+            &mut *out,
             &query,
             repo.as_deref(),
+            repo_path.as_deref(),
+            within.as_ref(),
+            exclude.as_ref(),
+            include_all,
             file_type.as_deref(),
             limit,
-            group_by_repo,
+            group_by,
+            group_sort,
+            absolute,
+            show_title,
+            type_labels,
+            highlight_mode,
+            snippet_lines,
+            colors,
             args,
-        );
+        )?;
+        log_query(&status_db, &config, &query, "fuzzy", results.len());
+        finish_output(out, output_path.as_deref(), args)?;
+        return pick(picker.as_ref(), &results);
     }
 
-    // Determine search mode
+    // Determine search mode (re-ranking and code block search are lexical)
     let mode = if semantic {
         SearchMode::Semantic
     } else if hybrid {
         SearchMode::Hybrid
-    } else if lexical {
+    } else if lexical || rerank || in_code {
         SearchMode::Lexical
     } else {
-        SearchMode::from_str(&config.default_search_mode)
+        SearchMode::from_str(mode.as_deref().unwrap_or(&config.default_search_mode))
     };
+    // Auto mode settles on a concrete mode before deciding whether to load the model
+    let auto_mode = mode == SearchMode::Auto;
+    let mode = mode.resolve(&query, config.enable_semantic_search);
+
+    let min_score = min_score.unwrap_or(config.min_lexical_score);
 
-    // Create searcher with embedder if needed for semantic/hybrid
-    let searcher = if (mode == SearchMode::Semantic || mode == SearchMode::Hybrid)
+    // Create searcher with embedder if needed for semantic/hybrid/rerank
+    let searcher = if (mode == SearchMode::Semantic || mode == SearchMode::Hybrid || rerank)
         && config.enable_semantic_search
     {
-        match Embedder::new(&config.embedding_model) {
+        match Embedder::new(&config.embedding_model, config.model_cache_dir().as_deref()) {
             Ok(embedder) => Searcher::with_embedder(db, embedder),
             Err(e) => {
                 if !args.quiet {
@@ -87,7 +935,15 @@ pub fn run(
         }
     } else {
         Searcher::new(db)
-    };
+    }
+    .with_repo_path(repo_path)
+    .with_within(within)
+    .with_include_all(include_all)
+    .with_exclude(exclude)
+    .with_deadline(deadline)
+    .with_min_score(min_score)
+    .with_near(near)
+    .with_code_scope(in_code, code_lang);
 
     // Check if semantic search was requested but not available
     let effective_mode = if (mode == SearchMode::Semantic || mode == SearchMode::Hybrid)
@@ -109,259 +965,331 @@ pub fn run(
         mode
     };
 
-    let results = searcher.search_with_mode(
-        &query,
-        effective_mode,
-        repo.as_deref(),
-        file_type.as_deref(),
+    // Re-ranking falls back to plain lexical ordering without embeddings
+    let reranked = rerank && searcher.has_semantic_search();
+    if rerank && !reranked && !args.quiet && !args.json {
+        eprintln!("Note: Semantic search not enabled. Results are not re-ranked.");
+    }
+
+    let results = fetch_capped(
+        per_repo_limit,
         limit,
-        0,
+        |limit| {
+            if rerank {
+                searcher.reranked_search(&query, repo.as_deref(), file_type.as_deref(), limit)
+            } else {
+                searcher.search_with_mode(
+                    &query,
+                    effective_mode,
+                    repo.as_deref(),
+                    file_type.as_deref(),
+                    limit,
+                    0,
+                )
+            }
+        },
+        |r| r.repo_name.as_str(),
     )?;
 
+    let timed_out = searcher.timed_out();
+    if timed_out {
+        warn_timed_out(args);
+    }
+    log_query(
+        &status_db,
+        &config,
+        &query,
+        effective_mode.as_str(),
+        results.len(),
+    );
+
+    if args.porcelain {
+        for r in &results {
+            print_result_porcelain(
+                &mut *out,
+                &r.repo_name,
+                &r.file_path,
+                &r.absolute_path,
+                &r.file_type,
+                r.score,
+                &r.snippet,
+            )?;
+        }
+        return finish_output(out, output_path.as_deref(), args);
+    }
+
+    if json_lines {
+        for r in &results {
+            let mut value = result_json(r, explain);
+            if let Some(cap) = content_cap {
+                add_content(&mut value, &reader, r, cap);
+            }
+            writeln!(out, "{value}")?;
+        }
+        warn_missing_files(&reader, args);
+        return finish_output(out, output_path.as_deref(), args);
+    }
+
     if results.is_empty() {
+        let did_you_mean = if args.json || !args.quiet {
+            searcher.suggest(&query)
+        } else {
+            Vec::new()
+        };
+        let not_ready = not_ready_repositories(&status_db, include_all)?;
         if args.json {
-            println!(
+            writeln!(
+                out,
                 "{}",
                 serde_json::json!({
                     "results": [],
                     "total": 0,
                     "query": query,
-                    "mode": effective_mode.as_str()
+                    "mode": effective_mode.as_str(),
+                    "auto_mode": auto_mode,
+                    "timed_out": timed_out,
+                    "did_you_mean": did_you_mean,
+                    "not_ready": not_ready_json(&not_ready),
                 })
-            );
+            )?;
         } else if !args.quiet {
             if colors {
-                println!("{} No results for \"{}\"", "!".yellow(), query.cyan());
+                writeln!(out, "{} No results for \"{}\"", "!".yellow(), query.cyan())?;
             } else {
-                println!("No results for \"{query}\"");
+                writeln!(out, "No results for \"{query}\"")?;
+            }
+            if !did_you_mean.is_empty() {
+                if colors {
+                    let names: Vec<String> =
+                        did_you_mean.iter().map(|s| s.cyan().to_string()).collect();
+                    writeln!(out, "Did you mean: {}?", names.join(", "))?;
+                } else {
+                    writeln!(out, "Did you mean: {}?", did_you_mean.join(", "))?;
+                }
+            }
+            writeln!(out)?;
+            writeln!(out, "Suggestions:")?;
+            writeln!(out, "  • Check spelling")?;
+            writeln!(out, "  • Try broader search terms")?;
+            if let Some(hint) = not_ready_hint(&not_ready) {
+                writeln!(out, "  • {hint}")?;
+            }
+            if min_score > 0.0 && effective_mode != SearchMode::Semantic {
+                writeln!(
+                    out,
+                    "  • Lower the relevance floor (--min-score {min_score})"
+                )?;
             }
-            println!();
-            println!("Suggestions:");
-            println!("  • Check spelling");
-            println!("  • Try broader search terms");
             if effective_mode == SearchMode::Lexical {
-                println!("  • Use prefix matching: \"func*\"");
-                println!("  • Try --semantic for conceptual matching");
+                writeln!(out, "  • Use prefix matching: \"func*\"")?;
+                writeln!(out, "  • Try --semantic for conceptual matching")?;
             }
         }
-        return Ok(());
+        return finish_output(out, output_path.as_deref(), args);
     }
 
     if args.json {
-        if group_by_repo {
-            // Group results by repository for JSON output
-            let mut grouped: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
-            for r in &results {
-                let entry = grouped.entry(r.repo_name.clone()).or_default();
-                entry.push(serde_json::json!({
-                    "file": r.file_path.to_string_lossy(),
-                    "absolute_path": r.absolute_path.to_string_lossy(),
-                    "snippet": r.snippet,
-                    "file_type": r.file_type,
-                    "score": r.score,
-                    "search_mode": r.search_mode.as_str(),
-                }));
-            }
-
-            println!(
-                "{}",
-                serde_json::json!({
-                    "grouped_results": grouped,
-                    "total": results.len(),
-                    "repo_count": grouped.len(),
-                    "query": query,
-                    "limit": limit,
-                    "mode": effective_mode.as_str(),
-                })
+        if group_by.is_grouped() {
+            let mut output = grouped_json(
+                &results,
+                group_by,
+                group_sort,
+                |r| (&r.repo_name, &r.file_type),
+                |r| {
+                    let mut value = result_json(r, explain);
+                    if let Some(cap) = content_cap {
+                        add_content(&mut value, &reader, r, cap);
+                    }
+                    value
+                },
             );
+            output["query"] = query.as_str().into();
+            output["limit"] = limit_json(limit).into();
+            output["mode"] = effective_mode.as_str().into();
+            output["auto_mode"] = auto_mode.into();
+            output["reranked"] = reranked.into();
+            output["timed_out"] = timed_out.into();
+            output["per_repo_limit"] = per_repo_limit.into();
+            writeln!(out, "{output}")?;
         } else {
-            let json_results: Vec<_> = results
-                .iter()
-                .map(|r| {
-                    serde_json::json!({
-                        "repo": r.repo_name,
-                        "file": r.file_path.to_string_lossy(),
-                        "absolute_path": r.absolute_path.to_string_lossy(),
-                        "snippet": r.snippet,
-                        "file_type": r.file_type,
-                        "score": r.score,
-                        "search_mode": r.search_mode.as_str(),
-                    })
-                })
-                .collect();
-
-            println!(
-                "{}",
-                serde_json::json!({
-                    "results": json_results,
-                    "total": results.len(),
-                    "query": query,
-                    "limit": limit,
-                    "mode": effective_mode.as_str(),
-                })
-            );
+            let mut output =
+                results_json(&results, &query, limit, effective_mode, reranked, explain);
+            output["auto_mode"] = auto_mode.into();
+            output["timed_out"] = timed_out.into();
+            output["per_repo_limit"] = per_repo_limit.into();
+            if let (Some(cap), Some(values)) = (content_cap, output["results"].as_array_mut()) {
+                for (value, r) in values.iter_mut().zip(&results) {
+                    add_content(value, &reader, r, cap);
+                }
+            }
+            writeln!(out, "{output}")?;
         }
     } else if !args.quiet {
         // Show search mode if not lexical
-        if effective_mode != SearchMode::Lexical && colors {
-            println!(
-                "{} {} search",
+        if reranked && colors {
+            writeln!(out, "{} lexical search, re-ranked", "Mode:".dimmed())?;
+            writeln!(out)?;
+        } else if effective_mode != SearchMode::Lexical && colors {
+            writeln!(
+                out,
+                "{} {} search{}",
                 "Mode:".dimmed(),
-                effective_mode.as_str().blue()
-            );
-            println!();
+                effective_mode.as_str().blue(),
+                if auto_mode { " (auto)" } else { "" }
+            )?;
+            writeln!(out)?;
+        } else if auto_mode {
+            // The picked mode is always worth showing when it wasn't chosen explicitly
+            if colors {
+                writeln!(
+                    out,
+                    "{} {} search (auto)",
+                    "Mode:".dimmed(),
+                    effective_mode.as_str()
+                )?;
+            } else {
+                writeln!(out, "Mode: {} search (auto)", effective_mode.as_str())?;
+            }
+            writeln!(out)?;
         }
 
-        if group_by_repo {
-            // Group results by repository for display
-            let mut grouped: BTreeMap<String, Vec<_>> = BTreeMap::new();
-            for result in &results {
-                grouped
-                    .entry(result.repo_name.clone())
-                    .or_default()
-                    .push(result);
-            }
+        // Grouping by file type already names the type of every result
+        let labels = type_labels && group_by != GroupBy::FileType;
+        if group_by.is_grouped() {
+            let grouped = group_results(
+                &results,
+                |r| group_key(group_by, &r.repo_name, &r.file_type),
+                group_sort,
+            );
 
-            for (repo_name, repo_results) in &grouped {
-                // Print repository header
-                if colors {
-                    println!(
-                        "{} {} ({} result{})",
-                        "▶".blue(),
-                        repo_name.blue().bold(),
-                        repo_results.len(),
-                        if repo_results.len() == 1 { "" } else { "s" }
-                    );
-                } else {
-                    println!(
-                        "▶ {} ({} result{})",
-                        repo_name,
-                        repo_results.len(),
-                        if repo_results.len() == 1 { "" } else { "s" }
-                    );
-                }
+            for (key, group) in &grouped {
+                print_group_header(&mut *out, key, group.len(), colors)?;
 
-                for result in repo_results {
-                    // Format: indented path
-                    if colors {
-                        println!("  {}", result.file_path.display().to_string().cyan());
-                    } else {
-                        println!("  {}", result.file_path.display());
+                for result in group {
+                    print_grouped_location(
+                        &mut *out,
+                        group_by,
+                        &result.repo_name,
+                        &result.file_path,
+                        &result.absolute_path,
+                        absolute,
+                        shown_title(result.title.as_deref(), show_title),
+                        show_score.then_some(result.score),
+                        labels.then_some(result.file_type.as_str()),
+                        colors,
+                    )?;
+                    if explain {
+                        print_explain(&mut *out, result, "    ", colors)?;
                     }
 
-                    // Show snippet with highlighting
-                    let snippet = result.snippet.trim();
-                    if !snippet.is_empty() {
-                        let formatted = if colors {
-                            snippet
-                                .replace(">>>", "\x1b[1;33m")
-                                .replace("<<<", "\x1b[0m")
-                        } else {
-                            snippet.replace(">>>", "[").replace("<<<", "]")
-                        };
-
-                        for line in formatted.lines() {
-                            if colors {
-                                println!("    {}", line.dimmed());
-                            } else {
-                                println!("    {line}");
-                            }
-                        }
-                    }
+                    print_snippet(
+                        &mut *out,
+                        &result.snippet,
+                        "    ",
+                        snippet_lines,
+                        highlight_mode,
+                        colors,
+                    )?;
                 }
-                println!();
+                writeln!(out)?;
             }
 
-            // Show count info
-            if colors {
-                println!(
-                    "{} {} result{} in {} repositor{}",
-                    "─".dimmed(),
-                    results.len().to_string().green(),
-                    if results.len() == 1 { "" } else { "s" },
-                    grouped.len().to_string().green(),
-                    if grouped.len() == 1 { "y" } else { "ies" }
-                );
-            } else {
-                println!(
-                    "─ {} result{} in {} repositor{}",
-                    results.len(),
-                    if results.len() == 1 { "" } else { "s" },
-                    grouped.len(),
-                    if grouped.len() == 1 { "y" } else { "ies" }
-                );
-            }
+            print_group_footer(&mut *out, group_by, results.len(), grouped.len(), colors)?;
         } else {
             for result in &results {
-                // Format: repo:path
-                if colors {
-                    println!(
-                        "{}{}{}",
-                        result.repo_name.blue(),
-                        ":".dimmed(),
-                        result.file_path.display().to_string().cyan()
-                    );
-                } else {
-                    println!("{}:{}", result.repo_name, result.file_path.display());
+                // Format: repo:path (or absolute path)
+                print_result_location(
+                    &mut *out,
+                    &result.repo_name,
+                    &result.file_path,
+                    &result.absolute_path,
+                    absolute,
+                    shown_title(result.title.as_deref(), show_title),
+                    show_score.then_some(result.score),
+                    labels.then_some(result.file_type.as_str()),
+                    colors,
+                )?;
+                if explain {
+                    print_explain(&mut *out, result, "  ", colors)?;
                 }
 
-                // Show snippet with highlighting
-                let snippet = result.snippet.trim();
-                if !snippet.is_empty() {
-                    // Replace >>> and <<< markers with colors or brackets
-                    let formatted = if colors {
-                        snippet
-                            .replace(">>>", "\x1b[1;33m")
-                            .replace("<<<", "\x1b[0m")
-                    } else {
-                        snippet.replace(">>>", "[").replace("<<<", "]")
-                    };
-
-                    for line in formatted.lines() {
-                        if colors {
-                            println!("  {}", line.dimmed());
-                        } else {
-                            println!("  {line}");
-                        }
-                    }
-                }
-                println!();
+                print_snippet(
+                    &mut *out,
+                    &result.snippet,
+                    "  ",
+                    snippet_lines,
+                    highlight_mode,
+                    colors,
+                )?;
+                writeln!(out)?;
             }
 
             // Show count info
             if colors {
-                println!(
+                writeln!(
+                    out,
                     "{} Showing {} result{}",
                     "─".dimmed(),
                     results.len().to_string().green(),
                     if results.len() == 1 { "" } else { "s" }
-                );
+                )?;
             } else {
-                println!(
+                writeln!(
+                    out,
                     "─ Showing {} result{}",
                     results.len(),
                     if results.len() == 1 { "" } else { "s" }
-                );
+                )?;
             }
         }
+        if labels {
+            print_type_legend(
+                &mut *out,
+                results.iter().map(|r| r.file_type.as_str()),
+                colors,
+            )?;
+        }
     }
 
+    warn_missing_files(&reader, args);
+    warn_not_ready(&status_db, include_all, args)?;
+    finish_output(out, output_path.as_deref(), args)?;
+    if let Some(picker) = &picker {
+        let choices: Vec<_> = results
+            .iter()
+            .map(|r| pick_choice(&r.repo_name, &r.file_path, &r.absolute_path))
+            .collect();
+        picker.run(&choices)?;
+    }
     Ok(())
 }
 
 /// Run fuzzy search with typo tolerance
+#[allow(clippy::fn_params_excessive_bools)]
 #[allow(clippy::too_many_arguments)]
+#[allow(clippy::too_many_lines)]
 fn run_fuzzy_search(
+    out: &mut dyn Write,
     query: &str,
     repo: Option<&str>,
+    repo_path: Option<&str>,
+    within: Option<&Within>,
+    exclude: Option<&PathExclude>,
+    include_all: bool,
     file_type: Option<&str>,
     limit: usize,
-    group_by_repo: bool,
+    group_by: GroupBy,
+    group_sort: GroupSort,
+    absolute: bool,
+    show_title: bool,
+    type_labels: bool,
+    highlight_mode: HighlightMode,
+    snippet_lines: usize,
+    colors: bool,
     args: &Args,
-) -> Result<()> {
+) -> Result<Vec<SearchResult>> {
     use strsim::jaro_winkler;
 
-    let colors = use_colors(args.no_color);
     let db = Database::open()?;
 
     // First get a broader set of results with prefix matching via FTS
@@ -369,10 +1297,17 @@ fn run_fuzzy_search(
         "{}*",
         query.split_whitespace().collect::<Vec<_>>().join("* ")
     );
-    let mut results = db.search(&wildcard_query, repo, file_type, limit * 5, 0)?;
+    let filters = SearchFilters {
+        repo,
+        repo_path,
+        within,
+        ready_only: !include_all,
+        file_type,
+    };
+    let mut results = db.search(&wildcard_query, &filters, limit.saturating_mul(5), 0)?;
 
     // Also do an exact match search
-    if let Ok(exact_results) = db.search(query, repo, file_type, limit * 5, 0) {
+    if let Ok(exact_results) = db.search(query, &filters, limit.saturating_mul(5), 0) {
         for r in exact_results {
             if !results
                 .iter()
@@ -383,6 +1318,10 @@ fn run_fuzzy_search(
         }
     }
 
+    if let Some(exclude) = exclude {
+        results.retain(|r| !exclude.is_excluded(&r.file_path));
+    }
+
     // Score by fuzzy similarity
     let query_lower = query.to_lowercase();
     #[allow(clippy::cast_precision_loss)]
@@ -416,75 +1355,130 @@ fn run_fuzzy_search(
     let results: Vec<_> = scored.into_iter().map(|(r, _)| r).collect();
 
     if results.is_empty() {
+        let not_ready = not_ready_repositories(&db, include_all)?;
         if args.json {
-            println!(
+            writeln!(
+                out,
                 "{}",
-                serde_json::json!({ "results": [], "total": 0, "query": query, "mode": "fuzzy" })
-            );
-        } else if !args.quiet {
-            println!("No fuzzy matches for \"{query}\"");
+                serde_json::json!({
+                    "results": [],
+                    "total": 0,
+                    "query": query,
+                    "mode": "fuzzy",
+                    "not_ready": not_ready_json(&not_ready),
+                })
+            )?;
+        } else if !args.quiet && !args.porcelain {
+            writeln!(out, "No fuzzy matches for \"{query}\"")?;
+            if let Some(hint) = not_ready_hint(&not_ready) {
+                writeln!(out, "{hint}")?;
+            }
         }
-        return Ok(());
+        return Ok(Vec::new());
     }
+    warn_not_ready(&db, include_all, args)?;
 
-    display_search_results(&results, query, "fuzzy", group_by_repo, colors, args);
-    Ok(())
+    display_search_results(
+        out,
+        &results,
+        query,
+        "fuzzy",
+        false,
+        group_by,
+        group_sort,
+        absolute,
+        show_title,
+        type_labels,
+        highlight_mode,
+        snippet_lines,
+        colors,
+        args,
+    )?;
+    Ok(results)
 }
 
 /// Run regex search
+#[allow(clippy::fn_params_excessive_bools)]
 #[allow(clippy::too_many_arguments)]
 #[allow(clippy::too_many_lines)]
 fn run_regex_search(
+    out: &mut dyn Write,
     pattern: &str,
     repo: Option<&str>,
+    repo_path: Option<&str>,
+    within: Option<&Within>,
+    exclude: Option<&PathExclude>,
+    include_all: bool,
     file_type: Option<&str>,
     limit: usize,
-    group_by_repo: bool,
+    deadline: Option<Instant>,
+    group_by: GroupBy,
+    group_sort: GroupSort,
+    absolute: bool,
+    show_title: bool,
+    type_labels: bool,
+    highlight_mode: HighlightMode,
+    snippet_lines: usize,
+    colors: bool,
     args: &Args,
-) -> Result<()> {
-    let colors = use_colors(args.no_color);
+) -> Result<Vec<SearchResult>> {
     let db = Database::open()?;
+    let reader = IndexedFileReader::new(db.clone());
 
     let regex = match Regex::new(pattern) {
         Ok(r) => r,
         Err(e) => {
-            if args.json {
-                println!(
-                    "{}",
-                    serde_json::json!({ "error": format!("Invalid regex: {e}") })
-                );
+            let examples = if args.json {
+                ""
             } else {
-                eprintln!("Invalid regex pattern: {e}");
-                eprintln!();
-                eprintln!("Examples of valid patterns:");
-                eprintln!("  fn\\s+\\w+         Match function definitions");
-                eprintln!("  TODO|FIXME        Match TODO or FIXME");
-            }
-            return Ok(());
+                "\n\nExamples of valid patterns:\n  fn\\s+\\w+         Match function definitions\n  TODO|FIXME        Match TODO or FIXME"
+            };
+            return Err(AppError::InvalidQuery(format!(
+                "invalid regex: {e}{examples}"
+            )));
         }
     };
 
     let repos = db.list_repositories()?;
     let mut results = Vec::new();
+    let mut timed_out = false;
 
-    for repo_info in &repos {
+    'repos: for repo_info in &repos {
+        if !include_all && repo_info.status != RepoStatus::Ready {
+            continue;
+        }
         if let Some(filter) = &repo {
             if !repo_info.name.contains(filter) {
                 continue;
             }
         }
+        if let Some(filter) = &repo_path {
+            if !repo_info.path.to_string_lossy().contains(filter) {
+                continue;
+            }
+        }
 
         let files = db.get_repository_files(repo_info.id)?;
 
         for file in &files {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                timed_out = true;
+                break 'repos;
+            }
             if let Some(ft) = &file_type {
                 if !file.file_type.contains(ft) {
                     continue;
                 }
             }
+            if within.is_some_and(|w| !w.contains(&repo_info.path, &file.relative_path)) {
+                continue;
+            }
+            if exclude.is_some_and(|e| e.is_excluded(Path::new(&file.relative_path))) {
+                continue;
+            }
 
             let full_path = repo_info.path.join(&file.relative_path);
-            if let Ok(content) = std::fs::read_to_string(&full_path) {
+            if let Some(content) = reader.read_from_disk(&full_path) {
                 if let Some(m) = regex.find(&content) {
                     let start = content[..m.start()].rfind('\n').map_or(0, |p| p + 1);
                     let end = content[m.end()..]
@@ -492,14 +1486,15 @@ fn run_regex_search(
                         .map_or(content.len(), |p| m.end() + p);
                     let snippet = &content[start..end];
 
-                    results.push(crate::db::SearchResult {
+                    results.push(SearchResult {
                         repo_name: repo_info.name.clone(),
                         repo_path: repo_info.path.clone(),
                         file_path: std::path::PathBuf::from(&file.relative_path),
                         absolute_path: full_path,
-                        snippet: format!(">>>{snippet}<<<<"),
+                        snippet: highlight(snippet),
                         file_type: file.file_type.clone(),
                         score: 1.0,
+                        title: None,
                     });
 
                     if results.len() >= limit {
@@ -514,139 +1509,298 @@ fn run_regex_search(
         }
     }
 
+    warn_missing_files(&reader, args);
+    if timed_out {
+        warn_timed_out(args);
+    }
+
     if results.is_empty() {
+        let not_ready = not_ready_repositories(&db, include_all)?;
         if args.json {
-            println!(
+            writeln!(
+                out,
                 "{}",
-                serde_json::json!({ "results": [], "total": 0, "pattern": pattern, "mode": "regex" })
-            );
-        } else if !args.quiet {
-            println!("No matches for regex /{pattern}/");
+                serde_json::json!({
+                    "results": [],
+                    "total": 0,
+                    "pattern": pattern,
+                    "mode": "regex",
+                    "not_ready": not_ready_json(&not_ready),
+                    "timed_out": timed_out,
+                })
+            )?;
+        } else if !args.quiet && !args.porcelain {
+            writeln!(out, "No matches for regex /{pattern}/")?;
+            if let Some(hint) = not_ready_hint(&not_ready) {
+                writeln!(out, "{hint}")?;
+            }
         }
-        return Ok(());
+        return Ok(Vec::new());
     }
+    warn_not_ready(&db, include_all, args)?;
 
-    display_search_results(&results, pattern, "regex", group_by_repo, colors, args);
-    Ok(())
+    display_search_results(
+        out,
+        &results,
+        pattern,
+        "regex",
+        timed_out,
+        group_by,
+        group_sort,
+        absolute,
+        show_title,
+        type_labels,
+        highlight_mode,
+        snippet_lines,
+        colors,
+        args,
+    )?;
+    Ok(results)
+}
+
+/// Run substring search against the stored content
+#[allow(clippy::fn_params_excessive_bools)]
+#[allow(clippy::too_many_arguments)]
+fn run_substring_search(
+    out: &mut dyn Write,
+    needle: &str,
+    repo: Option<&str>,
+    repo_path: Option<&str>,
+    within: Option<&Within>,
+    exclude: Option<&PathExclude>,
+    include_all: bool,
+    file_type: Option<&str>,
+    limit: usize,
+    group_by: GroupBy,
+    group_sort: GroupSort,
+    absolute: bool,
+    show_title: bool,
+    type_labels: bool,
+    highlight_mode: HighlightMode,
+    snippet_lines: usize,
+    colors: bool,
+    args: &Args,
+) -> Result<Vec<SearchResult>> {
+    let db = Database::open()?;
+    let results = fetch_kept(
+        exclude,
+        limit,
+        0,
+        |limit, _| {
+            db.substring_search(
+                needle,
+                &SearchFilters {
+                    repo,
+                    repo_path,
+                    within,
+                    ready_only: !include_all,
+                    file_type,
+                },
+                limit,
+            )
+        },
+        |r| r.file_path.as_path(),
+    )?;
+
+    if results.is_empty() {
+        let not_ready = not_ready_repositories(&db, include_all)?;
+        if args.json {
+            writeln!(
+                out,
+                "{}",
+                serde_json::json!({
+                    "results": [],
+                    "total": 0,
+                    "query": needle,
+                    "mode": "substring",
+                    "not_ready": not_ready_json(&not_ready),
+                })
+            )?;
+        } else if !args.quiet && !args.porcelain {
+            writeln!(out, "No substring matches for \"{needle}\"")?;
+            if let Some(hint) = not_ready_hint(&not_ready) {
+                writeln!(out, "{hint}")?;
+            }
+        }
+        return Ok(Vec::new());
+    }
+    warn_not_ready(&db, include_all, args)?;
+
+    display_search_results(
+        out,
+        &results,
+        needle,
+        "substring",
+        false,
+        group_by,
+        group_sort,
+        absolute,
+        show_title,
+        type_labels,
+        highlight_mode,
+        snippet_lines,
+        colors,
+        args,
+    )?;
+    Ok(results)
 }
 
 /// Display search results (shared between search modes)
+#[allow(clippy::fn_params_excessive_bools)]
+#[allow(clippy::too_many_arguments)]
 #[allow(clippy::too_many_lines)]
 fn display_search_results(
-    results: &[crate::db::SearchResult],
+    out: &mut dyn Write,
+    results: &[SearchResult],
     query: &str,
     mode: &str,
-    group_by_repo: bool,
+    timed_out: bool,
+    group_by: GroupBy,
+    group_sort: GroupSort,
+    absolute: bool,
+    show_title: bool,
+    type_labels: bool,
+    highlight_mode: HighlightMode,
+    snippet_lines: usize,
     colors: bool,
     args: &Args,
-) {
+) -> Result<()> {
     if args.json {
-        let json_results: Vec<_> = results
-            .iter()
-            .map(|r| {
-                serde_json::json!({
-                    "repo": r.repo_name,
-                    "path": r.file_path,
-                    "snippet": r.snippet.replace(">>>", "").replace("<<<", ""),
-                    "file_type": r.file_type
-                })
+        let result_json = |r: &SearchResult| {
+            serde_json::json!({
+                "repo": r.repo_name,
+                "path": r.file_path,
+                "snippet": strip_highlights(&r.snippet),
+                "file_type": r.file_type,
+                "title": r.title
             })
-            .collect();
-        println!(
+        };
+        if group_by.is_grouped() {
+            let mut output = grouped_json(
+                results,
+                group_by,
+                group_sort,
+                |r| (&r.repo_name, &r.file_type),
+                result_json,
+            );
+            output["query"] = query.into();
+            output["mode"] = mode.into();
+            output["timed_out"] = timed_out.into();
+            writeln!(out, "{output}")?;
+            return Ok(());
+        }
+
+        let json_results: Vec<_> = results.iter().map(result_json).collect();
+        writeln!(
+            out,
             "{}",
             serde_json::json!({
                 "results": json_results,
                 "total": results.len(),
                 "query": query,
-                "mode": mode
+                "mode": mode,
+                "timed_out": timed_out,
             })
-        );
-        return;
+        )?;
+        return Ok(());
     }
 
-    if group_by_repo {
-        let mut grouped: BTreeMap<String, Vec<_>> = BTreeMap::new();
-        for result in results {
-            grouped
-                .entry(result.repo_name.clone())
-                .or_default()
-                .push(result);
+    if args.porcelain {
+        for r in results {
+            print_result_porcelain(
+                out,
+                &r.repo_name,
+                &r.file_path,
+                &r.absolute_path,
+                &r.file_type,
+                r.score,
+                &r.snippet,
+            )?;
         }
+        return Ok(());
+    }
 
-        for (repo_name, repo_results) in &grouped {
-            if colors {
-                println!(
-                    "{} {} ({} result{})",
-                    "▶".blue(),
-                    repo_name.blue().bold(),
-                    repo_results.len(),
-                    if repo_results.len() == 1 { "" } else { "s" }
-                );
-            } else {
-                println!(
-                    "▶ {} ({} result{})",
-                    repo_name,
-                    repo_results.len(),
-                    if repo_results.len() == 1 { "" } else { "s" }
-                );
-            }
+    // Grouping by file type already names the type of every result
+    let labels = type_labels && group_by != GroupBy::FileType;
+    if group_by.is_grouped() {
+        let grouped = group_results(
+            results,
+            |r| group_key(group_by, &r.repo_name, &r.file_type),
+            group_sort,
+        );
 
-            for result in repo_results {
-                if colors {
-                    println!("  {}", result.file_path.display().to_string().cyan());
-                } else {
-                    println!("  {}", result.file_path.display());
-                }
+        for (key, group) in &grouped {
+            print_group_header(out, key, group.len(), colors)?;
+            for result in group {
+                print_grouped_location(
+                    out,
+                    group_by,
+                    &result.repo_name,
+                    &result.file_path,
+                    &result.absolute_path,
+                    absolute,
+                    shown_title(result.title.as_deref(), show_title),
+                    None,
+                    labels.then_some(result.file_type.as_str()),
+                    colors,
+                )?;
+                print_snippet(
+                    out,
+                    &result.snippet,
+                    "    ",
+                    snippet_lines,
+                    highlight_mode,
+                    colors,
+                )?;
             }
-            println!();
+            writeln!(out)?;
         }
     } else {
         for result in results {
-            if colors {
-                println!(
-                    "{}:{}",
-                    result.repo_name.blue(),
-                    result.file_path.display().to_string().cyan()
-                );
-            } else {
-                println!("{}:{}", result.repo_name, result.file_path.display());
-            }
-
-            let snippet = result.snippet.trim();
-            if !snippet.is_empty() {
-                let formatted = if colors {
-                    snippet
-                        .replace(">>>", "\x1b[1;33m")
-                        .replace("<<<", "\x1b[0m")
-                } else {
-                    snippet.replace(">>>", "[").replace("<<<", "]")
-                };
-                for line in formatted.lines().take(3) {
-                    if colors {
-                        println!("  {}", line.dimmed());
-                    } else {
-                        println!("  {line}");
-                    }
-                }
-            }
+            print_result_location(
+                out,
+                &result.repo_name,
+                &result.file_path,
+                &result.absolute_path,
+                absolute,
+                shown_title(result.title.as_deref(), show_title),
+                None,
+                labels.then_some(result.file_type.as_str()),
+                colors,
+            )?;
+            print_snippet(
+                out,
+                &result.snippet,
+                "  ",
+                snippet_lines,
+                highlight_mode,
+                colors,
+            )?;
         }
     }
 
     if !args.quiet {
-        println!();
+        writeln!(out)?;
         if colors {
-            println!(
+            writeln!(
+                out,
                 "{} Showing {} result{}",
                 "─".dimmed(),
                 results.len().to_string().green(),
                 if results.len() == 1 { "" } else { "s" }
-            );
+            )?;
         } else {
-            println!(
+            writeln!(
+                out,
                 "─ Showing {} result{}",
                 results.len(),
                 if results.len() == 1 { "" } else { "s" }
-            );
+            )?;
+        }
+        if labels {
+            print_type_legend(out, results.iter().map(|r| r.file_type.as_str()), colors)?;
         }
     }
+
+    Ok(())
 }
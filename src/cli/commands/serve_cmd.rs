@@ -0,0 +1,318 @@
+//! Serve command - local HTTP API over the index.
+//!
+//! Endpoints (all `GET`, JSON responses):
+//! - `/search?q=...&mode=...&limit=...&repo=...&type=...` - same shape as `search --json`
+//! - `/repos` - same shape as `list --json`
+//! - `/stats` - same shape as `stats --json`
+//! - `/file?path=...` - content of an indexed file, cut at `preview_max_kb`
+//!
+//! Requests whose `Host` header is not the bound address or `localhost` are
+//! refused, so a web page can't reach the API through DNS rebinding.
+
+use std::net::IpAddr;
+use std::path::Path;
+
+use owo_colors::OwoColorize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::cli::args::Args;
+use crate::config::Config;
+use crate::core::{Embedder, IndexedFileReader, SearchMode, Searcher};
+use crate::db::Database;
+use crate::error::{AppError, Result};
+
+use super::list_cmd::repositories_json;
+use super::search_cmd::results_json;
+use super::stats_cmd::StatsOutput;
+use super::use_colors;
+
+/// Default and maximum number of search results per request
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 200;
+
+/// HTTP error carried back to the client as `{"error": ...}`
+#[derive(Debug)]
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<AppError> for HttpError {
+    fn from(e: AppError) -> Self {
+        Self::new(500, e.to_string())
+    }
+}
+
+type HttpResult = std::result::Result<serde_json::Value, HttpError>;
+
+/// Serve the index over HTTP until interrupted
+pub fn run(host: &str, port: u16, args: &Args) -> Result<()> {
+    let colors = use_colors(args);
+    let config = Config::load()?;
+    let db = Database::open()?;
+
+    let ip: IpAddr = host
+        .parse()
+        .map_err(|_| AppError::Config(format!("Invalid host address: {host}")))?;
+
+    // Load the embedding model once so semantic/hybrid requests stay fast
+    let searcher = if config.enable_semantic_search {
+        match Embedder::new(&config.embedding_model, config.model_cache_dir().as_deref()) {
+            Ok(embedder) => Searcher::with_embedder(db.clone(), embedder),
+            Err(e) => {
+                if !args.quiet {
+                    eprintln!(
+                        "Warning: Could not load embeddings: {e}. Serving lexical search only."
+                    );
+                }
+                Searcher::new(db.clone())
+            }
+        }
+    } else {
+        Searcher::new(db.clone())
+    };
+
+    let server = Server::http((ip, port))
+        .map_err(|e| AppError::Other(format!("Failed to bind {ip}:{port}: {e}")))?;
+
+    if !args.quiet {
+        let url = format!("http://{ip}:{port}");
+        if colors {
+            println!("{} Serving kdex API on {}", "✓".green(), url.cyan());
+        } else {
+            println!("Serving kdex API on {url}");
+        }
+        println!("  GET /search?q=...  /repos  /stats  /file?path=...");
+        if !ip.is_loopback() {
+            eprintln!(
+                "Warning: {ip} is not a loopback address; the index is reachable from the network."
+            );
+        }
+        println!("Press Ctrl+C to stop.");
+    }
+
+    for request in server.incoming_requests() {
+        let result = handle(&request, ip, &db, &searcher, &config);
+        if args.verbose {
+            let status = result.as_ref().map_or_else(|e| e.status, |_| 200);
+            eprintln!("{} {} -> {status}", request.method(), request.url());
+        }
+        respond(request, result);
+    }
+
+    Ok(())
+}
+
+fn handle(
+    request: &Request,
+    ip: IpAddr,
+    db: &Database,
+    searcher: &Searcher,
+    config: &Config,
+) -> HttpResult {
+    let host = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Host"))
+        .map(|h| h.value.as_str());
+    if !host_allowed(host, ip) {
+        return Err(HttpError::new(403, "Host not allowed"));
+    }
+    if *request.method() != Method::Get {
+        return Err(HttpError::new(405, "Only GET is supported"));
+    }
+
+    let url = url::Url::parse(&format!("http://localhost{}", request.url()))
+        .map_err(|e| HttpError::new(400, format!("Invalid URL: {e}")))?;
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+    };
+
+    match url.path() {
+        "/search" => {
+            let query = param("q")
+                .filter(|q| !q.trim().is_empty())
+                .ok_or_else(|| HttpError::new(400, "Missing query parameter: q"))?;
+            let limit = match param("limit") {
+                Some(l) => l
+                    .parse::<usize>()
+                    .map_err(|_| HttpError::new(400, format!("Invalid limit: {l}")))?,
+                None => DEFAULT_LIMIT,
+            }
+            .min(MAX_LIMIT);
+            let mode = param("mode").map_or_else(
+                || SearchMode::from_str(&config.default_search_mode),
+                |m| SearchMode::from_str(&m),
+            );
+            search(
+                searcher,
+                &query,
+                mode,
+                param("repo").as_deref(),
+                param("type").as_deref(),
+                limit,
+            )
+        }
+        "/repos" => Ok(repositories_json(&db.list_repositories()?)),
+        "/stats" => Ok(
+            serde_json::to_value(StatsOutput::from(&db.get_stats()?)).map_err(AppError::from)?
+        ),
+        "/file" => {
+            let path = param("path")
+                .ok_or_else(|| HttpError::new(400, "Missing query parameter: path"))?;
+            read_file(
+                db,
+                Path::new(&path),
+                config.preview_max_kb.saturating_mul(1024),
+            )
+        }
+        other => Err(HttpError::new(404, format!("Unknown endpoint: {other}"))),
+    }
+}
+
+fn search(
+    searcher: &Searcher,
+    query: &str,
+    mode: SearchMode,
+    repo: Option<&str>,
+    file_type: Option<&str>,
+    limit: usize,
+) -> HttpResult {
+    // Same fallback as the CLI: lexical when embeddings aren't loaded
+    let mode = mode.resolve(query, searcher.has_semantic_search());
+    let mode = if mode == SearchMode::Lexical || searcher.has_semantic_search() {
+        mode
+    } else {
+        SearchMode::Lexical
+    };
+
+    let results = searcher.search_with_mode(query, mode, repo, file_type, limit, 0)?;
+    Ok(results_json(&results, query, limit, mode, false, false))
+}
+
+/// Whether a request's `Host` header names the bound address or `localhost`.
+/// Any IP address is accepted when bound to all interfaces; host names other
+/// than `localhost` never are, since that is what DNS rebinding relies on.
+fn host_allowed(host: Option<&str>, ip: IpAddr) -> bool {
+    let Some(host) = host else {
+        return false;
+    };
+    let name = match host.strip_prefix('[') {
+        // IPv6 literal, e.g. "[::1]:8080"
+        Some(rest) => rest.split_once(']').map_or(rest, |(name, _)| name),
+        None => host.split_once(':').map_or(host, |(name, _)| name),
+    };
+
+    if name.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    name.parse::<IpAddr>()
+        .is_ok_and(|addr| addr == ip || ip.is_unspecified())
+}
+
+/// Read at most `max_bytes` of an indexed file. Paths the index doesn't list
+/// (outside repositories, hidden, ignored) are refused.
+fn read_file(db: &Database, path: &Path, max_bytes: usize) -> HttpResult {
+    let not_found = || HttpError::new(404, format!("Not an indexed file: {}", path.display()));
+
+    let path = path.canonicalize().map_err(|_| not_found())?;
+    let meta = db.get_file_meta(&path)?.ok_or_else(not_found)?;
+
+    let (content, truncated) = IndexedFileReader::new(db.clone())
+        .read_prefix(&meta.repo_name, &meta.relative_path, &path, max_bytes)
+        .ok_or_else(|| HttpError::new(422, format!("Cannot read {}", path.display())))?;
+
+    Ok(serde_json::json!({
+        "repo": meta.repo_name,
+        "path": path.to_string_lossy(),
+        "content": content,
+        "truncated": truncated,
+    }))
+}
+
+fn respond(request: Request, result: HttpResult) {
+    let (status, body) = match result {
+        Ok(value) => (200, value),
+        Err(e) => (e.status, serde_json::json!({ "error": e.message })),
+    };
+
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(content_type);
+
+    // The client may have disconnected; nothing useful to do about it
+    let _ = request.respond(response);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Indexer;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_host_allowed() {
+        let loopback: IpAddr = "127.0.0.1".parse().unwrap();
+        assert!(host_allowed(Some("127.0.0.1:8080"), loopback));
+        assert!(host_allowed(Some("localhost:8080"), loopback));
+        assert!(host_allowed(Some("LOCALHOST"), loopback));
+        assert!(!host_allowed(Some("evil.example:8080"), loopback));
+        assert!(!host_allowed(Some("10.0.0.2:8080"), loopback));
+        assert!(!host_allowed(None, loopback));
+
+        assert!(host_allowed(Some("[::1]:8080"), "::1".parse().unwrap()));
+        let any: IpAddr = "0.0.0.0".parse().unwrap();
+        assert!(host_allowed(Some("192.168.1.5:8080"), any));
+        assert!(!host_allowed(Some("evil.example"), any));
+    }
+
+    #[test]
+    fn test_read_file_only_serves_indexed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().canonicalize().unwrap();
+        let root = base.join("repo");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/notes.md"), "# Notes").unwrap();
+        fs::create_dir_all(root.join(".private")).unwrap();
+        fs::write(root.join(".private/keys.md"), "# Keys").unwrap();
+        fs::create_dir_all(root.join("drafts")).unwrap();
+        fs::write(root.join(".ignore"), "drafts/\n").unwrap();
+        fs::write(root.join("drafts/plan.md"), "# Plan").unwrap();
+        fs::write(base.join("outside.md"), "# Outside").unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        Indexer::new(db.clone(), Config::default())
+            .index(&root, None, |_| {})
+            .unwrap();
+
+        let file = read_file(&db, &root.join("docs/notes.md"), 1024).unwrap();
+        assert_eq!(file["content"], "# Notes");
+        assert_eq!(file["truncated"], false);
+        let cut = read_file(&db, &root.join("docs/notes.md"), 3).unwrap();
+        assert_eq!(
+            (cut["content"].as_str(), cut["truncated"].as_bool()),
+            (Some("# N"), Some(true))
+        );
+
+        let status = |path: PathBuf| read_file(&db, &path, 1024).err().map(|e| e.status);
+        assert_eq!(status(root.join(".private/keys.md")), Some(404));
+        assert_eq!(status(root.join("drafts/plan.md")), Some(404));
+        assert_eq!(status(root.join("docs/../.private/keys.md")), Some(404));
+        assert_eq!(status(root.join("docs/../docs/notes.md")), None);
+        assert_eq!(status(root.join("../outside.md")), Some(404));
+    }
+}
@@ -1,15 +1,32 @@
 //! Knowledge statistics command.
 
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+
 use crate::cli::args::Args;
-use crate::db::Database;
+use crate::config::Config;
+use crate::db::{Database, KnowledgeStats};
 use crate::error::Result;
 use owo_colors::OwoColorize;
 use serde::Serialize;
 
 use super::use_colors;
 
+/// Version of the [`StatsOutput`] JSON shape.
+///
+/// Fields may be added without a bump; renaming, removing or retyping one
+/// increments it, so dashboards can detect breaking changes.
+pub(super) const STATS_SCHEMA_VERSION: u32 = 1;
+
+/// JSON shape shared by `stats --json` and the HTTP API
 #[derive(Serialize)]
-struct StatsOutput {
+pub(super) struct StatsOutput {
+    schema_version: u32,
+    /// RFC 3339 timestamp of when the statistics were read
+    generated_at: String,
     total_files: usize,
     total_repos: usize,
     file_types: Vec<FileTypeCount>,
@@ -18,6 +35,79 @@ struct StatsOutput {
     files_with_embeddings: usize,
     database_size_bytes: u64,
     database_size_human: String,
+    remote_repos: Vec<RemoteCommit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    largest_files: Option<Vec<LargestFile>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    queries: Option<QueryLog>,
+}
+
+/// Query log summary (`stats --queries`)
+#[derive(Serialize)]
+struct QueryLog {
+    logging_enabled: bool,
+    top: Vec<QueryCount>,
+    zero_results: Vec<QueryCount>,
+}
+
+#[derive(Serialize)]
+struct QueryCount {
+    query: String,
+    searches: usize,
+}
+
+impl QueryLog {
+    fn new(
+        logging_enabled: bool,
+        top: &[(String, usize)],
+        zero_results: &[(String, usize)],
+    ) -> Self {
+        let counts = |queries: &[(String, usize)]| {
+            queries
+                .iter()
+                .map(|(query, searches)| QueryCount {
+                    query: query.clone(),
+                    searches: *searches,
+                })
+                .collect()
+        };
+        Self {
+            logging_enabled,
+            top: counts(top),
+            zero_results: counts(zero_results),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LargestFile {
+    repo: String,
+    path: String,
+    bytes: u64,
+    size_human: String,
+}
+
+impl StatsOutput {
+    fn with_largest_files(mut self, files: &[(String, String, u64)]) -> Self {
+        self.largest_files = Some(
+            files
+                .iter()
+                .map(|(repo, path, bytes)| LargestFile {
+                    repo: repo.clone(),
+                    path: path.clone(),
+                    bytes: *bytes,
+                    size_human: format_bytes(*bytes),
+                })
+                .collect(),
+        );
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct RemoteCommit {
+    name: String,
+    last_indexed_commit: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -26,6 +116,40 @@ struct FileTypeCount {
     count: i64,
 }
 
+impl From<&KnowledgeStats> for StatsOutput {
+    fn from(stats: &KnowledgeStats) -> Self {
+        Self {
+            schema_version: STATS_SCHEMA_VERSION,
+            generated_at: Utc::now().to_rfc3339(),
+            total_files: stats.total_files,
+            total_repos: stats.total_repos,
+            file_types: stats
+                .file_counts
+                .iter()
+                .map(|(ft, count)| FileTypeCount {
+                    file_type: ft.clone(),
+                    count: *count,
+                })
+                .collect(),
+            total_tags: stats.total_tags,
+            total_links: stats.total_links,
+            files_with_embeddings: stats.files_with_embeddings,
+            database_size_bytes: stats.database_size_bytes,
+            database_size_human: format_bytes(stats.database_size_bytes),
+            remote_repos: stats
+                .remote_commits
+                .iter()
+                .map(|(name, commit)| RemoteCommit {
+                    name: name.clone(),
+                    last_indexed_commit: commit.clone(),
+                })
+                .collect(),
+            largest_files: None,
+            queries: None,
+        }
+    }
+}
+
 /// Format bytes as human-readable size
 #[allow(clippy::cast_precision_loss)]
 fn format_bytes(bytes: u64) -> String {
@@ -44,32 +168,105 @@ fn format_bytes(bytes: u64) -> String {
     }
 }
 
-/// Display knowledge statistics
-pub fn run(args: &Args) -> Result<()> {
+/// Abbreviated commit SHA, or a placeholder until the next sync records one
+fn short_commit(commit: Option<&str>) -> &str {
+    commit.map_or("(unknown, run kdex sync)", |c| &c[..c.len().min(12)])
+}
+
+/// Display knowledge statistics, optionally with the largest indexed files
+/// and the most frequent logged searches.
+///
+/// With `watch`, the statistics are re-read and re-printed every `watch`
+/// seconds until interrupted: text output redraws the screen, JSON output
+/// emits one compact object per line.
+pub fn run(
+    largest_files: Option<usize>,
+    repo: Option<&str>,
+    queries: Option<usize>,
+    watch: Option<u64>,
+    args: &Args,
+) -> Result<()> {
     let db = Database::open()?;
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
+    let top_queries = match queries {
+        Some(limit) => Some((limit, Config::load()?.log_queries)),
+        None => None,
+    };
+    let options = StatsOptions {
+        largest_files,
+        repo,
+        top_queries,
+    };
 
+    let Some(secs) = watch else {
+        return print_stats(&db, &options, false, colors, args);
+    };
+
+    loop {
+        if !args.json {
+            // Clear the screen and move the cursor home
+            print!("\x1b[2J\x1b[H");
+        }
+        print_stats(&db, &options, true, colors, args)?;
+        if !args.json {
+            println!();
+            let footer = format!(
+                "Updated {}, refreshing every {secs}s (Ctrl+C to stop)",
+                chrono::Local::now().format("%H:%M:%S")
+            );
+            if colors {
+                println!("{}", footer.dimmed());
+            } else {
+                println!("{footer}");
+            }
+        }
+        std::io::stdout().flush()?;
+        thread::sleep(Duration::from_secs(secs));
+    }
+}
+
+/// Optional sections of the statistics
+struct StatsOptions<'a> {
+    largest_files: Option<usize>,
+    repo: Option<&'a str>,
+    /// How many logged queries to list, and whether logging is on
+    top_queries: Option<(usize, bool)>,
+}
+
+/// Read statistics once and print them as text or JSON
+#[allow(clippy::too_many_lines)]
+fn print_stats(
+    db: &Database,
+    options: &StatsOptions,
+    compact: bool,
+    colors: bool,
+    args: &Args,
+) -> Result<()> {
     let stats = db.get_stats()?;
+    let largest = match options.largest_files {
+        Some(limit) => Some(db.get_largest_files(limit, options.repo)?),
+        None => None,
+    };
+    let queries = match options.top_queries {
+        Some((limit, enabled)) => Some(QueryLog::new(
+            enabled,
+            &db.get_top_queries(limit, false)?,
+            &db.get_top_queries(limit, true)?,
+        )),
+        None => None,
+    };
 
     if args.json {
-        let output = StatsOutput {
-            total_files: stats.total_files,
-            total_repos: stats.total_repos,
-            file_types: stats
-                .file_counts
-                .iter()
-                .map(|(ft, count)| FileTypeCount {
-                    file_type: ft.clone(),
-                    count: *count,
-                })
-                .collect(),
-            total_tags: stats.total_tags,
-            total_links: stats.total_links,
-            files_with_embeddings: stats.files_with_embeddings,
-            database_size_bytes: stats.database_size_bytes,
-            database_size_human: format_bytes(stats.database_size_bytes),
-        };
-        println!("{}", serde_json::to_string_pretty(&output)?);
+        let mut output = StatsOutput::from(&stats);
+        if let Some(files) = &largest {
+            output = output.with_largest_files(files);
+        }
+        output.queries = queries;
+        if compact {
+            println!("{}", serde_json::to_string(&output)?);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
         return Ok(());
     }
 
@@ -124,6 +321,15 @@ pub fn run(args: &Args) -> Result<()> {
             "   Database: {}",
             format_bytes(stats.database_size_bytes).cyan()
         );
+
+        // Remote revisions
+        if !stats.remote_commits.is_empty() {
+            println!();
+            println!("{}", "☁  Remote Revisions".bold());
+            for (name, commit) in &stats.remote_commits {
+                println!("   {name}: {}", short_commit(commit.as_deref()).cyan());
+            }
+        }
     } else {
         println!("Knowledge Index Statistics");
         println!("{}", "═".repeat(40));
@@ -151,7 +357,79 @@ pub fn run(args: &Args) -> Result<()> {
 
         println!("Storage");
         println!("  Database: {}", format_bytes(stats.database_size_bytes));
+
+        if !stats.remote_commits.is_empty() {
+            println!();
+            println!("Remote Revisions");
+            for (name, commit) in &stats.remote_commits {
+                println!("  {name}: {}", short_commit(commit.as_deref()));
+            }
+        }
+    }
+
+    if let Some(files) = &largest {
+        print_largest_files(files, colors);
+    }
+    if let Some(queries) = &queries {
+        print_query_log(queries, colors);
     }
 
     Ok(())
 }
+
+fn print_query_log(log: &QueryLog, colors: bool) {
+    let sections = [
+        ("🔍 Top Searches", "Top Searches", &log.top),
+        (
+            "🕳  Searches Without Results",
+            "Searches Without Results",
+            &log.zero_results,
+        ),
+    ];
+    for (colored_title, title, queries) in sections {
+        println!();
+        if colors {
+            println!("{}", colored_title.bold());
+        } else {
+            println!("{title}");
+        }
+        if queries.is_empty() {
+            println!("{}(none logged)", if colors { "   " } else { "  " });
+        }
+        for q in queries {
+            if colors {
+                println!("   {:>5}  {}", q.searches.to_string().cyan(), q.query);
+            } else {
+                println!("  {:>5}  {}", q.searches, q.query);
+            }
+        }
+    }
+    if !log.logging_enabled {
+        println!();
+        let hint = "Query logging is off; enable it with: kdex config log_queries true";
+        if colors {
+            println!("{}", hint.dimmed());
+        } else {
+            println!("{hint}");
+        }
+    }
+}
+
+fn print_largest_files(files: &[(String, String, u64)], colors: bool) {
+    println!();
+    if colors {
+        println!("{}", "📦 Largest Files".bold());
+        for (repo, path, bytes) in files {
+            println!(
+                "   {:>10}  {}:{path}",
+                format_bytes(*bytes).cyan(),
+                repo.dimmed()
+            );
+        }
+    } else {
+        println!("Largest Files");
+        for (repo, path, bytes) in files {
+            println!("  {:>10}  {repo}:{path}", format_bytes(*bytes));
+        }
+    }
+}
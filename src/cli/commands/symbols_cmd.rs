@@ -0,0 +1,108 @@
+//! Symbols command - export note headings as workspace symbols for editors.
+
+use std::path::Path;
+
+use crate::cli::args::Args;
+use crate::db::Database;
+use crate::error::Result;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use super::use_colors;
+
+#[derive(Serialize)]
+struct Symbol {
+    name: String,
+    kind: &'static str,
+    level: u8,
+    repo: String,
+    /// Path relative to the repository root
+    path: String,
+    /// Absolute path
+    file: String,
+    /// 1-based line number, when the heading can still be found in the file
+    line: Option<usize>,
+}
+
+/// List headings of indexed notes as a flat symbol list
+pub fn run(repo: Option<&str>, args: &Args) -> Result<()> {
+    let db = Database::open()?;
+    let colors = use_colors(args);
+
+    let mut symbols = Vec::new();
+    for file in db.get_file_headings(repo)? {
+        if file.headings.is_empty() {
+            continue;
+        }
+
+        let lines = heading_lines(&file.absolute_path, &file.headings);
+        for ((level, name), line) in file.headings.into_iter().zip(lines) {
+            symbols.push(Symbol {
+                name,
+                kind: "heading",
+                level,
+                repo: file.repo_name.clone(),
+                path: file.relative_path.to_string_lossy().to_string(),
+                file: file.absolute_path.to_string_lossy().to_string(),
+                line,
+            });
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&symbols)?);
+        return Ok(());
+    }
+
+    if args.quiet {
+        return Ok(());
+    }
+
+    if symbols.is_empty() {
+        println!("No headings found in indexed notes.");
+        return Ok(());
+    }
+
+    for symbol in &symbols {
+        let location = symbol.line.map_or_else(
+            || symbol.file.clone(),
+            |line| format!("{}:{line}", symbol.file),
+        );
+        let marker = "#".repeat(usize::from(symbol.level));
+        if colors {
+            println!(
+                "{} {} {}",
+                marker.dimmed(),
+                symbol.name.bold(),
+                location.cyan()
+            );
+        } else {
+            println!("{marker} {} {location}", symbol.name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Locate each heading's line in the file, matching in document order
+fn heading_lines(path: &Path, headings: &[(u8, String)]) -> Vec<Option<usize>> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return vec![None; headings.len()];
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut next = 0;
+
+    headings
+        .iter()
+        .map(|(_, text)| {
+            let found = lines[next..].iter().position(|line| {
+                let trimmed = line.trim_start();
+                (trimmed.starts_with('#') || trimmed.starts_with('*')) && trimmed.contains(text)
+            })?;
+            let index = next + found;
+            next = index + 1;
+            Some(index + 1)
+        })
+        .collect()
+}
@@ -4,17 +4,33 @@ use owo_colors::OwoColorize;
 
 use crate::cli::args::Args;
 use crate::config::Config;
-use crate::core::remote::sync_repository;
-use crate::core::Indexer;
-use crate::db::{Database, RepoStatus, SourceType};
+use crate::core::remote::{changed_files_between, head_commit, sync_repository};
+use crate::core::{run_post_index_command, IndexResult, Indexer};
+use crate::db::{Database, RepoResolution, RepoStatus, Repository, SourceType};
 use crate::error::Result;
 
-use super::{print_success, print_warning, use_colors};
+use super::{add_index_result_json, print_success, print_warning, use_colors};
+
+/// Re-index a synced clone. Only files touched by the commits since the last indexed
+/// one are re-indexed; without a known last commit (never indexed) or when it can't
+/// be diffed (e.g. history was force-pushed away), the whole tree is indexed.
+fn reindex_synced(indexer: &Indexer, repo: &Repository) -> Result<IndexResult> {
+    let changed = repo
+        .last_indexed_commit
+        .as_deref()
+        .zip(head_commit(&repo.path))
+        .and_then(|(old, new)| changed_files_between(&repo.path, old, &new).ok());
+
+    match changed {
+        Some(files) => indexer.index_files(repo, &files, &[]),
+        None => indexer.index(&repo.path, Some(repo.name.clone()), |_| {}),
+    }
+}
 
 /// Run the sync command
 #[allow(clippy::too_many_lines)]
 pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()> {
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
     let config = Config::load()?;
     let db = Database::open()?;
 
@@ -31,12 +47,12 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
         return Ok(());
     }
 
-    // Filter by repo name if specified
+    // Resolve the requested repository; ambiguous names are an error, not "sync all matches"
     let repos_to_sync: Vec<_> = if let Some(filter) = repo_filter {
-        remote_repos
-            .into_iter()
-            .filter(|r| r.name.contains(filter) || r.path.to_string_lossy().contains(filter))
-            .collect()
+        match RepoResolution::among(&remote_repos, filter) {
+            RepoResolution::NotFound => Vec::new(),
+            resolution => vec![resolution.into_result(filter)?],
+        }
     } else {
         remote_repos
     };
@@ -65,6 +81,7 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
     let mut synced = 0;
     let mut updated = 0;
     let mut failed = 0;
+    let mut repo_reports = Vec::new();
 
     for repo in &repos_to_sync {
         if !args.quiet && !args.json {
@@ -78,11 +95,17 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
         // Update status to syncing
         db.update_repository_status(repo.id, RepoStatus::Syncing)?;
 
+        let mut report = serde_json::json!({
+            "name": repo.name,
+            "path": repo.path.to_string_lossy(),
+        });
+
         // Sync the repository
         let branch = repo.remote_branch.as_deref();
         match sync_repository(&repo.path, branch) {
             Ok(had_changes) => {
                 synced += 1;
+                report["status"] = if had_changes { "updated" } else { "up_to_date" }.into();
 
                 if had_changes {
                     updated += 1;
@@ -101,8 +124,16 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
                         }
 
                         let indexer = Indexer::new(db.clone(), config.clone());
-                        match indexer.index(&repo.path, Some(repo.name.clone()), |_| {}) {
+                        match reindex_synced(&indexer, repo) {
                             Ok(result) => {
+                                let mut index = serde_json::json!({});
+                                add_index_result_json(&mut index, &result);
+                                report["index"] = index;
+                                db.update_repository_commit(
+                                    repo.id,
+                                    head_commit(&repo.path).as_deref(),
+                                )?;
+                                run_post_index_command(&config, &repo.path, &result);
                                 if !args.quiet && !args.json {
                                     let total = result.files_added + result.files_updated;
                                     if colors {
@@ -113,6 +144,7 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
                                 }
                             }
                             Err(e) => {
+                                report["index_error"] = e.to_string().into();
                                 if !args.quiet && !args.json {
                                     if colors {
                                         println!("{}: {}", "error".red(), e);
@@ -136,7 +168,10 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
             }
             Err(e) => {
                 failed += 1;
-                db.update_repository_status(repo.id, RepoStatus::Error)?;
+                // The index is intact; a failed fetch only leaves it behind the remote
+                db.update_repository_status(repo.id, repo.status)?;
+                report["status"] = "failed".into();
+                report["error"] = e.to_string().into();
 
                 if !args.quiet && !args.json {
                     if colors {
@@ -147,6 +182,7 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
                 }
             }
         }
+        repo_reports.push(report);
     }
 
     // Summary
@@ -158,6 +194,7 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
                 "synced": synced,
                 "updated": updated,
                 "failed": failed,
+                "repositories": repo_reports,
             })
         );
     } else if !args.quiet {
@@ -173,7 +210,7 @@ pub fn run(repo_filter: Option<&str>, no_index: bool, args: &Args) -> Result<()>
                 colors,
             );
         } else {
-            print_warning(&format!("Synced {synced}, failed {failed}",), colors);
+            print_warning(&format!("Synced {synced}, failed {failed}"), colors);
         }
     }
 
@@ -220,7 +257,10 @@ pub fn background_sync(db: &Database, config: &Config, stale_minutes: i64) -> Re
             if let Ok(true) = sync_repository(&repo.path, branch) {
                 // Re-index on changes
                 let indexer = Indexer::new(db.clone(), config.clone());
-                let _ = indexer.index(&repo.path, Some(repo.name.clone()), |_| {});
+                if reindex_synced(&indexer, &repo).is_ok() {
+                    let _ =
+                        db.update_repository_commit(repo.id, head_commit(&repo.path).as_deref());
+                }
                 let _ = db.update_repository_synced(repo.id);
             }
         }
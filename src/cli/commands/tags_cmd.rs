@@ -7,7 +7,7 @@ use crate::error::Result;
 use owo_colors::OwoColorize;
 use serde::Serialize;
 
-use super::use_colors;
+use super::{print_porcelain, use_colors};
 
 #[derive(Serialize)]
 struct TagInfo {
@@ -17,21 +17,44 @@ struct TagInfo {
 
 #[derive(Serialize)]
 struct TagsOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repo: Option<String>,
+    /// The tag whose co-occurring tags are listed (`--co-occur`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    co_occur: Option<String>,
     total_tags: usize,
     tags: Vec<TagInfo>,
 }
 
-/// List all tags from indexed files
-pub fn run(args: &Args) -> Result<()> {
+/// List all tags from indexed files, optionally of one repository, or the tags
+/// sharing files with `co_occur`
+pub fn run(repo: Option<&str>, co_occur: Option<&str>, args: &Args) -> Result<()> {
     let db = Database::open()?;
     let _config = Config::load()?;
-    let colors = use_colors(args.no_color);
+    let colors = use_colors(args);
 
-    // Get all tags with counts
-    let tags = db.get_all_tags()?;
+    let repo = repo
+        .map(|query| db.resolve_repository(query)?.into_result(query))
+        .transpose()?;
+    // Accept `#tag` as written in notes
+    let co_occur = co_occur.map(|tag| tag.trim_start_matches('#'));
+
+    if let Some(tag) = co_occur {
+        let tags = db.get_co_occurring_tags(tag, repo.as_ref().map(|r| r.id))?;
+        return print_co_occurring(tag, repo.map(|r| r.name), &tags, colors, args);
+    }
+
+    // Get all tags with counts, most used first
+    let mut tags = match &repo {
+        Some(repo) => db.get_tags_for_repo(repo.id)?,
+        None => db.get_all_tags()?,
+    };
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
     if args.json {
         let output = TagsOutput {
+            repo: repo.map(|r| r.name),
+            co_occur: None,
             total_tags: tags.len(),
             tags: tags
                 .into_iter()
@@ -42,9 +65,19 @@ pub fn run(args: &Args) -> Result<()> {
         return Ok(());
     }
 
+    if args.porcelain {
+        for (tag, count) in &tags {
+            print_porcelain(&[tag, &count.to_string()]);
+        }
+        return Ok(());
+    }
+
     if tags.is_empty() {
         if !args.quiet {
-            println!("No tags found in indexed files.");
+            match &repo {
+                Some(repo) => println!("No tags found in \"{}\".", repo.name),
+                None => println!("No tags found in indexed files."),
+            }
             println!();
             println!("Tags are extracted from YAML frontmatter in markdown files:");
             println!("  ---");
@@ -55,19 +88,18 @@ pub fn run(args: &Args) -> Result<()> {
     }
 
     if !args.quiet {
+        let title = repo
+            .as_ref()
+            .map_or_else(|| "Tags".to_string(), |r| format!("Tags in {}", r.name));
         if colors {
-            println!("{}", "Tags".bold());
+            println!("{}", title.bold());
             println!("{}", "─".repeat(40).dimmed());
         } else {
-            println!("Tags");
+            println!("{title}");
             println!("{}", "─".repeat(40));
         }
     }
 
-    // Sort by count descending
-    let mut tags: Vec<_> = tags.into_iter().collect();
-    tags.sort_by(|a, b| b.1.cmp(&a.1));
-
     for (tag, count) in &tags {
         if colors {
             println!(
@@ -94,3 +126,65 @@ pub fn run(args: &Args) -> Result<()> {
 
     Ok(())
 }
+
+/// Print the tags sharing files with `tag`, most shared first
+fn print_co_occurring(
+    tag: &str,
+    repo: Option<String>,
+    tags: &[(String, usize)],
+    colors: bool,
+    args: &Args,
+) -> Result<()> {
+    if args.json {
+        let output = TagsOutput {
+            repo,
+            co_occur: Some(tag.to_string()),
+            total_tags: tags.len(),
+            tags: tags
+                .iter()
+                .map(|(tag, count)| TagInfo {
+                    tag: tag.clone(),
+                    count: *count,
+                })
+                .collect(),
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    if args.porcelain {
+        for (other, count) in tags {
+            print_porcelain(&[other, &count.to_string()]);
+        }
+        return Ok(());
+    }
+
+    if tags.is_empty() {
+        if !args.quiet {
+            println!("No tags appear together with #{tag}.");
+        }
+        return Ok(());
+    }
+
+    if !args.quiet {
+        let title = format!("Tags used with #{tag}");
+        if colors {
+            println!("{}", title.bold());
+            println!("{}", "─".repeat(40).dimmed());
+        } else {
+            println!("{title}");
+            println!("{}", "─".repeat(40));
+        }
+    }
+
+    for (other, count) in tags {
+        let files = format!("({count} file{})", if *count == 1 { "" } else { "s" });
+        if colors {
+            println!("  {} {} {}", "#".dimmed(), other.cyan(), files.dimmed());
+        } else {
+            println!("  #{other} {files}");
+        }
+    }
+
+    Ok(())
+}
@@ -1,24 +1,37 @@
+use chrono::{DateTime, Utc};
 use indicatif::{ProgressBar, ProgressStyle};
 use owo_colors::OwoColorize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::cli::args::Args;
 use crate::config::Config;
-use crate::core::Indexer;
+use crate::core::{run_post_index_command, IndexProgress, IndexResult, Indexer};
 use crate::db::Database;
 use crate::error::{AppError, Result};
 
-use super::{print_success, print_warning, use_colors};
+use super::{
+    print_skipped_details, print_success, print_warning, skipped_details_json, use_colors,
+};
 
 #[allow(clippy::too_many_lines)]
-pub fn run(path: Option<PathBuf>, all: bool, args: &Args) -> Result<()> {
-    let colors = use_colors(args.no_color);
+pub fn run(
+    path: Option<PathBuf>,
+    all: bool,
+    since: Option<DateTime<Utc>>,
+    force: bool,
+    args: &Args,
+) -> Result<()> {
+    let colors = use_colors(args);
     let config = Config::load()?;
     let db = Database::open()?;
 
     if all {
-        // Update all repositories
-        let repos = db.list_repositories()?;
+        // Update all repositories; stdin repositories have no directory to rescan
+        let repos: Vec<_> = db
+            .list_repositories()?
+            .into_iter()
+            .filter(|r| !r.is_stdin())
+            .collect();
 
         if repos.is_empty() {
             if !args.quiet && !args.json {
@@ -34,21 +47,24 @@ pub fn run(path: Option<PathBuf>, all: bool, args: &Args) -> Result<()> {
 
         for repo in &repos {
             if !args.quiet && !args.json {
+                let verb = if force { "Rebuilding" } else { "Updating" };
                 if colors {
-                    println!("Updating {}...", repo.name.cyan());
+                    println!("{verb} {}...", repo.name.cyan());
                 } else {
-                    println!("Updating {}...", repo.name);
+                    println!("{verb} {}...", repo.name);
                 }
             }
 
             let indexer = Indexer::new(db.clone(), config.clone());
 
-            match indexer.index(&repo.path, None, |_| {}) {
+            match update_repository(&indexer, &repo.path, since, force, |_| {}) {
                 Ok(result) => {
+                    run_post_index_command(&config, &repo.path, &result);
                     results.push(serde_json::json!({
                         "name": repo.name,
                         "path": repo.path.to_string_lossy(),
                         "success": true,
+                        "full": force,
                         "files_added": result.files_added,
                         "files_updated": result.files_updated,
                         "files_deleted": result.files_deleted,
@@ -88,25 +104,32 @@ pub fn run(path: Option<PathBuf>, all: bool, args: &Args) -> Result<()> {
     } else {
         // Update single repository
         let path = path.ok_or_else(|| {
-            AppError::Other("Specify a path or use --all to update all repositories".into())
+            AppError::Other(
+                "Specify a path or repository name, or use --all to update all repositories".into(),
+            )
         })?;
 
-        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
-
-        // Check if repository exists
-        if db.get_repository_by_path(&canonical)?.is_none() {
-            return Err(AppError::RepoNotFound(canonical));
+        // Accept a path or a (prefix of a) repository name
+        let query = path.to_string_lossy();
+        let repo = db.resolve_repository(&query)?.into_result(&query)?;
+        if repo.is_stdin() {
+            return Err(AppError::Other(format!(
+                "\"{}\" was indexed from stdin and has no directory to update. Pipe the content again with: kdex index --stdin --name {} --path-label <PATH>",
+                repo.name, repo.name
+            )));
         }
+        let canonical = repo.path;
 
         if !args.quiet && !args.json {
+            let verb = if force { "Rebuilding" } else { "Updating" };
             if colors {
-                println!("Updating {}...", canonical.display().to_string().cyan());
+                println!("{verb} {}...", canonical.display().to_string().cyan());
             } else {
-                println!("Updating {}...", canonical.display());
+                println!("{verb} {}...", canonical.display());
             }
         }
 
-        let indexer = Indexer::new(db, config);
+        let indexer = Indexer::new(db, config.clone());
 
         let progress_bar = if !args.quiet && !args.json {
             let pb = ProgressBar::new(0);
@@ -121,7 +144,7 @@ pub fn run(path: Option<PathBuf>, all: bool, args: &Args) -> Result<()> {
             None
         };
 
-        let result = indexer.index(&canonical, None, |progress| {
+        let result = update_repository(&indexer, &canonical, since, force, |progress| {
             if let Some(pb) = &progress_bar {
                 pb.set_length(progress.total_files as u64);
                 pb.set_position(progress.processed_files as u64);
@@ -132,18 +155,47 @@ pub fn run(path: Option<PathBuf>, all: bool, args: &Args) -> Result<()> {
             pb.finish_and_clear();
         }
 
+        run_post_index_command(&config, &canonical, &result);
+
         if args.json {
             println!(
                 "{}",
                 serde_json::json!({
                     "success": true,
                     "path": canonical.to_string_lossy(),
+                    "full": force,
                     "files_added": result.files_added,
                     "files_updated": result.files_updated,
                     "files_deleted": result.files_deleted,
                     "files_unchanged": result.files_unchanged,
+                    "files_skipped": result.files_skipped,
+                    "files_empty": result.files_empty,
+                    "files_minified": result.files_minified,
+                    "skipped": skipped_details_json(&result.skipped_details),
+                    "failed": skipped_details_json(&result.failed_details),
+                    "embeddings_skipped": result.embeddings_skipped,
                 })
             );
+        } else if !args.quiet && force {
+            print_success(
+                &format!(
+                    "Rebuilt in {:.1}s: {} files indexed, {} skipped",
+                    result.elapsed_secs, result.files_added, result.files_skipped
+                ),
+                colors,
+            );
+            if result.files_empty > 0 {
+                println!("  Empty: {} (min_content_chars)", result.files_empty);
+            }
+            if result.files_minified > 0 {
+                println!(
+                    "  Minified: {} (max_avg_line_length)",
+                    result.files_minified
+                );
+            }
+            if args.verbose {
+                print_skipped_details(&result, colors);
+            }
         } else if !args.quiet {
             print_success(
                 &format!(
@@ -156,8 +208,39 @@ pub fn run(path: Option<PathBuf>, all: bool, args: &Args) -> Result<()> {
                 ),
                 colors,
             );
+            if result.files_empty > 0 {
+                println!("  Empty: {} (min_content_chars)", result.files_empty);
+            }
+            if result.files_minified > 0 {
+                println!(
+                    "  Minified: {} (max_avg_line_length)",
+                    result.files_minified
+                );
+            }
+            if args.verbose {
+                print_skipped_details(&result, colors);
+            }
         }
     }
 
     Ok(())
 }
+
+/// Run a full incremental update, a `--since` update when a cutoff is given,
+/// or a from-scratch rebuild with `force`
+fn update_repository<F>(
+    indexer: &Indexer,
+    path: &Path,
+    since: Option<DateTime<Utc>>,
+    force: bool,
+    progress_callback: F,
+) -> Result<IndexResult>
+where
+    F: Fn(&IndexProgress) + Send + Sync,
+{
+    match since {
+        _ if force => indexer.reindex_full(path, progress_callback),
+        Some(cutoff) => indexer.update_since(path, cutoff, progress_callback),
+        None => indexer.index(path, None, progress_callback),
+    }
+}
@@ -0,0 +1,204 @@
+//! Watch command - re-index repositories when files change
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::cli::args::Args;
+use crate::config::Config;
+use crate::core::{
+    check_inotify_limit, estimate_directory_count, unwatchable_filesystem, ChangeType, IndexWatcher,
+};
+use crate::core::{run_post_index_command, IndexResult, Indexer};
+use crate::db::{Database, Repository};
+use crate::error::Result;
+
+use super::add_index_result_json;
+
+/// Run the watch command. `poll` is `--poll`: `Some(None)` without a
+/// value, which uses the configured interval.
+#[allow(clippy::option_option)]
+pub fn run(all: bool, path: Option<PathBuf>, poll: Option<Option<u64>>, args: &Args) -> Result<()> {
+    let db = Database::open()?;
+    let config = Config::load()?;
+
+    let repos: Vec<_> = if all {
+        db.list_repositories()?
+            .into_iter()
+            .filter(|r| !r.is_stdin())
+            .collect()
+    } else if let Some(p) = path {
+        let abs_path = std::fs::canonicalize(&p)?;
+        db.list_repositories()?
+            .into_iter()
+            .filter(|r| r.path == abs_path)
+            .collect()
+    } else {
+        let cwd = std::env::current_dir()?;
+        db.list_repositories()?
+            .into_iter()
+            .filter(|r| r.path == cwd)
+            .collect()
+    };
+
+    if repos.is_empty() {
+        if !args.quiet {
+            eprintln!("No repositories to watch. Index a directory first.");
+        }
+        return Ok(());
+    }
+
+    let poll = poll
+        .map(|secs| Duration::from_secs(secs.unwrap_or(config.watcher_poll_interval_secs).max(1)));
+    watch_repositories(&db, &config, &repos, poll, args)
+}
+
+/// Watch already-indexed repositories and re-index them on change, scanning
+/// every `poll` interval instead of using OS file events when given. With
+/// `--json`, prints NDJSON events instead of progress lines.
+///
+/// Blocks until the process is interrupted.
+pub fn watch_repositories(
+    db: &Database,
+    config: &Config,
+    repos: &[Repository],
+    poll: Option<Duration>,
+    args: &Args,
+) -> Result<()> {
+    // Check platform limits (Linux inotify); polling needs no watches
+    if !args.quiet && poll.is_none() {
+        for repo in repos {
+            if let Some(fs_type) = unwatchable_filesystem(&repo.path) {
+                eprintln!(
+                    "Warning: {} is on a {fs_type} filesystem, where file change events are often not delivered.\n\
+                     Use `kdex watch --poll` to scan for changes periodically instead.",
+                    repo.path.display()
+                );
+                eprintln!();
+            }
+        }
+
+        let total_dirs: usize = repos
+            .iter()
+            .filter_map(|r| estimate_directory_count(&r.path).ok())
+            .sum();
+
+        let limits = check_inotify_limit(total_dirs);
+        if let Some(warning) = limits.warning {
+            eprintln!("{warning}");
+            eprintln!();
+        }
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "event": "watching",
+                "repos": repos
+                    .iter()
+                    .map(|r| serde_json::json!({"name": r.name, "path": r.path}))
+                    .collect::<Vec<_>>(),
+                "poll_secs": poll.map(|interval| interval.as_secs()),
+            })
+        );
+    } else if !args.quiet {
+        println!(
+            "Watching {} repositor{} for changes...",
+            repos.len(),
+            if repos.len() == 1 { "y" } else { "ies" }
+        );
+        for repo in repos {
+            println!("  • {}", repo.path.display());
+        }
+        if let Some(interval) = poll {
+            println!("Polling every {}s.", interval.as_secs());
+        }
+        println!("Press Ctrl+C to stop.");
+    }
+
+    let mut watcher = IndexWatcher::new(Arc::new(config.clone()), poll)?;
+
+    // Add all repository paths to watch
+    for repo in repos {
+        watcher.watch(repo.path.clone())?;
+    }
+
+    let indexer = Indexer::new(db.clone(), config.clone());
+
+    // Main watch loop
+    loop {
+        let batches = watcher.poll_changes();
+
+        for batch in batches {
+            let changes: Vec<(&str, &Path)> = batch
+                .changes
+                .iter()
+                .map(|change| {
+                    let action = match change.change_type {
+                        ChangeType::Created => "created",
+                        ChangeType::Modified => "modified",
+                        ChangeType::Deleted => "deleted",
+                    };
+                    (action, change.path.as_path())
+                })
+                .collect();
+
+            if !args.quiet && !args.json {
+                println!("Changes detected in {}:", batch.repo_path.display());
+                for (action, path) in &changes {
+                    println!("  {action} {}", path.display());
+                }
+            }
+
+            // Re-index the changed repository
+            if let Some(repo) = repos.iter().find(|r| r.path == batch.repo_path) {
+                let outcome = indexer.index(&repo.path, Some(repo.name.clone()), |_| {});
+                if args.json {
+                    print_reindex_event(repo, &changes, &outcome);
+                }
+                match outcome {
+                    Ok(result) => {
+                        if !args.quiet && !args.json {
+                            println!(
+                                "  ✓ Re-indexed: {} added, {} updated, {} deleted",
+                                result.files_added, result.files_updated, result.files_deleted
+                            );
+                        }
+                        run_post_index_command(config, &repo.path, &result);
+                    }
+                    Err(e) => {
+                        if !args.quiet && !args.json {
+                            eprintln!("  ✗ Failed to re-index: {e}");
+                        }
+                    }
+                }
+            }
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Print one NDJSON line for a re-index triggered by `changes` (for `--json`)
+fn print_reindex_event(
+    repo: &Repository,
+    changes: &[(&str, &Path)],
+    indexed: &Result<IndexResult>,
+) {
+    let mut event = serde_json::json!({
+        "event": if indexed.is_ok() { "reindexed" } else { "error" },
+        "repo": repo.name,
+        "path": repo.path,
+        "changes": changes
+            .iter()
+            .map(|(action, path)| serde_json::json!({"change": action, "path": path}))
+            .collect::<Vec<_>>(),
+    });
+    match indexed {
+        Ok(result) => add_index_result_json(&mut event, result),
+        Err(e) => event["error"] = e.to_string().into(),
+    }
+    println!("{event}");
+}
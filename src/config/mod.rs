@@ -15,6 +15,10 @@ pub const LEGACY_APP_NAME: &str = "knowledge-index";
 pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const CONFIG_FILE_NAME: &str = "config.toml";
 pub const DATABASE_FILE_NAME: &str = "index.db";
+pub const DEFAULT_FTS_TOKENIZER: &str = "porter unicode61";
+
+/// FTS5 tokenizers a `fts_tokenizer` value may start with
+const FTS_TOKENIZERS: &[&str] = &["porter", "unicode61", "ascii", "trigram"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -24,22 +28,74 @@ pub struct Config {
     pub max_file_size_mb: u32,
     /// Additional glob patterns to ignore
     pub ignore_patterns: Vec<String>,
+    /// File name patterns (`*` wildcards) of generated files that are never
+    /// indexed: minified assets, source maps, lockfiles
+    pub generated_file_patterns: Vec<String>,
+    /// Skip code and config files whose average line length exceeds this,
+    /// as minified or generated content (0 = never)
+    pub max_avg_line_length: usize,
     /// Enable colored output
     pub color_enabled: bool,
     /// Debounce duration for file watcher in milliseconds
     pub watcher_debounce_ms: u64,
+    /// Seconds between scans in `watch --poll` mode (used when `--poll` has no value)
+    pub watcher_poll_interval_secs: u64,
     /// Number of files per database transaction batch
     pub batch_size: usize,
     /// Enable semantic search with embeddings
     pub enable_semantic_search: bool,
     /// Embedding model name (from fastembed)
     pub embedding_model: String,
-    /// Default search mode: "lexical", "semantic", or "hybrid"
+    /// Directory embedding models are downloaded to and loaded from
+    /// (fastembed's default cache when unset; `KDEX_MODEL_DIR` overrides it)
+    pub model_cache_dir: Option<PathBuf>,
+    /// Directory remote repositories are cloned into
+    /// (`repos` in the config directory when unset; `KDEX_REPOS_DIR` overrides it)
+    pub clone_dir: Option<PathBuf>,
+    /// Default search mode: "lexical", "semantic", "hybrid", or "auto" (picked per query)
     pub default_search_mode: String,
-    /// Strip markdown syntax from indexed content for cleaner FTS
+    /// Number of search results when `--limit` is not given (CLI and TUI)
+    pub default_search_limit: usize,
+    /// Most of a file read into the TUI preview pane (and `serve`'s `/file`), in KB; the rest is cut off
+    pub preview_max_kb: usize,
+    /// Drop lexical results whose BM25 relevance (normalized to 0..1) is
+    /// below this (0 = keep everything; `search --min-score` overrides it)
+    pub min_lexical_score: f64,
+    /// Record each CLI search (query, mode, result count, time) in the local
+    /// database for `stats --queries`. Off by default; nothing leaves the machine.
+    pub log_queries: bool,
+    /// Strip markdown, org-mode and reStructuredText syntax from full-text
+    /// indexed content (the original is kept for hashes and embeddings)
     pub strip_markdown_syntax: bool,
     /// Index code blocks with their language tags
     pub index_code_blocks: bool,
+    /// FTS5 tokenizer of the full-text index, e.g. `porter unicode61`,
+    /// `unicode61 remove_diacritics 2`, or `trigram` for substring matching.
+    /// Changing it re-tokenizes the index (done by `kdex config fts_tokenizer`).
+    pub fts_tokenizer: String,
+    /// Number of leading bytes inspected when deciding whether a file is binary
+    pub binary_null_scan_bytes: usize,
+    /// Binary heuristic: "null" (any null byte) or "ratio" (share of control bytes)
+    pub binary_detection: String,
+    /// Skip files with fewer non-whitespace characters of body text than this
+    /// (frontmatter and markdown syntax excluded; 0 = index everything)
+    pub min_content_chars: usize,
+    /// Refuse to index a directory with more files than this (0 = no limit)
+    pub max_files: usize,
+    /// Refuse to index a directory with more content than this, in MB (0 = no limit)
+    pub max_total_mb: u64,
+    /// Descend into symlinked directories while indexing.
+    /// Symlink cycles are detected and skipped by the directory walker.
+    pub follow_symlinks: bool,
+    /// Index hidden files and directories (names starting with `.`, such as `.env`).
+    /// Off by default so dotfiles, which often hold secrets, stay out of the index.
+    pub index_hidden: bool,
+    /// File types (as stored, e.g. "config", "json", "markdown") that are never embedded.
+    /// Excluded files stay searchable lexically.
+    pub embedding_exclude_types: Vec<String>,
+    /// Shell command run after a repository is re-indexed with changes.
+    /// Supports `{repo_path}` and `{files_changed}` placeholders.
+    pub post_index_command: Option<String>,
 }
 
 impl Default for Config {
@@ -55,14 +111,49 @@ impl Default for Config {
                 String::from(".venv"),
                 String::from("venv"),
             ],
+            generated_file_patterns: [
+                "*.min.js",
+                "*.min.mjs",
+                "*.min.css",
+                "*.map",
+                "package-lock.json",
+                "npm-shrinkwrap.json",
+                "pnpm-lock.yaml",
+                "yarn.lock",
+                "Cargo.lock",
+                "poetry.lock",
+                "Gemfile.lock",
+                "composer.lock",
+                "go.sum",
+            ]
+            .map(String::from)
+            .to_vec(),
+            max_avg_line_length: 1000,
             color_enabled: true,
             watcher_debounce_ms: 500,
+            watcher_poll_interval_secs: 2,
             batch_size: 100,
             enable_semantic_search: false,
             embedding_model: String::from("all-MiniLM-L6-v2"),
+            model_cache_dir: None,
+            clone_dir: None,
             default_search_mode: String::from("lexical"),
+            default_search_limit: 20,
+            preview_max_kb: 1024,
+            min_lexical_score: 0.0,
+            log_queries: false,
             strip_markdown_syntax: false,
             index_code_blocks: true,
+            fts_tokenizer: String::from(DEFAULT_FTS_TOKENIZER),
+            binary_null_scan_bytes: 8192,
+            binary_detection: String::from("null"),
+            min_content_chars: 0,
+            max_files: 100_000,
+            max_total_mb: 2048,
+            follow_symlinks: false,
+            index_hidden: false,
+            embedding_exclude_types: vec![String::from("config")],
+            post_index_command: None,
         }
     }
 }
@@ -116,8 +207,14 @@ impl Config {
         Ok(Self::config_dir()?.join(CONFIG_FILE_NAME))
     }
 
-    /// Get the path to the database file
+    /// Get the path to the database file.
+    /// `KDEX_DB_PATH` (also set by `--db-path`) overrides the config-derived path.
     pub fn database_path() -> Result<PathBuf> {
+        if let Ok(path) = std::env::var("KDEX_DB_PATH") {
+            if !path.is_empty() {
+                return Ok(PathBuf::from(path));
+            }
+        }
         Ok(Self::config_dir()?.join(DATABASE_FILE_NAME))
     }
 
@@ -157,6 +254,80 @@ impl Config {
     pub fn max_file_size_bytes(&self) -> u64 {
         u64::from(self.max_file_size_mb) * 1024 * 1024
     }
+
+    /// Maximum total content size in bytes per indexed directory (0 = no limit)
+    #[must_use]
+    pub fn max_total_bytes(&self) -> u64 {
+        self.max_total_mb.saturating_mul(1024 * 1024)
+    }
+
+    /// Effective embedding model directory: `KDEX_MODEL_DIR`, then `model_cache_dir`
+    #[must_use]
+    pub fn model_cache_dir(&self) -> Option<PathBuf> {
+        std::env::var_os("KDEX_MODEL_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| self.model_cache_dir.clone())
+    }
+
+    /// Effective clone directory for remote repositories: `KDEX_REPOS_DIR`, then `clone_dir`
+    #[must_use]
+    pub fn clone_dir(&self) -> Option<PathBuf> {
+        std::env::var_os("KDEX_REPOS_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| self.clone_dir.clone())
+    }
+
+    /// Whether `fts_tokenizer` names a known FTS5 tokenizer followed only by plain
+    /// options (letters, digits, underscores), so it is safe to put in the schema
+    #[must_use]
+    pub fn is_valid_fts_tokenizer(value: &str) -> bool {
+        let mut words = value.split_whitespace();
+        words
+            .next()
+            .is_some_and(|name| FTS_TOKENIZERS.contains(&name))
+            && words.all(|w| w.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    }
+
+    /// Whether a file name matches `generated_file_patterns` (case-insensitive)
+    #[must_use]
+    pub fn is_generated_file(&self, file_name: &str) -> bool {
+        let name = file_name.to_lowercase();
+        self.generated_file_patterns
+            .iter()
+            .any(|pattern| wildcard_match(&pattern.to_lowercase(), &name))
+    }
+
+    /// Whether files of this type get embeddings (see `embedding_exclude_types`)
+    #[must_use]
+    pub fn embeds_file_type(&self, file_type: &str) -> bool {
+        !self
+            .embedding_exclude_types
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(file_type))
+    }
+}
+
+/// Match `text` against `pattern`, where `*` matches any run of characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: the whole text must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
 }
 
 #[cfg(test)]
@@ -172,6 +343,19 @@ mod tests {
         assert_eq!(config.batch_size, 100);
         assert!(!config.enable_semantic_search);
         assert_eq!(config.default_search_mode, "lexical");
+        assert!(config.min_lexical_score.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_valid_fts_tokenizer() {
+        assert!(Config::is_valid_fts_tokenizer(DEFAULT_FTS_TOKENIZER));
+        assert!(Config::is_valid_fts_tokenizer(
+            "unicode61 remove_diacritics 2"
+        ));
+        assert!(Config::is_valid_fts_tokenizer("trigram"));
+        assert!(!Config::is_valid_fts_tokenizer(""));
+        assert!(!Config::is_valid_fts_tokenizer("icu"));
+        assert!(!Config::is_valid_fts_tokenizer("unicode61 tokenchars '-'"));
     }
 
     #[test]
@@ -186,6 +370,51 @@ mod tests {
         assert_eq!(config.max_file_size_bytes(), 5 * 1024 * 1024);
     }
 
+    #[test]
+    fn test_max_total_bytes() {
+        let config = Config::default();
+        assert_eq!(config.max_total_bytes(), 2048 * 1024 * 1024);
+
+        let config = Config {
+            max_total_mb: 0,
+            ..Default::default()
+        };
+        assert_eq!(config.max_total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_embeds_file_type() {
+        let config = Config::default();
+        assert!(!config.embeds_file_type("config"));
+        assert!(config.embeds_file_type("markdown"));
+
+        let config = Config {
+            embedding_exclude_types: vec![String::from("JSON")],
+            ..Default::default()
+        };
+        assert!(!config.embeds_file_type("json"));
+        assert!(config.embeds_file_type("config"));
+    }
+
+    #[test]
+    fn test_is_generated_file() {
+        let config = Config::default();
+        assert!(config.is_generated_file("app.min.js"));
+        assert!(config.is_generated_file("Bundle.MIN.CSS"));
+        assert!(config.is_generated_file("app.js.map"));
+        assert!(config.is_generated_file("package-lock.json"));
+        assert!(!config.is_generated_file("app.js"));
+        assert!(!config.is_generated_file("package.json"));
+        assert!(!config.is_generated_file("mapping.rs"));
+
+        let config = Config {
+            generated_file_patterns: vec![String::from("*.gen.*")],
+            ..Default::default()
+        };
+        assert!(config.is_generated_file("schema.gen.ts"));
+        assert!(!config.is_generated_file("app.min.js"));
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = Config::default();
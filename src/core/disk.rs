@@ -0,0 +1,177 @@
+//! Reading indexed files back from disk at search time.
+//!
+//! The index drifts from disk when files are deleted or moved without a
+//! re-index. Readers count such files so commands can warn once with
+//! [`STALE_INDEX_HINT`] instead of failing per file.
+
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, ErrorKind, Read};
+use std::path::Path;
+
+use crate::db::Database;
+
+/// How to bring the index back in sync when indexed files are missing on disk
+pub const STALE_INDEX_HINT: &str =
+    "run `kdex update` to re-sync the index (or `kdex prune` if a repository was moved)";
+
+/// Reads indexed files from disk, counting files that no longer exist
+pub struct IndexedFileReader {
+    db: Database,
+    missing: Cell<usize>,
+}
+
+impl IndexedFileReader {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            missing: Cell::new(0),
+        }
+    }
+
+    /// Current content of an indexed file. When it can't be read from disk
+    /// (missing, or not plain text like a PDF), the content stored in the
+    /// index at its last indexing is returned instead.
+    pub fn read(
+        &self,
+        repo_name: &str,
+        relative_path: &Path,
+        absolute_path: &Path,
+    ) -> Option<String> {
+        self.read_from_disk(absolute_path).or_else(|| {
+            self.db
+                .get_stored_content(repo_name, relative_path)
+                .ok()
+                .flatten()
+        })
+    }
+
+    /// Like [`read`](Self::read), but reads at most `max_bytes` so huge files
+    /// don't have to fit in memory. Returns the content and whether it was cut.
+    pub fn read_prefix(
+        &self,
+        repo_name: &str,
+        relative_path: &Path,
+        absolute_path: &Path,
+        max_bytes: usize,
+    ) -> Option<(String, bool)> {
+        match read_prefix(absolute_path, max_bytes) {
+            Ok(prefix) => Some(prefix),
+            Err(e) => {
+                if e.kind() == ErrorKind::NotFound {
+                    self.missing.set(self.missing.get() + 1);
+                }
+                let mut content = self
+                    .db
+                    .get_stored_content(repo_name, relative_path)
+                    .ok()
+                    .flatten()?;
+                let truncated = truncate_to_char_boundary(&mut content, max_bytes);
+                Some((content, truncated))
+            }
+        }
+    }
+
+    /// Current content of an indexed file on disk only, for callers that
+    /// must not fall back to stale content (e.g. regex matching)
+    pub fn read_from_disk(&self, absolute_path: &Path) -> Option<String> {
+        match std::fs::read_to_string(absolute_path) {
+            Ok(content) => Some(content),
+            Err(e) => {
+                if e.kind() == ErrorKind::NotFound {
+                    self.missing.set(self.missing.get() + 1);
+                }
+                None
+            }
+        }
+    }
+
+    /// Number of files read so far that no longer exist on disk
+    pub fn missing(&self) -> usize {
+        self.missing.get()
+    }
+}
+
+/// Read at most `max_bytes` of a UTF-8 file, cut back to a character
+/// boundary. Returns the content and whether the file is longer.
+pub fn read_prefix(path: &Path, max_bytes: usize) -> io::Result<(String, bool)> {
+    let mut bytes = Vec::new();
+    File::open(path)?
+        .take(
+            u64::try_from(max_bytes)
+                .unwrap_or(u64::MAX)
+                .saturating_add(1),
+        )
+        .read_to_end(&mut bytes)?;
+    let truncated = bytes.len() > max_bytes;
+    bytes.truncate(max_bytes);
+
+    let invalid = || io::Error::new(ErrorKind::InvalidData, "file is not valid UTF-8");
+    match String::from_utf8(bytes) {
+        Ok(content) => Ok((content, truncated)),
+        // Only an incomplete character at the cut is expected
+        Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes)
+                .map(|content| (content, true))
+                .map_err(|_| invalid())
+        }
+        Err(_) => Err(invalid()),
+    }
+}
+
+/// Shorten `content` to at most `max_bytes` at a character boundary;
+/// returns whether anything was cut
+fn truncate_to_char_boundary(content: &mut String, max_bytes: usize) -> bool {
+    if content.len() <= max_bytes {
+        return false;
+    }
+    let mut end = max_bytes;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    content.truncate(end);
+    true
+}
+
+/// One-line warning for `missing` indexed files that are gone from disk
+#[must_use]
+pub fn missing_files_warning(missing: usize) -> String {
+    if missing == 1 {
+        format!("1 indexed file no longer exists on disk; {STALE_INDEX_HINT}")
+    } else {
+        format!("{missing} indexed files no longer exist on disk; {STALE_INDEX_HINT}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, "héllo wörld").unwrap();
+
+        assert_eq!(
+            read_prefix(&path, 100).unwrap(),
+            ("héllo wörld".to_string(), false)
+        );
+        // The cut falls inside "é" and moves back before it
+        assert_eq!(read_prefix(&path, 2).unwrap(), ("h".to_string(), true));
+        assert_eq!(read_prefix(&path, 3).unwrap(), ("hé".to_string(), true));
+
+        std::fs::write(&path, b"ok\xff\xfe").unwrap();
+        assert_eq!(
+            read_prefix(&path, 100).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        let mut content = "hé".to_string();
+        assert!(truncate_to_char_boundary(&mut content, 2));
+        assert_eq!(content, "h");
+    }
+}
@@ -0,0 +1,141 @@
+//! Diversified ordering of ranked results (`context --strategy diverse`).
+//!
+//! A maximal marginal relevance (MMR) pass: each pick trades the candidate's
+//! rank against how similar it is to what was already picked, so a handful of
+//! near-duplicate files from one directory don't crowd out everything else.
+
+use std::path::Path;
+
+use super::embedder::cosine_similarity;
+
+/// Weight of relevance against novelty (1.0 keeps the ranked order)
+pub const MMR_LAMBDA: f64 = 0.5;
+
+/// A ranked candidate: where it lives and, when embeddings are stored, its
+/// mean chunk vector
+pub struct DiversityItem<'a> {
+    pub repo: &'a str,
+    pub path: &'a Path,
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Order `items` (given best first) by maximal marginal relevance and return
+/// their indices. Relevance comes from rank alone, since lexical and semantic
+/// scores aren't on a common scale.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn mmr_order(items: &[DiversityItem], lambda: f64) -> Vec<usize> {
+    let n = items.len();
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut order: Vec<usize> = Vec::with_capacity(n);
+
+    while !remaining.is_empty() {
+        let mmr = |i: usize| {
+            let relevance = 1.0 - i as f64 / n as f64;
+            let redundancy = order
+                .iter()
+                .map(|&j| similarity(&items[i], &items[j]))
+                .fold(0.0, f64::max);
+            lambda * relevance - (1.0 - lambda) * redundancy
+        };
+        // Ties keep the ranked order
+        let best = (0..remaining.len())
+            .max_by(|&a, &b| {
+                mmr(remaining[a])
+                    .total_cmp(&mmr(remaining[b]))
+                    .then(remaining[b].cmp(&remaining[a]))
+            })
+            .unwrap_or(0);
+        order.push(remaining.remove(best));
+    }
+
+    order
+}
+
+/// Similarity of two candidates in [0, 1]: their embeddings' cosine when
+/// both have one, otherwise how close they sit in the same repository
+fn similarity(a: &DiversityItem, b: &DiversityItem) -> f64 {
+    match (&a.embedding, &b.embedding) {
+        (Some(x), Some(y)) => f64::from(cosine_similarity(x, y)).clamp(0.0, 1.0),
+        _ => path_similarity(a, b),
+    }
+}
+
+/// 0 across repositories; within one, from 0.25 for unrelated directories up
+/// to 1 for files in the same directory
+#[allow(clippy::cast_precision_loss)]
+fn path_similarity(a: &DiversityItem, b: &DiversityItem) -> f64 {
+    if a.repo != b.repo {
+        return 0.0;
+    }
+    let dirs = |path: &'_ Path| -> Vec<String> {
+        path.parent()
+            .map(|p| {
+                p.components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let (dirs_a, dirs_b) = (dirs(a.path), dirs(b.path));
+    let depth = dirs_a.len().max(dirs_b.len());
+    if depth == 0 {
+        return 1.0;
+    }
+    let shared = dirs_a
+        .iter()
+        .zip(&dirs_b)
+        .take_while(|(x, y)| x == y)
+        .count();
+    0.25 + 0.75 * shared as f64 / depth as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item<'a>(repo: &'a str, path: &'a str) -> DiversityItem<'a> {
+        DiversityItem {
+            repo,
+            path: Path::new(path),
+            embedding: None,
+        }
+    }
+
+    #[test]
+    fn test_mmr_order_spreads_directories() {
+        let items = [
+            item("api", "docs/auth/login.md"),
+            item("api", "docs/auth/tokens.md"),
+            item("api", "docs/auth/sessions.md"),
+            item("notes", "security.md"),
+            item("api", "src/auth.rs"),
+        ];
+        assert_eq!(mmr_order(&items, MMR_LAMBDA), vec![0, 3, 4, 1, 2]);
+        // Without the novelty term the ranked order is kept
+        assert_eq!(mmr_order(&items, 1.0), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_mmr_order_prefers_embeddings() {
+        let mut items = [
+            item("notes", "a.md"),
+            item("notes", "b.md"),
+            item("notes", "c.md"),
+        ];
+        items[0].embedding = Some(vec![1.0, 0.0]);
+        items[1].embedding = Some(vec![0.99, 0.1]);
+        items[2].embedding = Some(vec![0.0, 1.0]);
+        // Same directory, but only b duplicates a's content
+        assert_eq!(mmr_order(&items, MMR_LAMBDA), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn test_path_similarity() {
+        let sim = |a, b| path_similarity(&item("r", a), &item("r", b));
+        assert!((sim("a/b/x.md", "a/b/y.md") - 1.0).abs() < f64::EPSILON);
+        assert!((sim("a/b/x.md", "a/c/y.md") - 0.625).abs() < f64::EPSILON);
+        assert!((sim("a/x.md", "b/y.md") - 0.25).abs() < f64::EPSILON);
+        assert!(path_similarity(&item("r", "x.md"), &item("s", "x.md")).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,119 @@
+//! Opening files in the user's editor or a previewer, and directories in
+//! the platform file manager.
+//!
+//! Shared by the TUI (`Ctrl+O`), `search --open` / `--preview` and `repos open`.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::hooks::{shell_command, shell_quote};
+use crate::error::{AppError, Result};
+
+#[cfg(not(target_os = "windows"))]
+const DEFAULT_EDITOR: &str = "vim";
+#[cfg(target_os = "windows")]
+const DEFAULT_EDITOR: &str = "notepad";
+
+/// The editor command: `$VISUAL`, then `$EDITOR`, then vim (notepad on Windows)
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// Open `path` in the editor and wait for it to exit.
+///
+/// The editor runs through the shell like git's `core.editor`, so values
+/// with arguments such as `code --wait` work.
+pub fn open_in_editor(path: &Path) -> Result<()> {
+    let editor = editor_command();
+    run_interactive(
+        &format!("{editor} {}", shell_quote(&path.to_string_lossy())),
+        &editor,
+    )
+}
+
+/// Run a `--preview` command for `path` and wait for it to exit. `{}` in the
+/// template is replaced by the shell-quoted path; without `{}` the path is
+/// appended.
+pub fn run_preview(template: &str, path: &Path) -> Result<()> {
+    run_interactive(&expand_preview(template, path), template)
+}
+
+fn expand_preview(template: &str, path: &Path) -> String {
+    let quoted = shell_quote(&path.to_string_lossy());
+    if template.contains("{}") {
+        template.replace("{}", &quoted)
+    } else {
+        format!("{template} {quoted}")
+    }
+}
+
+/// The platform's file manager launcher, or `None` without a graphical session
+fn file_manager() -> Option<&'static str> {
+    if cfg!(target_os = "macos") {
+        Some("open")
+    } else if cfg!(target_os = "windows") {
+        Some("explorer")
+    } else {
+        ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+            .then_some("xdg-open")
+    }
+}
+
+/// Reveal `dir` in the platform file manager without waiting for it.
+/// Returns `false` in a headless session or when the launcher isn't
+/// installed, where there is nothing to open it in.
+pub fn open_in_file_manager(dir: &Path) -> Result<bool> {
+    let Some(launcher) = file_manager() else {
+        return Ok(false);
+    };
+    let spawned = Command::new(launcher)
+        .arg(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match spawned {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(AppError::Other(format!(
+            "Could not start `{launcher}`: {e}"
+        ))),
+    }
+}
+
+/// Run `command` with the terminal attached; `name` is used in errors
+fn run_interactive(command: &str, name: &str) -> Result<()> {
+    let status = shell_command(command)
+        .status()
+        .map_err(|e| AppError::Other(format!("Could not start `{name}`: {e}")))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::Other(format!("`{name}` exited with {status}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_expand_preview() {
+        let path = Path::new("/notes/it's.md");
+        assert_eq!(
+            expand_preview("bat --color=always {}", path),
+            r"bat --color=always '/notes/it'\''s.md'"
+        );
+        assert_eq!(
+            expand_preview("head -20", path),
+            r"head -20 '/notes/it'\''s.md'"
+        );
+    }
+}
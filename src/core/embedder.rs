@@ -2,10 +2,39 @@
 //!
 //! Uses fastembed for local embedding generation with the all-MiniLM-L6-v2 model.
 
+use std::path::Path;
 use std::sync::Mutex;
 
 use crate::error::{AppError, Result};
 
+/// Line prefixes that start a top-level definition in common languages
+const DEFINITION_PREFIXES: &[&str] = &[
+    "fn ",
+    "pub ",
+    "pub(",
+    "async ",
+    "impl ",
+    "impl<",
+    "struct ",
+    "enum ",
+    "trait ",
+    "mod ",
+    "def ",
+    "class ",
+    "function ",
+    "func ",
+    "export ",
+    "interface ",
+    "type ",
+    "const ",
+    "static ",
+    "module ",
+    "package ",
+];
+
+/// Line prefixes for comments, attributes and decorators that belong to the next definition
+const PREAMBLE_PREFIXES: &[&str] = &["//", "#", "@", "/*", "*", "--", ";"];
+
 /// Chunk of text with metadata for embedding
 #[derive(Debug, Clone)]
 pub struct TextChunk {
@@ -32,22 +61,45 @@ pub struct Embedder {
 }
 
 impl Embedder {
-    /// Create a new embedder with the specified model
-    pub fn new(model_name: &str) -> Result<Self> {
+    /// Create a new embedder with the specified model.
+    ///
+    /// The model is loaded from `cache_dir` (downloading it there if missing),
+    /// or from fastembed's default cache when no directory is given.
+    pub fn new(model_name: &str, cache_dir: Option<&Path>) -> Result<Self> {
         let model_type = Self::parse_model_name(model_name)?;
 
-        let options = fastembed::TextInitOptions::new(model_type);
+        let mut options = fastembed::TextInitOptions::new(model_type.clone());
+        if let Some(dir) = cache_dir {
+            options = options.with_cache_dir(dir.to_path_buf());
+        }
 
-        let model = fastembed::TextEmbedding::try_new(options)
-            .map_err(|e| AppError::Other(format!("Failed to load embedding model: {e}")))?;
+        let model = fastembed::TextEmbedding::try_new(options).map_err(|e| match cache_dir {
+            Some(dir) if !Self::is_cached(&model_type, dir) => AppError::Config(format!(
+                "Embedding model {model_name} is not in {} and could not be downloaded: {e}. \
+                 On a machine with network access, run `KDEX_MODEL_DIR=<dir> kdex rebuild-embeddings` \
+                 to download it into <dir>, then copy that directory to {}.",
+                dir.display(),
+                dir.display()
+            )),
+            _ => AppError::Other(format!("Failed to load embedding model: {e}")),
+        })?;
 
         Ok(Self {
             model: Mutex::new(model),
         })
     }
 
+    /// Whether a model's files exist in a fastembed cache directory
+    fn is_cached(model: &fastembed::EmbeddingModel, cache_dir: &Path) -> bool {
+        fastembed::TextEmbedding::get_model_info(model).is_ok_and(|info| {
+            cache_dir
+                .join(format!("models--{}", info.model_code.replace('/', "--")))
+                .is_dir()
+        })
+    }
+
     /// Parse model name string to fastembed model type
-    fn parse_model_name(name: &str) -> Result<fastembed::EmbeddingModel> {
+    pub fn parse_model_name(name: &str) -> Result<fastembed::EmbeddingModel> {
         match name.to_lowercase().as_str() {
             "all-minilm-l6-v2" | "minilm" => Ok(fastembed::EmbeddingModel::AllMiniLML6V2),
             "bge-small-en-v1.5" | "bge-small" => Ok(fastembed::EmbeddingModel::BGESmallENV15),
@@ -122,8 +174,84 @@ impl Embedder {
         chunks
     }
 
+    /// Split source code into chunks aligned to logical units
+    ///
+    /// Prefers to break before top-level definitions (`fn`, `def`, `class`, ...),
+    /// then at blank lines. Falls back to character chunking when a window has
+    /// no such boundary.
+    pub fn chunk_code(content: &str, max_tokens: usize, overlap_tokens: usize) -> Vec<TextChunk> {
+        let max_chars = max_tokens * 4;
+        let overlap_chars = overlap_tokens * 4;
+
+        if content.len() <= max_chars {
+            return Self::chunk_text(content, max_tokens, overlap_tokens);
+        }
+
+        let (definitions, blank_lines) = code_boundaries(content);
+        let mut chunks = Vec::new();
+        let mut start = 0;
+
+        while start < content.len() {
+            let mut limit = (start + max_chars).min(content.len());
+            while !content.is_char_boundary(limit) {
+                limit -= 1;
+            }
+
+            let (end, next_start) = if limit >= content.len() {
+                (content.len(), content.len())
+            } else {
+                // Don't accept boundaries that would leave a tiny chunk
+                let min_end = start + max_chars / 4;
+                let in_window = |b: &&usize| **b > min_end && **b <= limit;
+
+                if let Some(&b) = definitions.iter().rev().find(in_window) {
+                    (b, b)
+                } else if let Some(&b) = blank_lines.iter().rev().find(in_window) {
+                    (b, b)
+                } else {
+                    // No logical boundary: break at whitespace with overlap
+                    let end = content[start..limit]
+                        .rfind(char::is_whitespace)
+                        .map_or(limit, |pos| start + pos);
+                    let end = if end <= start { limit } else { end };
+                    let mut next = end.saturating_sub(overlap_chars).max(start + 1);
+                    while !content.is_char_boundary(next) {
+                        next += 1;
+                    }
+                    (end, next.min(end))
+                }
+            };
+
+            let chunk_text = content[start..end].trim();
+            if !chunk_text.is_empty() {
+                chunks.push(TextChunk {
+                    text: chunk_text.to_string(),
+                    start_offset: start,
+                    end_offset: end,
+                });
+            }
+
+            if next_start <= start {
+                break;
+            }
+            start = next_start;
+        }
+
+        chunks
+    }
+
     /// Generate embeddings for text chunks
     pub fn embed_chunks(&self, chunks: &[TextChunk]) -> Result<Vec<ChunkEmbedding>> {
+        self.embed_chunks_batched(chunks, None)
+    }
+
+    /// Generate embeddings for text chunks, sending at most `batch_size`
+    /// chunks to the model per inference call (model default when `None`)
+    pub fn embed_chunks_batched(
+        &self,
+        chunks: &[TextChunk],
+        batch_size: Option<usize>,
+    ) -> Result<Vec<ChunkEmbedding>> {
         if chunks.is_empty() {
             return Ok(Vec::new());
         }
@@ -136,7 +264,7 @@ impl Embedder {
             .map_err(|e| AppError::Other(format!("Failed to lock model: {e}")))?;
 
         let embeddings = model
-            .embed(texts, None)
+            .embed(texts, batch_size)
             .map_err(|e| AppError::Other(format!("Failed to generate embeddings: {e}")))?;
 
         let results = chunks
@@ -171,11 +299,51 @@ impl Embedder {
         let chunks = Self::chunk_text(content, 512, 50);
         self.embed_chunks(&chunks)
     }
+
+    /// Generate embeddings for source code, chunked along definitions
+    pub fn embed_code(&self, content: &str) -> Result<Vec<ChunkEmbedding>> {
+        let chunks = Self::chunk_code(content, 512, 50);
+        self.embed_chunks(&chunks)
+    }
+}
+
+/// Find byte offsets where code can be split: the start of top-level
+/// definitions (including their leading comments/attributes) and the line
+/// after each blank line
+fn code_boundaries(content: &str) -> (Vec<usize>, Vec<usize>) {
+    let mut definitions = Vec::new();
+    let mut blank_lines = Vec::new();
+    // Start of the current run of comment/attribute lines at column 0
+    let mut preamble_start: Option<usize> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            blank_lines.push(offset);
+            preamble_start = None;
+            continue;
+        }
+
+        let top_level = !line.starts_with(char::is_whitespace);
+        if top_level && DEFINITION_PREFIXES.iter().any(|p| trimmed.starts_with(p)) {
+            definitions.push(preamble_start.unwrap_or(line_start));
+            preamble_start = None;
+        } else if top_level && PREAMBLE_PREFIXES.iter().any(|p| trimmed.starts_with(p)) {
+            preamble_start.get_or_insert(line_start);
+        } else {
+            preamble_start = None;
+        }
+    }
+
+    (definitions, blank_lines)
 }
 
 /// Calculate cosine similarity between two vectors
 #[must_use]
-#[allow(dead_code)]
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() {
         return 0.0;
@@ -211,6 +379,25 @@ mod tests {
         assert!(chunks.len() > 1);
     }
 
+    #[test]
+    fn test_chunk_code_breaks_at_definitions() {
+        let body = "    let x = 1;\n".repeat(20);
+        let content =
+            format!("/// First\nfn first() {{\n{body}}}\n/// Second\nfn second() {{\n{body}}}\n");
+        let chunks = Embedder::chunk_code(&content, 100, 10);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].text.starts_with("/// First\nfn first()"));
+        assert!(chunks[1].text.starts_with("/// Second\nfn second()"));
+    }
+
+    #[test]
+    fn test_chunk_code_falls_back_without_boundaries() {
+        let content = "x ".repeat(1000);
+        let chunks = Embedder::chunk_code(&content, 100, 10);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.text.len() <= 400));
+    }
+
     #[test]
     fn test_cosine_similarity() {
         let a = vec![1.0, 0.0, 0.0];
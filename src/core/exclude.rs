@@ -0,0 +1,161 @@
+//! Search-time path exclusion (`search --exclude-pattern`).
+//!
+//! Patterns are matched against each result's path relative to its
+//! repository after the query runs, so the index itself is untouched.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+use crate::error::{AppError, Result};
+
+use super::overfetch::fetch_until;
+
+/// How many candidates to fetch per wanted result on the first try
+const OVERFETCH: usize = 4;
+
+/// Compiled exclude globs. `*` also matches `/`, so `*/test/*` drops any
+/// file under a nested `test` directory and `*.generated.*` matches at any depth.
+pub struct PathExclude(GlobSet);
+
+impl PathExclude {
+    /// Compile `patterns`; `None` when there are none
+    pub fn new(patterns: &[String]) -> Result<Option<Self>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern).map_err(|e| {
+                AppError::Other(format!("Invalid exclude pattern \"{pattern}\": {e}"))
+            })?;
+            builder.add(glob);
+        }
+        let set = builder
+            .build()
+            .map_err(|e| AppError::Other(format!("Invalid exclude patterns: {e}")))?;
+        Ok(Some(Self(set)))
+    }
+
+    /// Whether a repository-relative path matches any pattern
+    #[must_use]
+    pub fn is_excluded(&self, relative_path: &Path) -> bool {
+        self.0.is_match(relative_path)
+    }
+}
+
+/// Run `fetch(limit, offset)` and drop results whose `path` is excluded.
+///
+/// With patterns set the query runs from the start for `OVERFETCH` times the
+/// wanted results, and for more until enough survive (see [`fetch_until`]).
+pub fn fetch_kept<T>(
+    exclude: Option<&PathExclude>,
+    limit: usize,
+    offset: usize,
+    mut fetch: impl FnMut(usize, usize) -> Result<Vec<T>>,
+    path: impl Fn(&T) -> &Path,
+) -> Result<Vec<T>> {
+    let Some(exclude) = exclude else {
+        return fetch(limit, offset);
+    };
+
+    let wanted = offset.saturating_add(limit);
+    let mut kept = fetch_until(
+        wanted,
+        wanted.saturating_mul(OVERFETCH),
+        |batch_size| fetch(batch_size, 0),
+        |batch| {
+            batch
+                .into_iter()
+                .filter(|r| !exclude.is_excluded(path(r)))
+                .collect()
+        },
+    )?;
+    kept.drain(..offset.min(kept.len()));
+    kept.truncate(limit);
+    Ok(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn exclude(patterns: &[&str]) -> PathExclude {
+        let patterns: Vec<String> = patterns.iter().map(ToString::to_string).collect();
+        PathExclude::new(&patterns).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let ex = exclude(&["*/test/*", "*.generated.*"]);
+        assert!(ex.is_excluded(Path::new("src/test/util.rs")));
+        assert!(ex.is_excluded(Path::new("api/schema.generated.ts")));
+        assert!(ex.is_excluded(Path::new("deep/dir/types.generated.rs")));
+        assert!(!ex.is_excluded(Path::new("test/util.rs")));
+        assert!(!ex.is_excluded(Path::new("src/testing/util.rs")));
+
+        assert!(PathExclude::new(&[]).unwrap().is_none());
+        assert!(PathExclude::new(&["src/[a".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_fetch_kept_fetches_more_to_fill_limit() {
+        // Every other candidate is excluded
+        let paths: Vec<PathBuf> = (0..20)
+            .map(|i| {
+                PathBuf::from(if i % 2 == 0 {
+                    format!("gen/{i}.rs")
+                } else {
+                    format!("src/{i}.rs")
+                })
+            })
+            .collect();
+        let ex = exclude(&["gen/*"]);
+        let mut calls = Vec::new();
+        let kept = fetch_kept(
+            Some(&ex),
+            3,
+            1,
+            |limit, offset| {
+                calls.push((limit, offset));
+                Ok(paths.iter().skip(offset).take(limit).cloned().collect())
+            },
+            PathBuf::as_path,
+        )
+        .unwrap();
+        assert_eq!(
+            kept,
+            vec![
+                PathBuf::from("src/3.rs"),
+                PathBuf::from("src/5.rs"),
+                PathBuf::from("src/7.rs")
+            ]
+        );
+        assert_eq!(calls, vec![(16, 0)]);
+
+        // Runs out of candidates: returns what survives
+        let kept = fetch_kept(
+            Some(&ex),
+            50,
+            0,
+            |limit, offset| Ok(paths.iter().skip(offset).take(limit).cloned().collect()),
+            PathBuf::as_path,
+        )
+        .unwrap();
+        assert_eq!(kept.len(), 10);
+
+        // Without patterns the query runs once, as asked
+        let kept = fetch_kept(
+            None,
+            2,
+            4,
+            |limit, offset| Ok(paths.iter().skip(offset).take(limit).cloned().collect()),
+            PathBuf::as_path,
+        )
+        .unwrap();
+        assert_eq!(
+            kept,
+            vec![PathBuf::from("gen/4.rs"), PathBuf::from("src/5.rs")]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Post-index hook execution.
+//!
+//! Runs the user's `post_index_command` after a repository has been
+//! (re)indexed, e.g. to regenerate a static site or notify a dashboard.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use super::IndexResult;
+use crate::config::Config;
+
+/// Run the configured `post_index_command` for a repository, if any.
+///
+/// `{repo_path}` and `{files_changed}` in the template are substituted. The
+/// command runs through the shell without blocking the caller; a non-zero
+/// exit is reported on stderr once it finishes. Nothing runs when the index
+/// did not change.
+pub fn run_post_index_command(config: &Config, repo_path: &Path, result: &IndexResult) {
+    let Some(template) = config.post_index_command.as_deref() else {
+        return;
+    };
+
+    let files_changed = result.files_added + result.files_updated + result.files_deleted;
+    if template.trim().is_empty() || files_changed == 0 {
+        return;
+    }
+
+    let command = expand_template(template, repo_path, files_changed);
+    let mut child = match shell_command(&command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("post_index_command failed to start: {e}");
+            return;
+        }
+    };
+
+    // Reap the child in the background so the caller (e.g. the watch loop) keeps going
+    thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => {
+            eprintln!("post_index_command exited with {status}: {command}");
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("post_index_command failed: {e}"),
+    });
+}
+
+/// Substitute `{repo_path}` (shell-quoted) and `{files_changed}`
+fn expand_template(template: &str, repo_path: &Path, files_changed: usize) -> String {
+    template
+        .replace("{repo_path}", &shell_quote(&repo_path.to_string_lossy()))
+        .replace("{files_changed}", &files_changed.to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(super) fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+pub(super) fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(super) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(target_os = "windows")]
+pub(super) fn shell_quote(s: &str) -> String {
+    format!("\"{s}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_expand_template() {
+        let cmd = expand_template(
+            "make -C {repo_path} site # {files_changed} changed",
+            Path::new("/notes/it's here"),
+            3,
+        );
+        assert_eq!(cmd, r"make -C '/notes/it'\''s here' site # 3 changed");
+    }
+}
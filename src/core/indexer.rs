@@ -1,14 +1,20 @@
 use chrono::{DateTime, Utc};
 use ignore::WalkBuilder;
+use std::borrow::Cow;
 use std::collections::HashSet;
+use std::fmt;
 use std::fs::{self, File};
-use std::io::Read;
-use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::io::{ErrorKind, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
 use crate::config::Config;
-use crate::core::{parse_markdown, Embedder};
+use crate::core::{
+    extract_notebook_text, parse_markdown, parse_orgmode, strip_markdown_syntax,
+    strip_orgmode_syntax, strip_rst_syntax, Embedder,
+};
 use crate::db::{Database, FileRecord, FileType, RepoStatus, Repository};
 use crate::error::{AppError, Result};
 
@@ -25,22 +31,93 @@ pub struct IndexProgress {
 }
 
 /// Result of indexing operation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct IndexResult {
     pub files_added: usize,
     pub files_updated: usize,
     pub files_deleted: usize,
     pub files_unchanged: usize,
     pub files_skipped: usize,
+    /// Skipped files (also in `skipped_details`) with less content than `min_content_chars`
+    pub files_empty: usize,
+    /// Skipped files (also in `skipped_details`) with lines longer than `max_avg_line_length`
+    pub files_minified: usize,
+    /// Files intentionally skipped (binary, too large) with the reason
+    pub skipped_details: Vec<(PathBuf, String)>,
+    /// Files that failed to index (IO or database errors) with the error
+    pub failed_details: Vec<(PathBuf, String)>,
     pub total_bytes: u64,
+    /// Files indexed without embeddings because of `embedding_exclude_types`
+    pub embeddings_skipped: usize,
+    /// Stopped early by the cancel flag; files committed so far are kept
+    pub cancelled: bool,
     pub elapsed_secs: f64,
 }
 
+impl IndexResult {
+    /// Report directories the walker couldn't read as failures
+    fn record_unreadable(&mut self, dirs: Vec<PathBuf>) {
+        self.failed_details.extend(
+            dirs.into_iter()
+                .map(|dir| (dir, UNREADABLE_DIR.to_string())),
+        );
+    }
+
+    /// Record why a file was intentionally not indexed
+    fn record_skip(&mut self, path: &Path, reason: &SkipReason) {
+        match reason {
+            SkipReason::TooLittleContent(_) => self.files_empty += 1,
+            SkipReason::Minified(_) => self.files_minified += 1,
+            SkipReason::Unsupported(_) => {}
+        }
+        self.skipped_details
+            .push((path.to_path_buf(), reason.to_string()));
+    }
+
+    /// Record a file that failed to index (IO or database error)
+    fn record_failure(&mut self, path: &Path, error: &AppError) {
+        self.failed_details
+            .push((path.to_path_buf(), error.to_string()));
+    }
+}
+
+/// Why a file was intentionally left out of the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SkipReason {
+    /// Binary, too large or without extractable text
+    Unsupported(String),
+    /// Fewer characters of content than `min_content_chars`
+    TooLittleContent(usize),
+    /// Average line length above `max_avg_line_length`
+    Minified(usize),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(reason) => write!(f, "{reason}"),
+            Self::TooLittleContent(chars) => write!(f, "empty ({chars} characters)"),
+            Self::Minified(line_length) => {
+                write!(f, "minified (average line length {line_length})")
+            }
+        }
+    }
+}
+
+/// What became of a single file handed to the indexer
+enum FileOutcome {
+    /// Stored, with its size in bytes
+    Indexed(u64),
+    Skipped(SkipReason),
+}
+
 /// File indexer
 pub struct Indexer {
     db: Database,
     config: Config,
     embedder: Option<Embedder>,
+    /// Set from another thread (e.g. a Ctrl+C handler) to stop after the current file
+    cancel: Option<Arc<AtomicBool>>,
 }
 
 // Binary file extensions to skip
@@ -52,12 +129,193 @@ const BINARY_EXTENSIONS: &[&str] = &[
     "sqlite", "sqlite3", "lock", "sum",
 ];
 
+/// Share of control bytes above which `binary_detection = "ratio"` treats a file as binary
+const NON_TEXT_RATIO_THRESHOLD: f64 = 0.3;
+
+/// Whether a byte is a control character that doesn't occur in text
+fn is_non_text_byte(b: u8) -> bool {
+    matches!(b, 0x00..=0x08 | 0x0E..=0x1A | 0x1C..=0x1F | 0x7F)
+}
+
+/// Classify the first `scan_bytes` of `content` as binary, returning the reason.
+///
+/// By default any null byte marks a file as binary; in ratio mode only a high share
+/// of control bytes does, so text with a stray null is still indexed.
+#[allow(clippy::cast_precision_loss)]
+fn binary_reason(content: &[u8], scan_bytes: usize, ratio_mode: bool) -> Option<String> {
+    let sample = &content[..scan_bytes.min(content.len())];
+    if sample.is_empty() {
+        return None;
+    }
+
+    if ratio_mode {
+        let non_text = sample.iter().filter(|&&b| is_non_text_byte(b)).count();
+        let ratio = non_text as f64 / sample.len() as f64;
+        (ratio > NON_TEXT_RATIO_THRESHOLD).then(|| {
+            format!(
+                "binary file: {:.0}% non-text bytes in first {} bytes",
+                ratio * 100.0,
+                sample.len()
+            )
+        })
+    } else {
+        sample
+            .iter()
+            .position(|&b| b == 0)
+            .map(|offset| format!("binary file: null byte at offset {offset}"))
+    }
+}
+
+/// Decode UTF-16 text that starts with a byte-order mark (it is full of null bytes)
+fn decode_utf16_bom(content: &[u8]) -> Option<String> {
+    let (little_endian, body) = match content {
+        [0xFF, 0xFE, rest @ ..] => (true, rest),
+        [0xFE, 0xFF, rest @ ..] => (false, rest),
+        _ => return None,
+    };
+
+    let units: Vec<u16> = body
+        .chunks_exact(2)
+        .map(|pair| {
+            if little_endian {
+                u16::from_le_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_be_bytes([pair[0], pair[1]])
+            }
+        })
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// Error for a directory that exceeds the configured index size limits
+#[allow(clippy::cast_precision_loss)]
+fn limit_error(root: &Path, limit: &str, files: usize, bytes: u64) -> AppError {
+    AppError::IndexLimitExceeded(format!(
+        "{} exceeds {limit} (stopped after {files} files, {:.1} MB). \
+         Index a narrower directory, add ignore_patterns, or raise the limit \
+         with --max-files / --max-total-mb or `kdex config` (0 disables it).",
+        root.display(),
+        bytes as f64 / (1024.0 * 1024.0)
+    ))
+}
+
+/// Why files under a directory the walker couldn't read were not indexed
+const UNREADABLE_DIR: &str =
+    "permission denied: directory not readable, its files were not indexed";
+
+/// Resolve a directory to index to its canonical path, mapping failures to
+/// errors that name the attempted path instead of a bare OS error. A directory
+/// that can be listed but not entered counts as permission denied.
+pub fn resolve_root(path: &Path) -> Result<PathBuf> {
+    let attempted = || std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let io_error = |e: std::io::Error, at: PathBuf| match e.kind() {
+        ErrorKind::NotFound => AppError::PathNotFound(at),
+        ErrorKind::PermissionDenied => AppError::PermissionDenied(at),
+        _ => AppError::Io(e),
+    };
+
+    let canonical = path.canonicalize().map_err(|e| io_error(e, attempted()))?;
+    if !canonical.is_dir() {
+        return Err(AppError::NotADirectory(canonical));
+    }
+    let mut entries = fs::read_dir(&canonical).map_err(|e| io_error(e, canonical.clone()))?;
+    if let Some(Ok(entry)) = entries.next() {
+        if let Err(e) = entry.metadata() {
+            if e.kind() == ErrorKind::PermissionDenied {
+                return Err(AppError::PermissionDenied(canonical));
+            }
+        }
+    }
+    Ok(canonical)
+}
+
+/// The path of a directory walk error caused by missing permissions
+fn unreadable_path(error: &ignore::Error) -> Option<&Path> {
+    if error.io_error()?.kind() != ErrorKind::PermissionDenied {
+        return None;
+    }
+    let mut error = error;
+    loop {
+        match error {
+            ignore::Error::WithPath { path, .. } => return Some(path),
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+                error = err;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Whether a repository-relative path is hidden: it or one of its
+/// directories has a name starting with `.` (what the directory walker skips)
+fn is_hidden(relative_path: &Path) -> bool {
+    relative_path
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with('.') && c != Component::CurDir)
+}
+
+/// Marks a repository as indexing while alive. Unless [`finish`](Self::finish)
+/// is reached, dropping it (an error returned with `?`, a panic) marks the
+/// repository as failed, so it doesn't stay "indexing" forever.
+struct IndexingStatus<'a> {
+    db: &'a Database,
+    repo_id: i64,
+    finished: bool,
+}
+
+impl<'a> IndexingStatus<'a> {
+    fn begin(db: &'a Database, repo_id: i64) -> Result<Self> {
+        db.update_repository_status(repo_id, RepoStatus::Indexing)?;
+        Ok(Self {
+            db,
+            repo_id,
+            finished: false,
+        })
+    }
+
+    /// The run completed (or was cancelled) and its files are stored; the
+    /// status was set along with the repository totals
+    fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for IndexingStatus<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            // Best effort: the database may be what failed
+            let _ = self
+                .db
+                .update_repository_status(self.repo_id, RepoStatus::Error);
+        }
+    }
+}
+
+/// Whether a file on disk looks the same as when it was indexed: not modified
+/// since and the same size. Unreadable metadata counts as changed.
+fn is_unchanged(path: &Path, existing: &FileRecord) -> bool {
+    fs::metadata(path).is_ok_and(|metadata| {
+        let mtime = metadata
+            .modified()
+            .map_or_else(|_| Utc::now(), DateTime::<Utc>::from);
+        #[allow(clippy::cast_possible_wrap)]
+        let file_size = metadata.len() as i64;
+        mtime <= existing.last_modified_at && file_size == existing.file_size_bytes
+    })
+}
+
+/// Whether a binary extension can still be indexed via text extraction
+pub(crate) fn is_extractable_document(ext: &str) -> bool {
+    cfg!(feature = "pdf") && ext.eq_ignore_ascii_case("pdf")
+}
+
 impl Indexer {
     pub fn new(db: Database, config: Config) -> Self {
         Self {
             db,
             config,
             embedder: None,
+            cancel: None,
         }
     }
 
@@ -68,9 +326,25 @@ impl Indexer {
             db,
             config,
             embedder: Some(embedder),
+            cancel: None,
         }
     }
 
+    /// Stop indexing once `flag` is set. The open batch is committed, so a later
+    /// run (e.g. `kdex update`) skips the files already indexed and resumes.
+    #[must_use]
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    /// Whether cancellation was requested
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
     /// Check if embeddings are enabled
     #[must_use]
     #[allow(dead_code)]
@@ -90,43 +364,41 @@ impl Indexer {
     {
         let start = Instant::now();
 
-        // Validate path
-        if !path.exists() {
-            return Err(AppError::PathNotFound(path.to_path_buf()));
-        }
-        if !path.is_dir() {
-            return Err(AppError::NotADirectory(path.to_path_buf()));
-        }
-
-        let canonical = path.canonicalize()?;
+        let canonical = resolve_root(path)?;
 
         // Check if already indexed
-        let existing = self.db.get_repository_by_path(&canonical)?;
-        let repo = if let Some(repo) = existing {
+        if let Some(repo) = self.db.get_repository_by_path(&canonical)? {
             // Update existing
-            return self.update_repository(&repo, progress_callback);
-        } else {
-            self.db.add_repository(&canonical, name)?
-        };
-
-        // Set status to indexing
-        self.db
-            .update_repository_status(repo.id, RepoStatus::Indexing)?;
+            return self.update_repository(&repo, None, false, progress_callback);
+        }
 
-        // Collect files
-        let files = self.collect_files(&canonical);
+        // Collect files before registering, so a size-limit failure leaves nothing behind
+        let (files, unreadable) = self.collect_files(&canonical)?;
         let total_files = files.len();
 
+        let repo = self.db.add_repository(&canonical, name)?;
+
+        let status = IndexingStatus::begin(&self.db, repo.id)?;
+
         // Progress tracking
         let processed = AtomicUsize::new(0);
         let skipped = AtomicUsize::new(0);
+        let embeddings_skipped = AtomicUsize::new(0);
         let bytes_processed = AtomicU64::new(0);
 
+        let mut result = IndexResult::default();
+        result.record_unreadable(unreadable);
+
         // Process files
         self.db.begin_batch()?;
 
         let mut batch_count = 0;
         for file_path in &files {
+            if self.is_cancelled() {
+                result.cancelled = true;
+                break;
+            }
+
             let relative = file_path.strip_prefix(&canonical).unwrap_or(file_path);
 
             // Update progress
@@ -141,8 +413,8 @@ impl Indexer {
             });
 
             // Process file
-            match self.process_file(&canonical, file_path, repo.id) {
-                Ok(size) => {
+            match self.process_file(&canonical, file_path, repo.id, &embeddings_skipped) {
+                Ok(FileOutcome::Indexed(size)) => {
                     bytes_processed.fetch_add(size, Ordering::Relaxed);
                     batch_count += 1;
 
@@ -152,43 +424,223 @@ impl Indexer {
                         batch_count = 0;
                     }
                 }
-                Err(_) => {
+                Ok(FileOutcome::Skipped(reason)) => {
+                    skipped.fetch_add(1, Ordering::Relaxed);
+                    result.record_skip(relative, &reason);
+                }
+                Err(e) => {
                     skipped.fetch_add(1, Ordering::Relaxed);
+                    result.record_failure(relative, &e);
                 }
             }
         }
 
         self.db.commit_batch()?;
 
-        // Update repository stats
+        // Update repository stats (only files reached before a cancellation count)
         #[allow(clippy::cast_possible_wrap)]
-        let file_count = (total_files - skipped.load(Ordering::Relaxed)) as i64;
+        let file_count =
+            (processed.load(Ordering::Relaxed) - skipped.load(Ordering::Relaxed)) as i64;
         #[allow(clippy::cast_possible_wrap)]
         let total_bytes = bytes_processed.load(Ordering::Relaxed) as i64;
         self.db
             .update_repository_indexed(repo.id, file_count, total_bytes)?;
+        status.finish();
 
         #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
         Ok(IndexResult {
             files_added: file_count as usize,
-            files_updated: 0,
-            files_deleted: 0,
-            files_unchanged: 0,
             files_skipped: skipped.load(Ordering::Relaxed),
             total_bytes: bytes_processed.load(Ordering::Relaxed),
+            embeddings_skipped: embeddings_skipped.load(Ordering::Relaxed),
             elapsed_secs: start.elapsed().as_secs_f64(),
+            ..result
         })
     }
 
-    /// Update an existing repository (incremental indexing)
-    fn update_repository<F>(&self, repo: &Repository, progress_callback: F) -> Result<IndexResult>
+    /// Update an indexed repository, only considering files modified after `since`.
+    ///
+    /// Deleted files are not detected, since the full set of current files is not compared.
+    pub fn update_since<F>(
+        &self,
+        path: &Path,
+        since: DateTime<Utc>,
+        progress_callback: F,
+    ) -> Result<IndexResult>
+    where
+        F: Fn(&IndexProgress) + Send + Sync,
+    {
+        let canonical = resolve_root(path)?;
+        let repo = self
+            .db
+            .get_repository_by_path(&canonical)?
+            .ok_or(AppError::RepoNotFound(canonical))?;
+
+        self.update_repository(&repo, Some(since), false, progress_callback)
+    }
+
+    /// Rebuild a repository from scratch: drop everything indexed for it
+    /// (keeping the repository row) and index all files as new.
+    pub fn reindex_full<F>(&self, path: &Path, progress_callback: F) -> Result<IndexResult>
     where
         F: Fn(&IndexProgress) + Send + Sync,
     {
+        let canonical = resolve_root(path)?;
+        let repo = self
+            .db
+            .get_repository_by_path(&canonical)?
+            .ok_or(AppError::RepoNotFound(canonical))?;
+
+        self.update_repository(&repo, None, true, progress_callback)
+    }
+
+    /// Index exactly `paths` of an indexed repository and drop `deletes` from it,
+    /// e.g. the files touched by commits pulled during a sync. Paths are relative
+    /// to the repository root (absolute paths inside it work too).
+    ///
+    /// Listed files unchanged since they were indexed are left alone; listed
+    /// files that no longer exist or are no longer indexable are removed. A path
+    /// in both lists is indexed from disk. Writes are batched like a full index.
+    #[allow(clippy::too_many_lines)]
+    pub fn index_files(
+        &self,
+        repo: &Repository,
+        paths: &[PathBuf],
+        deletes: &[PathBuf],
+    ) -> Result<IndexResult> {
         let start = Instant::now();
+        let relative = |path: &PathBuf| path.strip_prefix(&repo.path).unwrap_or(path).to_path_buf();
+
+        let mut listed: HashSet<PathBuf> = HashSet::new();
+        let paths: Vec<PathBuf> = paths
+            .iter()
+            .map(relative)
+            .filter(|p| listed.insert(p.clone()))
+            .collect();
+
+        let existing_map: std::collections::HashMap<PathBuf, FileRecord> = self
+            .db
+            .get_repository_files(repo.id)?
+            .into_iter()
+            .map(|f| (f.relative_path.clone(), f))
+            .collect();
+
+        let status = IndexingStatus::begin(&self.db, repo.id)?;
+
+        let embeddings_skipped = AtomicUsize::new(0);
+        let mut result = IndexResult::default();
+
+        let deleted_ids: Vec<i64> = deletes
+            .iter()
+            .map(relative)
+            .filter(|p| !listed.contains(p))
+            .filter_map(|p| existing_map.get(&p).map(|f| f.id))
+            .collect();
+        self.db.delete_files(&deleted_ids)?;
+        result.files_deleted = deleted_ids.len();
+
+        self.db.begin_batch()?;
+        let mut batch_count = 0;
+
+        for relative_path in &paths {
+            if self.is_cancelled() {
+                result.cancelled = true;
+                break;
+            }
+
+            let existing = existing_map.get(relative_path);
+            let full_path = repo.path.join(relative_path);
+            if !full_path.is_file()
+                || !self.should_index(&full_path)
+                || (!self.config.index_hidden && is_hidden(relative_path))
+            {
+                if let Some(existing) = existing {
+                    self.db.delete_files(&[existing.id])?;
+                    result.files_deleted += 1;
+                }
+                continue;
+            }
+            if existing.is_some_and(|f| is_unchanged(&full_path, f)) {
+                result.files_unchanged += 1;
+                continue;
+            }
+
+            match self.reindex_file(
+                &repo.path,
+                &full_path,
+                repo.id,
+                existing,
+                &embeddings_skipped,
+            ) {
+                Ok(FileOutcome::Indexed(size)) => {
+                    result.total_bytes += size;
+                    if existing.is_some() {
+                        result.files_updated += 1;
+                    } else {
+                        result.files_added += 1;
+                    }
+                    batch_count += 1;
 
+                    if batch_count >= self.config.batch_size {
+                        self.db.commit_batch()?;
+                        self.db.begin_batch()?;
+                        batch_count = 0;
+                    }
+                }
+                Ok(FileOutcome::Skipped(reason)) => {
+                    result.files_skipped += 1;
+                    result.record_skip(relative_path, &reason);
+                }
+                Err(e) => {
+                    result.files_skipped += 1;
+                    result.record_failure(relative_path, &e);
+                }
+            }
+        }
+
+        self.db.commit_batch()?;
+
+        // Repository totals cover all stored files, not just the re-indexed ones
+        let stored = self.db.get_repository_files(repo.id)?;
+        let total_bytes: i64 = stored.iter().map(|f| f.file_size_bytes).sum();
+        #[allow(clippy::cast_possible_wrap)]
         self.db
-            .update_repository_status(repo.id, RepoStatus::Indexing)?;
+            .update_repository_indexed(repo.id, stored.len() as i64, total_bytes)?;
+        status.finish();
+
+        Ok(IndexResult {
+            embeddings_skipped: embeddings_skipped.load(Ordering::Relaxed),
+            elapsed_secs: start.elapsed().as_secs_f64(),
+            ..result
+        })
+    }
+
+    /// Update an existing repository (incremental indexing, or a full rebuild with `full`)
+    #[allow(clippy::too_many_lines)]
+    fn update_repository<F>(
+        &self,
+        repo: &Repository,
+        since: Option<DateTime<Utc>>,
+        full: bool,
+        progress_callback: F,
+    ) -> Result<IndexResult>
+    where
+        F: Fn(&IndexProgress) + Send + Sync,
+    {
+        let start = Instant::now();
+
+        // Collect current files (only recently modified ones in --since mode)
+        let (current_files, unreadable) = match since {
+            Some(cutoff) => self.collect_files_modified_since(&repo.path, cutoff)?,
+            None => self.collect_files(&repo.path)?,
+        };
+
+        let status = IndexingStatus::begin(&self.db, repo.id)?;
+
+        // A full rebuild starts from an empty index (after limits were checked above)
+        if full {
+            self.db.clear_repository_files(repo.id)?;
+        }
 
         // Get existing files
         let existing_files = self.db.get_repository_files(repo.id)?;
@@ -198,16 +650,26 @@ impl Indexer {
             .collect();
         let existing_paths: HashSet<PathBuf> = existing_map.keys().cloned().collect();
 
-        // Collect current files
-        let current_files = self.collect_files(&repo.path);
         let current_paths: HashSet<PathBuf> = current_files
             .iter()
             .filter_map(|p| p.strip_prefix(&repo.path).ok())
             .map(PathBuf::from)
             .collect();
 
-        // Determine changes
-        let deleted: Vec<_> = existing_paths.difference(&current_paths).cloned().collect();
+        // Determine changes; deletions can't be seen without the full file set,
+        // and files under unreadable directories may well still exist
+        let deleted: Vec<_> = if since.is_some() {
+            Vec::new()
+        } else {
+            existing_paths
+                .difference(&current_paths)
+                .filter(|p| {
+                    let full_path = repo.path.join(p);
+                    !unreadable.iter().any(|dir| full_path.starts_with(dir))
+                })
+                .cloned()
+                .collect()
+        };
         let new_files: Vec<_> = current_paths.difference(&existing_paths).cloned().collect();
 
         let mut modified = Vec::new();
@@ -215,18 +677,11 @@ impl Indexer {
 
         for path in current_paths.intersection(&existing_paths) {
             let full_path = repo.path.join(path);
-            if let Ok(metadata) = fs::metadata(&full_path) {
-                let existing = &existing_map[path];
-                let mtime = metadata
-                    .modified()
-                    .map_or_else(|_| Utc::now(), DateTime::<Utc>::from);
-
-                #[allow(clippy::cast_possible_wrap)]
-                let file_size = metadata.len() as i64;
-                if mtime > existing.last_modified_at || file_size != existing.file_size_bytes {
-                    modified.push(path.clone());
-                } else {
+            if full_path.exists() {
+                if is_unchanged(&full_path, &existing_map[path]) {
                     unchanged.push(path.clone());
+                } else {
+                    modified.push(path.clone());
                 }
             }
         }
@@ -234,6 +689,7 @@ impl Indexer {
         let total_to_process = new_files.len() + modified.len();
         let processed = AtomicUsize::new(0);
         let skipped = AtomicUsize::new(0);
+        let embeddings_skipped = AtomicUsize::new(0);
         let bytes_processed = AtomicU64::new(0);
 
         // Delete removed files
@@ -243,11 +699,21 @@ impl Indexer {
             .collect();
         self.db.delete_files(&deleted_ids)?;
 
+        let mut result = IndexResult::default();
+        result.record_unreadable(unreadable);
+        let mut added = 0;
+        let mut updated = 0;
+
         // Process new and modified files
         self.db.begin_batch()?;
         let mut batch_count = 0;
 
         for relative_path in new_files.iter().chain(modified.iter()) {
+            if self.is_cancelled() {
+                result.cancelled = true;
+                break;
+            }
+
             let full_path = repo.path.join(relative_path);
 
             let current_processed = processed.fetch_add(1, Ordering::Relaxed) + 1;
@@ -260,14 +726,20 @@ impl Indexer {
                 elapsed_secs: start.elapsed().as_secs_f64(),
             });
 
-            // Delete existing if modified
-            if let Some(existing) = existing_map.get(relative_path) {
-                self.db.delete_files(&[existing.id])?;
-            }
-
-            match self.process_file(&repo.path, &full_path, repo.id) {
-                Ok(size) => {
+            match self.reindex_file(
+                &repo.path,
+                &full_path,
+                repo.id,
+                existing_map.get(relative_path),
+                &embeddings_skipped,
+            ) {
+                Ok(FileOutcome::Indexed(size)) => {
                     bytes_processed.fetch_add(size, Ordering::Relaxed);
+                    if existing_map.contains_key(relative_path) {
+                        updated += 1;
+                    } else {
+                        added += 1;
+                    }
                     batch_count += 1;
 
                     if batch_count >= self.config.batch_size {
@@ -276,75 +748,175 @@ impl Indexer {
                         batch_count = 0;
                     }
                 }
-                Err(_) => {
+                Ok(FileOutcome::Skipped(reason)) => {
+                    skipped.fetch_add(1, Ordering::Relaxed);
+                    result.record_skip(relative_path, &reason);
+                }
+                Err(e) => {
                     skipped.fetch_add(1, Ordering::Relaxed);
+                    result.record_failure(relative_path, &e);
                 }
             }
         }
 
         self.db.commit_batch()?;
 
-        // Update repository stats
+        // Update repository stats; after a cancellation only the stored files are known
+        let indexed_files = if result.cancelled {
+            self.db.get_repository_files(repo.id)?.len()
+        } else if since.is_some() {
+            existing_map.len() + new_files.len()
+        } else {
+            current_files.len()
+        };
+        let skipped_count = if result.cancelled {
+            0
+        } else {
+            skipped.load(Ordering::Relaxed)
+        };
         #[allow(clippy::cast_possible_wrap)]
-        let file_count = (current_files.len() - skipped.load(Ordering::Relaxed)) as i64;
+        let file_count = indexed_files.saturating_sub(skipped_count) as i64;
         #[allow(clippy::cast_possible_wrap)]
         let total_bytes = bytes_processed.load(Ordering::Relaxed) as i64;
         self.db
             .update_repository_indexed(repo.id, file_count, total_bytes)?;
+        status.finish();
 
         Ok(IndexResult {
-            files_added: new_files.len() - skipped.load(Ordering::Relaxed),
-            files_updated: modified.len(),
+            files_added: added,
+            files_updated: updated,
             files_deleted: deleted.len(),
             files_unchanged: unchanged.len(),
             files_skipped: skipped.load(Ordering::Relaxed),
             total_bytes: bytes_processed.load(Ordering::Relaxed),
+            embeddings_skipped: embeddings_skipped.load(Ordering::Relaxed),
             elapsed_secs: start.elapsed().as_secs_f64(),
+            ..result
+        })
+    }
+
+    /// Collect all indexable files in a directory, and the directories that
+    /// couldn't be read for lack of permissions.
+    ///
+    /// Fails once the `max_files` / `max_total_mb` limits are exceeded, before anything is written.
+    fn collect_files(&self, root: &Path) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        self.collect_files_matching(root, |_| true)
+    }
+
+    /// Collect indexable files whose modification time is after `cutoff`.
+    /// The limits only count these files.
+    fn collect_files_modified_since(
+        &self,
+        root: &Path,
+        cutoff: DateTime<Utc>,
+    ) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        self.collect_files_matching(root, |metadata| {
+            metadata
+                .modified()
+                .is_ok_and(|mtime| DateTime::<Utc>::from(mtime) > cutoff)
         })
     }
 
-    /// Collect all indexable files in a directory
-    fn collect_files(&self, root: &Path) -> Vec<PathBuf> {
+    /// Walk `root` for indexable files that `keep` accepts, judged by the
+    /// metadata read once per walked entry
+    fn collect_files_matching(
+        &self,
+        root: &Path,
+        keep: impl Fn(&fs::Metadata) -> bool,
+    ) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
         let mut files = Vec::new();
+        let mut unreadable = Vec::new();
+        let mut total_bytes = 0u64;
+        let max_files = self.config.max_files;
+        let max_bytes = self.config.max_total_bytes();
 
         let mut builder = WalkBuilder::new(root);
         builder
-            .hidden(false)
+            .hidden(!self.config.index_hidden)
             .git_ignore(true)
             .git_global(true)
-            .git_exclude(true);
+            .git_exclude(true)
+            .follow_links(self.config.follow_symlinks);
 
         // Add custom ignore patterns
         for pattern in &self.config.ignore_patterns {
             builder.add_ignore(root.join(pattern));
         }
 
-        for entry in builder.build().flatten() {
+        for entry in builder.build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    unreadable.extend(unreadable_path(&e).map(Path::to_path_buf));
+                    continue;
+                }
+            };
             let path = entry.path();
 
-            if path.is_file() && self.should_index(path) {
+            // Symlinks count as the file they point to, as `Path::is_file` would
+            let metadata = if entry.path_is_symlink() {
+                fs::metadata(path).ok()
+            } else {
+                entry.metadata().ok()
+            };
+            let Some(metadata) = metadata else {
+                continue;
+            };
+
+            if metadata.is_file() && self.is_indexable(path, metadata.len()) && keep(&metadata) {
                 files.push(path.to_path_buf());
+                total_bytes += metadata.len();
+
+                if max_files > 0 && files.len() > max_files {
+                    return Err(limit_error(
+                        root,
+                        &format!("max_files = {max_files}"),
+                        files.len(),
+                        total_bytes,
+                    ));
+                }
+                if max_bytes > 0 && total_bytes > max_bytes {
+                    return Err(limit_error(
+                        root,
+                        &format!("max_total_mb = {}", self.config.max_total_mb),
+                        files.len(),
+                        total_bytes,
+                    ));
+                }
             }
         }
 
-        files
+        Ok((files, unreadable))
     }
 
     /// Check if a file should be indexed
     fn should_index(&self, path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|metadata| self.is_indexable(path, metadata.len()))
+    }
+
+    /// Check if a file of `size` bytes should be indexed
+    fn is_indexable(&self, path: &Path, size: u64) -> bool {
         // Check extension
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
             let ext_lower = ext.to_lowercase();
-            if BINARY_EXTENSIONS.contains(&ext_lower.as_str()) {
+            if BINARY_EXTENSIONS.contains(&ext_lower.as_str())
+                && !is_extractable_document(&ext_lower)
+            {
                 return false;
             }
         }
 
         // Check size
-        if let Ok(metadata) = fs::metadata(path) {
-            if metadata.len() > self.config.max_file_size_bytes() {
-                return false;
-            }
+        if size > self.config.max_file_size_bytes() {
+            return false;
+        }
+
+        // Skip minified assets, source maps and lockfiles
+        if path
+            .file_name()
+            .is_some_and(|name| self.config.is_generated_file(&name.to_string_lossy()))
+        {
+            return false;
         }
 
         // Check if in ignored directory
@@ -358,8 +930,29 @@ impl Indexer {
         true
     }
 
+    /// Replace the stored copy of a file (if any) with its current content
+    fn reindex_file(
+        &self,
+        root: &Path,
+        path: &Path,
+        repo_id: i64,
+        existing: Option<&FileRecord>,
+        embeddings_skipped: &AtomicUsize,
+    ) -> Result<FileOutcome> {
+        if let Some(existing) = existing {
+            self.db.delete_files(&[existing.id])?;
+        }
+        self.process_file(root, path, repo_id, embeddings_skipped)
+    }
+
     /// Process a single file
-    fn process_file(&self, root: &Path, path: &Path, repo_id: i64) -> Result<u64> {
+    fn process_file(
+        &self,
+        root: &Path,
+        path: &Path,
+        repo_id: i64,
+        embeddings_skipped: &AtomicUsize,
+    ) -> Result<FileOutcome> {
         let relative = path.strip_prefix(root).unwrap_or(path);
 
         // Read file
@@ -369,37 +962,223 @@ impl Indexer {
 
         // Check size limit
         if size > self.config.max_file_size_bytes() {
-            return Err(AppError::Other("File too large".into()));
+            return Ok(FileOutcome::Skipped(SkipReason::Unsupported(
+                "file too large".into(),
+            )));
         }
 
         #[allow(clippy::cast_possible_truncation)]
         let mut content = Vec::with_capacity(size as usize);
         file.read_to_end(&mut content)?;
 
-        // Check for binary content (null bytes in first 8KB)
-        let check_len = std::cmp::min(8192, content.len());
-        if content[..check_len].contains(&0) {
-            return Err(AppError::Other("Binary file".into()));
-        }
-
-        // Convert to string
-        let content_str = String::from_utf8_lossy(&content);
+        // Detect file type
+        let file_type = file_type_of(path);
+        let content_str = match self.decode_content(&file_type, &content) {
+            Ok(text) => text,
+            Err(reason) => return Ok(FileOutcome::Skipped(reason)),
+        };
 
-        // Compute hash
-        let hash = blake3::hash(content_str.as_bytes());
-        let hash_str = hash.to_hex().to_string();
+        let min_chars = self.config.min_content_chars;
+        if min_chars > 0 {
+            let chars = body_chars(&file_type, &content_str);
+            if chars < min_chars {
+                return Ok(FileOutcome::Skipped(SkipReason::TooLittleContent(chars)));
+            }
+        }
 
-        // Detect file type
-        let file_type = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .map_or(FileType::Unknown, FileType::from_extension);
+        // Prose legitimately has paragraph-long lines; only check code and data
+        let max_line_length = self.config.max_avg_line_length;
+        if max_line_length > 0
+            && matches!(
+                file_type,
+                FileType::Code(_) | FileType::Config | FileType::Unknown
+            )
+        {
+            let line_length = average_line_length(&content_str);
+            if line_length > max_line_length {
+                return Ok(FileOutcome::Skipped(SkipReason::Minified(line_length)));
+            }
+        }
 
         // Get modification time
         let mtime = metadata
             .modified()
             .map_or_else(|_| Utc::now(), DateTime::<Utc>::from);
 
+        self.store_file(
+            repo_id,
+            relative,
+            &file_type,
+            &content_str,
+            size,
+            mtime,
+            embeddings_skipped,
+        )?;
+
+        Ok(FileOutcome::Indexed(size))
+    }
+
+    /// Index content that does not live on disk as a single file of the
+    /// stdin repository `repo_name`, creating the repository on first use.
+    /// Re-sending the same `relative` path replaces the earlier content.
+    /// Content that can't be indexed (binary, too large) is reported in
+    /// `skipped_details` and leaves an earlier version in place.
+    pub fn index_content(
+        &self,
+        repo_name: &str,
+        relative: &Path,
+        content: &[u8],
+    ) -> Result<IndexResult> {
+        let start = Instant::now();
+
+        let repo = match self
+            .db
+            .list_repositories()?
+            .into_iter()
+            .find(|r| r.name == repo_name)
+        {
+            Some(repo) if repo.is_stdin() => repo,
+            Some(repo) => {
+                return Err(AppError::Other(format!(
+                    "Repository \"{repo_name}\" ({}) is not a stdin repository. Choose another --name.",
+                    repo.path.display()
+                )));
+            }
+            None => self.db.add_stdin_repository(repo_name)?,
+        };
+
+        let size = content.len() as u64;
+        let file_type = file_type_of(relative);
+        let decoded = if size > self.config.max_file_size_bytes() {
+            Err(SkipReason::Unsupported("content too large".into()))
+        } else {
+            self.decode_content(&file_type, content)
+        };
+        let content_str = match decoded {
+            Ok(text) => text,
+            Err(reason) => {
+                let mut result = IndexResult {
+                    files_skipped: 1,
+                    elapsed_secs: start.elapsed().as_secs_f64(),
+                    ..IndexResult::default()
+                };
+                result.record_skip(relative, &reason);
+                return Ok(result);
+            }
+        };
+
+        let existing = self
+            .db
+            .get_repository_files(repo.id)?
+            .into_iter()
+            .find(|f| f.relative_path == relative);
+
+        let embeddings_skipped = AtomicUsize::new(0);
+        self.db.begin_batch()?;
+        if let Some(existing) = &existing {
+            self.db.delete_files(&[existing.id])?;
+        }
+        self.store_file(
+            repo.id,
+            relative,
+            &file_type,
+            &content_str,
+            size,
+            Utc::now(),
+            &embeddings_skipped,
+        )?;
+        self.db.commit_batch()?;
+
+        // Repository totals cover every file sent so far
+        let files = self.db.get_repository_files(repo.id)?;
+        let total_bytes: i64 = files.iter().map(|f| f.file_size_bytes).sum();
+        #[allow(clippy::cast_possible_wrap)]
+        self.db
+            .update_repository_indexed(repo.id, files.len() as i64, total_bytes)?;
+
+        Ok(IndexResult {
+            files_added: usize::from(existing.is_none()),
+            files_updated: usize::from(existing.is_some()),
+            total_bytes: size,
+            embeddings_skipped: embeddings_skipped.load(Ordering::Relaxed),
+            elapsed_secs: start.elapsed().as_secs_f64(),
+            ..IndexResult::default()
+        })
+    }
+
+    /// Text to embed for an indexed file, decoded from disk the way indexing
+    /// decodes it (PDFs, notebooks, UTF-16). Stdin repositories and files
+    /// that can no longer be decoded fall back to the content stored in the
+    /// index; `None` when neither is available.
+    pub fn embedding_text(&self, repo: &Repository, file: &FileRecord) -> Result<Option<String>> {
+        if !repo.is_stdin() {
+            let file_type = file_type_of(&file.relative_path);
+            if let Ok(bytes) = fs::read(repo.path.join(&file.relative_path)) {
+                if let Ok(text) = self.decode_content(&file_type, &bytes) {
+                    return Ok(Some(text.into_owned()));
+                }
+            }
+        }
+        self.db.get_stored_content(&repo.name, &file.relative_path)
+    }
+
+    /// Decode raw bytes to text, extracting PDFs and notebook cells and
+    /// rejecting binary content
+    fn decode_content<'a>(
+        &self,
+        file_type: &FileType,
+        content: &'a [u8],
+    ) -> std::result::Result<Cow<'a, str>, SkipReason> {
+        if *file_type == FileType::Pdf {
+            return Ok(Cow::Owned(extract_pdf_text(content)?));
+        }
+        if let Some(text) = decode_utf16_bom(content) {
+            return Ok(Cow::Owned(text));
+        }
+
+        // Check for binary content in the first bytes
+        if let Some(reason) = binary_reason(
+            content,
+            self.config.binary_null_scan_bytes,
+            self.config.binary_detection == "ratio",
+        ) {
+            return Err(SkipReason::Unsupported(reason));
+        }
+
+        let text = String::from_utf8_lossy(content);
+        if *file_type == FileType::Notebook {
+            // Malformed notebooks are indexed as they are
+            if let Some(cells) = extract_notebook_text(&text) {
+                return Ok(Cow::Owned(cells));
+            }
+        }
+        Ok(text)
+    }
+
+    /// Store decoded content with its note metadata and embeddings.
+    /// With `strip_markdown_syntax`, the full-text index gets the content with
+    /// its markup removed; hashes, metadata and embeddings use the original.
+    #[allow(clippy::too_many_arguments)]
+    fn store_file(
+        &self,
+        repo_id: i64,
+        relative: &Path,
+        file_type: &FileType,
+        content_str: &str,
+        size: u64,
+        mtime: DateTime<Utc>,
+        embeddings_skipped: &AtomicUsize,
+    ) -> Result<()> {
+        // Compute hash
+        let hash = blake3::hash(content_str.as_bytes());
+        let hash_str = hash.to_hex().to_string();
+
+        let fts_content = if self.config.strip_markdown_syntax {
+            strip_syntax_for(file_type, content_str)
+        } else {
+            Cow::Borrowed(content_str)
+        };
+
         // Insert into database
         #[allow(clippy::cast_possible_wrap)]
         let file_id = self.db.insert_file(
@@ -409,12 +1188,16 @@ impl Indexer {
             size as i64,
             mtime,
             file_type.as_str(),
-            &content_str,
+            &fts_content,
         )?;
 
-        // Parse and store markdown metadata if it's a markdown file
-        if file_type == FileType::Markdown {
-            let meta = parse_markdown(&content_str);
+        // Parse and store note metadata for markdown and org-mode files
+        let meta = match file_type {
+            FileType::Markdown => Some(parse_markdown(content_str)),
+            FileType::OrgMode => Some(parse_orgmode(content_str)),
+            _ => None,
+        };
+        if let Some(meta) = meta {
             let _ = self.db.store_markdown_meta(
                 file_id,
                 meta.title.as_deref(),
@@ -437,31 +1220,421 @@ impl Indexer {
                     .collect();
                 let _ = self.db.add_links(file_id, &links);
             }
-        }
 
-        // Generate and store embeddings if enabled
-        if let Some(ref embedder) = self.embedder {
-            // Generate embeddings for chunks
-            if let Ok(chunk_embeddings) = embedder.embed_content(&content_str) {
-                let embeddings: Vec<(usize, usize, usize, &str, &[f32])> = chunk_embeddings
+            // Store fenced code blocks for language-scoped search
+            if self.config.index_code_blocks && !meta.code_blocks.is_empty() {
+                let blocks: Vec<(Option<&str>, &str)> = meta
+                    .code_blocks
                     .iter()
-                    .enumerate()
-                    .map(|(idx, ce)| {
-                        (
-                            idx,
-                            ce.chunk.start_offset,
-                            ce.chunk.end_offset,
-                            ce.chunk.text.as_str(),
-                            ce.embedding.as_slice(),
-                        )
-                    })
+                    .map(|b| (b.language.as_deref(), b.content.as_str()))
                     .collect();
+                let _ = self.db.store_code_blocks(file_id, &blocks);
+            }
+        }
 
-                // Store embeddings (ignore errors to not block indexing)
-                let _ = self.db.store_embeddings(file_id, &embeddings);
+        // Generate and store embeddings if enabled and not excluded for this file type
+        if let Some(ref embedder) = self.embedder {
+            if self.config.embeds_file_type(file_type.as_str()) {
+                self.store_file_embeddings(embedder, file_id, file_type, content_str);
+            } else {
+                embeddings_skipped.fetch_add(1, Ordering::Relaxed);
             }
         }
 
-        Ok(size)
+        Ok(())
+    }
+
+    /// Chunk, embed and store one file's content.
+    /// Failures are ignored so embedding never blocks indexing.
+    fn store_file_embeddings(
+        &self,
+        embedder: &Embedder,
+        file_id: i64,
+        file_type: &FileType,
+        content: &str,
+    ) {
+        let chunk_embeddings = if matches!(file_type, FileType::Code(_)) {
+            embedder.embed_code(content)
+        } else {
+            embedder.embed_content(content)
+        };
+        if let Ok(chunk_embeddings) = chunk_embeddings {
+            let embeddings: Vec<(usize, usize, usize, &str, &[f32])> = chunk_embeddings
+                .iter()
+                .enumerate()
+                .map(|(idx, ce)| {
+                    (
+                        idx,
+                        ce.chunk.start_offset,
+                        ce.chunk.end_offset,
+                        ce.chunk.text.as_str(),
+                        ce.embedding.as_slice(),
+                    )
+                })
+                .collect();
+
+            let _ = self.db.store_embeddings(file_id, &embeddings);
+        }
+    }
+}
+
+/// File type from a path's extension
+fn file_type_of(path: &Path) -> FileType {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map_or(FileType::Unknown, FileType::from_extension)
+}
+
+/// Content with the markup of its format (markdown, org, rST) removed;
+/// other file types are returned unchanged
+fn strip_syntax_for<'a>(file_type: &FileType, content: &'a str) -> Cow<'a, str> {
+    match file_type {
+        FileType::Markdown => Cow::Owned(strip_markdown_syntax(content)),
+        FileType::OrgMode => Cow::Owned(strip_orgmode_syntax(content)),
+        FileType::ReStructuredText => Cow::Owned(strip_rst_syntax(content)),
+        _ => Cow::Borrowed(content),
+    }
+}
+
+/// Non-whitespace characters of body text, ignoring frontmatter and markup
+fn body_chars(file_type: &FileType, content: &str) -> usize {
+    strip_syntax_for(file_type, content)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .count()
+}
+
+/// Average number of characters per line, a proxy for minified content
+fn average_line_length(content: &str) -> usize {
+    let lines = content.lines().count().max(1);
+    content.chars().count() / lines
+}
+
+/// Extract plain text from a PDF document
+#[cfg(feature = "pdf")]
+fn extract_pdf_text(bytes: &[u8]) -> std::result::Result<String, SkipReason> {
+    // pdf-extract can panic on malformed documents; treat that as a skip
+    let extracted = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(bytes))
+        .map_err(|_| SkipReason::Unsupported("PDF text extraction failed".into()))?
+        .map_err(|e| SkipReason::Unsupported(format!("PDF text extraction failed: {e}")))?;
+
+    if extracted.trim().is_empty() {
+        return Err(SkipReason::Unsupported(
+            "PDF has no extractable text".into(),
+        ));
+    }
+
+    Ok(extracted)
+}
+
+/// Extract plain text from a PDF document
+#[cfg(not(feature = "pdf"))]
+fn extract_pdf_text(_bytes: &[u8]) -> std::result::Result<String, SkipReason> {
+    Err(SkipReason::Unsupported("PDF support not enabled".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_reason_null_mode() {
+        assert_eq!(binary_reason(b"plain text", 8192, false), None);
+        assert_eq!(
+            binary_reason(b"ab\0cd", 8192, false).as_deref(),
+            Some("binary file: null byte at offset 2")
+        );
+        // Nulls past the scan window are not seen
+        assert_eq!(binary_reason(b"abcd\0", 4, false), None);
+    }
+
+    #[test]
+    fn test_binary_reason_ratio_mode() {
+        // A stray null in otherwise normal text is tolerated
+        assert_eq!(
+            binary_reason(b"hello\0 world, this is text", 8192, true),
+            None
+        );
+        assert!(binary_reason(&[0u8, 1, 2, 3, b'a'], 8192, true).is_some());
+    }
+
+    #[test]
+    fn test_decode_utf16_bom() {
+        let le = [0xFF, 0xFE, b'h', 0, b'i', 0];
+        assert_eq!(decode_utf16_bom(&le).as_deref(), Some("hi"));
+        let be = [0xFE, 0xFF, 0, b'h', 0, b'i'];
+        assert_eq!(decode_utf16_bom(&be).as_deref(), Some("hi"));
+        assert_eq!(decode_utf16_bom(b"hi"), None);
+    }
+
+    #[test]
+    fn test_average_line_length() {
+        assert_eq!(average_line_length(""), 0);
+        assert_eq!(average_line_length("ab\ncd\n"), 3);
+        let minified = format!("var a={};\n", "x".repeat(5000));
+        assert!(average_line_length(&minified) > 5000);
+    }
+
+    #[test]
+    fn test_body_chars_ignores_frontmatter() {
+        let note = "---\ntitle: Empty\ntags: [a, b]\n---\n\n# \n";
+        assert_eq!(body_chars(&FileType::Markdown, note), 0);
+        assert_eq!(body_chars(&FileType::Markdown, "# Hi there"), 7);
+        assert_eq!(body_chars(&FileType::PlainText, "  a b\n"), 2);
+    }
+
+    #[test]
+    fn test_record_skip_separates_skips_from_failures() {
+        let mut result = IndexResult::default();
+        result.record_skip(
+            Path::new("a.bin"),
+            &SkipReason::Unsupported("binary".into()),
+        );
+        result.record_skip(Path::new("b.md"), &SkipReason::TooLittleContent(3));
+        result.record_skip(Path::new("c.js"), &SkipReason::Minified(4000));
+        result.record_failure(
+            Path::new("d.md"),
+            &AppError::Io(std::io::Error::from(ErrorKind::PermissionDenied)),
+        );
+
+        assert_eq!(result.files_empty, 1);
+        assert_eq!(result.files_minified, 1);
+        let reasons: Vec<&str> = result
+            .skipped_details
+            .iter()
+            .map(|(_, reason)| reason.as_str())
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                "binary",
+                "empty (3 characters)",
+                "minified (average line length 4000)"
+            ]
+        );
+        assert_eq!(result.failed_details.len(), 1);
+        assert_eq!(result.failed_details[0].0, Path::new("d.md"));
+    }
+
+    #[test]
+    fn test_resolve_root_names_the_attempted_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::write(root.join("notes.md"), "# Notes").unwrap();
+
+        assert_eq!(resolve_root(dir.path()).unwrap(), root);
+        match resolve_root(&root.join("missing")) {
+            Err(AppError::PathNotFound(path)) => assert_eq!(path, root.join("missing")),
+            other => panic!("expected PathNotFound, got {other:?}"),
+        }
+        assert!(matches!(
+            resolve_root(&root.join("notes.md")),
+            Err(AppError::NotADirectory(_))
+        ));
+    }
+
+    #[test]
+    fn test_collect_files_skips_hidden_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.md"), "# Notes").unwrap();
+        fs::write(dir.path().join(".env"), "API_KEY=secret").unwrap();
+        fs::create_dir(dir.path().join(".config")).unwrap();
+        fs::write(dir.path().join(".config/app.toml"), "debug = true").unwrap();
+
+        let collect = |config: Config| {
+            let indexer = Indexer::new(Database::open_in_memory().unwrap(), config);
+            let mut files: Vec<PathBuf> = indexer
+                .collect_files(dir.path())
+                .unwrap()
+                .0
+                .into_iter()
+                .map(|p| p.strip_prefix(dir.path()).unwrap().to_path_buf())
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(collect(Config::default()), vec![PathBuf::from("notes.md")]);
+        let config = Config {
+            index_hidden: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            collect(config),
+            vec![
+                PathBuf::from(".config/app.toml"),
+                PathBuf::from(".env"),
+                PathBuf::from("notes.md")
+            ]
+        );
+
+        assert!(is_hidden(Path::new(".env")));
+        assert!(is_hidden(Path::new("docs/.drafts/idea.md")));
+        assert!(!is_hidden(Path::new("docs/idea.md")));
+    }
+    #[test]
+    fn test_collect_files_modified_since_limits_only_recent_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        for name in ["a.md", "b.md"] {
+            let path = dir.path().join(name);
+            fs::write(&path, format!("# {name}")).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+        fs::write(dir.path().join("c.md"), "# c.md").unwrap();
+
+        let config = Config {
+            max_files: 1,
+            ..Config::default()
+        };
+        let indexer = Indexer::new(Database::open_in_memory().unwrap(), config);
+        assert!(matches!(
+            indexer.collect_files(dir.path()),
+            Err(AppError::IndexLimitExceeded(_))
+        ));
+
+        let cutoff = Utc::now() - chrono::Duration::minutes(10);
+        let (files, _) = indexer
+            .collect_files_modified_since(dir.path(), cutoff)
+            .unwrap();
+        assert_eq!(files, vec![dir.path().join("c.md")]);
+    }
+
+    #[test]
+    fn test_indexing_status_is_never_left_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::write(root.join("a.md"), "# a").unwrap();
+
+        // A cancelled run keeps its committed files searchable
+        let db = Database::open_in_memory().unwrap();
+        let cancel = Arc::new(AtomicBool::new(true));
+        let result = Indexer::new(db.clone(), Config::default())
+            .with_cancel_flag(cancel)
+            .index(&root, None, |_| {})
+            .unwrap();
+        assert!(result.cancelled);
+        let repo = db.get_repository_by_path(&root).unwrap().unwrap();
+        assert_eq!(repo.status, RepoStatus::Ready);
+
+        // A run that ends without finishing (an error or panic) marks the repository failed
+        drop(IndexingStatus::begin(&db, repo.id).unwrap());
+        let repo = db.get_repository_by_path(&root).unwrap().unwrap();
+        assert_eq!(repo.status, RepoStatus::Error);
+    }
+
+    #[test]
+    fn test_embedding_text_decodes_like_indexing() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        // UTF-16 text isn't valid UTF-8 but is indexed as text
+        fs::write(root.join("wide.txt"), [0xFF, 0xFE, b'h', 0, b'i', 0]).unwrap();
+        fs::write(root.join("gone.md"), "# Gone soon").unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        let indexer = Indexer::new(db.clone(), Config::default());
+        indexer.index(&root, None, |_| {}).unwrap();
+        indexer
+            .index_content("piped", Path::new("note.md"), b"# Piped")
+            .unwrap();
+        fs::remove_file(root.join("gone.md")).unwrap();
+
+        let text = |repo: &Repository, name: &str| {
+            let file = db
+                .get_repository_files(repo.id)
+                .unwrap()
+                .into_iter()
+                .find(|f| f.relative_path == Path::new(name))
+                .unwrap();
+            indexer.embedding_text(repo, &file).unwrap()
+        };
+        let repos = db.list_repositories().unwrap();
+        let disk = repos.iter().find(|r| r.path == root).unwrap();
+        let stdin = repos.iter().find(|r| r.is_stdin()).unwrap();
+        assert_eq!(text(disk, "wide.txt").as_deref(), Some("hi"));
+        // Deleted files and stdin repositories fall back to the stored content
+        assert_eq!(text(disk, "gone.md").as_deref(), Some("# Gone soon"));
+        assert_eq!(text(stdin, "note.md").as_deref(), Some("# Piped"));
+    }
+
+    #[test]
+    fn test_index_content_strips_markdown_like_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let note = "# Plan\n\nShip **the** [release](https://example.com)\n";
+        fs::write(root.join("plan.md"), note).unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        let config = Config {
+            strip_markdown_syntax: true,
+            ..Config::default()
+        };
+        let indexer = Indexer::new(db.clone(), config);
+        indexer.index(&root, None, |_| {}).unwrap();
+        indexer
+            .index_content("piped", Path::new("plan.md"), note.as_bytes())
+            .unwrap();
+
+        let repo = db.get_repository_by_path(&root).unwrap().unwrap();
+        let from_disk = db.get_stored_content(&repo.name, Path::new("plan.md"));
+        let from_stdin = db.get_stored_content("piped", Path::new("plan.md"));
+        let from_disk = from_disk.unwrap().unwrap();
+        assert!(!from_disk.contains("**"));
+        assert_eq!(from_stdin.unwrap().unwrap(), from_disk);
+    }
+
+    #[test]
+    fn test_index_files_only_changes_listed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        for name in ["a.md", "b.md", "c.md"] {
+            fs::write(root.join(name), format!("# {name}")).unwrap();
+        }
+
+        let db = Database::open_in_memory().unwrap();
+        let indexer = Indexer::new(db.clone(), Config::default());
+        indexer.index(&root, None, |_| {}).unwrap();
+        let repo = db.get_repository_by_path(&root).unwrap().unwrap();
+        let hashes = || -> std::collections::HashMap<PathBuf, String> {
+            db.get_repository_files(repo.id)
+                .unwrap()
+                .into_iter()
+                .map(|f| (f.relative_path, f.content_hash))
+                .collect()
+        };
+        let before = hashes();
+
+        fs::write(root.join("b.md"), "# b.md, edited").unwrap();
+        fs::write(root.join("c.md"), "# c.md, edited too").unwrap();
+        let listed: Vec<PathBuf> = ["a.md", "b.md", "c.md"].map(PathBuf::from).to_vec();
+        let result = indexer.index_files(&repo, &listed, &[]).unwrap();
+        assert_eq!(
+            (
+                result.files_added,
+                result.files_updated,
+                result.files_unchanged
+            ),
+            (0, 2, 1)
+        );
+
+        let after = hashes();
+        assert_eq!(after[Path::new("a.md")], before[Path::new("a.md")]);
+        assert_ne!(after[Path::new("b.md")], before[Path::new("b.md")]);
+        assert_ne!(after[Path::new("c.md")], before[Path::new("c.md")]);
+
+        // New files are added, deletes dropped; absolute paths work too
+        fs::write(root.join("d.md"), "# d.md").unwrap();
+        let result = indexer
+            .index_files(&repo, &[root.join("d.md")], &[PathBuf::from("a.md")])
+            .unwrap();
+        assert_eq!((result.files_added, result.files_deleted), (1, 1));
+        let mut paths: Vec<PathBuf> = hashes().into_keys().collect();
+        paths.sort();
+        assert_eq!(paths, ["b.md", "c.md", "d.md"].map(PathBuf::from).to_vec());
     }
 }
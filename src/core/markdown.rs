@@ -4,10 +4,12 @@
 //! - YAML frontmatter (Obsidian, Hugo, Jekyll style)
 //! - Heading extraction
 //! - Wiki-style links `[[link]]`
+//! - External `http(s)` links
 //! - Code block extraction with language tags
 //! - Markdown syntax stripping
 
 use std::collections::HashSet;
+use std::ops::Range;
 
 /// Metadata extracted from a markdown file
 #[derive(Debug, Default, Clone)]
@@ -33,7 +35,6 @@ pub struct Heading {
 
 /// A fenced code block extracted from markdown
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct CodeBlock {
     /// Language tag (e.g., "rust", "python", "javascript")
     pub language: Option<String>,
@@ -118,20 +119,41 @@ pub fn parse_markdown_with_options(content: &str, extract_code: bool) -> Markdow
     meta
 }
 
-/// Extract YAML frontmatter from markdown content
-fn extract_frontmatter(content: &str) -> Option<String> {
-    let content = content.trim_start();
-
-    // Must start with ---
-    if !content.starts_with("---") {
+/// Locate a frontmatter block at the start of `content`: the byte range of
+/// the YAML between the fences, and where the content after the closing
+/// fence starts. Fences are lines of just `---` (trailing whitespace and
+/// `\r\n` line endings are fine); the closing fence may directly follow the
+/// opening one (empty frontmatter) or end the file without a newline.
+fn frontmatter_bounds(content: &str) -> Option<(Range<usize>, usize)> {
+    let is_fence = |line: &str| line.trim_end() == "---";
+
+    let mut lines = content.split_inclusive('\n');
+    let opening = lines.next()?;
+    if !is_fence(opening) {
         return None;
     }
 
-    // Find the closing ---
-    let after_opening = &content[3..];
-    let closing_pos = after_opening.find("\n---")?;
+    let yaml_start = opening.len();
+    let mut pos = yaml_start;
+    for line in lines {
+        if is_fence(line) {
+            return Some((yaml_start..pos, pos + line.len()));
+        }
+        pos += line.len();
+    }
+    None
+}
+
+/// Leading whitespace and byte order mark that may precede frontmatter
+fn trim_leading(content: &str) -> &str {
+    content.trim_start_matches(|c: char| c == '\u{feff}' || c.is_whitespace())
+}
 
-    Some(after_opening[..closing_pos].trim().to_string())
+/// Extract YAML frontmatter from markdown content
+fn extract_frontmatter(content: &str) -> Option<String> {
+    let content = trim_leading(content);
+    let (yaml, _) = frontmatter_bounds(content)?;
+    Some(content[yaml].trim().to_string())
 }
 
 /// Parse YAML frontmatter and populate metadata
@@ -329,21 +351,56 @@ fn extract_wiki_links(content: &str) -> Vec<String> {
     result
 }
 
-/// Skip frontmatter and return content after it
-fn skip_frontmatter(content: &str) -> &str {
-    let content = content.trim_start();
+/// Extract external `http(s)` URLs from markdown content.
+///
+/// Covers inline links, autolinks and bare URLs. URLs inside fenced code
+/// blocks are ignored since they are usually examples, not references.
+pub fn extract_external_links(content: &str) -> Vec<String> {
+    let mut links = HashSet::new();
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
 
-    if !content.starts_with("---") {
-        return content;
+        let mut rest = line;
+        while let Some(start) = rest.find("http") {
+            let candidate = &rest[start..];
+            if !(candidate.starts_with("http://") || candidate.starts_with("https://")) {
+                rest = &rest[start + 4..];
+                continue;
+            }
+
+            let end = candidate
+                .find(|c: char| {
+                    c.is_whitespace() || matches!(c, '<' | '>' | '"' | '\'' | '`' | ')' | ']')
+                })
+                .unwrap_or(candidate.len());
+            let url = candidate[..end].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            if url.len() > "https://".len() {
+                links.insert(url.to_string());
+            }
+            rest = &candidate[end..];
+        }
     }
 
-    let after_opening = &content[3..];
-    if let Some(closing_pos) = after_opening.find("\n---") {
-        // Return content after the closing ---
-        let after_closing = &after_opening[closing_pos + 4..];
-        after_closing.trim_start_matches('\n')
-    } else {
-        content
+    let mut result: Vec<_> = links.into_iter().collect();
+    result.sort();
+    result
+}
+
+/// Skip frontmatter and return content after it
+fn skip_frontmatter(content: &str) -> &str {
+    let content = trim_leading(content);
+    match frontmatter_bounds(content) {
+        Some((_, end)) => content[end..].trim_start_matches(['\r', '\n']),
+        None => content,
     }
 }
 
@@ -412,7 +469,6 @@ fn extract_code_blocks(content: &str) -> Vec<CodeBlock> {
 /// - HTML tags
 /// - Blockquote markers (>)
 #[must_use]
-#[allow(dead_code)]
 pub fn strip_markdown_syntax(content: &str) -> String {
     let content = skip_frontmatter(content);
     let mut result = String::with_capacity(content.len());
@@ -628,6 +684,46 @@ tags: [rust, programming]
         assert_eq!(meta.tags, vec!["rust", "programming"]);
     }
 
+    #[test]
+    fn test_parse_frontmatter_crlf() {
+        let content =
+            "---\r\ntitle: Windows Note\r\ntags: [a, b]\r\n---\r\n\r\n# Heading\r\nBody\r\n";
+        let meta = parse_markdown(content);
+        assert_eq!(meta.title, Some("Windows Note".to_string()));
+        assert_eq!(meta.tags, vec!["a", "b"]);
+        assert_eq!(skip_frontmatter(content), "# Heading\r\nBody\r\n");
+    }
+
+    #[test]
+    fn test_parse_frontmatter_at_eof() {
+        let meta = parse_markdown("---\ntitle: x\n---");
+        assert_eq!(meta.title, Some("x".to_string()));
+        assert_eq!(skip_frontmatter("---\ntitle: x\n---"), "");
+    }
+
+    #[test]
+    fn test_frontmatter_fences() {
+        // Empty frontmatter: the closing fence follows the opening one
+        assert_eq!(
+            extract_frontmatter("---\n---\n# Title\n"),
+            Some(String::new())
+        );
+        assert_eq!(skip_frontmatter("---\n---\n# Title\n"), "# Title\n");
+        // Byte order mark and trailing spaces on the fences
+        assert_eq!(
+            extract_frontmatter("\u{feff}--- \ntitle: y\n---  \nBody"),
+            Some("title: y".to_string())
+        );
+        // Only a line of exactly `---` closes the block
+        assert_eq!(
+            extract_frontmatter("---\ntitle: z\n----\n---\n"),
+            Some("title: z\n----".to_string())
+        );
+        // Unclosed blocks and horizontal rules are not frontmatter
+        assert_eq!(extract_frontmatter("---\ntitle: z\n"), None);
+        assert_eq!(extract_frontmatter("---- \ntext\n---\n"), None);
+    }
+
     #[test]
     fn test_extract_headings() {
         let content = r"# Main Title
@@ -653,4 +749,23 @@ Some content
         assert!(meta.links.contains(&"Other Note".to_string()));
         assert!(meta.links.contains(&"another".to_string()));
     }
+
+    #[test]
+    fn test_external_links() {
+        let content = "See [docs](https://example.com/docs), <http://example.org> and
+https://example.com/docs.
+
+```
+curl https://ignored.example.com
+```
+";
+        let links = extract_external_links(content);
+        assert_eq!(
+            links,
+            vec![
+                "http://example.org".to_string(),
+                "https://example.com/docs".to_string()
+            ]
+        );
+    }
 }
@@ -1,22 +1,45 @@
+mod disk;
+mod diversify;
+mod editor;
 mod embedder;
+mod exclude;
+mod hooks;
 mod indexer;
 mod markdown;
+mod notebook;
+mod orgmode;
+mod overfetch;
 mod platform;
 pub mod remote;
+mod rst;
 mod searcher;
+mod text;
 mod vault;
 mod watcher;
 
+pub use disk::{missing_files_warning, read_prefix, IndexedFileReader, STALE_INDEX_HINT};
+pub use diversify::{mmr_order, DiversityItem, MMR_LAMBDA};
+pub use editor::{open_in_editor, open_in_file_manager, run_preview};
 #[allow(unused_imports)]
 pub use embedder::{ChunkEmbedding, Embedder, TextChunk};
-pub use indexer::Indexer;
-pub use markdown::parse_markdown;
+pub use exclude::{fetch_kept, PathExclude};
+pub use hooks::run_post_index_command;
+pub use indexer::{resolve_root, IndexProgress, IndexResult, Indexer};
+pub use markdown::{extract_external_links, parse_markdown};
 #[allow(unused_imports)]
 pub use markdown::{strip_markdown_syntax, CodeBlock, Heading, MarkdownMeta};
+pub use notebook::extract_notebook_text;
+pub use orgmode::{parse_orgmode, strip_orgmode_syntax};
+pub use overfetch::fetch_capped;
 #[allow(unused_imports)]
 pub use platform::PlatformLimits;
-pub use platform::{check_inotify_limit, estimate_directory_count};
-pub use searcher::{SearchMode, Searcher};
+pub use platform::{check_inotify_limit, estimate_directory_count, unwatchable_filesystem};
+pub use rst::strip_rst_syntax;
+pub use searcher::{SearchMode, Searcher, UnifiedSearchResult};
+pub use text::{
+    highlight, highlight_substring, replace_highlights, strip_highlights, truncate_chars,
+    truncate_chars_start, HIGHLIGHT_END, HIGHLIGHT_START,
+};
 #[allow(unused_imports)]
 pub use vault::VaultType;
 #[allow(unused_imports)]
@@ -0,0 +1,89 @@
+//! Jupyter notebook (`.ipynb`) text extraction.
+//!
+//! Notebooks are JSON documents; indexing the raw JSON buries prose and code
+//! under escaping, metadata and base64 outputs. Only cell sources are kept:
+//! - Markdown and raw cells as written
+//! - Code cells as code (outputs are dropped)
+
+use serde_json::Value;
+
+/// Extract the markdown, code and raw cell sources of a notebook as plain
+/// text, one cell after another separated by blank lines.
+///
+/// Supports nbformat 4 (`cells`) and 3 (`worksheets[].cells`, code in
+/// `input`). Returns `None` if the content is not a notebook, so callers can
+/// fall back to the raw content.
+#[must_use]
+pub fn extract_notebook_text(content: &str) -> Option<String> {
+    let notebook: Value = serde_json::from_str(content).ok()?;
+
+    let cells: Vec<&Value> = match notebook.get("cells") {
+        Some(cells) => cells.as_array()?.iter().collect(),
+        None => notebook
+            .get("worksheets")?
+            .as_array()?
+            .iter()
+            .filter_map(|sheet| sheet.get("cells")?.as_array())
+            .flatten()
+            .collect(),
+    };
+
+    let sources: Vec<String> = cells
+        .into_iter()
+        .filter_map(cell_source)
+        .filter(|source| !source.trim().is_empty())
+        .collect();
+    Some(sources.join("\n\n"))
+}
+
+/// A cell's source, given either as one string or as a list of lines
+fn cell_source(cell: &Value) -> Option<String> {
+    let source = cell.get("source").or_else(|| cell.get("input"))?;
+    match source {
+        Value::String(text) => Some(text.trim_end().to_string()),
+        Value::Array(lines) => {
+            let text: String = lines.iter().filter_map(Value::as_str).collect();
+            Some(text.trim_end().to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_notebook_text() {
+        let notebook = r##"{
+            "cells": [
+                {"cell_type": "markdown", "source": ["# Analysis\n", "Load the data."]},
+                {"cell_type": "code", "source": "import pandas as pd",
+                 "outputs": [{"output_type": "stream", "text": ["noise"]}]},
+                {"cell_type": "code", "source": []}
+            ],
+            "metadata": {}, "nbformat": 4, "nbformat_minor": 5
+        }"##;
+        assert_eq!(
+            extract_notebook_text(notebook).as_deref(),
+            Some("# Analysis\nLoad the data.\n\nimport pandas as pd")
+        );
+    }
+
+    #[test]
+    fn test_extract_notebook_text_v3() {
+        let notebook = r#"{"worksheets": [{"cells": [
+            {"cell_type": "code", "input": ["x = 1\n", "print(x)"]}
+        ]}], "nbformat": 3}"#;
+        assert_eq!(
+            extract_notebook_text(notebook).as_deref(),
+            Some("x = 1\nprint(x)")
+        );
+    }
+
+    #[test]
+    fn test_extract_notebook_text_malformed() {
+        assert_eq!(extract_notebook_text("{\"cells\": [truncated"), None);
+        assert_eq!(extract_notebook_text("{\"name\": \"package\"}"), None);
+    }
+}
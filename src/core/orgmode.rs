@@ -0,0 +1,339 @@
+//! Org-mode parsing utilities for extracting metadata from `.org` files.
+//!
+//! Handles:
+//! - `#+TITLE:` and `#+FILETAGS:` keywords
+//! - Headlines (`* Heading`) with trailing `:tag:` lists
+//! - Org links `[[target]]` and `[[target][description]]`
+//! - Syntax stripping for cleaner full-text indexing
+
+use std::collections::HashSet;
+
+use super::markdown::{Heading, MarkdownMeta};
+
+/// Parse org-mode content and extract metadata
+#[must_use]
+pub fn parse_orgmode(content: &str) -> MarkdownMeta {
+    let mut meta = MarkdownMeta::default();
+    let mut seen_tags = HashSet::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let lower = trimmed.to_lowercase();
+
+        // Skip the contents of #+BEGIN_SRC / #+BEGIN_EXAMPLE blocks
+        if lower.starts_with("#+begin_") {
+            in_block = true;
+            continue;
+        }
+        if lower.starts_with("#+end_") {
+            in_block = false;
+            continue;
+        }
+        if in_block {
+            continue;
+        }
+
+        if let Some(value) = keyword_value(trimmed, "title") {
+            if meta.title.is_none() && !value.is_empty() {
+                meta.title = Some(value.to_string());
+            }
+            continue;
+        }
+
+        if let Some(value) = keyword_value(trimmed, "filetags") {
+            for tag in value.split([':', ' ']) {
+                push_tag(&mut meta.tags, &mut seen_tags, tag);
+            }
+            continue;
+        }
+
+        // Headlines must start at column 0
+        if let Some((level, text, tags)) = parse_headline(line) {
+            for tag in tags {
+                push_tag(&mut meta.tags, &mut seen_tags, tag);
+            }
+            if !text.is_empty() {
+                meta.headings.push(Heading {
+                    level,
+                    text: text.to_string(),
+                });
+            }
+        }
+    }
+
+    // If no #+TITLE, use the first top-level headline
+    if meta.title.is_none() {
+        if let Some(h1) = meta.headings.iter().find(|h| h.level == 1) {
+            meta.title = Some(h1.text.clone());
+        }
+    }
+
+    meta.links = extract_org_links(content);
+
+    meta
+}
+
+/// Keywords whose value is prose worth indexing; other `#+KEY:` lines are settings
+const TEXT_KEYWORDS: &[&str] = &["title", "subtitle", "description", "author"];
+
+/// Leading words of planning lines (`SCHEDULED: <2024-06-01 Sat>`)
+const PLANNING_KEYWORDS: &[&str] = &["SCHEDULED:", "DEADLINE:", "CLOSED:"];
+
+/// Remove org-mode syntax from content, leaving plain text for full-text indexing.
+///
+/// Drops settings keywords (keeping the values of `#+TITLE:` and the like),
+/// comments, planning lines, property and logbook drawers and block
+/// delimiters; block contents are kept as-is. Headlines lose their stars,
+/// `TODO`/`DONE` keyword, priority and tags, and links are reduced to their
+/// description (or target).
+#[must_use]
+pub fn strip_orgmode_syntax(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut in_block = false;
+    let mut in_drawer = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let lower = trimmed.to_lowercase();
+
+        if lower.starts_with("#+begin_") || lower.starts_with("#+end_") {
+            in_block = lower.starts_with("#+begin_");
+            result.push('\n');
+            continue;
+        }
+        if in_block {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+        if in_drawer || lower == ":properties:" || lower == ":logbook:" {
+            in_drawer = lower != ":end:";
+            result.push('\n');
+            continue;
+        }
+
+        let text = if let Some(rest) = trimmed.strip_prefix("#+") {
+            rest.split_once(':')
+                .filter(|(name, _)| TEXT_KEYWORDS.contains(&name.to_lowercase().as_str()))
+                .map_or("", |(_, value)| value.trim())
+        } else if trimmed == "#"
+            || trimmed.starts_with("# ")
+            || PLANNING_KEYWORDS.iter().any(|k| trimmed.starts_with(k))
+        {
+            // Comments and SCHEDULED/DEADLINE/CLOSED lines
+            ""
+        } else if let Some((_, text, _)) = parse_headline(line) {
+            strip_headline_prefixes(text)
+        } else {
+            line
+        };
+        result.push_str(&strip_org_links(text));
+        result.push('\n');
+    }
+
+    result
+}
+
+/// Remove a leading `TODO`/`DONE` keyword and `[#A]` priority from headline text
+fn strip_headline_prefixes(text: &str) -> &str {
+    let text = ["TODO ", "DONE "]
+        .iter()
+        .find_map(|k| text.strip_prefix(k))
+        .unwrap_or(text)
+        .trim_start();
+    match text.strip_prefix("[#") {
+        Some(rest) if rest.get(1..2) == Some("]") => rest[2..].trim_start(),
+        _ => text,
+    }
+}
+
+/// Replace `[[target][description]]` with the description and `[[target]]` with the target
+fn strip_org_links(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[[") {
+        let Some(end) = rest[start..].find("]]") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + end];
+        result.push_str(&rest[..start]);
+        result.push_str(inner.split_once("][").map_or(inner, |(_, desc)| desc));
+        rest = &rest[start + end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Return the value of a `#+KEY:` line if it matches `key` (case-insensitive)
+fn keyword_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix("#+")?;
+    let (name, value) = rest.split_once(':')?;
+    if name.eq_ignore_ascii_case(key) {
+        Some(value.trim())
+    } else {
+        None
+    }
+}
+
+/// Parse a headline into (level, text, tags)
+fn parse_headline(line: &str) -> Option<(u8, &str, Vec<&str>)> {
+    let stars = line.chars().take_while(|&c| c == '*').count();
+    if stars == 0 || !line[stars..].starts_with(' ') {
+        return None;
+    }
+
+    let level = u8::try_from(stars.min(6)).unwrap_or(6);
+    let rest = line[stars..].trim();
+
+    // Trailing tags look like `:tag1:tag2:`, separated from the text by whitespace
+    if let Some((text, last)) = rest.rsplit_once(char::is_whitespace) {
+        if let Some(tags) = parse_tag_list(last) {
+            return Some((level, text.trim(), tags));
+        }
+    } else if let Some(tags) = parse_tag_list(rest) {
+        return Some((level, "", tags));
+    }
+
+    Some((level, rest, Vec::new()))
+}
+
+/// Parse a `:tag1:tag2:` string into its tags
+fn parse_tag_list(s: &str) -> Option<Vec<&str>> {
+    if s.len() < 3 || !s.starts_with(':') || !s.ends_with(':') {
+        return None;
+    }
+
+    let tags: Vec<&str> = s[1..s.len() - 1].split(':').collect();
+    let valid = tags.iter().all(|t| {
+        !t.is_empty()
+            && t.chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%'))
+    });
+
+    valid.then_some(tags)
+}
+
+fn push_tag(tags: &mut Vec<String>, seen: &mut HashSet<String>, tag: &str) {
+    let tag = tag.trim();
+    if !tag.is_empty() && seen.insert(tag.to_string()) {
+        tags.push(tag.to_string());
+    }
+}
+
+/// Extract link targets from `[[target]]` and `[[target][description]]`
+fn extract_org_links(content: &str) -> Vec<String> {
+    let mut links = HashSet::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find("[[") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("]]") else {
+            break;
+        };
+
+        let inner = &rest[..end];
+        rest = &rest[end + 2..];
+
+        // Links don't span lines
+        if inner.contains('\n') {
+            continue;
+        }
+
+        let target = inner.split_once("][").map_or(inner, |(t, _)| t);
+        if let Some(target) = normalize_link_target(target) {
+            links.insert(target);
+        }
+    }
+
+    let mut result: Vec<_> = links.into_iter().collect();
+    result.sort();
+    result
+}
+
+/// Normalize an org link target to a note name, dropping external links
+fn normalize_link_target(target: &str) -> Option<String> {
+    let target = target.trim();
+
+    let target = if let Some(path) = target.strip_prefix("file:") {
+        // Drop search options like `file:notes.org::*Heading`
+        let path = path.split("::").next().unwrap_or(path);
+        path.strip_suffix(".org").unwrap_or(path)
+    } else if target.contains("://") || target.starts_with("mailto:") {
+        return None;
+    } else {
+        target
+    };
+
+    if target.is_empty() {
+        None
+    } else {
+        Some(target.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_title_keyword() {
+        let content = "#+TITLE: Project Notes\n#+AUTHOR: Me\n\n* First heading\n";
+        let meta = parse_orgmode(content);
+        assert_eq!(meta.title, Some("Project Notes".to_string()));
+        assert_eq!(meta.headings.len(), 1);
+    }
+
+    #[test]
+    fn test_strip_orgmode_syntax() {
+        let content = "#+TITLE: Project Notes\n#+STARTUP: indent\n\
+* TODO [#A] Ship it :work:\n\
+SCHEDULED: <2024-06-01 Sat>\n\
+:PROPERTIES:\n:ID: 1234\n:END:\n\
+See [[file:other.org][the other note]] and [[Inbox]].\n\
+# a comment\n\
+#+BEGIN_SRC rust\nfn main() {}\n#+END_SRC\n";
+        let stripped = strip_orgmode_syntax(content);
+        let lines: Vec<&str> = stripped.lines().filter(|l| !l.is_empty()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Project Notes",
+                "Ship it",
+                "See the other note and Inbox.",
+                "fn main() {}"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_title_falls_back_to_headline() {
+        let meta = parse_orgmode("Intro text\n* Top Level\n** Nested\n");
+        assert_eq!(meta.title, Some("Top Level".to_string()));
+        assert_eq!(meta.headings[1].level, 2);
+    }
+
+    #[test]
+    fn test_headline_tags() {
+        let content = "* TODO Ship release   :work:urgent:\n** Notes\n";
+        let meta = parse_orgmode(content);
+        assert_eq!(meta.tags, vec!["work", "urgent"]);
+        assert_eq!(meta.headings[0].text, "TODO Ship release");
+    }
+
+    #[test]
+    fn test_filetags_and_dedup() {
+        let content = "#+FILETAGS: :work:emacs:\n* Task :work:\n";
+        let meta = parse_orgmode(content);
+        assert_eq!(meta.tags, vec!["work", "emacs"]);
+    }
+
+    #[test]
+    fn test_org_links() {
+        let content =
+            "See [[file:other.org][Other]] and [[Some Note]].\nAlso [[https://example.com][site]].";
+        let meta = parse_orgmode(content);
+        assert_eq!(meta.links, vec!["Some Note", "other"]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Filling a result limit when results are filtered after the query runs
+//! (`search --exclude-pattern`, `search --per-repo-limit`).
+
+use std::collections::HashMap;
+
+use crate::error::Result;
+
+/// Run `fetch(n)` for the top `n` ranked candidates and `filter` them until
+/// `wanted` survive or the query runs out of matches.
+///
+/// Filtered results leave gaps, so the query runs again from the start for
+/// twice as many candidates each time, beginning with `first_batch`. `filter`
+/// sees each batch whole, so it may depend on earlier results in it. All
+/// survivors of the last batch are returned, possibly more than `wanted`.
+pub fn fetch_until<T>(
+    wanted: usize,
+    first_batch: usize,
+    mut fetch: impl FnMut(usize) -> Result<Vec<T>>,
+    mut filter: impl FnMut(Vec<T>) -> Vec<T>,
+) -> Result<Vec<T>> {
+    let mut batch_size = first_batch;
+    loop {
+        let batch = fetch(batch_size)?;
+        let exhausted = batch.len() < batch_size || batch_size == usize::MAX;
+        let kept = filter(batch);
+        if kept.len() >= wanted || exhausted {
+            return Ok(kept);
+        }
+        batch_size = batch_size.saturating_mul(2);
+    }
+}
+
+/// Keep at most `per_repo` results of each repository, in ranked order
+pub fn cap_per_repo<T>(results: Vec<T>, per_repo: usize, repo: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    results
+        .into_iter()
+        .filter(|r| {
+            let count = counts.entry(repo(r).to_string()).or_insert(0);
+            *count += 1;
+            *count <= per_repo
+        })
+        .collect()
+}
+
+/// Run `fetch(limit)` and keep at most `per_repo` results of each repository,
+/// fetching more candidates until `limit` survive (see [`fetch_until`]).
+/// Without a cap the query runs once, as asked.
+pub fn fetch_capped<T>(
+    per_repo: Option<usize>,
+    limit: usize,
+    mut fetch: impl FnMut(usize) -> Result<Vec<T>>,
+    repo: impl Fn(&T) -> &str,
+) -> Result<Vec<T>> {
+    let Some(per_repo) = per_repo else {
+        return fetch(limit);
+    };
+
+    let mut kept = fetch_until(limit, limit, fetch, |batch| {
+        cap_per_repo(batch, per_repo, &repo)
+    })?;
+    kept.truncate(limit);
+    Ok(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_capped_fills_limit_across_repos() {
+        // The first repository dominates the ranking
+        let ranked: Vec<&str> = ["a", "a", "a", "a", "a", "a", "b", "a", "c", "b"].to_vec();
+        let mut calls = Vec::new();
+        let kept = fetch_capped(
+            Some(2),
+            4,
+            |limit| {
+                calls.push(limit);
+                Ok(ranked.iter().take(limit).copied().collect())
+            },
+            |r| r,
+        )
+        .unwrap();
+        assert_eq!(kept, vec!["a", "a", "b", "c"]);
+        assert_eq!(calls, vec![4, 8, 16]);
+
+        // Fewer repositories than needed: fewer results than the limit
+        let kept = fetch_capped(
+            Some(1),
+            5,
+            |limit| Ok(ranked[..limit.min(8)].to_vec()),
+            |r| r,
+        )
+        .unwrap();
+        assert_eq!(kept, vec!["a", "b"]);
+
+        // Without a cap the query runs once
+        let kept = fetch_capped(None, 3, |limit| Ok(ranked[..limit].to_vec()), |r| r).unwrap();
+        assert_eq!(kept, vec!["a", "a", "a"]);
+    }
+}
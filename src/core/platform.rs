@@ -93,6 +93,62 @@ fn read_inotify_max_user_watches() -> Option<u64> {
         .and_then(|s| s.trim().parse().ok())
 }
 
+/// Filesystem types whose change notifications are unreliable or missing
+/// (network mounts, WSL-mounted Windows drives, FUSE)
+#[cfg(target_os = "linux")]
+const UNWATCHABLE_FILESYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "9p",
+    "drvfs",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "davfs",
+];
+
+/// The filesystem type of `path` if it is one where inotify does not see
+/// changes (NFS, SMB, WSL drives, ...), meaning `watch` needs `--poll`.
+///
+/// Only detected on Linux; returns `None` elsewhere.
+pub fn unwatchable_filesystem(path: &Path) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+        let path = std::fs::canonicalize(path).ok()?;
+        mount_fs_type(&mounts, &path)
+            .filter(|fs| UNWATCHABLE_FILESYSTEMS.contains(fs))
+            .map(str::to_string)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Filesystem type of the longest mount point in `/proc/mounts` containing `path`
+#[cfg(target_os = "linux")]
+fn mount_fs_type<'a>(mounts: &'a str, path: &Path) -> Option<&'a str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            path.starts_with(&mount_point)
+                .then_some((mount_point.len(), fs_type))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, fs_type)| fs_type)
+}
+
 /// Estimate the number of directories in a path (for inotify limit check).
 /// This does a quick walk without reading file contents.
 pub fn estimate_directory_count(path: &Path) -> io::Result<usize> {
@@ -150,6 +206,24 @@ mod tests {
         assert!(!result.may_be_insufficient);
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_mount_fs_type() {
+        let mounts = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+server:/export /mnt/notes nfs4 rw,relatime 0 0
+C:\\134 /mnt/c 9p rw,relatime 0 0
+//nas/share /mnt/my\\040share cifs rw 0 0
+";
+        let fs = |p: &str| mount_fs_type(mounts, Path::new(p));
+        assert_eq!(fs("/home/me/notes"), Some("ext4"));
+        assert_eq!(fs("/mnt/notes/work"), Some("nfs4"));
+        assert_eq!(fs("/mnt/c/Users"), Some("9p"));
+        assert_eq!(fs("/mnt/my share/docs"), Some("cifs"));
+        // Mount points match whole path components
+        assert_eq!(fs("/mnt/notes2"), Some("ext4"));
+    }
+
     #[test]
     fn test_estimate_directory_count() {
         // Test with temp dir
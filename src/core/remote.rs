@@ -61,10 +61,18 @@ pub fn parse_github_url(input: &str) -> Result<(String, String, String)> {
     }
 }
 
-/// Get the path where remote repos are cloned
+/// Get the path where remote repos are cloned: `KDEX_REPOS_DIR`, then the
+/// `clone_dir` config key, then `repos` inside the config directory
 pub fn get_repos_dir() -> Result<PathBuf> {
-    let config_dir = Config::config_dir()?;
-    Ok(config_dir.join("repos"))
+    match Config::load()?.clone_dir() {
+        Some(dir) => Ok(dir),
+        None => default_repos_dir(),
+    }
+}
+
+/// Clone location used when no `clone_dir` is configured
+fn default_repos_dir() -> Result<PathBuf> {
+    Ok(Config::config_dir()?.join("repos"))
 }
 
 /// Get the clone path for a specific remote repo
@@ -165,7 +173,7 @@ pub fn clone_repository(
         Err(e) => {
             // Clean up failed clone
             let _ = std::fs::remove_dir_all(target_path);
-            Err(AppError::Other(format!("Clone failed: {e}")))
+            Err(AppError::Remote(format!("Clone failed: {e}")))
         }
     }
 }
@@ -205,7 +213,7 @@ pub fn sync_repository(repo_path: &Path, branch: Option<&str>) -> Result<bool> {
     let refspecs: &[&str] = &[];
     remote
         .fetch(refspecs, Some(&mut fetch_opts), None)
-        .map_err(|e| AppError::Other(format!("Fetch failed: {e}")))?;
+        .map_err(|e| AppError::Remote(format!("Fetch failed: {e}")))?;
 
     // Get the target branch
     let branch_name = branch.unwrap_or("HEAD");
@@ -234,6 +242,47 @@ pub fn sync_repository(repo_path: &Path, branch: Option<&str>) -> Result<bool> {
     Ok(true) // Changes were made
 }
 
+/// Commit SHA that a clone's `HEAD` points at
+pub fn head_commit(repo_path: &Path) -> Option<String> {
+    let repo = GitRepo::open(repo_path).ok()?;
+    let commit = repo.head().ok()?.peel_to_commit().ok()?;
+    Some(commit.id().to_string())
+}
+
+/// Files added, modified, or deleted between two commits of a clone, relative to its root.
+/// Renamed files appear under both their old and new path.
+pub fn changed_files_between(
+    repo_path: &Path,
+    old_oid: &str,
+    new_oid: &str,
+) -> Result<Vec<PathBuf>> {
+    let repo = GitRepo::open(repo_path)
+        .map_err(|e| AppError::Other(format!("Failed to open repository: {e}")))?;
+
+    let tree_of = |oid: &str| {
+        git2::Oid::from_str(oid)
+            .and_then(|oid| repo.find_commit(oid))
+            .and_then(|commit| commit.tree())
+            .map_err(|e| AppError::Other(format!("Failed to find commit {oid}: {e}")))
+    };
+    let old_tree = tree_of(old_oid)?;
+    let new_tree = tree_of(new_oid)?;
+
+    let diff = repo
+        .diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)
+        .map_err(|e| AppError::Other(format!("Failed to diff commits: {e}")))?;
+
+    let mut files: Vec<PathBuf> = diff
+        .deltas()
+        .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .map(Path::to_path_buf)
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
 /// Delete a cloned repository directory
 pub fn delete_clone(repo_path: &Path) -> Result<()> {
     if repo_path.exists() {
@@ -249,10 +298,10 @@ pub fn delete_clone(repo_path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Check if a path is inside the repos directory (i.e., a remote clone)
+/// Check if a path is inside the repos directory (i.e., a remote clone).
+/// Clones made under the default location before `clone_dir` was set still count.
 pub fn is_remote_clone(path: &Path) -> Result<bool> {
-    let repos_dir = get_repos_dir()?;
-    Ok(path.starts_with(&repos_dir))
+    Ok(path.starts_with(get_repos_dir()?) || path.starts_with(default_repos_dir()?))
 }
 
 #[cfg(test)]
@@ -295,4 +344,61 @@ mod tests {
     fn test_invalid_format() {
         assert!(parse_github_url("invalid").is_err());
     }
+
+    #[test]
+    fn test_head_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(head_commit(dir.path()), None);
+
+        let repo = GitRepo::init(dir.path()).unwrap();
+        let sig = git2::Signature::now("kdex", "kdex@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let oid = repo
+            .commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[])
+            .unwrap();
+
+        assert_eq!(head_commit(dir.path()), Some(oid.to_string()));
+    }
+
+    #[test]
+    fn test_changed_files_between() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = GitRepo::init(dir.path()).unwrap();
+        let sig = git2::Signature::now("kdex", "kdex@example.com").unwrap();
+
+        let commit = |message: &str, parents: &[&git2::Commit]| {
+            let mut index = repo.index().unwrap();
+            index
+                .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            index.update_all(["*"], None).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, parents)
+                .unwrap()
+        };
+
+        std::fs::write(dir.path().join("kept.md"), "same").unwrap();
+        std::fs::write(dir.path().join("edited.md"), "before").unwrap();
+        std::fs::write(dir.path().join("removed.md"), "gone soon").unwrap();
+        let first = commit("first", &[]);
+
+        std::fs::write(dir.path().join("edited.md"), "after").unwrap();
+        std::fs::remove_file(dir.path().join("removed.md")).unwrap();
+        std::fs::write(dir.path().join("added.md"), "new").unwrap();
+        let parent = repo.find_commit(first).unwrap();
+        let second = commit("second", &[&parent]);
+
+        let changed =
+            changed_files_between(dir.path(), &first.to_string(), &second.to_string()).unwrap();
+        assert_eq!(
+            changed,
+            vec![
+                PathBuf::from("added.md"),
+                PathBuf::from("edited.md"),
+                PathBuf::from("removed.md"),
+            ]
+        );
+        assert!(changed_files_between(dir.path(), "not-an-oid", &second.to_string()).is_err());
+    }
 }
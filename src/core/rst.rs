@@ -0,0 +1,212 @@
+//! reStructuredText utilities.
+//!
+//! Handles syntax stripping for cleaner full-text indexing:
+//! - Section title adornments (`=====`, `-----`, ...)
+//! - Directives (`.. note::`, `.. code-block:: python`), comments and targets
+//! - Roles (`:ref:`, `:doc:`), hyperlink references and inline literals
+
+/// Characters section titles may be underlined (and overlined) with
+const ADORNMENT_CHARS: &str = "=-`:'\"~^_*+#<>";
+
+/// Remove reStructuredText syntax from content, leaving plain text for
+/// full-text indexing.
+///
+/// Drops title adornments, comments, hyperlink targets and directive markers
+/// (keeping a directive's argument and option values, e.g. the caption text).
+/// Inline roles, hyperlink references and literals are reduced to their
+/// text, and `**`/`*` emphasis markers are removed. Indented literal blocks
+/// are kept as-is.
+#[must_use]
+pub fn strip_rst_syntax(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let text = if is_adornment(trimmed) || trimmed == "::" {
+            String::new()
+        } else if let Some(explicit) = trimmed.strip_prefix("..") {
+            strip_explicit_markup(explicit)
+        } else if let Some(value) = field_value(trimmed) {
+            // Directive options (indented) keep their value, field lists their name too
+            if line.starts_with(char::is_whitespace) {
+                strip_inline(value)
+            } else {
+                strip_inline(trimmed.trim_start_matches(':'))
+            }
+        } else {
+            // `Paragraph::` introduces a literal block and renders as `Paragraph:`
+            strip_inline(line.strip_suffix("::").map_or(line, |l| &line[..=l.len()]))
+        };
+        result.push_str(&text);
+        result.push('\n');
+    }
+
+    result
+}
+
+/// Whether a line is a section title underline or overline
+fn is_adornment(line: &str) -> bool {
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(first) if ADORNMENT_CHARS.contains(first) => {
+            line.len() >= 2 && chars.all(|c| c == first)
+        }
+        _ => false,
+    }
+}
+
+/// Text of an explicit markup line (after its leading `..`): a directive's
+/// argument, a footnote or citation's text, nothing for comments and targets
+fn strip_explicit_markup(rest: &str) -> String {
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        // `..` followed directly by text is not explicit markup
+        return strip_inline(rest);
+    }
+    let rest = rest.trim();
+
+    // Substitution definitions: `.. |name| image:: logo.png`
+    let rest = match rest.strip_prefix('|').and_then(|r| r.split_once("| ")) {
+        Some((_, definition)) => definition.trim_start(),
+        None => rest,
+    };
+
+    if let Some((name, argument)) = rest.split_once("::") {
+        if !name.is_empty() && !name.contains(char::is_whitespace) {
+            return strip_inline(argument.trim());
+        }
+    }
+    if let Some(label_end) = rest.strip_prefix('[').and_then(|r| r.find(']')) {
+        return strip_inline(rest[label_end + 2..].trim());
+    }
+    // Comments and hyperlink targets (`.. _name: url`)
+    String::new()
+}
+
+/// Value of a field list line (`:Author: Jane`, `:alt: Diagram`)
+fn field_value(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix(':')?;
+    let end = rest.find(':')?;
+    let name = &rest[..end];
+    let value = &rest[end + 1..];
+    let valid = !name.is_empty() && !name.contains(char::is_whitespace);
+    (valid && (value.is_empty() || value.starts_with(' '))).then(|| value.trim())
+}
+
+/// Reduce inline markup to its text: inline literals, roles, hyperlink
+/// references and `**strong**` / `*emphasis*`
+fn strip_inline(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('`') {
+        let delimiter = if rest[start..].starts_with("``") {
+            "``"
+        } else {
+            "`"
+        };
+        let inner_start = start + delimiter.len();
+        let Some(len) = rest[inner_start..].find(delimiter) else {
+            break;
+        };
+        let inner = &rest[inner_start..inner_start + len];
+        result.push_str(strip_role(&rest[..start], true));
+
+        let mut after = &rest[inner_start + len + delimiter.len()..];
+        if delimiter == "`" {
+            // Hyperlink references keep their text: `text <url>`_
+            let text = match inner.rfind(" <") {
+                Some(i) if inner.ends_with('>') => &inner[..i],
+                _ => inner,
+            };
+            result.push_str(text);
+            after = strip_role(after.trim_start_matches('_'), false);
+        } else {
+            result.push_str(inner);
+        }
+        rest = after;
+    }
+    result.push_str(rest);
+
+    result
+        .replace("**", "")
+        .replace(" *", " ")
+        .replace("* ", " ")
+}
+
+/// Remove a `:role:` directly before (`before`) or after (`!before`) interpreted text
+fn strip_role(text: &str, before: bool) -> &str {
+    let is_role = |role: &str| {
+        role.len() >= 3
+            && role.starts_with(':')
+            && role.ends_with(':')
+            && role[1..role.len() - 1]
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+    };
+
+    if before {
+        let start = text.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        if is_role(&text[start..]) {
+            return &text[..start];
+        }
+    } else if let Some(end) = text.get(1..).and_then(|r| r.find(':')) {
+        if is_role(&text[..end + 2]) {
+            return &text[end + 2..];
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_rst_syntax() {
+        let content = "\
+=====
+Guide
+=====
+
+Intro with **bold**, ``code`` and a `link <https://example.com>`_.
+
+.. note:: Read this first
+
+.. image:: diagram.png
+   :alt: Architecture diagram
+
+.. _target: https://example.com
+.. a comment
+
+See :ref:`installation` and :doc:`usage`.
+
+Example::
+
+    pip install kdex
+";
+        let stripped = strip_rst_syntax(content);
+        let lines: Vec<&str> = stripped.lines().filter(|l| !l.trim().is_empty()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Guide",
+                "Intro with bold, code and a link.",
+                "Read this first",
+                "diagram.png",
+                "Architecture diagram",
+                "See installation and usage.",
+                "Example:",
+                "    pip install kdex",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_adornment() {
+        assert!(is_adornment("====="));
+        assert!(is_adornment("~~"));
+        assert!(!is_adornment("=-=-"));
+        assert!(!is_adornment("-"));
+        assert!(!is_adornment("Text"));
+    }
+}
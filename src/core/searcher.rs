@@ -1,6 +1,23 @@
-use crate::core::Embedder;
-use crate::db::{Database, SearchResult, VectorSearchResult};
+use super::embedder::cosine_similarity;
+use super::text::strip_highlights;
+use crate::core::{fetch_kept, Embedder, PathExclude, TextChunk};
+use crate::db::{Database, SearchFilters, SearchResult, VectorSearchResult, Within};
 use crate::error::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Number of lexical candidates considered when re-ranking
+const RERANK_CANDIDATES: usize = 50;
+
+/// Minimum Jaro-Winkler similarity for a "did you mean" suggestion
+const SUGGEST_MIN_SIMILARITY: f64 = 0.85;
+
+/// Maximum number of "did you mean" suggestions
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Reciprocal Rank Fusion constant (standard k=60)
+pub const RRF_K: f64 = 60.0;
 
 /// Search mode selection
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -12,14 +29,23 @@ pub enum SearchMode {
     Semantic,
     /// Combined search with RRF fusion
     Hybrid,
+    /// One of the above, picked per query (see [`SearchMode::infer`])
+    Auto,
 }
 
+/// First words of a query that make it read as a question
+const QUESTION_WORDS: &[&str] = &[
+    "how", "what", "why", "when", "where", "which", "who", "can", "does", "do", "is", "are",
+    "should",
+];
+
 impl SearchMode {
     #[must_use]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "semantic" | "vector" => Self::Semantic,
             "hybrid" | "combined" => Self::Hybrid,
+            "auto" => Self::Auto,
             _ => Self::Lexical,
         }
     }
@@ -30,6 +56,51 @@ impl SearchMode {
             Self::Lexical => "lexical",
             Self::Semantic => "semantic",
             Self::Hybrid => "hybrid",
+            Self::Auto => "auto",
+        }
+    }
+
+    /// Pick a mode for `query`. Quoted, symbol-like (`parse_args`, `Foo::bar`,
+    /// `config.rs`, `camelCase`) and one- or two-word queries are lexical;
+    /// questions are semantic and other phrases hybrid. Without embeddings
+    /// everything is lexical.
+    #[must_use]
+    pub fn infer(query: &str, has_embeddings: bool) -> Self {
+        let words: Vec<&str> = query
+            .split_whitespace()
+            .map(|w| w.trim_end_matches(['?', '!', '.', ',', ';', ':']))
+            .filter(|w| !w.is_empty())
+            .collect();
+        let symbol_like = |word: &str| {
+            word.contains(|c: char| !c.is_alphanumeric() && c != '-' && c != '\'')
+                || word
+                    .chars()
+                    .zip(word.chars().skip(1))
+                    .any(|(a, b)| a.is_lowercase() && b.is_uppercase())
+        };
+
+        if !has_embeddings
+            || query.contains('"')
+            || words.len() < 3
+            || words.iter().any(|w| symbol_like(w))
+        {
+            return Self::Lexical;
+        }
+
+        let first = words[0].to_lowercase();
+        if query.trim_end().ends_with('?') || QUESTION_WORDS.contains(&first.as_str()) {
+            Self::Semantic
+        } else {
+            Self::Hybrid
+        }
+    }
+
+    /// The mode to run `query` with: `Auto` is inferred, the others are kept
+    #[must_use]
+    pub fn resolve(self, query: &str, has_embeddings: bool) -> Self {
+        match self {
+            Self::Auto => Self::infer(query, has_embeddings),
+            mode => mode,
         }
     }
 }
@@ -46,6 +117,25 @@ pub struct UnifiedSearchResult {
     pub file_type: String,
     pub score: f64,
     pub search_mode: SearchMode,
+    /// 1-based position in the lexical result list
+    pub lexical_rank: Option<usize>,
+    /// BM25 score from FTS5 (more negative is better)
+    pub lexical_score: Option<f64>,
+    /// 1-based position in the semantic result list
+    pub semantic_rank: Option<usize>,
+    /// Cosine similarity between query and content embeddings
+    pub semantic_similarity: Option<f64>,
+    /// Title from frontmatter or the first heading (markdown and org files)
+    pub title: Option<String>,
+}
+
+impl UnifiedSearchResult {
+    /// This result's RRF contribution from a 1-based rank
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn rrf_contribution(rank: usize) -> f64 {
+        1.0 / (RRF_K + rank as f64)
+    }
 }
 
 impl From<SearchResult> for UnifiedSearchResult {
@@ -59,6 +149,11 @@ impl From<SearchResult> for UnifiedSearchResult {
             file_type: r.file_type,
             score: r.score,
             search_mode: SearchMode::Lexical,
+            lexical_rank: None,
+            lexical_score: Some(r.score),
+            semantic_rank: None,
+            semantic_similarity: None,
+            title: r.title,
         }
     }
 }
@@ -74,6 +169,11 @@ impl From<VectorSearchResult> for UnifiedSearchResult {
             file_type: r.file_type,
             score: f64::from(r.similarity),
             search_mode: SearchMode::Semantic,
+            lexical_rank: None,
+            lexical_score: None,
+            semantic_rank: None,
+            semantic_similarity: Some(f64::from(r.similarity)),
+            title: r.title,
         }
     }
 }
@@ -81,19 +181,168 @@ impl From<VectorSearchResult> for UnifiedSearchResult {
 /// Search engine wrapper
 pub struct Searcher {
     db: Database,
-    embedder: Option<Embedder>,
+    embedder: Option<Arc<Embedder>>,
+    /// Substring the repository path must contain
+    repo_path: Option<String>,
+    /// Subtree files must lie in
+    within: Option<Within>,
+    /// Minimum `bm25_relevance` of lexical results
+    min_score: Option<f64>,
+    /// Match multi-word queries as `NEAR(..., N)` instead of plain AND
+    near: Option<u32>,
+    /// Match only within fenced code blocks
+    in_code: bool,
+    /// Language tag code block matches must have
+    code_lang: Option<String>,
+    /// Also search repositories that aren't ready (indexing, syncing, failed)
+    include_all: bool,
+    /// Globs for result paths to drop after the query
+    exclude: Option<PathExclude>,
+    /// When to stop scanning vector candidates (`search --timeout`)
+    deadline: Option<Instant>,
+    /// Set when a scan stopped at the deadline
+    timed_out: AtomicBool,
 }
 
 impl Searcher {
     pub fn new(db: Database) -> Self {
-        Self { db, embedder: None }
+        Self {
+            db,
+            embedder: None,
+            repo_path: None,
+            within: None,
+            min_score: None,
+            near: None,
+            in_code: false,
+            code_lang: None,
+            include_all: false,
+            exclude: None,
+            deadline: None,
+            timed_out: AtomicBool::new(false),
+        }
     }
 
-    /// Create searcher with embedding support
-    pub fn with_embedder(db: Database, embedder: Embedder) -> Self {
+    /// Create searcher with embedding support. Pass an `Arc` to share one
+    /// loaded model between searchers.
+    pub fn with_embedder(db: Database, embedder: impl Into<Arc<Embedder>>) -> Self {
         Self {
             db,
-            embedder: Some(embedder),
+            embedder: Some(embedder.into()),
+            repo_path: None,
+            within: None,
+            min_score: None,
+            near: None,
+            in_code: false,
+            code_lang: None,
+            include_all: false,
+            exclude: None,
+            deadline: None,
+            timed_out: AtomicBool::new(false),
+        }
+    }
+
+    /// Only search repositories whose path contains `repo_path`
+    #[must_use]
+    pub fn with_repo_path(mut self, repo_path: Option<String>) -> Self {
+        self.repo_path = repo_path;
+        self
+    }
+
+    /// Only search files inside the `within` subtree
+    #[must_use]
+    pub fn with_within(mut self, within: Option<Within>) -> Self {
+        self.within = within;
+        self
+    }
+
+    /// Drop lexical results whose `bm25_relevance` is below `min_score` (0 = keep all)
+    #[must_use]
+    pub fn with_min_score(mut self, min_score: f64) -> Self {
+        self.min_score = Some(min_score).filter(|min| *min > 0.0);
+        self
+    }
+
+    /// Require the query's terms to appear within `near` tokens of each other
+    #[must_use]
+    pub fn with_near(mut self, near: Option<u32>) -> Self {
+        self.near = near;
+        self
+    }
+
+    /// Restrict lexical matches to fenced code blocks, optionally of one language
+    #[must_use]
+    pub fn with_code_scope(mut self, in_code: bool, code_lang: Option<String>) -> Self {
+        self.in_code = in_code;
+        self.code_lang = code_lang;
+        self
+    }
+
+    /// Also search repositories that are still indexing, syncing or failed;
+    /// by default only ready repositories are searched
+    #[must_use]
+    pub fn with_include_all(mut self, include_all: bool) -> Self {
+        self.include_all = include_all;
+        self
+    }
+
+    /// Drop results whose repository-relative path matches `exclude`
+    #[must_use]
+    pub fn with_exclude(mut self, exclude: Option<PathExclude>) -> Self {
+        self.exclude = exclude;
+        self
+    }
+
+    /// Stop scanning vector candidates at `deadline`, ranking only those seen
+    #[must_use]
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Whether a search stopped early at the deadline, returning partial results
+    #[must_use]
+    pub fn timed_out(&self) -> bool {
+        self.timed_out.load(Ordering::Relaxed)
+    }
+
+    /// Build the FTS5 expression for a user query
+    fn fts_query(&self, query: &str) -> String {
+        match self.near {
+            Some(distance) if query.split_whitespace().nth(1).is_some() => {
+                Self::near_query(query, distance)
+            }
+            _ => Self::escape_fts_query(query),
+        }
+    }
+
+    /// Rewrite `a b c` into `NEAR("a" "b" "c", N)`.
+    /// Terms are quoted as FTS5 strings instead of escaped; a trailing `*` stays a prefix match.
+    fn near_query(query: &str, distance: u32) -> String {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|term| {
+                let (word, prefix) = match term.strip_suffix('*') {
+                    Some(word) if !word.is_empty() => (word, "*"),
+                    _ => (term, ""),
+                };
+                format!("\"{}\"{prefix}", word.replace('"', "\"\""))
+            })
+            .collect();
+        format!("NEAR({}, {distance})", terms.join(" "))
+    }
+
+    /// This searcher's filters with the repository and file type of one search
+    fn filters<'a>(
+        &'a self,
+        repo: Option<&'a str>,
+        file_type: Option<&'a str>,
+    ) -> SearchFilters<'a> {
+        SearchFilters {
+            repo,
+            repo_path: self.repo_path.as_deref(),
+            within: self.within.as_ref(),
+            ready_only: !self.include_all,
+            file_type,
         }
     }
 
@@ -107,8 +356,10 @@ impl Searcher {
         limit: usize,
         offset: usize,
     ) -> Result<Vec<UnifiedSearchResult>> {
-        match mode {
-            SearchMode::Lexical => self.lexical_search(query, repo, file_type, limit, offset),
+        match mode.resolve(query, self.has_semantic_search()) {
+            SearchMode::Lexical | SearchMode::Auto => {
+                self.lexical_search(query, repo, file_type, limit, offset)
+            }
             SearchMode::Semantic => self.semantic_search(query, repo, file_type, limit),
             SearchMode::Hybrid => self.hybrid_search(query, repo, file_type, limit),
         }
@@ -123,11 +374,40 @@ impl Searcher {
         limit: usize,
         offset: usize,
     ) -> Result<Vec<UnifiedSearchResult>> {
-        let escaped_query = Self::escape_fts_query(query);
-        let results = self
-            .db
-            .search(&escaped_query, repo, file_type, limit, offset)?;
-        Ok(results.into_iter().map(UnifiedSearchResult::from).collect())
+        let escaped_query = self.fts_query(query);
+        let fetch = |limit, offset| {
+            if self.in_code {
+                self.db.search_code_blocks(
+                    &escaped_query,
+                    self.code_lang.as_deref(),
+                    &self.filters(repo, file_type),
+                    limit,
+                    offset,
+                )
+            } else {
+                self.db.search(
+                    &escaped_query,
+                    &self.filters(repo, file_type),
+                    limit,
+                    offset,
+                )
+            }
+        };
+        let results = fetch_kept(self.exclude.as_ref(), limit, offset, fetch, |r| {
+            r.file_path.as_path()
+        })?;
+        Ok(results
+            .into_iter()
+            .enumerate()
+            .filter(|(_, r)| {
+                self.min_score
+                    .is_none_or(|min| bm25_relevance(r.score) >= min)
+            })
+            .map(|(i, r)| UnifiedSearchResult {
+                lexical_rank: Some(offset + i + 1),
+                ..r.into()
+            })
+            .collect())
     }
 
     /// Semantic (vector) search
@@ -146,10 +426,32 @@ impl Searcher {
         })?;
 
         let query_embedding = embedder.embed_query(query)?;
-        let results = self
-            .db
-            .vector_search(&query_embedding, repo, file_type, limit)?;
-        Ok(results.into_iter().map(UnifiedSearchResult::from).collect())
+        let results = fetch_kept(
+            self.exclude.as_ref(),
+            limit,
+            0,
+            |limit, _| {
+                let (results, timed_out) = self.db.vector_search(
+                    &query_embedding,
+                    &self.filters(repo, file_type),
+                    limit,
+                    self.deadline,
+                )?;
+                if timed_out {
+                    self.timed_out.store(true, Ordering::Relaxed);
+                }
+                Ok(results)
+            },
+            |r| r.file_path.as_path(),
+        )?;
+        Ok(results
+            .into_iter()
+            .enumerate()
+            .map(|(i, r)| UnifiedSearchResult {
+                semantic_rank: Some(i + 1),
+                ..r.into()
+            })
+            .collect())
     }
 
     /// Hybrid search with Reciprocal Rank Fusion
@@ -160,12 +462,11 @@ impl Searcher {
         file_type: Option<&str>,
         limit: usize,
     ) -> Result<Vec<UnifiedSearchResult>> {
-        // RRF fusion with k=60 (standard constant)
-        const RRF_K: f64 = 60.0;
-
         // Get results from both search methods
-        let lexical_results = self.lexical_search(query, repo, file_type, limit * 2, 0)?;
-        let semantic_results = self.semantic_search(query, repo, file_type, limit * 2)?;
+        let lexical_results =
+            self.lexical_search(query, repo, file_type, limit.saturating_mul(2), 0)?;
+        let semantic_results =
+            self.semantic_search(query, repo, file_type, limit.saturating_mul(2))?;
 
         // Calculate RRF scores
         let mut scores: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
@@ -174,18 +475,23 @@ impl Searcher {
 
         for (rank, result) in lexical_results.into_iter().enumerate() {
             let key = result.absolute_path.to_string_lossy().to_string();
-            #[allow(clippy::cast_precision_loss)]
-            let rrf_score = 1.0 / (RRF_K + (rank as f64) + 1.0);
-            *scores.entry(key.clone()).or_insert(0.0) += rrf_score;
+            *scores.entry(key.clone()).or_insert(0.0) +=
+                UnifiedSearchResult::rrf_contribution(rank + 1);
             result_map.entry(key).or_insert(result);
         }
 
         for (rank, result) in semantic_results.into_iter().enumerate() {
             let key = result.absolute_path.to_string_lossy().to_string();
-            #[allow(clippy::cast_precision_loss)]
-            let rrf_score = 1.0 / (RRF_K + (rank as f64) + 1.0);
-            *scores.entry(key.clone()).or_insert(0.0) += rrf_score;
-            result_map.entry(key).or_insert(result);
+            *scores.entry(key.clone()).or_insert(0.0) +=
+                UnifiedSearchResult::rrf_contribution(rank + 1);
+            // Keep the lexical snippet but record where the semantic list ranked it
+            result_map
+                .entry(key)
+                .and_modify(|r| {
+                    r.semantic_rank = r.semantic_rank.or(result.semantic_rank);
+                    r.semantic_similarity = r.semantic_similarity.or(result.semantic_similarity);
+                })
+                .or_insert(result);
         }
 
         // Sort by combined RRF score
@@ -205,6 +511,57 @@ impl Searcher {
         Ok(results)
     }
 
+    /// Lexical search with the top candidates re-ranked by semantic similarity
+    ///
+    /// Keeps lexical recall but orders the best `RERANK_CANDIDATES` matches by
+    /// cosine similarity between the query and each snippet. Falls back to
+    /// plain lexical ordering when no embedder is available.
+    pub fn reranked_search(
+        &self,
+        query: &str,
+        repo: Option<&str>,
+        file_type: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<UnifiedSearchResult>> {
+        let Some(embedder) = self.embedder.as_ref() else {
+            return self.lexical_search(query, repo, file_type, limit, 0);
+        };
+
+        let candidates = limit.max(RERANK_CANDIDATES);
+        let mut results = self.lexical_search(query, repo, file_type, candidates, 0)?;
+        if results.len() < 2 {
+            return Ok(results);
+        }
+
+        let query_embedding = embedder.embed_query(query)?;
+        let chunks: Vec<TextChunk> = results
+            .iter()
+            .map(|r| {
+                let text = strip_highlights(&r.snippet);
+                TextChunk {
+                    start_offset: 0,
+                    end_offset: text.len(),
+                    text,
+                }
+            })
+            .collect();
+        let embeddings = embedder.embed_chunks(&chunks)?;
+
+        for (result, chunk) in results.iter_mut().zip(embeddings) {
+            let similarity = f64::from(cosine_similarity(&query_embedding, &chunk.embedding));
+            result.score = similarity;
+            result.semantic_similarity = Some(similarity);
+        }
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        results.truncate(limit);
+
+        Ok(results)
+    }
+
     /// Legacy search method (lexical only)
     pub fn search(
         &self,
@@ -215,16 +572,29 @@ impl Searcher {
         offset: usize,
     ) -> Result<Vec<SearchResult>> {
         // Escape special FTS5 characters in query
-        let escaped_query = Self::escape_fts_query(query);
-        self.db
-            .search(&escaped_query, repo, file_type, limit, offset)
+        let escaped_query = self.fts_query(query);
+        fetch_kept(
+            self.exclude.as_ref(),
+            limit,
+            offset,
+            |limit, offset| {
+                self.db.search(
+                    &escaped_query,
+                    &self.filters(repo, file_type),
+                    limit,
+                    offset,
+                )
+            },
+            |r| r.file_path.as_path(),
+        )
     }
 
     /// Count total results
     #[allow(dead_code)]
     pub fn count(&self, query: &str, repo: Option<&str>, file_type: Option<&str>) -> Result<i64> {
-        let escaped_query = Self::escape_fts_query(query);
-        self.db.search_count(&escaped_query, repo, file_type)
+        let escaped_query = self.fts_query(query);
+        self.db
+            .search_count(&escaped_query, &self.filters(repo, file_type))
     }
 
     /// Check if semantic search is available
@@ -233,6 +603,36 @@ impl Searcher {
         self.embedder.is_some()
     }
 
+    /// Suggest indexed file names and tags close to a query that found nothing.
+    ///
+    /// Best effort: index lookups that fail just yield fewer suggestions.
+    pub fn suggest(&self, query: &str) -> Vec<String> {
+        let mut candidates: Vec<String> = Vec::new();
+
+        if let Ok(paths) = self.db.get_all_file_paths() {
+            for (path, _) in paths {
+                let Some(stem) = std::path::Path::new(&path)
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                else {
+                    continue;
+                };
+                // Whole stems and their words, so "retry-policy" can match "retyr"
+                candidates.extend(
+                    stem.split(|c: char| !c.is_alphanumeric())
+                        .filter(|word| word.len() >= 3)
+                        .map(str::to_string),
+                );
+                candidates.push(stem.to_string());
+            }
+        }
+        if let Ok(tags) = self.db.get_all_tags() {
+            candidates.extend(tags.into_iter().map(|(tag, _)| tag));
+        }
+
+        rank_suggestions(query, &candidates)
+    }
+
     /// Escape special FTS5 characters
     fn escape_fts_query(query: &str) -> String {
         // Handle quoted phrases
@@ -255,9 +655,58 @@ impl Searcher {
     }
 }
 
+/// Map an FTS5 `bm25()` score to a relevance in `0..1`.
+/// `SQLite` negates BM25 (lower is better, 0 for no relevance), so with
+/// `r = -score` this is `r / (1 + r)`: a raw score of -1 gives 0.5, -3 gives
+/// 0.75 and -9 gives 0.9. The mapping is fixed, not relative to the other
+/// results, so a floor means the same thing for every query.
+#[must_use]
+pub fn bm25_relevance(score: f64) -> f64 {
+    let r = (-score).max(0.0);
+    r / (1.0 + r)
+}
+
+/// Pick the candidates most similar to the query (or one of its words),
+/// above `SUGGEST_MIN_SIMILARITY` and excluding exact matches
+fn rank_suggestions(query: &str, candidates: &[String]) -> Vec<String> {
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let mut scored: Vec<(f64, &String)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let lower = candidate.to_lowercase();
+            if lower == query || words.contains(&lower.as_str()) {
+                return None;
+            }
+            let score = words
+                .iter()
+                .map(|word| strsim::jaro_winkler(word, &lower))
+                .fold(strsim::jaro_winkler(&query, &lower), f64::max);
+            (score >= SUGGEST_MIN_SIMILARITY).then_some((score, candidate))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut suggestions: Vec<String> = Vec::new();
+    for (_, candidate) in scored {
+        if !suggestions
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(candidate))
+        {
+            suggestions.push(candidate.clone());
+        }
+        if suggestions.len() == MAX_SUGGESTIONS {
+            break;
+        }
+    }
+    suggestions
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::RepoStatus;
 
     #[test]
     fn test_search_mode_from_str() {
@@ -268,6 +717,52 @@ mod tests {
         assert_eq!(SearchMode::from_str("combined"), SearchMode::Hybrid);
         assert_eq!(SearchMode::from_str("unknown"), SearchMode::Lexical);
         assert_eq!(SearchMode::from_str("SEMANTIC"), SearchMode::Semantic);
+        assert_eq!(SearchMode::from_str("auto"), SearchMode::Auto);
+    }
+
+    #[test]
+    fn test_search_mode_infer() {
+        let infer = |query| SearchMode::infer(query, true);
+        assert_eq!(infer("parse_args"), SearchMode::Lexical);
+        assert_eq!(infer("retry backoff"), SearchMode::Lexical);
+        assert_eq!(infer("\"connection pool exhausted\""), SearchMode::Lexical);
+        assert_eq!(infer("where is Config::load called"), SearchMode::Lexical);
+        assert_eq!(infer("why does parseArgs fail"), SearchMode::Lexical);
+        assert_eq!(infer("how do we rotate secrets?"), SearchMode::Semantic);
+        assert_eq!(
+            infer("deploys failing after the upgrade?"),
+            SearchMode::Semantic
+        );
+        assert_eq!(
+            infer("onboarding checklist for new engineers"),
+            SearchMode::Hybrid
+        );
+        assert_eq!(
+            SearchMode::infer("how do we rotate secrets", false),
+            SearchMode::Lexical
+        );
+        assert_eq!(
+            SearchMode::Hybrid.resolve("parse_args", true),
+            SearchMode::Hybrid
+        );
+    }
+
+    #[test]
+    fn test_rrf_contribution() {
+        assert!((UnifiedSearchResult::rrf_contribution(1) - 1.0 / 61.0).abs() < f64::EPSILON);
+        assert!(
+            UnifiedSearchResult::rrf_contribution(1) > UnifiedSearchResult::rrf_contribution(2)
+        );
+    }
+
+    #[test]
+    fn test_bm25_relevance() {
+        assert!(bm25_relevance(0.0).abs() < f64::EPSILON);
+        assert!((bm25_relevance(-1.0) - 0.5).abs() < f64::EPSILON);
+        assert!((bm25_relevance(-3.0) - 0.75).abs() < f64::EPSILON);
+        assert!(bm25_relevance(-20.0) > bm25_relevance(-2.0));
+        assert!(bm25_relevance(-1e9) < 1.0);
+        assert!(bm25_relevance(0.5).abs() < f64::EPSILON);
     }
 
     #[test]
@@ -277,6 +772,22 @@ mod tests {
         assert_eq!(SearchMode::Hybrid.as_str(), "hybrid");
     }
 
+    #[test]
+    fn test_near_query() {
+        assert_eq!(
+            Searcher::near_query("retry backoff", 5),
+            "NEAR(\"retry\" \"backoff\", 5)"
+        );
+        assert_eq!(
+            Searcher::near_query("fn() conn*", 3),
+            "NEAR(\"fn()\" \"conn\"*, 3)"
+        );
+        assert_eq!(
+            Searcher::near_query("say \"hi\"", 2),
+            "NEAR(\"say\" \"\"\"hi\"\"\", 2)"
+        );
+    }
+
     #[test]
     fn test_escape_fts_query_simple() {
         assert_eq!(Searcher::escape_fts_query("hello"), "hello");
@@ -306,4 +817,89 @@ mod tests {
         assert_eq!(Searcher::escape_fts_query("func*"), "func*");
         assert_eq!(Searcher::escape_fts_query("*pattern"), "*pattern");
     }
+
+    #[test]
+    fn test_code_block_search() {
+        let db = Database::open_in_memory().unwrap();
+        let repo = db.add_stdin_repository("notes").unwrap();
+        let file_id = db
+            .insert_file(
+                repo.id,
+                std::path::Path::new("a.md"),
+                "hash",
+                0,
+                chrono::Utc::now(),
+                "markdown",
+                "spawn in prose",
+            )
+            .unwrap();
+        db.store_code_blocks(
+            file_id,
+            &[(Some("Rust"), "tokio::spawn(task)"), (None, "plain block")],
+        )
+        .unwrap();
+        db.update_repository_indexed(repo.id, 1, 0).unwrap();
+
+        let search = |lang: Option<&str>, query: &str| {
+            Searcher::new(db.clone())
+                .with_code_scope(true, lang.map(str::to_string))
+                .search_with_mode(query, SearchMode::Lexical, None, None, 10, 0)
+                .unwrap()
+                .len()
+        };
+        assert_eq!(search(None, "spawn"), 1);
+        assert_eq!(search(Some("rust"), "spawn"), 1);
+        assert_eq!(search(Some("python"), "spawn"), 0);
+        assert_eq!(search(None, "prose"), 0);
+    }
+
+    #[test]
+    fn test_search_skips_repos_not_ready() {
+        let db = Database::open_in_memory().unwrap();
+        let repo = db.add_stdin_repository("notes").unwrap();
+        db.insert_file(
+            repo.id,
+            std::path::Path::new("a.md"),
+            "hash",
+            0,
+            chrono::Utc::now(),
+            "markdown",
+            "retry with backoff",
+        )
+        .unwrap();
+
+        let search = |include_all: bool| {
+            Searcher::new(db.clone())
+                .with_include_all(include_all)
+                .search_with_mode("retry", SearchMode::Lexical, None, None, 10, 0)
+                .unwrap()
+                .len()
+        };
+        db.update_repository_status(repo.id, RepoStatus::Indexing)
+            .unwrap();
+        assert_eq!(search(false), 0);
+        assert_eq!(search(true), 1);
+
+        db.update_repository_indexed(repo.id, 1, 0).unwrap();
+        assert_eq!(search(false), 1);
+    }
+
+    #[test]
+    fn test_rank_suggestions() {
+        let candidates: Vec<String> = ["authentication", "retry", "database", "Retry", "rust"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(
+            rank_suggestions("authentcation", &candidates),
+            vec!["authentication".to_string()]
+        );
+        assert_eq!(
+            rank_suggestions("retyr", &candidates),
+            vec!["retry".to_string()]
+        );
+        assert!(rank_suggestions("retry", &candidates).is_empty());
+        assert!(rank_suggestions("kubernetes", &candidates).is_empty());
+    }
 }
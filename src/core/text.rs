@@ -0,0 +1,144 @@
+//! Snippet highlight markers and character-safe string truncation.
+//!
+//! Slicing a `str` by byte index panics when the cut lands inside a
+//! multi-byte character, so the truncation helpers count `char`s instead.
+
+/// Marker placed before a highlighted match in search snippets
+pub const HIGHLIGHT_START: &str = ">>>";
+/// Marker placed after a highlighted match in search snippets
+pub const HIGHLIGHT_END: &str = "<<<";
+
+/// Wrap `s` in highlight markers
+#[must_use]
+pub fn highlight(s: &str) -> String {
+    format!("{HIGHLIGHT_START}{s}{HIGHLIGHT_END}")
+}
+
+/// The line of `content` holding the first occurrence of `needle`, with the
+/// match wrapped in highlight markers.
+///
+/// Matching ignores ASCII case only, like `LIKE` in `SQLite`; folding ASCII
+/// keeps byte offsets valid in the original string. Long lines are cut to
+/// about `max_chars` characters around the match.
+#[must_use]
+pub fn highlight_substring(content: &str, needle: &str, max_chars: usize) -> Option<String> {
+    if needle.is_empty() {
+        return None;
+    }
+    let start = content
+        .to_ascii_lowercase()
+        .find(&needle.to_ascii_lowercase())?;
+    let end = start + needle.len();
+
+    let line_start = content[..start].rfind('\n').map_or(0, |p| p + 1);
+    let line_end = content[end..].find('\n').map_or(content.len(), |p| end + p);
+
+    let side = max_chars.saturating_sub(needle.chars().count()) / 2;
+    let before = truncate_chars_start(&content[line_start..start], side, "...");
+    let after = truncate_chars(&content[end..line_end], side, "...");
+
+    Some(format!(
+        "{}{}{}",
+        before.trim_start(),
+        highlight(&content[start..end]),
+        after.trim_end()
+    ))
+}
+
+/// Replace highlight markers with `start` / `end` (ANSI codes, brackets, ...)
+#[must_use]
+pub fn replace_highlights(s: &str, start: &str, end: &str) -> String {
+    s.replace(HIGHLIGHT_START, start)
+        .replace(HIGHLIGHT_END, end)
+}
+
+/// Remove highlight markers
+#[must_use]
+pub fn strip_highlights(s: &str) -> String {
+    replace_highlights(s, "", "")
+}
+
+/// Keep the first characters of `s`, ending with `ellipsis` when it is cut.
+///
+/// The result is at most `max_chars` characters including the ellipsis.
+#[must_use]
+pub fn truncate_chars(s: &str, max_chars: usize, ellipsis: &str) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+
+    let keep = max_chars.saturating_sub(ellipsis.chars().count());
+    let end = s.char_indices().nth(keep).map_or(s.len(), |(i, _)| i);
+    format!("{}{ellipsis}", &s[..end])
+}
+
+/// Keep the last characters of `s`, starting with `ellipsis` when it is cut.
+///
+/// Useful for file paths, where the end is the informative part.
+#[must_use]
+pub fn truncate_chars_start(s: &str, max_chars: usize, ellipsis: &str) -> String {
+    let count = s.chars().count();
+    if count <= max_chars {
+        return s.to_string();
+    }
+
+    let keep = max_chars.saturating_sub(ellipsis.chars().count());
+    let start = s
+        .char_indices()
+        .nth(count - keep)
+        .map_or(s.len(), |(i, _)| i);
+    format!("{ellipsis}{}", &s[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_round_trip() {
+        let snippet = highlight("fn main() {}");
+        assert_eq!(replace_highlights(&snippet, "[", "]"), "[fn main() {}]");
+        assert_eq!(strip_highlights(&snippet), "fn main() {}");
+    }
+
+    #[test]
+    fn test_highlight_substring() {
+        let content = "# Login\nHandles Authentication for users\n";
+        assert_eq!(
+            highlight_substring(content, "uth", 80).as_deref(),
+            Some("Handles A>>>uth<<<entication for users")
+        );
+        assert_eq!(
+            highlight_substring("Ünïcode AUTH", "auth", 80).as_deref(),
+            Some("Ünïcode >>>AUTH<<<")
+        );
+        assert_eq!(
+            highlight_substring("aaaaaaaaaa needle bbbbbbbbbb", "needle", 12).as_deref(),
+            Some("...>>>needle<<<...")
+        );
+        assert_eq!(highlight_substring(content, "oauth", 80), None);
+    }
+
+    #[test]
+    fn test_truncate_ascii() {
+        assert_eq!(truncate_chars("hello world", 8, "..."), "hello...");
+        assert_eq!(truncate_chars("short", 8, "..."), "short");
+        assert_eq!(
+            truncate_chars_start("src/core/text.rs", 10, "..."),
+            "...text.rs"
+        );
+    }
+
+    #[test]
+    fn test_truncate_multibyte_boundaries() {
+        // Byte slicing at 4 or 5 would land inside these characters and panic
+        let emoji = "🦀🦀🦀🦀🦀🦀";
+        assert_eq!(truncate_chars(emoji, 5, "..."), "🦀🦀...");
+        assert_eq!(truncate_chars_start(emoji, 4, "…"), "…🦀🦀🦀");
+
+        let cjk = "知识索引工具知识索引工具";
+        assert_eq!(truncate_chars(cjk, 6, "…"), "知识索引工…");
+        assert_eq!(truncate_chars(cjk, 12, "…"), cjk);
+        assert_eq!(truncate_chars_start(cjk, 7, "..."), "...索引工具");
+    }
+}
@@ -1,8 +1,8 @@
 //! File system watcher for automatic re-indexing.
 
 use notify::{
-    event::{CreateKind, ModifyKind, RemoveKind},
-    Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    event::{CreateKind, MetadataKind, ModifyKind, RemoveKind},
+    Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher,
 };
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -11,6 +11,7 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use crate::config::Config as AppConfig;
+use crate::core::indexer::is_extractable_document;
 use crate::error::Result;
 
 /// Type of change detected in a file.
@@ -42,7 +43,7 @@ pub struct RepoBatch {
 /// File system watcher for automatic re-indexing.
 #[allow(dead_code)]
 pub struct IndexWatcher {
-    watcher: RecommendedWatcher,
+    watcher: Box<dyn Watcher + Send>,
     watched_paths: Arc<Mutex<Vec<PathBuf>>>,
     pending_changes: Arc<Mutex<HashMap<PathBuf, PendingChange>>>,
     event_receiver: Receiver<notify::Result<Event>>,
@@ -53,18 +54,29 @@ pub struct IndexWatcher {
 #[allow(dead_code)]
 impl IndexWatcher {
     /// Create a new watcher instance.
-    pub fn new(config: Arc<AppConfig>) -> Result<Self> {
+    ///
+    /// With `poll_interval`, the watched trees are scanned for changes at that
+    /// interval instead of relying on OS notifications (inotify, `FSEvents`),
+    /// which many network filesystems and WSL-mounted drives never deliver.
+    pub fn new(config: Arc<AppConfig>, poll_interval: Option<Duration>) -> Result<Self> {
         let (tx, rx): (
             Sender<notify::Result<Event>>,
             Receiver<notify::Result<Event>>,
         ) = mpsc::channel();
+        let handler = move |res| {
+            let _ = tx.send(res);
+        };
 
-        let watcher = RecommendedWatcher::new(
-            move |res| {
-                let _ = tx.send(res);
-            },
-            Config::default().with_poll_interval(Duration::from_secs(2)),
-        )?;
+        let watcher: Box<dyn Watcher + Send> = match poll_interval {
+            Some(interval) => Box::new(PollWatcher::new(
+                handler,
+                Config::default().with_poll_interval(interval),
+            )?),
+            None => Box::new(RecommendedWatcher::new(
+                handler,
+                Config::default().with_poll_interval(Duration::from_secs(2)),
+            )?),
+        };
 
         Ok(Self {
             watcher,
@@ -131,23 +143,23 @@ impl IndexWatcher {
 
     /// Check if there are pending changes waiting for debounce.
     pub fn has_pending_changes(&self) -> bool {
-        self.pending_changes
-            .lock()
-            .map(|p| !p.is_empty())
-            .unwrap_or(false)
+        self.pending_changes.lock().is_ok_and(|p| !p.is_empty())
     }
 
     /// Get count of pending changes.
     pub fn pending_count(&self) -> usize {
-        self.pending_changes.lock().map(|p| p.len()).unwrap_or(0)
+        self.pending_changes.lock().map_or(0, |p| p.len())
     }
 
     /// Process a single notify event.
     fn process_event(&self, event: Event) {
+        // The poll watcher reports `Any` kinds and modifications as write-time changes
         let change_type = match event.kind {
-            EventKind::Create(CreateKind::File) => Some(ChangeType::Created),
-            EventKind::Modify(ModifyKind::Data(_)) => Some(ChangeType::Modified),
-            EventKind::Remove(RemoveKind::File) => Some(ChangeType::Deleted),
+            EventKind::Create(CreateKind::File | CreateKind::Any) => Some(ChangeType::Created),
+            EventKind::Modify(
+                ModifyKind::Data(_) | ModifyKind::Metadata(MetadataKind::WriteTime),
+            ) => Some(ChangeType::Modified),
+            EventKind::Remove(RemoveKind::File | RemoveKind::Any) => Some(ChangeType::Deleted),
             _ => None,
         };
 
@@ -161,8 +173,10 @@ impl IndexWatcher {
                 continue;
             }
 
-            // Skip binary files for Created/Modified
-            if change_type != ChangeType::Deleted && Self::is_binary_extension(&path) {
+            // Skip directories and binary files for Created/Modified
+            if change_type != ChangeType::Deleted
+                && (path.is_dir() || Self::is_binary_extension(&path))
+            {
                 continue;
             }
 
@@ -222,7 +236,10 @@ impl IndexWatcher {
 
         path.extension()
             .and_then(|e| e.to_str())
-            .is_some_and(|ext| binary_extensions.contains(&ext.to_lowercase().as_str()))
+            .is_some_and(|ext| {
+                binary_extensions.contains(&ext.to_lowercase().as_str())
+                    && !is_extractable_document(ext)
+            })
     }
 
     /// Group changes by their parent repository.
@@ -1,14 +1,28 @@
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
-use crate::config::Config;
-use crate::core::VaultType;
+use crate::config::{Config, DEFAULT_FTS_TOKENIZER};
+use crate::core::{highlight_substring, VaultType, HIGHLIGHT_END, HIGHLIGHT_START};
 use crate::error::{AppError, Result};
 
 mod schema;
 
+pub use schema::SCHEMA_VERSION;
+
+/// Number of prepared statements kept per connection (indexing and search
+/// statements, including each filter combination of the search queries)
+const STATEMENT_CACHE_CAPACITY: usize = 32;
+
+/// How long a statement waits for another process (a running `kdex watch`,
+/// an index in another terminal) to release the database before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// FTS5 tables checked and optimized by `kdex maintenance`
+const FTS_TABLES: [&str; 2] = ["contents", "code_blocks"];
+
 /// Repository status in the index
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RepoStatus {
@@ -51,6 +65,8 @@ impl RepoStatus {
 pub enum SourceType {
     Local,
     Remote,
+    /// Virtual repository fed through `kdex index --stdin`; has no directory on disk
+    Stdin,
 }
 
 impl SourceType {
@@ -59,6 +75,7 @@ impl SourceType {
         match self {
             Self::Local => "local",
             Self::Remote => "remote",
+            Self::Stdin => "stdin",
         }
     }
 
@@ -66,6 +83,7 @@ impl SourceType {
     pub fn from_str(s: &str) -> Self {
         match s {
             "remote" => Self::Remote,
+            "stdin" => Self::Stdin,
             _ => Self::Local,
         }
     }
@@ -79,6 +97,9 @@ pub enum FileType {
     PlainText,
     OrgMode,
     ReStructuredText,
+    Pdf,
+    /// Jupyter notebook, indexed as the text of its cells
+    Notebook,
     Config,
     Unknown,
 }
@@ -92,6 +113,8 @@ impl FileType {
             Self::PlainText => "plaintext",
             Self::OrgMode => "orgmode",
             Self::ReStructuredText => "rst",
+            Self::Pdf => "pdf",
+            Self::Notebook => "notebook",
             Self::Config => "config",
             Self::Unknown => "unknown",
         }
@@ -138,6 +161,8 @@ impl FileType {
             "txt" => Self::PlainText,
             "org" => Self::OrgMode,
             "rst" => Self::ReStructuredText,
+            "pdf" => Self::Pdf,
+            "ipynb" => Self::Notebook,
             // Config
             "json" | "jsonc" | "yaml" | "yml" | "toml" | "xml" | "ini" | "cfg" | "env" => {
                 Self::Config
@@ -163,6 +188,8 @@ pub struct Repository {
     pub remote_branch: Option<String>,
     pub last_synced_at: Option<DateTime<Utc>>,
     pub vault_type: VaultType,
+    /// Commit SHA a remote clone was at when last synced and indexed
+    pub last_indexed_commit: Option<String>,
 }
 
 impl Repository {
@@ -172,6 +199,93 @@ impl Repository {
     pub fn is_remote(&self) -> bool {
         self.source_type == SourceType::Remote
     }
+
+    /// Check if this repository was fed through stdin and has no directory to walk
+    #[must_use]
+    pub fn is_stdin(&self) -> bool {
+        self.source_type == SourceType::Stdin
+    }
+}
+
+/// Outcome of resolving a repository given by name or path
+#[derive(Debug)]
+pub enum RepoResolution {
+    /// The path or full name matched a single repository
+    Exact(Repository),
+    /// Only one repository starts with (or contains) the query
+    UniquePrefix(Repository),
+    /// Several repositories match and none of them exactly
+    Ambiguous(Vec<Repository>),
+    NotFound,
+}
+
+impl RepoResolution {
+    /// Resolve `query` among `repos`: exact path, then exact name, then
+    /// name prefix, then name/path substring
+    pub fn among(repos: &[Repository], query: &str) -> Self {
+        let path = Path::new(query);
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if let Some(repo) = repos.iter().find(|r| r.path == canonical) {
+            return Self::Exact(repo.clone());
+        }
+
+        let query_lower = query.to_lowercase();
+        let tiers: [&dyn Fn(&Repository) -> bool; 3] = [
+            &|r| r.name == query,
+            &|r| r.name.to_lowercase().starts_with(&query_lower),
+            &|r| {
+                r.name.to_lowercase().contains(&query_lower)
+                    || r.path.to_string_lossy().contains(query)
+            },
+        ];
+
+        for (tier, matches) in tiers.iter().enumerate() {
+            let mut found: Vec<Repository> = repos.iter().filter(|r| matches(r)).cloned().collect();
+            match found.len() {
+                0 => {}
+                1 if tier == 0 => return Self::Exact(found.remove(0)),
+                1 => return Self::UniquePrefix(found.remove(0)),
+                _ => return Self::Ambiguous(found),
+            }
+        }
+
+        Self::NotFound
+    }
+
+    /// The resolved repository, or an error listing the candidates
+    pub fn into_result(self, query: &str) -> Result<Repository> {
+        match self {
+            Self::Exact(repo) | Self::UniquePrefix(repo) => Ok(repo),
+            other => other.into_exact(query),
+        }
+    }
+
+    /// The repository matching `query` by exact path or name, for commands
+    /// that change or delete it. Partial matches become an error listing them.
+    pub fn into_exact(self, query: &str) -> Result<Repository> {
+        let candidates = match self {
+            Self::Exact(repo) => return Ok(repo),
+            Self::NotFound => return Err(AppError::RepoNotFound(PathBuf::from(query))),
+            Self::UniquePrefix(repo) => vec![repo],
+            Self::Ambiguous(repos) => repos,
+        };
+        let list: Vec<String> = candidates
+            .iter()
+            .map(|r| format!("  {} ({})", r.name, r.path.display()))
+            .collect();
+        let matches = if candidates.len() == 1 {
+            "only partially matches a repository. Did you mean:".to_string()
+        } else {
+            format!(
+                "matches {} repositories. Did you mean one of:",
+                candidates.len()
+            )
+        };
+        Err(AppError::Other(format!(
+            "\"{query}\" {matches}\n{}\nUse the full name or path.",
+            list.join("\n")
+        )))
+    }
 }
 
 /// File record
@@ -187,6 +301,91 @@ pub struct FileRecord {
     pub file_type: String,
 }
 
+/// Subtree a search is restricted to (`search --within`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Within {
+    /// Path relative to each repository's root, e.g. `src` or `docs/guide.md`
+    Relative(String),
+    /// Absolute directory or file path, inside one repository
+    Absolute(PathBuf),
+}
+
+impl Within {
+    /// ` AND ...` clause restricting `f.relative_path` (and `r.path`) to the
+    /// subtree, and its `LIKE` pattern. Appending `/` to both sides makes
+    /// `src` match `src/main.rs` and `src` itself, but not `srcx/`.
+    fn sql(&self) -> (&'static str, String) {
+        match self {
+            Self::Relative(prefix) => (
+                " AND (f.relative_path || '/') LIKE ? ESCAPE '\\'",
+                format!("{}/%", escape_like(prefix)),
+            ),
+            Self::Absolute(path) => (
+                " AND (r.path || '/' || f.relative_path || '/') LIKE ? ESCAPE '\\'",
+                format!(
+                    "{}/%",
+                    escape_like(path.to_string_lossy().trim_end_matches('/'))
+                ),
+            ),
+        }
+    }
+
+    /// Whether a file of the repository at `repo_path` lies in the subtree
+    #[must_use]
+    pub fn contains(&self, repo_path: &Path, relative_path: &Path) -> bool {
+        match self {
+            Self::Relative(prefix) => relative_path.starts_with(prefix),
+            Self::Absolute(path) => repo_path.join(relative_path).starts_with(path),
+        }
+    }
+}
+
+/// Filters shared by the search queries
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchFilters<'a> {
+    /// Repository name contains this
+    pub repo: Option<&'a str>,
+    /// Repository path contains this
+    pub repo_path: Option<&'a str>,
+    pub within: Option<&'a Within>,
+    /// Leave out repositories that are still indexing, syncing or whose last run failed
+    pub ready_only: bool,
+    pub file_type: Option<&'a str>,
+}
+
+/// Append the ` AND ...` clauses of `filters` to a query joining `files f`
+/// and `repositories r`, and their parameters to `params`
+fn push_filters(
+    sql: &mut String,
+    params: &mut Vec<Box<dyn rusqlite::ToSql>>,
+    filters: &SearchFilters,
+) {
+    if let Some(repo) = filters.repo {
+        sql.push_str(" AND r.name LIKE ?");
+        params.push(Box::new(format!("%{repo}%")));
+    }
+
+    if let Some(repo_path) = filters.repo_path {
+        sql.push_str(" AND r.path LIKE ?");
+        params.push(Box::new(format!("%{repo_path}%")));
+    }
+
+    if let Some(within) = filters.within {
+        let (clause, pattern) = within.sql();
+        sql.push_str(clause);
+        params.push(Box::new(pattern));
+    }
+
+    if filters.ready_only {
+        sql.push_str(" AND r.status = 'ready'");
+    }
+
+    if let Some(file_type) = filters.file_type {
+        sql.push_str(" AND f.file_type = ?");
+        params.push(Box::new(file_type.to_string()));
+    }
+}
+
 /// Search result
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -198,6 +397,8 @@ pub struct SearchResult {
     pub snippet: String,
     pub file_type: String,
     pub score: f64,
+    /// Title from frontmatter or the first heading (markdown and org files)
+    pub title: Option<String>,
 }
 
 /// Database connection wrapper
@@ -217,11 +418,23 @@ impl Database {
         }
 
         let conn = Connection::open(&db_path)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // WAL lets searches read while another process writes
+        conn.query_row("PRAGMA journal_mode = WAL", [], |row| {
+            row.get::<_, String>(0)
+        })?;
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
         };
 
-        db.initialize()?;
+        // The configured tokenizer is used when the index is first created
+        let fts_tokenizer = Config::load()
+            .ok()
+            .map(|c| c.fts_tokenizer)
+            .filter(|t| Config::is_valid_fts_tokenizer(t))
+            .unwrap_or_else(|| DEFAULT_FTS_TOKENIZER.to_string());
+        db.initialize(&fts_tokenizer)?;
         Ok(db)
     }
 
@@ -229,29 +442,110 @@ impl Database {
     #[allow(dead_code)]
     pub fn open_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
         };
-        db.initialize()?;
+        db.initialize(DEFAULT_FTS_TOKENIZER)?;
         Ok(db)
     }
 
+    /// Lock the connection. A mutex poisoned by a panic on another thread
+    /// becomes [`AppError::LockPoisoned`] instead of a raw poison error.
+    fn lock(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|_| AppError::LockPoisoned)
+    }
+
     /// Initialize database schema
-    fn initialize(&self) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
-        schema::initialize(&conn)?;
+    fn initialize(&self, fts_tokenizer: &str) -> Result<()> {
+        let conn = self.lock()?;
+        schema::initialize(&conn, fts_tokenizer)?;
+        Ok(())
+    }
+
+    /// Tokenizer the full-text index was created with
+    pub fn fts_tokenizer(&self) -> Result<Option<String>> {
+        let conn = self.lock()?;
+        schema::fts_tokenizer(&conn)
+    }
+
+    /// Re-tokenize the full-text index with another tokenizer from the stored
+    /// content. Returns the number of files re-tokenized.
+    pub fn rebuild_fts(&self, tokenizer: &str) -> Result<usize> {
+        let conn = self.lock()?;
+        schema::rebuild_contents(&conn, tokenizer)
+    }
+
+    /// Recreate the code block index from its stored rows. Returns the
+    /// number of code blocks carried over.
+    pub fn rebuild_code_blocks(&self) -> Result<usize> {
+        let conn = self.lock()?;
+        schema::rebuild_code_blocks(&conn)
+    }
+
+    /// Problems reported by `PRAGMA integrity_check`; empty when the database is sound
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let problems = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(std::result::Result::ok)
+            .filter(|message| message != "ok")
+            .collect();
+        Ok(problems)
+    }
+
+    /// Run FTS5's `integrity-check` on each full-text table and return the
+    /// tables that failed, with the error `SQLite` reported
+    pub fn fts_integrity_check(&self) -> Result<Vec<(&'static str, String)>> {
+        let conn = self.lock()?;
+        Ok(FTS_TABLES
+            .into_iter()
+            .filter_map(|table| {
+                conn.execute(
+                    &format!("INSERT INTO {table}({table}) VALUES('integrity-check')"),
+                    [],
+                )
+                .err()
+                .map(|e| (table, e.to_string()))
+            })
+            .collect())
+    }
+
+    /// Merge each full-text table's index segments into one (FTS5 `optimize`);
+    /// worthwhile after many incremental updates
+    pub fn optimize_fts(&self) -> Result<()> {
+        let conn = self.lock()?;
+        for table in FTS_TABLES {
+            conn.execute(
+                &format!("INSERT INTO {table}({table}) VALUES('optimize')"),
+                [],
+            )?;
+        }
         Ok(())
     }
 
+    /// Schema version of the database file, read without creating or
+    /// migrating anything. `None` if there is no database yet.
+    pub fn stored_schema_version() -> Result<Option<i32>> {
+        let db_path = Config::database_path()?;
+        if !db_path.exists() {
+            return Ok(None);
+        }
+
+        let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let version = conn
+            .query_row("SELECT version FROM schema_version LIMIT 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(version)
+    }
+
     /// Add a new repository
     pub fn add_repository(&self, path: &Path, name: Option<String>) -> Result<Repository> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let canonical = path.canonicalize()?;
         let name = name.unwrap_or_else(|| {
@@ -290,6 +584,49 @@ impl Database {
             remote_branch: None,
             last_synced_at: None,
             vault_type,
+            last_indexed_commit: None,
+        })
+    }
+
+    /// Add a virtual repository for content indexed from stdin.
+    /// Its path is the placeholder `stdin:<name>`.
+    pub fn add_stdin_repository(&self, name: &str) -> Result<Repository> {
+        let conn = self.lock()?;
+
+        let path = PathBuf::from(format!("stdin:{name}"));
+        let now = Utc::now();
+        let vault_type = VaultType::Generic;
+
+        conn.execute(
+            "INSERT INTO repositories (path, name, created_at, status, source_type, vault_type)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                path.to_string_lossy(),
+                name,
+                now.to_rfc3339(),
+                RepoStatus::Pending.as_str(),
+                SourceType::Stdin.as_str(),
+                vault_type.as_str(),
+            ],
+        )?;
+
+        let id = conn.last_insert_rowid();
+
+        Ok(Repository {
+            id,
+            path,
+            name: name.to_string(),
+            created_at: now,
+            last_indexed_at: None,
+            file_count: 0,
+            total_size_bytes: 0,
+            status: RepoStatus::Pending,
+            source_type: SourceType::Stdin,
+            remote_url: None,
+            remote_branch: None,
+            last_synced_at: None,
+            vault_type,
+            last_indexed_commit: None,
         })
     }
 
@@ -301,10 +638,7 @@ impl Database {
         remote_url: &str,
         branch: Option<&str>,
     ) -> Result<Repository> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let now = Utc::now();
         // For remote repos, we detect vault type after clone completes
@@ -342,20 +676,19 @@ impl Database {
             remote_branch: branch.map(String::from),
             last_synced_at: None,
             vault_type,
+            last_indexed_commit: None,
         })
     }
 
     /// Get repository by path
     pub fn get_repository_by_path(&self, path: &Path) -> Result<Option<Repository>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
 
         let mut stmt = conn.prepare(
             "SELECT id, path, name, created_at, last_indexed_at, file_count, total_size_bytes, status,
-                    source_type, remote_url, remote_branch, last_synced_at, vault_type
+                    source_type, remote_url, remote_branch, last_synced_at, vault_type,
+                    last_indexed_commit
              FROM repositories WHERE path = ?1"
         )?;
 
@@ -385,6 +718,7 @@ impl Database {
                 vault_type: VaultType::from_str(
                     &row.get::<_, Option<String>>(12)?.unwrap_or_default(),
                 ),
+                last_indexed_commit: row.get(13)?,
             })
         });
 
@@ -397,14 +731,12 @@ impl Database {
 
     /// Get all repositories
     pub fn list_repositories(&self) -> Result<Vec<Repository>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let mut stmt = conn.prepare(
             "SELECT id, path, name, created_at, last_indexed_at, file_count, total_size_bytes, status,
-                    source_type, remote_url, remote_branch, last_synced_at, vault_type
+                    source_type, remote_url, remote_branch, last_synced_at, vault_type,
+                    last_indexed_commit
              FROM repositories ORDER BY name"
         )?;
 
@@ -435,6 +767,7 @@ impl Database {
                     vault_type: VaultType::from_str(
                         &row.get::<_, Option<String>>(12)?.unwrap_or_default(),
                     ),
+                    last_indexed_commit: row.get(13)?,
                 })
             })?
             .filter_map(std::result::Result::ok)
@@ -443,16 +776,19 @@ impl Database {
         Ok(repos)
     }
 
+    /// Resolve a repository given by name or path, see [`RepoResolution::among`]
+    pub fn resolve_repository(&self, query: &str) -> Result<RepoResolution> {
+        Ok(RepoResolution::among(&self.list_repositories()?, query))
+    }
+
     /// Get remote repositories that need syncing
     pub fn get_remote_repositories(&self) -> Result<Vec<Repository>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let mut stmt = conn.prepare(
             "SELECT id, path, name, created_at, last_indexed_at, file_count, total_size_bytes, status,
-                    source_type, remote_url, remote_branch, last_synced_at, vault_type
+                    source_type, remote_url, remote_branch, last_synced_at, vault_type,
+                    last_indexed_commit
              FROM repositories WHERE source_type = 'remote' ORDER BY name"
         )?;
 
@@ -481,6 +817,7 @@ impl Database {
                     vault_type: VaultType::from_str(
                         &row.get::<_, Option<String>>(12)?.unwrap_or_default(),
                     ),
+                    last_indexed_commit: row.get(13)?,
                 })
             })?
             .filter_map(std::result::Result::ok)
@@ -491,10 +828,7 @@ impl Database {
 
     /// Update last synced time for a repository
     pub fn update_repository_synced(&self, repo_id: i64) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         let now = Utc::now();
 
         conn.execute(
@@ -504,17 +838,25 @@ impl Database {
         Ok(())
     }
 
+    /// Record the commit a remote repository's index reflects
+    pub fn update_repository_commit(&self, repo_id: i64, commit: Option<&str>) -> Result<()> {
+        let conn = self.lock()?;
+        conn.execute(
+            "UPDATE repositories SET last_indexed_commit = ?1 WHERE id = ?2",
+            params![commit, repo_id],
+        )?;
+        Ok(())
+    }
+
     /// Get repository by ID
     #[allow(dead_code)]
     pub fn get_repository_by_id(&self, repo_id: i64) -> Result<Option<Repository>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let mut stmt = conn.prepare(
             "SELECT id, path, name, created_at, last_indexed_at, file_count, total_size_bytes, status,
-                    source_type, remote_url, remote_branch, last_synced_at, vault_type
+                    source_type, remote_url, remote_branch, last_synced_at, vault_type,
+                    last_indexed_commit
              FROM repositories WHERE id = ?1"
         )?;
 
@@ -544,6 +886,7 @@ impl Database {
                 vault_type: VaultType::from_str(
                     &row.get::<_, Option<String>>(12)?.unwrap_or_default(),
                 ),
+                last_indexed_commit: row.get(13)?,
             })
         });
 
@@ -554,12 +897,22 @@ impl Database {
         }
     }
 
+    /// Rename a repository. Stdin repositories also get the matching
+    /// `stdin:<name>` placeholder path, so the old name can be reused.
+    pub fn rename_repository(&self, repo_id: i64, new_name: &str) -> Result<()> {
+        let conn = self.lock()?;
+        conn.execute(
+            "UPDATE repositories SET name = ?1,
+                path = CASE WHEN source_type = ?2 THEN 'stdin:' || ?1 ELSE path END
+             WHERE id = ?3",
+            params![new_name, SourceType::Stdin.as_str(), repo_id],
+        )?;
+        Ok(())
+    }
+
     /// Update repository status
     pub fn update_repository_status(&self, repo_id: i64, status: RepoStatus) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         conn.execute(
             "UPDATE repositories SET status = ?1 WHERE id = ?2",
             params![status.as_str(), repo_id],
@@ -574,10 +927,7 @@ impl Database {
         file_count: i64,
         total_size_bytes: i64,
     ) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         let now = Utc::now();
 
         conn.execute(
@@ -597,10 +947,7 @@ impl Database {
     /// Update vault type for a repository (typically after clone completes)
     #[allow(dead_code)]
     pub fn update_repository_vault_type(&self, repo_id: i64, vault_type: VaultType) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         conn.execute(
             "UPDATE repositories SET vault_type = ?1 WHERE id = ?2",
             params![vault_type.as_str(), repo_id],
@@ -610,19 +957,9 @@ impl Database {
 
     /// Delete a repository and all its files
     pub fn delete_repository(&self, repo_id: i64) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
-
-        // Delete FTS content first
-        conn.execute(
-            "DELETE FROM contents WHERE file_id IN (SELECT id FROM files WHERE repo_id = ?1)",
-            params![repo_id],
-        )?;
+        self.clear_repository_files(repo_id)?;
 
-        // Delete files
-        conn.execute("DELETE FROM files WHERE repo_id = ?1", params![repo_id])?;
+        let conn = self.lock()?;
 
         // Delete repository
         conn.execute("DELETE FROM repositories WHERE id = ?1", params![repo_id])?;
@@ -630,6 +967,27 @@ impl Database {
         Ok(())
     }
 
+    /// Delete every indexed file of a repository and the data derived from it
+    /// (FTS content, code blocks, markdown metadata, tags, links, embeddings), keeping the repository row
+    pub fn clear_repository_files(&self, repo_id: i64) -> Result<()> {
+        let conn = self.lock()?;
+
+        // Foreign-key cascades are not enabled, so derived tables are cleared explicitly
+        for sql in [
+            "DELETE FROM contents WHERE file_id IN (SELECT id FROM files WHERE repo_id = ?1)",
+            "DELETE FROM code_blocks WHERE file_id IN (SELECT id FROM files WHERE repo_id = ?1)",
+            "DELETE FROM markdown_meta WHERE file_id IN (SELECT id FROM files WHERE repo_id = ?1)",
+            "DELETE FROM tags WHERE file_id IN (SELECT id FROM files WHERE repo_id = ?1)",
+            "DELETE FROM links WHERE source_file_id IN (SELECT id FROM files WHERE repo_id = ?1)",
+            "DELETE FROM embeddings WHERE file_id IN (SELECT id FROM files WHERE repo_id = ?1)",
+            "DELETE FROM files WHERE repo_id = ?1",
+        ] {
+            conn.execute(sql, params![repo_id])?;
+        }
+
+        Ok(())
+    }
+
     /// Delete repository by path
     /// Delete repository by path
     #[allow(dead_code)]
@@ -642,20 +1000,14 @@ impl Database {
 
     /// Begin a transaction for batch operations
     pub fn begin_batch(&self) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         conn.execute("BEGIN TRANSACTION", [])?;
         Ok(())
     }
 
     /// Commit the current transaction
     pub fn commit_batch(&self) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         conn.execute("COMMIT", [])?;
         Ok(())
     }
@@ -663,10 +1015,7 @@ impl Database {
     /// Rollback the current transaction
     #[allow(dead_code)]
     pub fn rollback_batch(&self) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         conn.execute("ROLLBACK", [])?;
         Ok(())
     }
@@ -683,41 +1032,33 @@ impl Database {
         file_type: &str,
         content: &str,
     ) -> Result<i64> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
-        conn.execute(
+        conn.prepare_cached(
             "INSERT OR REPLACE INTO files (repo_id, relative_path, content_hash, file_size_bytes, last_modified_at, file_type)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
+        )?
+        .execute(params![
                 repo_id,
                 relative_path.to_string_lossy(),
                 content_hash,
                 file_size_bytes,
                 last_modified.to_rfc3339(),
                 file_type,
-            ],
-        )?;
+            ])?;
 
         let file_id = conn.last_insert_rowid();
 
         // Insert into FTS table
-        conn.execute(
-            "INSERT INTO contents (file_id, content) VALUES (?1, ?2)",
-            params![file_id, content],
-        )?;
+        conn.prepare_cached("INSERT INTO contents (rowid, file_id, content) VALUES (?1, ?1, ?2)")?
+            .execute(params![file_id, content])?;
 
         Ok(file_id)
     }
 
     /// Get existing files for a repository (for incremental updates)
     pub fn get_repository_files(&self, repo_id: i64) -> Result<Vec<FileRecord>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let mut stmt = conn.prepare(
             "SELECT id, repo_id, relative_path, content_hash, file_size_bytes, last_modified_at, file_type
@@ -749,10 +1090,7 @@ impl Database {
             return Ok(());
         }
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let placeholders: Vec<String> = file_ids.iter().map(|_| "?".to_string()).collect();
         let placeholders_str = placeholders.join(",");
@@ -762,6 +1100,10 @@ impl Database {
             &format!("DELETE FROM contents WHERE file_id IN ({placeholders_str})"),
             rusqlite::params_from_iter(file_ids),
         )?;
+        conn.execute(
+            &format!("DELETE FROM code_blocks WHERE file_id IN ({placeholders_str})"),
+            rusqlite::params_from_iter(file_ids),
+        )?;
 
         // Delete from files
         conn.execute(
@@ -776,46 +1118,187 @@ impl Database {
     pub fn search(
         &self,
         query: &str,
-        repo_filter: Option<&str>,
-        file_type_filter: Option<&str>,
+        filters: &SearchFilters,
         limit: usize,
         offset: usize,
     ) -> Result<Vec<SearchResult>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         // Build query with optional filters
-        let mut sql = String::from(
+        let mut sql = format!(
             "SELECT r.name, r.path, f.relative_path, f.file_type,
-                    snippet(contents, 1, '>>>', '<<<', '...', 64) as snippet,
-                    bm25(contents) as score
+                    snippet(contents, 1, '{HIGHLIGHT_START}', '{HIGHLIGHT_END}', '...', 64) as snippet,
+                    bm25(contents, 0.0, 1.0, 10.0, 5.0) as score, mm.title
              FROM contents c
              JOIN files f ON c.file_id = f.id
              JOIN repositories r ON f.repo_id = r.id
+             LEFT JOIN markdown_meta mm ON mm.file_id = f.id
              WHERE contents MATCH ?1",
         );
 
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(query.to_string())];
 
-        if let Some(repo) = repo_filter {
-            sql.push_str(" AND r.name LIKE ?");
-            params_vec.push(Box::new(format!("%{repo}%")));
-        }
-
-        if let Some(file_type) = file_type_filter {
-            sql.push_str(" AND f.file_type = ?");
-            params_vec.push(Box::new(file_type.to_string()));
-        }
+        push_filters(&mut sql, &mut params_vec, filters);
 
         sql.push_str(" ORDER BY score LIMIT ? OFFSET ?");
+        params_vec.push(Box::new(sql_limit(limit)));
         #[allow(clippy::cast_possible_wrap)]
-        params_vec.push(Box::new(limit as i64));
+        params_vec.push(Box::new(offset as i64));
+
+        // Only a few filter combinations exist, so each SQL shape is cached separately
+        let mut stmt = conn.prepare_cached(&sql)?;
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params_vec.iter().map(std::convert::AsRef::as_ref).collect();
+
+        let results = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                let repo_path = PathBuf::from(row.get::<_, String>(1)?);
+                let relative_path = PathBuf::from(row.get::<_, String>(2)?);
+                let absolute_path = repo_path.join(&relative_path);
+
+                Ok(SearchResult {
+                    repo_name: row.get(0)?,
+                    repo_path,
+                    file_path: relative_path,
+                    absolute_path,
+                    snippet: row.get(4)?,
+                    file_type: row.get(3)?,
+                    score: row.get(5)?,
+                    title: row.get(6)?,
+                })
+            })?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Find files containing `needle` as a literal substring (ASCII case-insensitive).
+    ///
+    /// Runs `LIKE` against the stored content, so matches inside words are found
+    /// without reading files from disk. With the `trigram` tokenizer, FTS5 answers
+    /// the `LIKE` from its index for needles of three or more characters; other
+    /// tokenizers scan the content table. Results are ordered by number of
+    /// occurrences, which is reported as the score.
+    pub fn substring_search(
+        &self,
+        needle: &str,
+        filters: &SearchFilters,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        if needle.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.lock()?;
+
+        // ESCAPE keeps FTS5 from using the trigram index, so only add it when needed
+        let needs_escape = needle.contains(['%', '_', '\\']);
+        let pattern = if needs_escape {
+            escape_like(needle)
+        } else {
+            needle.to_string()
+        };
+
+        let mut sql = format!(
+            "SELECT r.name, r.path, f.relative_path, f.file_type, c.content,
+                    (length(c.content) - length(replace(lower(c.content), lower(?2), '')))
+                        / length(?2) AS occurrences,
+                    mm.title
+             FROM contents c
+             JOIN files f ON c.file_id = f.id
+             JOIN repositories r ON f.repo_id = r.id
+             LEFT JOIN markdown_meta mm ON mm.file_id = f.id
+             WHERE c.content LIKE '%' || ?1 || '%'{}",
+            if needs_escape { " ESCAPE '\\'" } else { "" }
+        );
+
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> =
+            vec![Box::new(pattern), Box::new(needle.to_string())];
+
+        push_filters(&mut sql, &mut params_vec, filters);
+
+        sql.push_str(" ORDER BY occurrences DESC, r.name, f.relative_path LIMIT ?");
+        params_vec.push(Box::new(sql_limit(limit)));
+
+        let mut stmt = conn.prepare_cached(&sql)?;
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params_vec.iter().map(std::convert::AsRef::as_ref).collect();
+
+        let results = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                let repo_path = PathBuf::from(row.get::<_, String>(1)?);
+                let relative_path = PathBuf::from(row.get::<_, String>(2)?);
+                let absolute_path = repo_path.join(&relative_path);
+                let content: String = row.get(4)?;
+
+                Ok(SearchResult {
+                    repo_name: row.get(0)?,
+                    repo_path,
+                    file_path: relative_path,
+                    absolute_path,
+                    snippet: highlight_substring(&content, needle, 200).unwrap_or_default(),
+                    file_type: row.get(3)?,
+                    score: row.get(5)?,
+                    title: row.get(6)?,
+                })
+            })?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Search fenced code blocks using FTS5, returning the best-matching block per file
+    pub fn search_code_blocks(
+        &self,
+        query: &str,
+        language: Option<&str>,
+        filters: &SearchFilters,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let conn = self.lock()?;
+
+        let language_clause = if language.is_some() {
+            " AND language = lower(?2)"
+        } else {
+            " AND ?2 IS NULL"
+        };
+        let mut sql = format!(
+            "SELECT r.name, r.path, f.relative_path, f.file_type, m.snippet, m.score, mm.title
+             FROM (
+                 SELECT file_id, snippet, score,
+                        ROW_NUMBER() OVER (PARTITION BY file_id ORDER BY score) AS block_rank
+                 FROM (
+                     SELECT file_id,
+                            snippet(code_blocks, 2, '{HIGHLIGHT_START}', '{HIGHLIGHT_END}', '...', 64) AS snippet,
+                            bm25(code_blocks) AS score
+                     FROM code_blocks
+                     WHERE code_blocks MATCH ?1{language_clause}
+                 )
+             ) m
+             JOIN files f ON m.file_id = f.id
+             JOIN repositories r ON f.repo_id = r.id
+             LEFT JOIN markdown_meta mm ON mm.file_id = f.id
+             WHERE m.block_rank = 1",
+        );
+
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![
+            Box::new(query.to_string()),
+            Box::new(language.map(str::to_string)),
+        ];
+
+        push_filters(&mut sql, &mut params_vec, filters);
+
+        sql.push_str(" ORDER BY m.score LIMIT ? OFFSET ?");
+        params_vec.push(Box::new(sql_limit(limit)));
         #[allow(clippy::cast_possible_wrap)]
         params_vec.push(Box::new(offset as i64));
 
-        let mut stmt = conn.prepare(&sql)?;
+        let mut stmt = conn.prepare_cached(&sql)?;
 
         let params_refs: Vec<&dyn rusqlite::ToSql> =
             params_vec.iter().map(std::convert::AsRef::as_ref).collect();
@@ -834,6 +1317,7 @@ impl Database {
                     snippet: row.get(4)?,
                     file_type: row.get(3)?,
                     score: row.get(5)?,
+                    title: row.get(6)?,
                 })
             })?
             .filter_map(std::result::Result::ok)
@@ -844,16 +1328,8 @@ impl Database {
 
     /// Count total search results
     #[allow(dead_code)]
-    pub fn search_count(
-        &self,
-        query: &str,
-        repo_filter: Option<&str>,
-        file_type_filter: Option<&str>,
-    ) -> Result<i64> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+    pub fn search_count(&self, query: &str, filters: &SearchFilters) -> Result<i64> {
+        let conn = self.lock()?;
 
         let mut sql = String::from(
             "SELECT COUNT(*) FROM contents c
@@ -864,15 +1340,7 @@ impl Database {
 
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(query.to_string())];
 
-        if let Some(repo) = repo_filter {
-            sql.push_str(" AND r.name LIKE ?");
-            params_vec.push(Box::new(format!("%{repo}%")));
-        }
-
-        if let Some(file_type) = file_type_filter {
-            sql.push_str(" AND f.file_type = ?");
-            params_vec.push(Box::new(file_type.to_string()));
-        }
+        push_filters(&mut sql, &mut params_vec, filters);
 
         let params_refs: Vec<&dyn rusqlite::ToSql> =
             params_vec.iter().map(std::convert::AsRef::as_ref).collect();
@@ -894,31 +1362,58 @@ impl Database {
         links_json: &str,
         headings_json: &str,
     ) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
-        conn.execute(
+        conn.prepare_cached(
             "INSERT OR REPLACE INTO markdown_meta (file_id, title, tags, links, headings)
              VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![file_id, title, tags_json, links_json, headings_json],
-        )?;
+        )?
+        .execute(params![
+            file_id,
+            title,
+            tags_json,
+            links_json,
+            headings_json
+        ])?;
+
+        // Copy title and heading text into their weighted FTS columns
+        conn.prepare_cached(
+            "UPDATE contents SET title = COALESCE(?2, ''),
+                 headings = COALESCE((SELECT group_concat(substr(value, instr(value, ':') + 1), ' ')
+                                      FROM json_each(?3)), '')
+             WHERE rowid = ?1",
+        )?
+        .execute(params![file_id, title, headings_json])?;
 
         Ok(())
     }
 
-    /// Delete markdown metadata for specific files
-    #[allow(dead_code)]
-    pub fn delete_markdown_meta(&self, file_ids: &[i64]) -> Result<()> {
-        if file_ids.is_empty() {
+    /// Store fenced code blocks for a file (replaces existing blocks).
+    /// Each block is a tuple of (language tag, code content).
+    pub fn store_code_blocks(&self, file_id: i64, blocks: &[(Option<&str>, &str)]) -> Result<()> {
+        let conn = self.lock()?;
+
+        conn.prepare_cached("DELETE FROM code_blocks WHERE file_id = ?1")?
+            .execute([file_id])?;
+
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO code_blocks (file_id, language, content) VALUES (?1, lower(?2), ?3)",
+        )?;
+        for (language, content) in blocks {
+            stmt.execute(params![file_id, language, content])?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete markdown metadata for specific files
+    #[allow(dead_code)]
+    pub fn delete_markdown_meta(&self, file_ids: &[i64]) -> Result<()> {
+        if file_ids.is_empty() {
             return Ok(());
         }
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let placeholders: Vec<String> = file_ids.iter().map(|_| "?".to_string()).collect();
         let placeholders_str = placeholders.join(",");
@@ -941,18 +1436,13 @@ impl Database {
         file_id: i64,
         embeddings: &[(usize, usize, usize, &str, &[f32])], // (chunk_index, start, end, text, embedding)
     ) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         // Delete existing embeddings for this file
-        conn.execute(
-            "DELETE FROM embeddings WHERE file_id = ?1",
-            params![file_id],
-        )?;
+        conn.prepare_cached("DELETE FROM embeddings WHERE file_id = ?1")?
+            .execute(params![file_id])?;
 
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             "INSERT INTO embeddings (file_id, chunk_index, start_offset, end_offset, chunk_text, embedding)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
         )?;
@@ -976,16 +1466,12 @@ impl Database {
     }
 
     /// Delete embeddings for specific files
-    #[allow(dead_code)]
     pub fn delete_embeddings(&self, file_ids: &[i64]) -> Result<()> {
         if file_ids.is_empty() {
             return Ok(());
         }
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let placeholders: Vec<String> = file_ids.iter().map(|_| "?".to_string()).collect();
         let placeholders_str = placeholders.join(",");
@@ -998,45 +1484,156 @@ impl Database {
         Ok(())
     }
 
-    /// Search by vector similarity
+    /// Mean of a file's chunk embeddings, or `None` if it has none
+    #[allow(clippy::cast_precision_loss)]
+    pub fn file_embedding(
+        &self,
+        repo_path: &Path,
+        relative_path: &Path,
+    ) -> Result<Option<Vec<f32>>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT e.embedding
+             FROM embeddings e
+             JOIN files f ON e.file_id = f.id
+             JOIN repositories r ON f.repo_id = r.id
+             WHERE r.path = ?1 AND f.relative_path = ?2",
+        )?;
+        let vectors: Vec<Vec<f32>> = stmt
+            .query_map(
+                params![repo_path.to_string_lossy(), relative_path.to_string_lossy()],
+                |row| Ok(decode_embedding(&row.get::<_, Vec<u8>>(0)?)),
+            )?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        let Some(first) = vectors.first() else {
+            return Ok(None);
+        };
+        // Chunks of another model's dimension (a half-finished rebuild) are ignored
+        let dimension = first.len();
+        let matching: Vec<&Vec<f32>> = vectors.iter().filter(|v| v.len() == dimension).collect();
+        let mut mean = vec![0.0_f32; dimension];
+        for vector in &matching {
+            for (sum, x) in mean.iter_mut().zip(vector.iter()) {
+                *sum += x;
+            }
+        }
+        let count = matching.len() as f32;
+        for x in &mut mean {
+            *x /= count;
+        }
+        Ok(Some(mean))
+    }
+
+    /// Files that have no stored embeddings, optionally only in repositories
+    /// whose name contains `repo_filter`
+    pub fn files_without_embeddings(&self, repo_filter: Option<&str>) -> Result<Vec<FileRecord>> {
+        let conn = self.lock()?;
+
+        let mut sql = String::from(
+            "SELECT f.id, f.repo_id, f.relative_path, f.content_hash, f.file_size_bytes,
+                    f.last_modified_at, f.file_type
+             FROM files f
+             JOIN repositories r ON f.repo_id = r.id
+             LEFT JOIN embeddings e ON e.file_id = f.id
+             WHERE e.file_id IS NULL",
+        );
+        let mut params_vec: Vec<String> = Vec::new();
+        if let Some(repo) = repo_filter {
+            sql.push_str(" AND r.name LIKE ?");
+            params_vec.push(format!("%{repo}%"));
+        }
+        sql.push_str(" ORDER BY f.repo_id, f.relative_path");
+
+        let mut stmt = conn.prepare(&sql)?;
+        let files = stmt
+            .query_map(rusqlite::params_from_iter(params_vec), |row| {
+                Ok(FileRecord {
+                    id: row.get(0)?,
+                    repo_id: row.get(1)?,
+                    relative_path: PathBuf::from(row.get::<_, String>(2)?),
+                    content_hash: row.get(3)?,
+                    file_size_bytes: row.get(4)?,
+                    last_modified_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                        .map_or_else(|_| Utc::now(), |dt| dt.with_timezone(&Utc)),
+                    file_type: row.get(6)?,
+                })
+            })?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        Ok(files)
+    }
+
+    /// File counts per repository and file type, ordered by repository name,
+    /// optionally for a single repository
+    pub fn embedding_coverage(
+        &self,
+        repo_filter: Option<&str>,
+    ) -> Result<Vec<EmbeddingCoverageRow>> {
+        let conn = self.lock()?;
+
+        let mut sql = String::from(
+            "SELECT r.id, r.name, f.file_type, COUNT(*),
+                    SUM(EXISTS (SELECT 1 FROM embeddings e WHERE e.file_id = f.id))
+             FROM files f
+             JOIN repositories r ON f.repo_id = r.id",
+        );
+        if repo_filter.is_some() {
+            sql.push_str(" WHERE r.name = ?1");
+        }
+        sql.push_str(" GROUP BY r.id, f.file_type ORDER BY r.name, r.id");
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(repo_filter), |row| {
+                Ok(EmbeddingCoverageRow {
+                    repo_id: row.get(0)?,
+                    repo: row.get(1)?,
+                    file_type: row.get(2)?,
+                    files: usize::try_from(row.get::<_, i64>(3)?).unwrap_or(0),
+                    files_with_embeddings: usize::try_from(row.get::<_, i64>(4)?).unwrap_or(0),
+                })
+            })?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        Ok(rows)
+    }
+
+    /// Search by vector similarity. Stops scanning candidates once `deadline`
+    /// passes and ranks the ones seen so far; the flag reports whether it did.
+    #[allow(clippy::too_many_lines)]
     pub fn vector_search(
         &self,
         query_embedding: &[f32],
-        repo_filter: Option<&str>,
-        file_type_filter: Option<&str>,
+        filters: &SearchFilters,
         limit: usize,
-    ) -> Result<Vec<VectorSearchResult>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        deadline: Option<Instant>,
+    ) -> Result<(Vec<VectorSearchResult>, bool)> {
+        let conn = self.lock()?;
 
         // Build query with optional filters
         let mut sql = String::from(
             "SELECT r.name, r.path, f.relative_path, f.file_type,
-                    e.chunk_text, e.embedding, e.start_offset, e.end_offset
+                    e.chunk_text, e.embedding, e.start_offset, e.end_offset, mm.title
              FROM embeddings e
              JOIN files f ON e.file_id = f.id
              JOIN repositories r ON f.repo_id = r.id
+             LEFT JOIN markdown_meta mm ON mm.file_id = f.id
              WHERE 1=1",
         );
 
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
-        if let Some(repo) = repo_filter {
-            sql.push_str(" AND r.name LIKE ?");
-            params_vec.push(Box::new(format!("%{repo}%")));
-        }
-
-        if let Some(file_type) = file_type_filter {
-            sql.push_str(" AND f.file_type = ?");
-            params_vec.push(Box::new(file_type.to_string()));
-        }
+        push_filters(&mut sql, &mut params_vec, filters);
 
         let params_refs: Vec<&dyn rusqlite::ToSql> =
             params_vec.iter().map(std::convert::AsRef::as_ref).collect();
 
-        let mut stmt = conn.prepare(&sql)?;
+        // Only a few filter combinations exist, so each SQL shape is cached separately
+        let mut stmt = conn.prepare_cached(&sql)?;
 
         let rows = stmt.query_map(params_refs.as_slice(), |row| {
             let repo_name: String = row.get(0)?;
@@ -1047,6 +1644,7 @@ impl Database {
             let embedding_bytes: Vec<u8> = row.get(5)?;
             let start_offset: i64 = row.get(6)?;
             let end_offset: i64 = row.get(7)?;
+            let title: Option<String> = row.get(8)?;
 
             Ok((
                 repo_name,
@@ -1057,13 +1655,19 @@ impl Database {
                 embedding_bytes,
                 start_offset,
                 end_offset,
+                title,
             ))
         })?;
 
         // Calculate similarities and collect results
         let mut results: Vec<VectorSearchResult> = Vec::new();
+        let mut timed_out = false;
 
         for row_result in rows {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                timed_out = true;
+                break;
+            }
             let (
                 repo_name,
                 repo_path,
@@ -1073,19 +1677,10 @@ impl Database {
                 embedding_bytes,
                 start_offset,
                 end_offset,
+                title,
             ) = row_result?;
 
-            // Deserialize embedding from bytes
-            let doc_embedding: Vec<f32> = embedding_bytes
-                .chunks(4)
-                .filter_map(|chunk| {
-                    if chunk.len() == 4 {
-                        Some(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+            let doc_embedding = decode_embedding(&embedding_bytes);
 
             // Calculate cosine similarity
             let similarity = Self::cosine_sim(query_embedding, &doc_embedding);
@@ -1106,6 +1701,7 @@ impl Database {
                 start_offset: start_offset as usize,
                 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
                 end_offset: end_offset as usize,
+                title,
             });
         }
 
@@ -1117,7 +1713,7 @@ impl Database {
         });
         results.truncate(limit);
 
-        Ok(results)
+        Ok((results, timed_out))
     }
 
     /// Calculate cosine similarity between two vectors
@@ -1137,23 +1733,56 @@ impl Database {
         }
     }
 
+    /// Stream stored embeddings (optionally of repositories whose name
+    /// contains `repo_filter`) to `f` in file and chunk order, one row at a
+    /// time, so exports never hold all vectors in memory. The database stays
+    /// locked until the iteration finishes; an error from `f` stops it.
+    /// Returns the number of rows visited.
+    pub fn iter_embeddings(
+        &self,
+        repo_filter: Option<&str>,
+        mut f: impl FnMut(EmbeddingRow) -> Result<()>,
+    ) -> Result<usize> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT r.name, f.relative_path, e.chunk_index, e.start_offset, e.end_offset,
+                    e.chunk_text, e.embedding
+             FROM embeddings e
+             JOIN files f ON e.file_id = f.id
+             JOIN repositories r ON f.repo_id = r.id
+             WHERE ?1 IS NULL OR r.name LIKE '%' || ?1 || '%'
+             ORDER BY r.name, f.relative_path, e.chunk_index",
+        )?;
+        let mut rows = stmt.query(params![repo_filter])?;
+
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let embedding: Vec<u8> = row.get(6)?;
+            f(EmbeddingRow {
+                repo_name: row.get(0)?,
+                file_path: row.get(1)?,
+                chunk_index: row.get(2)?,
+                start_offset: row.get(3)?,
+                end_offset: row.get(4)?,
+                chunk_text: row.get(5)?,
+                embedding: decode_embedding(&embedding),
+            })?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
     /// Check if embeddings are enabled (table exists and has data)
     #[allow(dead_code)]
     pub fn has_embeddings(&self) -> Result<bool> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM embeddings", [], |row| row.get(0))?;
         Ok(count > 0)
     }
 
     /// Get all unique tags with counts
     pub fn get_all_tags(&self) -> Result<Vec<(String, usize)>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let mut stmt = conn
             .prepare("SELECT tag, COUNT(*) as count FROM tags GROUP BY tag ORDER BY count DESC")?;
@@ -1169,16 +1798,71 @@ impl Database {
         Ok(tags)
     }
 
+    /// Get the unique tags of one repository's files with counts
+    pub fn get_tags_for_repo(&self, repo_id: i64) -> Result<Vec<(String, usize)>> {
+        let conn = self.lock()?;
+
+        let mut stmt = conn.prepare(
+            r"
+            SELECT t.tag, COUNT(*) as count
+            FROM tags t
+            JOIN files f ON t.file_id = f.id
+            WHERE f.repo_id = ?1
+            GROUP BY t.tag
+            ORDER BY count DESC
+            ",
+        )?;
+
+        let tags = stmt
+            .query_map([repo_id], |row| {
+                let tag: String = row.get(0)?;
+                let count: i64 = row.get(1)?;
+                Ok((tag, usize::try_from(count).unwrap_or(0)))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
+    /// Tags that appear on the same files as `tag`, with the number of shared files,
+    /// most frequent first. Optionally limited to one repository.
+    pub fn get_co_occurring_tags(
+        &self,
+        tag: &str,
+        repo_id: Option<i64>,
+    ) -> Result<Vec<(String, usize)>> {
+        let conn = self.lock()?;
+
+        let mut stmt = conn.prepare(
+            r"
+            SELECT other.tag, COUNT(DISTINCT other.file_id) as count
+            FROM tags t
+            JOIN tags other ON other.file_id = t.file_id AND other.tag != t.tag
+            JOIN files f ON t.file_id = f.id
+            WHERE t.tag = ?1 AND (?2 IS NULL OR f.repo_id = ?2)
+            GROUP BY other.tag
+            ORDER BY count DESC, other.tag
+            ",
+        )?;
+
+        let tags = stmt
+            .query_map(params![tag, repo_id], |row| {
+                let tag: String = row.get(0)?;
+                let count: i64 = row.get(1)?;
+                Ok((tag, usize::try_from(count).unwrap_or(0)))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
     /// Get backlinks to a file (files that link to the given target)
     #[allow(clippy::type_complexity)]
     pub fn get_backlinks(
         &self,
         target_name: &str,
     ) -> Result<Vec<(String, String, String, Option<usize>)>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let mut stmt = conn.prepare(
             r"
@@ -1214,20 +1898,16 @@ impl Database {
 
     /// Add tags for a file (replaces existing tags)
     pub fn add_tags(&self, file_id: i64, tags: &[String]) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         // First delete existing tags for this file
-        conn.execute("DELETE FROM tags WHERE file_id = ?1", [file_id])?;
+        conn.prepare_cached("DELETE FROM tags WHERE file_id = ?1")?
+            .execute([file_id])?;
 
         // Insert new tags
+        let mut stmt = conn.prepare_cached("INSERT INTO tags (file_id, tag) VALUES (?1, ?2)")?;
         for tag in tags {
-            conn.execute(
-                "INSERT INTO tags (file_id, tag) VALUES (?1, ?2)",
-                rusqlite::params![file_id, tag],
-            )?;
+            stmt.execute(rusqlite::params![file_id, tag])?;
         }
 
         Ok(())
@@ -1236,25 +1916,23 @@ impl Database {
     /// Add links for a file (replaces existing links).
     /// Each link is a tuple of (target name, optional line number).
     pub fn add_links(&self, file_id: i64, links: &[(String, Option<usize>)]) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         // First delete existing links for this file
-        conn.execute("DELETE FROM links WHERE source_file_id = ?1", [file_id])?;
+        conn.prepare_cached("DELETE FROM links WHERE source_file_id = ?1")?
+            .execute([file_id])?;
 
         // Insert new links
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO links (source_file_id, target_name, link_text, line_number) VALUES (?1, ?2, ?3, ?4)",
+        )?;
         for (target_name, line_number) in links {
-            conn.execute(
-                "INSERT INTO links (source_file_id, target_name, link_text, line_number) VALUES (?1, ?2, ?3, ?4)",
-                rusqlite::params![
-                    file_id,
-                    target_name,
-                    target_name, // link_text is same as target for now
-                    line_number.map(|n| i64::try_from(n).unwrap_or(0))
-                ],
-            )?;
+            stmt.execute(rusqlite::params![
+                file_id,
+                target_name,
+                target_name, // link_text is same as target for now
+                line_number.map(|n| i64::try_from(n).unwrap_or(0))
+            ])?;
         }
 
         Ok(())
@@ -1262,10 +1940,7 @@ impl Database {
 
     /// Get knowledge statistics
     pub fn get_stats(&self) -> Result<KnowledgeStats> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let total_files: i64 =
             conn.query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))?;
@@ -1293,9 +1968,18 @@ impl Database {
             )
             .unwrap_or(0);
 
+        let mut stmt = conn.prepare(
+            "SELECT name, last_indexed_commit FROM repositories
+             WHERE source_type = 'remote' ORDER BY name",
+        )?;
+        let remote_commits: Vec<(String, Option<String>)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
         // Database size
         let db_path = Config::database_path()?;
-        let db_size = std::fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0);
+        let db_size = std::fs::metadata(&db_path).map_or(0, |m| m.len());
 
         Ok(KnowledgeStats {
             total_files: usize::try_from(total_files).unwrap_or(0),
@@ -1305,16 +1989,14 @@ impl Database {
             total_links: usize::try_from(total_links).unwrap_or(0),
             files_with_embeddings: usize::try_from(total_embeddings).unwrap_or(0),
             database_size_bytes: db_size,
+            remote_commits,
         })
     }
 
     /// Get all links for graph visualization.
     /// Returns vector of `GraphLink` structs.
     pub fn get_all_links(&self, repo_filter: Option<&str>) -> Result<Vec<GraphLink>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let query = if repo_filter.is_some() {
             r"
@@ -1364,10 +2046,7 @@ impl Database {
 
     /// Get all indexed file paths for health checks
     pub fn get_all_file_paths(&self) -> Result<Vec<(String, String)>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let mut stmt = conn.prepare(
             r"
@@ -1386,12 +2065,115 @@ impl Database {
         Ok(paths)
     }
 
+    /// Content of a file as stored in the full-text index at its last indexing
+    pub fn get_stored_content(
+        &self,
+        repo_name: &str,
+        relative_path: &Path,
+    ) -> Result<Option<String>> {
+        let conn = self.lock()?;
+
+        let content = conn
+            .prepare_cached(
+                "SELECT c.content
+                 FROM contents c
+                 JOIN files f ON c.rowid = f.id
+                 JOIN repositories r ON f.repo_id = r.id
+                 WHERE r.name = ?1 AND f.relative_path = ?2",
+            )?
+            .query_row(params![repo_name, relative_path.to_string_lossy()], |row| {
+                row.get(0)
+            });
+        match content {
+            Ok(content) => Ok(Some(content)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get stored metadata for the indexed file at an absolute path.
+    /// Returns `None` if no indexed repository contains the file.
+    pub fn get_file_meta(&self, path: &Path) -> Result<Option<FileMeta>> {
+        let conn = self.lock()?;
+
+        // Nested repositories are possible, so the deepest containing one wins
+        let mut stmt = conn.prepare_cached("SELECT id, name, path FROM repositories")?;
+        let repo = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    PathBuf::from(row.get::<_, String>(2)?),
+                ))
+            })?
+            .filter_map(std::result::Result::ok)
+            .filter(|(_, _, repo_path)| path.starts_with(repo_path))
+            .max_by_key(|(_, _, repo_path)| repo_path.components().count());
+        let Some((repo_id, repo_name, repo_path)) = repo else {
+            return Ok(None);
+        };
+        let relative_path = path.strip_prefix(&repo_path).unwrap_or(path).to_path_buf();
+
+        let file = conn
+            .prepare_cached(
+                "SELECT f.id, f.file_type, f.file_size_bytes, f.last_modified_at, m.title, m.headings
+                 FROM files f
+                 LEFT JOIN markdown_meta m ON m.file_id = f.id
+                 WHERE f.repo_id = ?1 AND f.relative_path = ?2",
+            )?
+            .query_row(
+                params![repo_id, relative_path.to_string_lossy()],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                    ))
+                },
+            );
+        let (file_id, file_type, size_bytes, modified, title, headings_json) = match file {
+            Ok(file) => file,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let tags = conn
+            .prepare_cached("SELECT tag FROM tags WHERE file_id = ?1 ORDER BY tag")?
+            .query_map([file_id], |row| row.get(0))?
+            .filter_map(std::result::Result::ok)
+            .collect();
+        let links = conn
+            .prepare_cached(
+                "SELECT DISTINCT target_name FROM links WHERE source_file_id = ?1 ORDER BY target_name",
+            )?
+            .query_map([file_id], |row| row.get(0))?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        Ok(Some(FileMeta {
+            repo_name,
+            absolute_path: path.to_path_buf(),
+            relative_path,
+            file_type: file_type.unwrap_or_default(),
+            size_bytes,
+            last_modified_at: DateTime::parse_from_rfc3339(&modified)
+                .map_or_else(|_| Utc::now(), |dt| dt.with_timezone(&Utc)),
+            title,
+            headings: headings_json
+                .as_deref()
+                .map(parse_headings_json)
+                .unwrap_or_default(),
+            tags,
+            links,
+        }))
+    }
+
     /// Get files with no incoming links (orphans)
     pub fn get_orphan_files(&self, repo_filter: Option<&str>) -> Result<Vec<(String, String)>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| AppError::Other(e.to_string()))?;
+        let conn = self.lock()?;
 
         let query = if repo_filter.is_some() {
             r"
@@ -1436,6 +2218,208 @@ impl Database {
 
         Ok(orphans)
     }
+
+    /// Get the largest indexed files as `(repo, path, bytes)`, biggest first
+    pub fn get_largest_files(
+        &self,
+        limit: usize,
+        repo_filter: Option<&str>,
+    ) -> Result<Vec<(String, String, u64)>> {
+        let conn = self.lock()?;
+
+        let mut stmt = conn.prepare(
+            r"
+            SELECT r.name, f.relative_path, f.file_size_bytes
+            FROM files f
+            JOIN repositories r ON f.repo_id = r.id
+            WHERE ?1 IS NULL OR r.name = ?1
+            ORDER BY f.file_size_bytes DESC, r.name, f.relative_path
+            LIMIT ?2
+            ",
+        )?;
+
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let files = stmt
+            .query_map(params![repo_filter, limit], |row| {
+                let bytes: i64 = row.get(2)?;
+                Ok((row.get(0)?, row.get(1)?, u64::try_from(bytes).unwrap_or(0)))
+            })?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        Ok(files)
+    }
+
+    /// Record one search in the query log (only called with `log_queries` on)
+    pub fn log_search(&self, query: &str, mode: &str, result_count: usize) -> Result<()> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT INTO search_log (query, mode, result_count, searched_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                query,
+                mode,
+                i64::try_from(result_count).unwrap_or(i64::MAX),
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Most frequent logged queries as `(query, searches)`, most searched first.
+    /// With `zero_results`, only searches that returned nothing are counted.
+    pub fn get_top_queries(
+        &self,
+        limit: usize,
+        zero_results: bool,
+    ) -> Result<Vec<(String, usize)>> {
+        let conn = self.lock()?;
+
+        let mut stmt = conn.prepare(
+            r"
+            SELECT query, COUNT(*) AS searches
+            FROM search_log
+            WHERE ?1 = 0 OR result_count = 0
+            GROUP BY query
+            ORDER BY searches DESC, MAX(searched_at) DESC, query
+            LIMIT ?2
+            ",
+        )?;
+
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let queries = stmt
+            .query_map(params![zero_results, limit], |row| {
+                let searches: i64 = row.get(1)?;
+                Ok((row.get(0)?, usize::try_from(searches).unwrap_or(0)))
+            })?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        Ok(queries)
+    }
+
+    /// Get stored headings for every file with markdown metadata
+    pub fn get_file_headings(&self, repo_filter: Option<&str>) -> Result<Vec<FileHeadings>> {
+        let conn = self.lock()?;
+
+        let query = if repo_filter.is_some() {
+            r"
+            SELECT r.name, r.path, f.relative_path, m.headings
+            FROM markdown_meta m
+            JOIN files f ON m.file_id = f.id
+            JOIN repositories r ON f.repo_id = r.id
+            WHERE r.name = ?1 AND m.headings IS NOT NULL
+            ORDER BY f.relative_path
+            "
+        } else {
+            r"
+            SELECT r.name, r.path, f.relative_path, m.headings
+            FROM markdown_meta m
+            JOIN files f ON m.file_id = f.id
+            JOIN repositories r ON f.repo_id = r.id
+            WHERE m.headings IS NOT NULL
+            ORDER BY r.name, f.relative_path
+            "
+        };
+
+        let mut stmt = conn.prepare(query)?;
+        let map_row = |row: &rusqlite::Row| {
+            let repo_path: String = row.get(1)?;
+            let relative_path: String = row.get(2)?;
+            let headings_json: String = row.get(3)?;
+            Ok(FileHeadings {
+                repo_name: row.get(0)?,
+                absolute_path: PathBuf::from(&repo_path).join(&relative_path),
+                relative_path: PathBuf::from(relative_path),
+                headings: parse_headings_json(&headings_json),
+            })
+        };
+
+        let files = if let Some(repo) = repo_filter {
+            stmt.query_map([repo], map_row)?
+                .filter_map(std::result::Result::ok)
+                .collect()
+        } else {
+            stmt.query_map([], map_row)?
+                .filter_map(std::result::Result::ok)
+                .collect()
+        };
+
+        Ok(files)
+    }
+}
+
+/// Escape `LIKE` wildcards (and the `\` escape character) in `s`, for
+/// patterns used with `ESCAPE '\'`
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Decode an embedding stored as little-endian `f32`s
+fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+/// `LIMIT` value for `limit` results; `usize::MAX` (unlimited) becomes -1,
+/// which `SQLite` reads as no limit
+fn sql_limit(limit: usize) -> i64 {
+    i64::try_from(limit).unwrap_or(-1)
+}
+
+/// Decode stored `["h1:Title", "h2:Section"]` headings into (level, text)
+fn parse_headings_json(json: &str) -> Vec<(u8, String)> {
+    serde_json::from_str::<Vec<String>>(json)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|h| {
+            let (level, text) = h.split_once(':')?;
+            let level = level.strip_prefix('h')?.parse().ok()?;
+            Some((level, text.to_string()))
+        })
+        .collect()
+}
+
+/// Files of one type in one repository, and how many of them have embeddings
+#[derive(Debug, Clone)]
+pub struct EmbeddingCoverageRow {
+    pub repo_id: i64,
+    pub repo: String,
+    pub file_type: String,
+    pub files: usize,
+    pub files_with_embeddings: usize,
+}
+
+/// Stored metadata of a single indexed file, without its content
+#[derive(Debug, Clone)]
+pub struct FileMeta {
+    pub repo_name: String,
+    pub relative_path: PathBuf,
+    pub absolute_path: PathBuf,
+    pub file_type: String,
+    pub size_bytes: i64,
+    pub last_modified_at: DateTime<Utc>,
+    /// Title from frontmatter or first H1 (markdown and org-mode only)
+    pub title: Option<String>,
+    /// (level, text) in document order
+    pub headings: Vec<(u8, String)>,
+    pub tags: Vec<String>,
+    /// Wiki-link targets
+    pub links: Vec<String>,
+}
+
+/// Headings stored for a single file
+#[derive(Debug, Clone)]
+pub struct FileHeadings {
+    pub repo_name: String,
+    pub relative_path: PathBuf,
+    pub absolute_path: PathBuf,
+    /// (level, text) in document order
+    pub headings: Vec<(u8, String)>,
 }
 
 /// Link for graph visualization
@@ -1456,6 +2440,22 @@ pub struct KnowledgeStats {
     pub total_links: usize,
     pub files_with_embeddings: usize,
     pub database_size_bytes: u64,
+    /// Remote repositories with the commit their index reflects
+    pub remote_commits: Vec<(String, Option<String>)>,
+}
+
+/// One stored chunk embedding, as streamed by `Database::iter_embeddings`
+#[derive(Debug, Clone)]
+pub struct EmbeddingRow {
+    pub repo_name: String,
+    /// Path relative to the repository root
+    pub file_path: String,
+    pub chunk_index: i64,
+    /// Character offsets of the chunk in the file's content
+    pub start_offset: i64,
+    pub end_offset: i64,
+    pub chunk_text: String,
+    pub embedding: Vec<f32>,
 }
 
 /// Vector search result
@@ -1472,4 +2472,103 @@ pub struct VectorSearchResult {
     pub start_offset: usize,
     #[allow(dead_code)]
     pub end_offset: usize,
+    /// Title from frontmatter or the first heading (markdown and org files)
+    pub title: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether the `--within` clause matches a file at `relative_path` of the
+    /// repository at `repo_path`
+    fn within_matches(within: &Within, repo_path: &str, relative_path: &str) -> bool {
+        let conn = Connection::open_in_memory().unwrap();
+        let (clause, pattern) = within.sql();
+        let sql = format!(
+            "SELECT EXISTS (SELECT 1 FROM (SELECT ?1 AS path) r, \
+             (SELECT ?2 AS relative_path) f WHERE 1{})",
+            clause.replace('?', "?3")
+        );
+        conn.query_row(&sql, params![repo_path, relative_path, pattern], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_migration_8_fills_code_blocks_from_stored_content() {
+        let db = Database::open_in_memory().unwrap();
+        let repo = db.add_stdin_repository("notes").unwrap();
+        db.insert_file(
+            repo.id,
+            Path::new("a.md"),
+            "hash",
+            0,
+            Utc::now(),
+            "markdown",
+            "Prose\n\n```Rust\ntokio::spawn(task);\n```\n",
+        )
+        .unwrap();
+        db.update_repository_indexed(repo.id, 1, 0).unwrap();
+
+        // Roll the database back to version 7, before code blocks were indexed
+        {
+            let conn = db.lock().unwrap();
+            conn.execute_batch("DROP TABLE code_blocks; UPDATE schema_version SET version = 7;")
+                .unwrap();
+            schema::initialize(&conn, DEFAULT_FTS_TOKENIZER).unwrap();
+        }
+
+        let results = db
+            .search_code_blocks("spawn", Some("rust"), &SearchFilters::default(), 10, 0)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_into_exact_rejects_partial_matches() {
+        let db = Database::open_in_memory().unwrap();
+        db.add_stdin_repository("notes").unwrap();
+        db.add_stdin_repository("notebook").unwrap();
+        let exact = |query: &str| db.resolve_repository(query).unwrap().into_exact(query);
+
+        assert_eq!(exact("notes").unwrap().name, "notes");
+        assert_eq!(exact("stdin:notebook").unwrap().name, "notebook");
+        // A unique partial match still resolves for read-only commands only
+        let partial = db.resolve_repository("book").unwrap();
+        assert!(matches!(partial, RepoResolution::UniquePrefix(_)));
+        assert!(exact("book").unwrap_err().to_string().contains("notebook"));
+        assert!(exact("note").is_err());
+        assert!(matches!(exact("todo"), Err(AppError::RepoNotFound(_))));
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("100%_done"), "100\\%\\_done");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+        assert_eq!(escape_like("src/lib"), "src/lib");
+    }
+
+    #[test]
+    fn test_within_sql_matches_subtree_only() {
+        let src = Within::Relative("src".to_string());
+        assert!(within_matches(&src, "/r", "src"));
+        assert!(within_matches(&src, "/r", "src/main.rs"));
+        assert!(!within_matches(&src, "/r", "srcx/main.rs"));
+        assert!(!within_matches(&src, "/r", "lib/src/main.rs"));
+
+        // Wildcards in the prefix match only themselves
+        let percent = Within::Relative("100%".to_string());
+        assert!(within_matches(&percent, "/r", "100%/notes.md"));
+        assert!(!within_matches(&percent, "/r", "1000/notes.md"));
+        let underscore = Within::Relative("a_b".to_string());
+        assert!(within_matches(&underscore, "/r", "a_b/notes.md"));
+        assert!(!within_matches(&underscore, "/r", "axb/notes.md"));
+
+        let absolute = Within::Absolute(PathBuf::from("/r/src/"));
+        assert!(within_matches(&absolute, "/r", "src/main.rs"));
+        assert!(!within_matches(&absolute, "/r", "srcx/main.rs"));
+        assert!(!within_matches(&absolute, "/other", "src/main.rs"));
+    }
 }
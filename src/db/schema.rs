@@ -1,11 +1,12 @@
 use rusqlite::Connection;
 
+use crate::core::parse_markdown;
 use crate::error::Result;
 
-pub const SCHEMA_VERSION: i32 = 5;
+pub const SCHEMA_VERSION: i32 = 9;
 
-/// Initialize database schema
-pub fn initialize(conn: &Connection) -> Result<()> {
+/// Initialize database schema; `fts_tokenizer` only applies to a fresh database
+pub fn initialize(conn: &Connection, fts_tokenizer: &str) -> Result<()> {
     // Check and update schema version
     conn.execute(
         "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER PRIMARY KEY)",
@@ -21,7 +22,7 @@ pub fn initialize(conn: &Connection) -> Result<()> {
     match current_version {
         None => {
             // Fresh database, create all tables
-            create_schema(conn)?;
+            create_schema(conn, fts_tokenizer)?;
             conn.execute(
                 "INSERT INTO schema_version (version) VALUES (?1)",
                 [SCHEMA_VERSION],
@@ -40,7 +41,7 @@ pub fn initialize(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
-fn create_schema(conn: &Connection) -> Result<()> {
+fn create_schema(conn: &Connection, fts_tokenizer: &str) -> Result<()> {
     conn.execute_batch(
         r"
         -- Indexed repositories
@@ -57,7 +58,8 @@ fn create_schema(conn: &Connection) -> Result<()> {
             remote_url TEXT,
             remote_branch TEXT,
             last_synced_at TEXT,
-            vault_type TEXT DEFAULT 'generic'
+            vault_type TEXT DEFAULT 'generic',
+            last_indexed_commit TEXT
         );
 
         -- Individual files
@@ -72,13 +74,6 @@ fn create_schema(conn: &Connection) -> Result<()> {
             UNIQUE(repo_id, relative_path)
         );
 
-        -- Full-text search content
-        CREATE VIRTUAL TABLE IF NOT EXISTS contents USING fts5(
-            file_id UNINDEXED,
-            content,
-            tokenize='porter unicode61'
-        );
-
         -- Markdown metadata (optional)
         CREATE TABLE IF NOT EXISTS markdown_meta (
             file_id INTEGER PRIMARY KEY REFERENCES files(id) ON DELETE CASCADE,
@@ -100,18 +95,167 @@ fn create_schema(conn: &Connection) -> Result<()> {
             UNIQUE(file_id, chunk_index)
         );
 
+        -- Tags for efficient tag queries
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            FOREIGN KEY (file_id) REFERENCES files(id) ON DELETE CASCADE
+        );
+
+        -- Links for backlink discovery
+        CREATE TABLE IF NOT EXISTS links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_file_id INTEGER NOT NULL,
+            target_name TEXT NOT NULL,
+            link_text TEXT NOT NULL,
+            line_number INTEGER,
+            FOREIGN KEY (source_file_id) REFERENCES files(id) ON DELETE CASCADE
+        );
+
         -- Indexes
         CREATE INDEX IF NOT EXISTS idx_files_repo ON files(repo_id);
         CREATE INDEX IF NOT EXISTS idx_files_hash ON files(content_hash);
         CREATE INDEX IF NOT EXISTS idx_files_type ON files(file_type);
         CREATE INDEX IF NOT EXISTS idx_embeddings_file ON embeddings(file_id);
         CREATE INDEX IF NOT EXISTS idx_repos_source_type ON repositories(source_type);
+        CREATE INDEX IF NOT EXISTS idx_tags_tag ON tags(tag);
+        CREATE INDEX IF NOT EXISTS idx_tags_file ON tags(file_id);
+        CREATE INDEX IF NOT EXISTS idx_links_target ON links(target_name);
+        CREATE INDEX IF NOT EXISTS idx_links_source ON links(source_file_id);
+        ",
+    )?;
+
+    create_code_blocks_table(conn, "code_blocks")?;
+    create_search_log_table(conn)?;
+    create_contents_table(conn, "contents", fts_tokenizer)
+}
+
+/// Create the opt-in query log (`log_queries`), one row per search
+fn create_search_log_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r"
+        CREATE TABLE IF NOT EXISTS search_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            query TEXT NOT NULL,
+            mode TEXT NOT NULL,
+            result_count INTEGER NOT NULL,
+            searched_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_search_log_query ON search_log(query);
         ",
     )?;
+    Ok(())
+}
 
+/// Create the full-text search content table (rowid = `file_id`); title and headings
+/// are separate columns so they can be weighted higher in bm25
+fn create_contents_table(conn: &Connection, name: &str, tokenizer: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS {name} USING fts5(
+            file_id UNINDEXED,
+            content,
+            title,
+            headings,
+            tokenize='{tokenizer}'
+        );"
+    ))?;
     Ok(())
 }
 
+/// Create the table of fenced code blocks from markdown, searchable by language
+fn create_code_blocks_table(conn: &Connection, name: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS {name} USING fts5(
+            file_id UNINDEXED,
+            language UNINDEXED,
+            content,
+            tokenize='unicode61'
+        );"
+    ))?;
+    Ok(())
+}
+
+/// Tokenizer the `contents` table was created with, e.g. `porter unicode61`
+pub fn fts_tokenizer(conn: &Connection) -> Result<Option<String>> {
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE name = 'contents'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(sql
+        .split_once("tokenize='")
+        .and_then(|(_, rest)| rest.split_once('\''))
+        .map(|(tokenizer, _)| tokenizer.to_string()))
+}
+
+/// Recreate the `contents` table with another tokenizer, re-tokenizing the stored
+/// content. Returns the number of files carried over.
+pub fn rebuild_contents(conn: &Connection, tokenizer: &str) -> Result<usize> {
+    // Rolled back on drop unless committed
+    let tx = conn.unchecked_transaction()?;
+    create_contents_table(&tx, "contents_rebuild", tokenizer)?;
+    let rows = tx.execute(
+        "INSERT INTO contents_rebuild (rowid, file_id, content, title, headings)
+         SELECT rowid, file_id, content, title, headings FROM contents",
+        [],
+    )?;
+    tx.execute_batch(
+        "DROP TABLE contents;
+         ALTER TABLE contents_rebuild RENAME TO contents;",
+    )?;
+    tx.commit()?;
+    Ok(rows)
+}
+
+/// Recreate the `code_blocks` table from its stored rows. Returns the number
+/// of code blocks carried over.
+pub fn rebuild_code_blocks(conn: &Connection) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    create_code_blocks_table(&tx, "code_blocks_rebuild")?;
+    let rows = tx.execute(
+        "INSERT INTO code_blocks_rebuild (rowid, file_id, language, content)
+         SELECT rowid, file_id, language, content FROM code_blocks",
+        [],
+    )?;
+    tx.execute_batch(
+        "DROP TABLE code_blocks;
+         ALTER TABLE code_blocks_rebuild RENAME TO code_blocks;",
+    )?;
+    tx.commit()?;
+    Ok(rows)
+}
+
+/// Extract the fenced code blocks of indexed markdown files from their stored
+/// content. Files indexed with `strip_markdown_syntax` have no fences left and
+/// get their blocks on the next update that re-indexes them.
+fn fill_code_blocks(conn: &Connection) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut blocks = 0;
+    {
+        let mut select = tx.prepare(
+            "SELECT c.file_id, c.content FROM contents c
+             JOIN files f ON f.id = c.file_id
+             WHERE f.file_type = 'markdown'",
+        )?;
+        let mut insert = tx.prepare(
+            "INSERT INTO code_blocks (file_id, language, content) VALUES (?1, lower(?2), ?3)",
+        )?;
+        let mut rows = select.query([])?;
+        while let Some(row) = rows.next()? {
+            let file_id: i64 = row.get(0)?;
+            let content: String = row.get(1)?;
+            for block in parse_markdown(&content).code_blocks {
+                insert.execute(rusqlite::params![file_id, block.language, block.content])?;
+                blocks += 1;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(blocks)
+}
+
+#[allow(clippy::too_many_lines)]
 fn migrate(conn: &Connection, from_version: i32) -> Result<()> {
     if from_version < 2 {
         // Add embeddings table for version 2
@@ -187,5 +331,53 @@ fn migrate(conn: &Connection, from_version: i32) -> Result<()> {
         )?;
     }
 
+    if from_version < 6 {
+        // Rebuild the FTS table with weighted title/headings columns for version 6
+        conn.execute_batch(
+            r"
+            CREATE VIRTUAL TABLE contents_v6 USING fts5(
+                file_id UNINDEXED,
+                content,
+                title,
+                headings,
+                tokenize='porter unicode61'
+            );
+
+            INSERT INTO contents_v6 (rowid, file_id, content, title, headings)
+            SELECT c.file_id, c.file_id, c.content, COALESCE(m.title, ''),
+                   COALESCE((SELECT group_concat(substr(value, instr(value, ':') + 1), ' ')
+                             FROM json_each(m.headings)), '')
+            FROM contents c
+            LEFT JOIN markdown_meta m ON m.file_id = c.file_id
+            WHERE c.file_id IN (SELECT id FROM files)
+            GROUP BY c.file_id;
+
+            DROP TABLE contents;
+            ALTER TABLE contents_v6 RENAME TO contents;
+            ",
+        )?;
+    }
+
+    if from_version < 7 {
+        // Track the indexed commit of remote clones for version 7 (NULL until next sync)
+        conn.execute_batch(
+            r"
+            ALTER TABLE repositories ADD COLUMN last_indexed_commit TEXT;
+            ",
+        )?;
+    }
+
+    if from_version < 8 {
+        // Add searchable code blocks for version 8, extracted from the stored
+        // markdown content so unchanged files don't need a forced update
+        create_code_blocks_table(conn, "code_blocks")?;
+        fill_code_blocks(conn)?;
+    }
+
+    if from_version < 9 {
+        // Add the opt-in query log for version 9
+        create_search_log_table(conn)?;
+    }
+
     Ok(())
 }
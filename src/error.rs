@@ -16,11 +16,22 @@ pub enum AppError {
     #[error("Not a directory: {0}")]
     NotADirectory(PathBuf),
 
-    #[error("Permission denied: {0}")]
+    #[error(
+        "Permission denied: {0} (kdex needs permission to list and enter it; check with `ls -ld`)"
+    )]
     PermissionDenied(PathBuf),
 
+    #[error("Index limit exceeded: {0}")]
+    IndexLimitExceeded(String),
+
     #[error("Database error: {0}")]
-    Database(#[from] rusqlite::Error),
+    Database(rusqlite::Error),
+
+    #[error("The index is locked by another kdex process; try again when it finishes")]
+    DatabaseBusy,
+
+    #[error("Index lock poisoned by an earlier failure; restart kdex")]
+    LockPoisoned,
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -34,6 +45,10 @@ pub enum AppError {
     #[error("Configuration error: {0}")]
     Config(String),
 
+    /// A query the search can't run, e.g. an invalid `--regex` pattern
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
     #[error("No repositories indexed yet")]
     NoRepositories,
 
@@ -48,8 +63,116 @@ pub enum AppError {
         min_height: u16,
     },
 
+    /// Cloning from or fetching a remote repository failed
+    #[error("{0}")]
+    Remote(String),
+
+    /// Failure a command already described in its `--json` report, so no
+    /// JSON error document follows it
+    #[error("{0}")]
+    Reported(String),
+
+    /// Interrupted with Ctrl+C after the command reported what it completed
+    #[error("{0}")]
+    Cancelled(String),
+
     #[error("{0}")]
     Other(String),
 }
 
+impl AppError {
+    /// Stable identifier of the error variant for `--json` error output
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::RepoNotFound(_) => "repo_not_found",
+            Self::RepoAlreadyIndexed(_) => "repo_already_indexed",
+            Self::PathNotFound(_) => "path_not_found",
+            Self::NotADirectory(_) => "not_a_directory",
+            Self::PermissionDenied(_) => "permission_denied",
+            Self::IndexLimitExceeded(_) => "index_limit_exceeded",
+            Self::Database(_) => "database",
+            Self::DatabaseBusy => "database_busy",
+            Self::LockPoisoned => "lock_poisoned",
+            Self::Io(_) => "io",
+            Self::Watcher(_) => "watcher",
+            Self::Json(_) => "json",
+            Self::Config(_) => "config",
+            Self::InvalidQuery(_) => "invalid_query",
+            Self::NoRepositories => "no_repositories",
+            Self::NoResults => "no_results",
+            Self::TerminalTooSmall { .. } => "terminal_too_small",
+            Self::Remote(_) => "remote",
+            Self::Reported(_) => "reported",
+            Self::Cancelled(_) => "cancelled",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// Process exit code, grouped by what a script can do about the error.
+    /// 2 is shared with argument parsing errors and 130 means Ctrl+C.
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::RepoNotFound(_)
+            | Self::PathNotFound(_)
+            | Self::NotADirectory(_)
+            | Self::NoRepositories
+            | Self::NoResults => 3,
+            Self::InvalidQuery(_) => 2,
+            Self::RepoAlreadyIndexed(_) => 4,
+            Self::Config(_) => 5,
+            Self::Database(_) | Self::LockPoisoned => 6,
+            Self::DatabaseBusy => 7,
+            Self::Io(_) | Self::PermissionDenied(_) | Self::Watcher(_) => 8,
+            Self::Remote(_) => 9,
+            Self::IndexLimitExceeded(_) => 10,
+            Self::Cancelled(_) => 130,
+            Self::Json(_) | Self::TerminalTooSmall { .. } | Self::Reported(_) | Self::Other(_) => 1,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    /// `SQLITE_BUSY` after the busy timeout means another process holds the
+    /// write lock; report that instead of a bare "database is locked"
+    fn from(e: rusqlite::Error) -> Self {
+        match e.sqlite_error_code() {
+            // SQLITE_LOCKED is a conflict inside one connection, not another process
+            Some(rusqlite::ErrorCode::DatabaseBusy) => Self::DatabaseBusy,
+            _ => Self::Database(e),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sqlite_error(code: i32) -> rusqlite::Error {
+        rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(code), None)
+    }
+
+    #[test]
+    fn test_only_sqlite_busy_is_database_busy() {
+        let busy = AppError::from(sqlite_error(rusqlite::ffi::SQLITE_BUSY));
+        assert!(matches!(busy, AppError::DatabaseBusy));
+        assert_eq!(busy.exit_code(), 7);
+
+        let locked = AppError::from(sqlite_error(rusqlite::ffi::SQLITE_LOCKED));
+        assert!(matches!(locked, AppError::Database(_)));
+        assert_eq!(locked.exit_code(), 6);
+
+        let missing = AppError::from(rusqlite::Error::QueryReturnedNoRows);
+        assert!(matches!(missing, AppError::Database(_)));
+    }
+
+    #[test]
+    fn test_cancelled_exits_like_sigint() {
+        let cancelled = AppError::Cancelled("Indexing interrupted".into());
+        assert_eq!(cancelled.exit_code(), 130);
+        assert_eq!(cancelled.kind(), "cancelled");
+    }
+}
@@ -8,12 +8,14 @@ mod tui;
 
 use atty::is;
 use clap::Parser;
-use cli::args::{Args, Commands};
+use cli::args::{Args, Commands, GroupBy, ReposAction};
 use cli::commands;
 use error::Result;
+use std::time::Duration;
 
 /// Known subcommands - if first arg doesn't match, treat as search query
 const KNOWN_COMMANDS: &[&str] = &[
+    "init",
     "index",
     "add",
     "add-mcp",
@@ -22,17 +24,24 @@ const KNOWN_COMMANDS: &[&str] = &[
     "sync",
     "list",
     "remove",
+    "prune",
+    "maintenance",
+    "repos",
     "config",
     "mcp",
+    "serve",
     "watch",
     "rebuild-embeddings",
+    "export-embeddings",
     "completions",
     "backlinks",
     "tags",
+    "symbols",
     "context",
     "stats",
     "graph",
     "health",
+    "doctor",
     "self-update",
     "help",
 ];
@@ -40,16 +49,39 @@ const KNOWN_COMMANDS: &[&str] = &[
 fn main() {
     // Rewrite args: if first positional isn't a known command, assume it's a search query
     let args = rewrite_args_for_default_search();
-    let parsed = Args::parse_from(args);
+    let mut parsed = Args::parse_from(args);
+
+    // Porcelain output is a parsing contract: never colored
+    parsed.no_color |= parsed.porcelain;
 
     if let Err(e) = run_with_args(&parsed) {
-        if parsed.debug {
+        if parsed.json {
+            // JSON consumers read the error from stdout like any other
+            // result; a command's own report may already carry it
+            if !matches!(
+                e,
+                error::AppError::Reported(_) | error::AppError::Cancelled(_)
+            ) {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "error": e.to_string(),
+                        "kind": e.kind(),
+                        "exit_code": e.exit_code(),
+                    })
+                );
+            }
+            if parsed.debug {
+                eprintln!("Error: {e:?}");
+            }
+        } else if parsed.debug {
             eprintln!("Error: {e:?}");
-        } else {
+        } else if !matches!(e, error::AppError::Cancelled(_)) {
+            // Not for a cancelled command, which already printed how to resume
             eprintln!("Error: {e}");
             eprintln!("Run with --debug for more details.");
         }
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
 
@@ -96,6 +128,11 @@ fn run_with_args(args: &Args) -> Result<()> {
         std::env::set_var("RUST_BACKTRACE", "1");
     }
 
+    // Make the database override visible to every Database::open (TUI, MCP, commands)
+    if let Some(db_path) = &args.db_path {
+        std::env::set_var("KDEX_DB_PATH", db_path);
+    }
+
     match &args.command {
         Some(cmd) => run_command(cmd.clone(), args),
         None => {
@@ -113,198 +150,278 @@ fn run_with_args(args: &Args) -> Result<()> {
 #[allow(clippy::too_many_lines)]
 fn run_command(cmd: Commands, args: &Args) -> Result<()> {
     match cmd {
-        Commands::Index { path, name } => commands::index::run(&path, name, args),
+        Commands::Index {
+            stdin: true,
+            name,
+            path_label,
+            ..
+        } => commands::index::run_stdin(
+            &name.unwrap_or_default(),
+            &path_label.unwrap_or_default(),
+            args,
+        ),
+        Commands::Index {
+            path,
+            name,
+            watch,
+            follow_symlinks,
+            include_hidden,
+            exclude_hidden,
+            max_files,
+            max_total_mb,
+            progress,
+            embed,
+            no_embed,
+            ..
+        } => commands::index::run(
+            &path,
+            name,
+            watch,
+            follow_symlinks,
+            (include_hidden || exclude_hidden).then_some(include_hidden),
+            max_files,
+            max_total_mb,
+            progress,
+            (embed || no_embed).then_some(embed),
+            args,
+        ),
         Commands::Add {
             path,
             remote,
             branch,
             shallow,
             name,
+            watch,
+            progress,
+            embed,
+            no_embed,
         } => commands::add::run(
             path.as_deref(),
             remote.as_deref(),
             branch.as_deref(),
             shallow,
             name,
+            watch,
+            progress,
+            (embed || no_embed).then_some(embed),
             args,
         ),
         Commands::Search {
             query,
             repo,
+            repo_path,
+            within,
+            include_all,
+            exclude_patterns,
             file_type,
             tag,
             limit,
+            all,
+            per_repo_limit,
+            timeout,
             group_by_repo,
+            group_by,
+            group_sort,
             semantic,
             hybrid,
             lexical,
+            mode,
             fuzzy,
             regex,
+            substring,
+            rerank,
+            absolute,
+            explain,
+            show_score,
+            min_score,
+            show_title,
+            no_type_labels,
+            near,
+            in_code,
+            code_lang,
+            highlight,
+            snippet_lines,
+            json_lines,
+            with_content,
+            max_chars,
+            output,
+            open,
+            preview,
         } => commands::search::run(
-            query,
-            repo,
-            file_type,
-            tag,
-            limit,
-            group_by_repo,
-            semantic,
-            hybrid,
-            lexical,
-            fuzzy,
-            regex,
+            commands::search::SearchOptions {
+                query,
+                repo,
+                repo_path,
+                within,
+                include_all,
+                exclude_patterns,
+                file_type,
+                tag,
+                limit: if all { Some(0) } else { limit },
+                per_repo_limit: per_repo_limit.filter(|n| *n > 0),
+                timeout: timeout.map(Duration::from_secs),
+                group_by: group_by.unwrap_or(if group_by_repo {
+                    GroupBy::Repo
+                } else {
+                    GroupBy::None
+                }),
+                group_sort,
+                semantic,
+                hybrid,
+                lexical,
+                mode,
+                fuzzy,
+                regex,
+                substring,
+                rerank,
+                absolute,
+                explain,
+                show_score,
+                min_score,
+                show_title,
+                type_labels: !no_type_labels,
+                near,
+                in_code,
+                code_lang,
+                highlight_mode: highlight,
+                snippet_lines,
+                json_lines,
+                with_content,
+                max_chars,
+                output_path: output,
+                open,
+                preview,
+            },
             args,
         ),
-        Commands::List {} => commands::list::run(args),
-        Commands::Update { path, all } => commands::update::run(path, all, args),
+        Commands::List {
+            sort,
+            filter,
+            limit,
+            offset,
+        } => commands::list::run(sort, filter.as_deref(), limit, offset, args),
+        Commands::Update {
+            path,
+            all,
+            since,
+            force,
+        } => commands::update::run(path, all, since, force, args),
         Commands::Sync { repo, no_index } => commands::sync::run(repo.as_deref(), no_index, args),
-        Commands::Remove { path, force } => commands::remove::run(&path, force, args),
+        Commands::Remove {
+            target,
+            all,
+            force,
+            delete_clone,
+            keep_clone,
+        } => commands::remove::run(
+            target.as_deref(),
+            all,
+            force,
+            delete_clone,
+            keep_clone,
+            args,
+        ),
+        Commands::Prune { force } => commands::prune::run(force, args),
+        Commands::Maintenance { check_only, force } => {
+            commands::maintenance::run(check_only, force, args)
+        }
+        Commands::Repos { action } => match action {
+            ReposAction::Rename { old, new } => commands::repos::rename(&old, &new, args),
+            ReposAction::Open { name } => commands::repos::open(&name, args),
+        },
         Commands::Config {
             action,
             key,
             value,
             reset,
         } => commands::config::run(action, key, value, reset, args),
-        Commands::Mcp {} => run_mcp_server(),
-        Commands::Watch { all, path } => run_watcher(all, path, args),
-        Commands::RebuildEmbeddings { repo } => commands::rebuild_embeddings::run(repo, args),
+        Commands::Mcp { no_warmup } => run_mcp_server(!no_warmup),
+        Commands::Serve { port, host } => commands::serve::run(&host, port, args),
+        Commands::Watch { all, path, poll } => commands::watch::run(all, path, poll, args),
+        Commands::RebuildEmbeddings {
+            repo,
+            batch,
+            model,
+            missing_only,
+        } => commands::rebuild_embeddings::run(repo, batch, model, missing_only, args),
+        Commands::ExportEmbeddings {
+            format,
+            repo,
+            output,
+        } => commands::export_embeddings::run(format, repo.as_deref(), output, args),
         Commands::Completions { shell } => {
             commands::completions::run(shell);
             Ok(())
         }
         Commands::Backlinks { file } => commands::backlinks::run(&file, args),
-        Commands::Tags => commands::tags::run(args),
+        Commands::Tags { repo, co_occur } => {
+            commands::tags::run(repo.as_deref(), co_occur.as_deref(), args)
+        }
+        Commands::Symbols { repo } => commands::symbols::run(repo.as_deref(), args),
         Commands::Context {
             query,
+            repo,
+            file_type,
             limit,
             tokens,
             format,
-        } => commands::context::run(&query, limit, tokens, &format, args),
-        Commands::Stats {} => commands::stats::run(args),
-        Commands::Graph { format, repo } => commands::graph::run(&format, repo.as_deref(), args),
-        Commands::Health { repo } => commands::health::run(repo.as_deref(), args),
+            strategy,
+            max_per_repo,
+            output,
+        } => commands::context::run(
+            &query,
+            repo.as_deref(),
+            file_type.as_deref(),
+            limit,
+            tokens,
+            &format,
+            strategy,
+            max_per_repo,
+            output.as_deref(),
+            args,
+        ),
+        Commands::Stats {
+            largest_files,
+            repo,
+            queries,
+            watch,
+        } => commands::stats::run(largest_files, repo.as_deref(), queries, watch, args),
+        Commands::Graph {
+            format,
+            repo,
+            focus,
+            depth,
+        } => commands::graph::run(&format, repo.as_deref(), focus.as_deref(), depth, args),
+        Commands::Health {
+            repo,
+            check_external,
+            timeout,
+        } => commands::health::run(repo.as_deref(), check_external.then_some(timeout), args),
+        Commands::Doctor => commands::doctor::run(args),
+        Commands::Init {
+            semantic,
+            no_semantic,
+            mcp,
+            no_mcp,
+            index,
+            no_index,
+        } => commands::init::run(
+            (semantic || no_semantic).then_some(semantic),
+            mcp,
+            no_mcp,
+            (index || no_index).then_some(index),
+            args,
+        ),
         Commands::AddMcp { tool } => commands::add_mcp::run(tool, args.json),
         Commands::SelfUpdate => commands::self_update::run(args.json),
     }
 }
 
-fn run_watcher(all: bool, path: Option<std::path::PathBuf>, args: &Args) -> Result<()> {
-    use crate::core::{check_inotify_limit, estimate_directory_count, IndexWatcher};
-    use std::sync::Arc;
-    use std::thread;
-    use std::time::Duration;
-
-    let db = db::Database::open()?;
-    let config = Arc::new(config::Config::load()?);
-
-    let repos = if all {
-        db.list_repositories()?
-    } else if let Some(p) = path {
-        let abs_path = std::fs::canonicalize(&p)?;
-        db.list_repositories()?
-            .into_iter()
-            .filter(|r| r.path == abs_path)
-            .collect()
-    } else {
-        let cwd = std::env::current_dir()?;
-        db.list_repositories()?
-            .into_iter()
-            .filter(|r| r.path == cwd)
-            .collect()
-    };
-
-    if repos.is_empty() {
-        if !args.quiet {
-            eprintln!("No repositories to watch. Index a directory first.");
-        }
-        return Ok(());
-    }
-
-    // Check platform limits (Linux inotify)
-    if !args.quiet {
-        let total_dirs: usize = repos
-            .iter()
-            .filter_map(|r| estimate_directory_count(&r.path).ok())
-            .sum();
-
-        let limits = check_inotify_limit(total_dirs);
-        if let Some(warning) = limits.warning {
-            eprintln!("{warning}");
-            eprintln!();
-        }
-    }
-
-    if !args.quiet {
-        println!(
-            "Watching {} repositor{} for changes...",
-            repos.len(),
-            if repos.len() == 1 { "y" } else { "ies" }
-        );
-        for repo in &repos {
-            println!("  • {}", repo.path.display());
-        }
-        println!("Press Ctrl+C to stop.");
-    }
-
-    let mut watcher = IndexWatcher::new(config)?;
-
-    // Add all repository paths to watch
-    for repo in &repos {
-        watcher.watch(repo.path.clone())?;
-    }
-
-    // Main watch loop
-    loop {
-        let batches = watcher.poll_changes();
-
-        for batch in batches {
-            if !args.quiet {
-                println!("Changes detected in {}:", batch.repo_path.display());
-            }
-
-            for change in &batch.changes {
-                if !args.quiet {
-                    let action = match change.change_type {
-                        crate::core::ChangeType::Created => "created",
-                        crate::core::ChangeType::Modified => "modified",
-                        crate::core::ChangeType::Deleted => "deleted",
-                    };
-                    println!("  {} {}", action, change.path.display());
-                }
-            }
-
-            // Re-index the changed repository
-            if let Some(repo) = repos.iter().find(|r| r.path == batch.repo_path) {
-                let indexer_config = config::Config::load()?;
-                let indexer_db = db::Database::open()?;
-                let indexer = crate::core::Indexer::new(indexer_db, indexer_config);
-
-                match indexer.index(&repo.path, Some(repo.name.clone()), |_| {}) {
-                    Ok(result) => {
-                        if !args.quiet {
-                            println!(
-                                "  ✓ Re-indexed: {} added, {} updated, {} deleted",
-                                result.files_added, result.files_updated, result.files_deleted
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        if !args.quiet {
-                            eprintln!("  ✗ Failed to re-index: {e}");
-                        }
-                    }
-                }
-            }
-        }
-
-        thread::sleep(Duration::from_millis(100));
-    }
-}
-
-fn run_mcp_server() -> Result<()> {
+fn run_mcp_server(warmup: bool) -> Result<()> {
     let config = config::Config::load()?;
     let db = db::Database::open()?;
 
     tokio::runtime::Runtime::new()
         .map_err(|e| error::AppError::Other(format!("Failed to create runtime: {e}")))?
-        .block_on(mcp::run_mcp_server(db, config))
+        .block_on(mcp::run_mcp_server(db, config, warmup))
 }
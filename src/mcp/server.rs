@@ -5,11 +5,12 @@ use rmcp::{
     schemars, tool, ServerHandler, ServiceExt,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 use tokio::sync::Mutex;
 
 use crate::config::Config;
-use crate::core::{Embedder, SearchMode, Searcher};
+use crate::core::{read_prefix, Embedder, SearchMode, Searcher};
 use crate::db::Database;
 
 /// MCP server for kdex.
@@ -17,6 +18,8 @@ use crate::db::Database;
 pub struct KnowledgeIndexMcp {
     db: Arc<Mutex<Database>>,
     config: Arc<Config>,
+    /// Embedding model, loaded once on first use (or at startup with warmup)
+    embedder: Arc<OnceLock<std::result::Result<Arc<Embedder>, String>>>,
 }
 
 /// Search result for MCP response.
@@ -57,6 +60,28 @@ struct McpListReposResponse {
     total: usize,
 }
 
+/// Heading of a file for MCP.
+#[derive(Debug, Serialize, Deserialize)]
+struct McpHeading {
+    level: u8,
+    text: String,
+}
+
+/// File metadata response for MCP.
+#[derive(Debug, Serialize, Deserialize)]
+struct McpFileMeta {
+    file: String,
+    repo: String,
+    relative_path: String,
+    file_type: String,
+    size_bytes: i64,
+    last_modified: String,
+    title: Option<String>,
+    tags: Vec<String>,
+    headings: Vec<McpHeading>,
+    links: Vec<String>,
+}
+
 /// Search request parameters.
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct SearchRequest {
@@ -68,7 +93,9 @@ pub struct SearchRequest {
     pub repo: Option<String>,
     #[schemars(description = "Filter by file type (e.g., 'rust', 'markdown', 'python')")]
     pub file_type: Option<String>,
-    #[schemars(description = "Search mode: 'lexical' (default), 'semantic', or 'hybrid'")]
+    #[schemars(
+        description = "Search mode: 'lexical' (default), 'semantic', 'hybrid', or 'auto' to pick one from the query"
+    )]
     pub mode: Option<String>,
 }
 
@@ -81,6 +108,13 @@ pub struct GetFileRequest {
     pub max_chars: Option<u32>,
 }
 
+/// Get file metadata request parameters.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetFileMetaRequest {
+    #[schemars(description = "Absolute path to the file")]
+    pub path: String,
+}
+
 /// Get context request parameters.
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct GetContextRequest {
@@ -96,28 +130,31 @@ pub struct GetContextRequest {
 impl KnowledgeIndexMcp {
     /// Search indexed content across all repositories.
     #[tool(
-        description = "Search indexed code and knowledge repositories for relevant content. Supports lexical (default), semantic (vector), or hybrid search modes."
+        description = "Search indexed code and knowledge repositories for relevant content. Supports lexical (default), semantic (vector), or hybrid search modes, or auto to pick one per query."
     )]
     async fn search(&self, #[tool(aggr)] req: SearchRequest) -> String {
         let limit = req.limit.unwrap_or(10).min(50) as usize;
         let db = self.db.lock().await;
 
         // Determine search mode
-        let search_mode = req.mode.as_deref().map_or_else(
-            || SearchMode::from_str(&self.config.default_search_mode),
-            SearchMode::from_str,
-        );
+        let search_mode = req
+            .mode
+            .as_deref()
+            .map_or_else(
+                || SearchMode::from_str(&self.config.default_search_mode),
+                SearchMode::from_str,
+            )
+            .resolve(&req.query, self.config.enable_semantic_search);
 
         // Create searcher with embedder if needed
-        let searcher = if (search_mode == SearchMode::Semantic || search_mode == SearchMode::Hybrid)
-            && self.config.enable_semantic_search
-        {
-            match Embedder::new(&self.config.embedding_model) {
-                Ok(embedder) => Searcher::with_embedder(db.clone(), embedder),
-                Err(_) => Searcher::new(db.clone()),
-            }
+        let embedder = if search_mode == SearchMode::Semantic || search_mode == SearchMode::Hybrid {
+            self.embedder().ok()
         } else {
-            Searcher::new(db.clone())
+            None
+        };
+        let searcher = match embedder {
+            Some(embedder) => Searcher::with_embedder(db.clone(), embedder),
+            None => Searcher::new(db.clone()),
         };
 
         // Use lexical if semantic requested but not available
@@ -210,12 +247,15 @@ impl KnowledgeIndexMcp {
     fn get_file(&self, #[tool(aggr)] req: GetFileRequest) -> String {
         let max_chars = req.max_chars.unwrap_or(50000) as usize;
 
-        let file_content = match std::fs::read_to_string(&req.path) {
-            Ok(c) => c,
-            Err(e) => return format!("{{\"error\": \"Failed to read file: {e}\"}}"),
-        };
+        // A character takes at most 4 bytes, so this prefix holds `max_chars`
+        // characters without reading all of a huge file
+        let (file_content, cut) =
+            match read_prefix(std::path::Path::new(&req.path), max_chars.saturating_mul(4)) {
+                Ok(prefix) => prefix,
+                Err(e) => return format!("{{\"error\": \"Failed to read file: {e}\"}}"),
+            };
 
-        let truncated = file_content.len() > max_chars;
+        let truncated = cut || file_content.chars().count() > max_chars;
         let content_str = if truncated {
             file_content.chars().take(max_chars).collect()
         } else {
@@ -233,6 +273,43 @@ impl KnowledgeIndexMcp {
         )
     }
 
+    /// Get stored metadata of a file without its content.
+    #[tool(
+        description = "Get metadata of an indexed file without its content: title, tags, headings, wiki-links, size and file type. Use it to decide whether to fetch the full file."
+    )]
+    async fn get_file_meta(&self, #[tool(aggr)] req: GetFileMetaRequest) -> String {
+        let db = self.db.lock().await;
+
+        let meta = match db.get_file_meta(std::path::Path::new(&req.path)) {
+            Ok(Some(meta)) => meta,
+            Ok(None) => {
+                return serde_json::json!({"error": format!("File not indexed: {}", req.path)})
+                    .to_string()
+            }
+            Err(e) => return serde_json::json!({"error": e.to_string()}).to_string(),
+        };
+
+        let response = McpFileMeta {
+            file: meta.absolute_path.to_string_lossy().to_string(),
+            repo: meta.repo_name,
+            relative_path: meta.relative_path.to_string_lossy().to_string(),
+            file_type: meta.file_type,
+            size_bytes: meta.size_bytes,
+            last_modified: meta.last_modified_at.to_rfc3339(),
+            title: meta.title,
+            tags: meta.tags,
+            headings: meta
+                .headings
+                .into_iter()
+                .map(|(level, text)| McpHeading { level, text })
+                .collect(),
+            links: meta.links,
+        };
+
+        serde_json::to_string_pretty(&response)
+            .unwrap_or_else(|e| serde_json::json!({"error": e.to_string()}).to_string())
+    }
+
     /// Get context around a specific line in a file.
     #[allow(clippy::unused_self, clippy::needless_pass_by_value)]
     #[tool(description = "Get lines of context around a specific line number in a file")]
@@ -272,7 +349,8 @@ impl ServerHandler for KnowledgeIndexMcp {
             instructions: Some(
                 "Search and retrieve content from indexed code repositories and knowledge bases. \
                  Use 'search' to find relevant files, 'list_repos' to see indexed repositories, \
-                 'get_file' to read full file content, and 'get_context' to get context around \
+                 'get_file' to read full file content, 'get_file_meta' to see a file's title, tags \
+                 and headings without its content, and 'get_context' to get context around \
                  specific lines."
                     .into(),
             ),
@@ -288,16 +366,59 @@ impl KnowledgeIndexMcp {
         Self {
             db: Arc::new(Mutex::new(db)),
             config: Arc::new(config),
+            embedder: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// The shared embedding model, loading it on first call. Errors (semantic
+    /// search disabled, model unavailable) are remembered, not retried.
+    fn embedder(&self) -> std::result::Result<Arc<Embedder>, String> {
+        self.embedder
+            .get_or_init(|| {
+                if !self.config.enable_semantic_search {
+                    return Err("semantic search is disabled".into());
+                }
+                Embedder::new(
+                    &self.config.embedding_model,
+                    self.config.model_cache_dir().as_deref(),
+                )
+                .map(Arc::new)
+                .map_err(|e| e.to_string())
+            })
+            .clone()
+    }
+
+    /// Load the embedding model now so the first semantic query doesn't
+    /// stall. Returns a status line for the startup banner, or `None` when
+    /// semantic search is disabled.
+    fn warm_up(&self) -> Option<String> {
+        if !self.config.enable_semantic_search {
+            return None;
         }
+        let start = Instant::now();
+        Some(match self.embedder() {
+            Ok(_) => format!(
+                "{} loaded in {:.1}s",
+                self.config.embedding_model,
+                start.elapsed().as_secs_f64()
+            ),
+            Err(e) => format!("not available ({e}); semantic queries fall back to lexical"),
+        })
     }
 }
 
-/// Run the MCP server over stdio.
-pub async fn run_mcp_server(db: Database, config: Config) -> crate::error::Result<()> {
+/// Run the MCP server over stdio. With `warmup`, the embedding model is
+/// loaded before serving instead of on the first semantic query.
+pub async fn run_mcp_server(
+    db: Database,
+    config: Config,
+    warmup: bool,
+) -> crate::error::Result<()> {
     let server = KnowledgeIndexMcp::new(db, config);
+    let embeddings = if warmup { server.warm_up() } else { None };
 
     // Log to stderr only (stdout is for MCP protocol)
-    print_mcp_startup_info();
+    print_mcp_startup_info(embeddings.as_deref());
 
     let service = server
         .serve(rmcp::transport::io::stdio())
@@ -312,8 +433,9 @@ pub async fn run_mcp_server(db: Database, config: Config) -> crate::error::Resul
     Ok(())
 }
 
-/// Print startup information and integration guide to stderr.
-fn print_mcp_startup_info() {
+/// Print startup information and integration guide to stderr, with the
+/// embedding model warmup result if there was one.
+fn print_mcp_startup_info(embeddings: Option<&str>) {
     eprintln!("\x1b[1;36m╭─────────────────────────────────────────────────────────────╮\x1b[0m");
     eprintln!("\x1b[1;36m│\x1b[0m  \x1b[1mkdex MCP Server\x1b[0m                                          \x1b[1;36m│\x1b[0m");
     eprintln!("\x1b[1;36m╰─────────────────────────────────────────────────────────────╯\x1b[0m");
@@ -322,6 +444,7 @@ fn print_mcp_startup_info() {
     eprintln!("  \x1b[32m•\x1b[0m search       - Search indexed content (lexical/semantic/hybrid)");
     eprintln!("  \x1b[32m•\x1b[0m list_repos   - List all indexed repositories");
     eprintln!("  \x1b[32m•\x1b[0m get_file     - Read full file content");
+    eprintln!("  \x1b[32m•\x1b[0m get_file_meta - Title, tags, headings and links of a file");
     eprintln!("  \x1b[32m•\x1b[0m get_context  - Get lines around a specific line number");
     eprintln!();
     eprintln!("\x1b[1mIntegration:\x1b[0m");
@@ -341,6 +464,9 @@ fn print_mcp_startup_info() {
     eprintln!("  \x1b[33mClaude Desktop\x1b[0m (~/.config/claude/claude_desktop_config.json):");
     eprintln!("  \x1b[90m  Same configuration as above\x1b[0m");
     eprintln!();
+    if let Some(embeddings) = embeddings {
+        eprintln!("\x1b[1mEmbedding model:\x1b[0m {embeddings}");
+    }
     eprintln!("\x1b[1mStatus:\x1b[0m \x1b[32mListening on stdio...\x1b[0m");
     eprintln!("\x1b[90mPress Ctrl+C to stop\x1b[0m");
     eprintln!();
@@ -1,5 +1,7 @@
+use std::path::PathBuf;
+
 use crate::config::{Config, SearchHistory};
-use crate::core::Searcher;
+use crate::core::{missing_files_warning, IndexedFileReader, Searcher, STALE_INDEX_HINT};
 use crate::db::{Database, Repository, SearchResult};
 
 /// Application mode/view
@@ -58,6 +60,11 @@ pub struct App {
     pub show_preview: bool,
     pub preview_content: Option<String>,
     pub preview_scroll: usize,
+    /// The previewed file is longer than `preview_max_kb`
+    pub preview_truncated: bool,
+
+    // File to open in the editor once the terminal is released
+    pub pending_open: Option<PathBuf>,
 
     // Repository state
     pub repos: Vec<Repository>,
@@ -103,6 +110,8 @@ impl App {
             show_preview: false,
             preview_content: None,
             preview_scroll: 0,
+            preview_truncated: false,
+            pending_open: None,
             repos,
             repos_selected: 0,
             confirm_dialog: None,
@@ -133,7 +142,13 @@ impl App {
         let _ = self.search_history.save(); // Ignore save errors
         self.history_index = None; // Reset history navigation
 
-        match self.searcher.search(&self.search_input, None, None, 50, 0) {
+        match self.searcher.search(
+            &self.search_input,
+            None,
+            None,
+            self.config.default_search_limit,
+            0,
+        ) {
             Ok(results) => {
                 self.search_results = results;
                 self.search_selected = 0;
@@ -246,14 +261,11 @@ impl App {
             return;
         }
 
-        let result = &self.search_results[self.search_selected];
-        let path = &result.absolute_path;
-
-        let _editor = std::env::var("EDITOR").unwrap_or_else(|_| "vim".to_string());
-
-        // We need to restore terminal, run editor, then reinitialize
-        // For simplicity, just show a message for now
-        self.set_status(format!("Open: {}", path.display()), StatusLevel::Info);
+        // The run loop suspends the TUI while the editor runs
+        let path = self.search_results[self.search_selected]
+            .absolute_path
+            .clone();
+        self.pending_open = Some(path);
     }
 
     /// Delete selected repository (direct, no confirmation)
@@ -288,6 +300,7 @@ impl App {
             self.show_preview = false;
             self.preview_content = None;
             self.preview_scroll = 0;
+            self.preview_truncated = false;
         } else {
             self.load_preview();
         }
@@ -300,16 +313,35 @@ impl App {
         }
 
         let result = &self.search_results[self.search_selected];
-        let path = &result.absolute_path;
-
-        match std::fs::read_to_string(path) {
-            Ok(content) => {
+        let reader = IndexedFileReader::new(self.db.clone());
+
+        // Only a prefix of huge files is read, so the TUI stays responsive.
+        // Files gone from disk are previewed from the index, with a hint to re-sync
+        match reader.read_prefix(
+            &result.repo_name,
+            &result.file_path,
+            &result.absolute_path,
+            self.config.preview_max_kb.saturating_mul(1024),
+        ) {
+            Some((content, truncated)) => {
                 self.preview_content = Some(content);
+                self.preview_truncated = truncated;
                 self.show_preview = true;
                 self.preview_scroll = 0;
+                if reader.missing() > 0 {
+                    self.set_status(
+                        format!(
+                            "File no longer on disk, showing indexed content; {STALE_INDEX_HINT}"
+                        ),
+                        StatusLevel::Warning,
+                    );
+                }
             }
-            Err(e) => {
-                self.set_status(format!("Cannot read file: {e}"), StatusLevel::Error);
+            None if reader.missing() > 0 => {
+                self.set_status(missing_files_warning(1), StatusLevel::Warning);
+            }
+            None => {
+                self.set_status("Cannot read file".to_string(), StatusLevel::Error);
             }
         }
     }
@@ -107,11 +107,9 @@ fn handle_search_keys(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
         KeyCode::Char('q') if modifiers.contains(KeyModifiers::CONTROL) => {
             app.should_quit = true;
         }
-        KeyCode::Esc => {
-            if !app.search_input.is_empty() {
-                app.search_input.clear();
-                app.search_results.clear();
-            }
+        KeyCode::Esc if !app.search_input.is_empty() => {
+            app.search_input.clear();
+            app.search_results.clear();
         }
         KeyCode::Tab => {
             app.mode = AppMode::Repos;
@@ -4,6 +4,7 @@ mod ui;
 mod views;
 
 pub use app::App;
+use app::StatusLevel;
 
 use crossterm::{
     event::{
@@ -15,8 +16,10 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io::{self, stdout};
 use std::panic;
+use std::path::Path;
 
 use crate::config::Config;
+use crate::core::open_in_editor;
 use crate::db::Database;
 use crate::error::Result;
 
@@ -68,6 +71,10 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
             }
         }
 
+        if let Some(path) = app.pending_open.take() {
+            edit_file(terminal, app, &path)?;
+        }
+
         if app.should_quit {
             break;
         }
@@ -75,3 +82,21 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
 
     Ok(())
 }
+
+/// Leave the TUI, open `path` in the editor and restore the TUI afterwards
+fn edit_file(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    path: &Path,
+) -> Result<()> {
+    restore_terminal()?;
+    let opened = open_in_editor(path);
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+
+    if let Err(e) = opened {
+        app.set_status(format!("Editor error: {e}"), StatusLevel::Error);
+    }
+    Ok(())
+}
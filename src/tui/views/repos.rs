@@ -7,6 +7,7 @@ use ratatui::{
     Frame,
 };
 
+use crate::core::truncate_chars;
 use crate::db::RepoStatus;
 use crate::tui::app::App;
 
@@ -119,9 +120,5 @@ fn format_time_ago(duration: chrono::Duration) -> String {
 }
 
 fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() > max_len {
-        format!("{}…", &s[..max_len - 1])
-    } else {
-        s.to_string()
-    }
+    truncate_chars(s, max_len, "…")
 }
@@ -6,6 +6,7 @@ use ratatui::{
     Frame,
 };
 
+use crate::core::{strip_highlights, truncate_chars};
 use crate::tui::app::App;
 
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
@@ -252,7 +253,14 @@ fn render_results_with_preview(frame: &mut Frame, app: &App, area: Rect) {
     let preview = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
         Block::default()
             .borders(Borders::ALL)
-            .title(format!(" Preview: {selected_file} "))
+            .title(if app.preview_truncated {
+                format!(
+                    " Preview: {selected_file} (truncated at {} KB) ",
+                    app.config.preview_max_kb
+                )
+            } else {
+                format!(" Preview: {selected_file} ")
+            })
             .border_style(Style::default().fg(Color::Green)),
     );
 
@@ -260,16 +268,10 @@ fn render_results_with_preview(frame: &mut Frame, app: &App, area: Rect) {
 }
 
 fn truncate_snippet(snippet: &str, max_len: usize) -> String {
-    let cleaned = snippet
-        .replace(">>>", "")
-        .replace("<<<", "")
+    let cleaned = strip_highlights(snippet)
         .replace('\n', " ")
         .trim()
         .to_string();
 
-    if cleaned.len() > max_len {
-        format!("{}...", &cleaned[..max_len.saturating_sub(3)])
-    } else {
-        cleaned
-    }
+    truncate_chars(&cleaned, max_len, "...")
 }
@@ -159,6 +159,90 @@ fn test_cli_search_no_results() {
     assert!(stdout.contains("results") || stdout.contains("[]"));
 }
 
+#[test]
+fn test_cli_json_error_envelope() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let output = test_command(config_dir.path())
+        .args(["update", "no_such_repo_12345", "--json"])
+        .output()
+        .expect("Failed to run binary");
+
+    // Repository not found maps to exit code 3
+    assert_eq!(output.status.code(), Some(3));
+    let error: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("error should be JSON on stdout");
+    assert_eq!(error["kind"], "repo_not_found");
+    assert_eq!(error["exit_code"], 3);
+    assert!(error["error"]
+        .as_str()
+        .is_some_and(|e| e.contains("no_such_repo_12345")));
+}
+
+#[test]
+fn test_cli_invalid_regex_is_an_error() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let output = test_command(config_dir.path())
+        .args(["search", "a(", "--regex", "--json"])
+        .output()
+        .expect("Failed to run binary");
+
+    assert_eq!(output.status.code(), Some(2));
+    let error: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("error should be JSON on stdout");
+    assert_eq!(error["kind"], "invalid_query");
+    assert_eq!(error["exit_code"], 2);
+}
+
+#[test]
+fn test_cli_remove_all_requires_force_without_terminal() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let config_dir = tempfile::tempdir().unwrap();
+    let mut child = test_command(config_dir.path())
+        .args([
+            "index",
+            "--stdin",
+            "--name",
+            "notes",
+            "--path-label",
+            "a.md",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .expect("Failed to run index");
+    child.stdin.take().unwrap().write_all(b"# Notes").unwrap();
+    assert!(child.wait().unwrap().success());
+
+    let repo_count = || {
+        let output = test_command(config_dir.path())
+            .args(["list", "--json"])
+            .output()
+            .expect("Failed to run list");
+        let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        list["repositories"].as_array().map_or(0, Vec::len)
+    };
+
+    // Neither --json nor a piped stdin can confirm, so nothing is removed
+    for args in [&["remove", "--all", "--json"][..], &["remove", "--all"][..]] {
+        let output = test_command(config_dir.path())
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .expect("Failed to run remove");
+        assert_eq!(output.status.code(), Some(1), "{args:?}");
+        assert_eq!(repo_count(), 1);
+    }
+
+    let output = test_command(config_dir.path())
+        .args(["remove", "--all", "--json", "--force"])
+        .output()
+        .expect("Failed to run remove");
+    assert!(output.status.success());
+    assert_eq!(repo_count(), 0);
+}
+
 #[test]
 #[ignore = "Requires full index cycle, run with --ignored"]
 fn test_full_index_search_cycle() {